@@ -0,0 +1,32 @@
+//! Insertion of large domains into `Argmax2D`, under different chunk visitation orders.
+
+use {
+  space_filling::{
+    geometry::{Shape, Circle},
+    sdf::SDF,
+    solver::{Argmax2D, argmax2d::z_order_storage::ChunkOrder}
+  },
+  criterion::{criterion_group, criterion_main, Criterion, BenchmarkId},
+  euclid::{Rect, Point2D, Size2D}
+};
+
+fn insert_large_domain(c: &mut Criterion) {
+  let mut group = c.benchmark_group("insert_sdf_domain");
+  group.sample_size(10);
+  let circle = Circle.translate([0.5, 0.5].into()).scale(0.25);
+  let domain = Rect::new(Point2D::splat(0.125), Size2D::splat(0.75));
+
+  for chunk_order in [ChunkOrder::RowMajor, ChunkOrder::Morton, ChunkOrder::Hilbert] {
+    let mut argmax = Argmax2D::new(4096, 16).unwrap()
+      .with_chunk_order(chunk_order);
+    group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", chunk_order)), &domain, |b, &domain| {
+      // a single-threaded pool, where locality matters the most
+      let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+      b.iter(|| pool.install(|| argmax.insert_sdf_domain(domain, |p| circle.sdf(p))))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, insert_large_domain);
+criterion_main!(benches);
@@ -1,13 +1,12 @@
-/// Generate a fractal-like pattern using discrete distance field representation.
-/// Simple impeative code style is shown.
+//! Generate a fractal-like pattern using discrete distance field representation.
+//! Simple impeative code style is shown.
 
 use {
   space_filling::{
-    geometry::{Shape, Circle},
-    sdf::{self, SDF},
-    solver::Argmax2D,
-    drawing::Draw,
-    util
+    geometry::Shape,
+    sdf,
+    solver::{Argmax2D, Concentric},
+    drawing::Draw
   },
   anyhow::Result,
   image::{Luma, Pixel, RgbaImage}
@@ -16,16 +15,8 @@ use {
 fn fractal_distribution(representation: &mut Argmax2D, image: &mut RgbaImage) {
   representation.insert_sdf(sdf::boundary_rect);
 
-  for _ in 0..1000 {
-    let global_max = representation.find_max();
-    let circle = Circle
-      .translate(global_max.point.to_vector())
-      .scale(global_max.distance / 4.0);
-    representation.insert_sdf_domain(
-      util::domain_empirical(global_max),
-      |v| circle.sdf(v)
-    );
-    circle
+  for placed in representation.place_iter(Concentric { ratio: 1.0 / 4.0 }, 0).take(1000) {
+    placed.circle()
       .texture(Luma([255u8]).to_rgba())
       .draw(image);
   }
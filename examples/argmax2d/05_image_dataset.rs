@@ -4,12 +4,12 @@
 use {
   space_filling::{
     solver::Argmax2D,
-    drawing::{self, Draw},
+    drawing::{self, DrawTile},
     geometry::{Shape, BoundingBox}
   },
   embedded::embedded,
   anyhow::Result,
-  image::RgbaImage
+  image::Rgba
 };
 #[path = "03_embedded.rs"]
 mod embedded;
@@ -32,11 +32,8 @@ fn find_files(
 }
 
 fn main() -> Result<()> {
-  use rayon::prelude::*;
-
   let image_folder = std::env::args().nth(1)
-    .map(|path| std::path::Path::new(&path).is_dir().then(|| path))
-    .flatten()
+    .and_then(|path| std::path::Path::new(&path).is_dir().then_some(path))
     .expect("Please provide a valid folder path in arguments");
 
   let mut argmax = Argmax2D::new(16384, 64)?;
@@ -53,13 +50,17 @@ fn main() -> Result<()> {
     .filter_map(|(shape, file)| {
       image::open(&file).map(|tex| {
         println!("{:?} -> {:?}", shape.bounding_box(), file);
-        Box::new(shape.texture(tex)) as Box<dyn Draw<_, _> + Send + Sync>
+        Box::new(shape.texture(tex)) as Box<dyn DrawTile<_, _> + Send + Sync>
       }).map_err(|_| println!("unable to open {:?}", file)).ok()
-    })
-    .par_bridge();
+    });
 
-  drawing::draw_parallel(&mut RgbaImage::new(16384, 16384), shapes)
-    .save("out.png")?;
+  // the framebuffer alone would take 1 GiB
+  drawing::draw_png_streaming(
+    std::io::BufWriter::new(std::fs::File::create("out.png")?),
+    euclid::Size2D::new(16384, 16384),
+    Rgba([0; 4]),
+    shapes
+  )?;
   open::that("out.png")?;
   Ok(())
 }
\ No newline at end of file
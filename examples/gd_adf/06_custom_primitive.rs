@@ -1,10 +1,9 @@
-/// An example of user-defined shape.
-/// Unsafe lock-free ADF access is used for additional 50% speedup.
+//! An example of user-defined shape.
 
 use {
   space_filling::{
     sdf::{self, SDF},
-    solver::{ADF, LineSearch},
+    solver::{ADF, SharedADF, LineSearch},
     drawing::Draw,
     geometry::{WorldSpace, BoundingBox, Shape, Scale, Translation},
     util
@@ -59,19 +58,17 @@ fn mandel_de_norm<T: Float>() -> Scale<Translation<MandlelDE, T>, T> {
     .scale(T::one() / T::from(1.5).unwrap())
 }
 
-// profile, safe: 51.8s, 20k primitives, adf_subdiv = 7, gd_lattice = 1
-// unsafe: 34.3s
-// unsafe, gd_lattice = 3: 165.1s
+// 20k primitives, adf_subdiv = 7, gd_lattice = 1
 fn main() -> Result<()> {
   let path = "out.png";
   let main_de = mandel_de_norm()
     .translate(V2::new(0.4, 0.5))
     .scale(0.5);
   let mut image = RgbaImage::new(2048, 2048);
-  let representation = ADF::new(7, vec![
+  let representation = SharedADF::new(ADF::new(7, vec![
     Arc::new(sdf::boundary_rect),
     Arc::new(move |p| main_de.sdf(p))
-  ]).with_gd_lattice_density(1);
+  ]).with_gd_lattice_density(1));
 
   util::local_maxima_iter(
     Box::new(|p| representation.sdf(p)),
@@ -89,15 +86,15 @@ fn main() -> Result<()> {
       .translate(local_max.point.to_vector())
       .scale(local_max.distance / 4.0);
 
-    // alternately use safe RwLock<ADF> or imperative style
-    unsafe { representation.as_mut() }.insert_sdf_domain(
+    representation.insert_sdf_domain(
       util::domain_empirical(local_max),
       Arc::new(move |p| primitive.sdf(p))
-    ).then(|| primitive)
+    ).then_some(primitive)
   }).enumerate()
     .take(20000)
     .for_each(|(i, _)| if i % 1000 == 0 { println!("#{i}"); });
 
+  let representation = representation.into_inner();
   println!("{representation:#?}");
   // ADF implements SDF - combining all primitives into one complex distance function.
   // Therefore, Draw is implemented automatically as well, making it possible to display the field
@@ -0,0 +1,169 @@
+//! Recording of frames, while a distribution is being generated.
+//!
+//! ```ignore
+//! let mut recorder = FrameRecorder::new(RgbaImage::new(512, 512), FrameOutput::Apng("out.png".into()))
+//!   .with_stride(16);
+//! local_maxima_iter(...)
+//!   .record(&mut recorder, |local_max, canvas| Circle
+//!     .translate(local_max.point.to_vector())
+//!     .scale(local_max.distance)
+//!     .texture(Rgba([0xFF; 4]))
+//!     .draw(canvas))
+//!   .take(1000)
+//!   .for_each(|_| ());
+//! let image = recorder.finish()?;
+//! ```
+
+use {
+  std::{fs::File, io::BufWriter, path::PathBuf, time::Duration},
+  anyhow::Result,
+  image::RgbaImage
+};
+
+/// Destination of the frames of a [`FrameRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameOutput {
+  /// A directory of `00000.png`, `00001.png`, ..., written as they are captured
+  Numbered(PathBuf),
+  /// An animated PNG, written by [`FrameRecorder::finish`]; frames are kept in memory till then
+  Apng(PathBuf)
+}
+
+/// Draws onto a canvas, and captures it as a frame every `stride` steps.
+pub struct FrameRecorder {
+  canvas: RgbaImage,
+  output: FrameOutput,
+  stride: u64,
+  frame_delay: Duration,
+  steps: u64,
+  captured: u64,
+  frames: Vec<RgbaImage>,
+  error: Option<anyhow::Error>
+}
+
+impl FrameRecorder {
+  pub fn new(canvas: RgbaImage, output: FrameOutput) -> Self {
+    Self {
+      canvas,
+      output,
+      stride: 1,
+      frame_delay: Duration::from_millis(40),
+      steps: 0,
+      captured: 0,
+      frames: vec![],
+      error: None
+    }
+  }
+
+  /// Capture every `stride`-th step.
+  pub fn with_stride(mut self, stride: u64) -> Self {
+    self.stride = stride.max(1);
+    self
+  }
+
+  /// Duration of each frame of [`FrameOutput::Apng`].
+  pub fn with_frame_delay(mut self, frame_delay: Duration) -> Self {
+    self.frame_delay = frame_delay;
+    self
+  }
+
+  pub fn canvas(&self) -> &RgbaImage {
+    &self.canvas
+  }
+
+  /// Frames captured so far.
+  pub fn frame_count(&self) -> u64 {
+    self.captured
+  }
+
+  /// Draw a step onto the canvas, and capture a frame, if it is due.
+  pub fn record(&mut self, draw: impl FnOnce(&mut RgbaImage)) -> Result<()> {
+    draw(&mut self.canvas);
+    self.steps += 1;
+    if self.steps.is_multiple_of(self.stride) {
+      self.capture()?;
+    }
+    Ok(())
+  }
+
+  fn capture(&mut self) -> Result<()> {
+    match &self.output {
+      FrameOutput::Numbered(dir) => {
+        std::fs::create_dir_all(dir)?;
+        self.canvas.save(dir.join(format!("{:05}.png", self.captured)))?;
+      },
+      FrameOutput::Apng(_) => self.frames.push(self.canvas.clone())
+    }
+    self.captured += 1;
+    Ok(())
+  }
+
+  /// Capture the last step, if it was skipped by the stride, and write the animation.
+  /// Fails on the first error of [`Record`], if any. Returns the final canvas.
+  pub fn finish(mut self) -> Result<RgbaImage> {
+    if let Some(error) = self.error.take() {
+      return Err(error);
+    }
+    if !self.steps.is_multiple_of(self.stride) {
+      self.capture()?;
+    }
+    if let FrameOutput::Apng(path) = &self.output {
+      write_apng(path, &self.frames, self.frame_delay)?;
+    }
+    Ok(self.canvas)
+  }
+}
+
+fn write_apng(path: &PathBuf, frames: &[RgbaImage], frame_delay: Duration) -> Result<()> {
+  let Some(first) = frames.first() else {
+    anyhow::bail!("no frames were captured");
+  };
+  let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), first.width(), first.height());
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder.set_animated(frames.len() as u32, 0)?;
+  encoder.set_frame_delay(frame_delay.as_millis().min(u16::MAX as u128) as u16, 1000)?;
+  let mut writer = encoder.write_header()?;
+  for frame in frames {
+    writer.write_image_data(frame.as_raw())?;
+  }
+  writer.finish()?;
+  Ok(())
+}
+
+/// Iterator, drawing each item onto the canvas of a [`FrameRecorder`], see [`RecordFrames`].
+pub struct Record<'a, I, F> {
+  iter: I,
+  recorder: &'a mut FrameRecorder,
+  draw: F
+}
+
+impl<I, F> Iterator for Record<'_, I, F>
+  where I: Iterator,
+        F: FnMut(&I::Item, &mut RgbaImage)
+{
+  type Item = I::Item;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let item = self.iter.next()?;
+    if self.recorder.error.is_none() {
+      let draw = &mut self.draw;
+      if let Err(error) = self.recorder.record(|canvas| draw(&item, canvas)) {
+        self.recorder.error = Some(error);
+      }
+    }
+    Some(item)
+  }
+}
+
+/// Records frames of any iterator, such as the ones of [`util`](crate::util).
+pub trait RecordFrames: Iterator + Sized {
+  /// Draw each item with `draw`; errors are deferred to [`FrameRecorder::finish`].
+  fn record<F>(self, recorder: &mut FrameRecorder, draw: F) -> Record<'_, Self, F>
+    where F: FnMut(&Self::Item, &mut RgbaImage)
+  {
+    Record { iter: self, recorder, draw }
+  }
+}
+
+impl<I: Iterator> RecordFrames for I {}
@@ -0,0 +1,97 @@
+use image::Rgba;
+
+/// Mapping of a scalar in `[0, 1]` to a color, for visualizing fields.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Colormap {
+  #[default]
+  Grayscale,
+  /// Perceptually uniform, dark blue to yellow
+  Viridis,
+  /// Rainbow, with a smooth lightness profile
+  Turbo,
+  /// Blue to white to red, for signed fields centered at 0.5
+  Diverging
+}
+
+impl Colormap {
+  /// Color at `t`, which is clamped to `[0, 1]`.
+  pub fn map(self, t: f64) -> Rgba<u8> {
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    let rgb = match self {
+      Self::Grayscale => [t; 3],
+      // polynomial fits of the original colormaps
+      Self::Viridis => [
+        [0.2777273272234177, 0.1050930431085774, -0.3308618287255563, -4.634230498983486, 6.228269936347081, 4.776384997670288, -5.435455855934631],
+        [0.005407344544966578, 1.404613529898575, 0.214847559468213, -5.799100973351585, 14.17993336680509, -13.74514537774601, 4.645852612178535],
+        [0.3340998053353061, 1.384590162594685, 0.09509516302823659, -19.33244095627987, 56.69055260068105, -65.35303263337234, 26.3124352495832]
+      ].map(|c| polynomial(&c, t)),
+      Self::Turbo => [
+        [0.13572138, 4.61539260, -42.66032258, 132.13108234, -152.94239396, 59.28637943],
+        [0.09140261, 2.19418839, 4.84296658, -14.18503333, 4.27729857, 2.82956604],
+        [0.10667330, 12.64194608, -60.58204836, 110.36276771, -89.90310912, 27.34824973]
+      ].map(|c| polynomial(&c, t)),
+      Self::Diverging => {
+        let (blue, white, red) = ([0.23, 0.30, 0.75], [0.87, 0.87, 0.87], [0.71, 0.02, 0.15]);
+        let (a, b, t) = if t < 0.5 { (blue, white, t * 2.0) } else { (white, red, t * 2.0 - 1.0) };
+        [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+      }
+    };
+    let [r, g, b] = rgb.map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8);
+    Rgba([r, g, b, 255])
+  }
+}
+
+fn polynomial(coefficients: &[f64], t: f64) -> f64 {
+  coefficients.iter().rev().fold(0.0, |acc, c| acc * t + c)
+}
+
+/// Options of [`display_sdf_with`](super::display_sdf_with).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SdfDisplay {
+  pub colormap: Colormap,
+  /// Distances mapped to the ends of the colormap; others are clamped
+  pub range: [f64; 2],
+  /// Draw iso-contours at multiples of this distance
+  pub contour_interval: Option<f64>,
+  /// Draw the zero iso-contour in red
+  pub zero_contour: bool
+}
+
+impl Default for SdfDisplay {
+  fn default() -> Self {
+    Self { colormap: Colormap::Grayscale, range: [0.0, 1.0], contour_interval: None, zero_contour: true }
+  }
+}
+
+impl SdfDisplay {
+  /// Diverging colormap, symmetric around the zero iso-contour.
+  pub fn signed(max_dist: f64) -> Self {
+    Self { colormap: Colormap::Diverging, range: [-max_dist, max_dist], ..Default::default() }
+  }
+
+  pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+    self.colormap = colormap;
+    self
+  }
+
+  pub fn with_range(mut self, min: f64, max: f64) -> Self {
+    self.range = [min, max];
+    self
+  }
+
+  pub fn with_contour_interval(mut self, interval: f64) -> Self {
+    self.contour_interval = Some(interval);
+    self
+  }
+
+  pub fn with_zero_contour(mut self, zero_contour: bool) -> Self {
+    self.zero_contour = zero_contour;
+    self
+  }
+
+  /// Position of `distance` along the colormap.
+  pub(crate) fn normalize(&self, distance: f64) -> f64 {
+    let [min, max] = self.range;
+    (distance - min) / (max - min)
+  }
+}
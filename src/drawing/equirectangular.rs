@@ -0,0 +1,59 @@
+use {
+  std::f64::consts::PI,
+  euclid::Point2D,
+  image::{Pixel, Rgba, RgbaImage},
+  crate::solver::sphere::{self, Cap, S2}
+};
+
+/// Raster backend of the sphere, see [`solver::sphere`](crate::solver::sphere): the
+/// equirectangular projection of it, `2 height × height` pixels, e.g. a planet texture.
+/// Antialiased by the SDF, in pixels of the latitude.
+#[derive(Debug, Clone)]
+pub struct Equirectangular {
+  pub image: RgbaImage
+}
+
+impl Equirectangular {
+  /// A transparent canvas.
+  pub fn new(height: u32) -> Self {
+    Self { image: RgbaImage::new(2 * height, height) }
+  }
+
+  /// Draw a shape of the sphere, given by its `sdf`, in radians, lying within `bound`, over
+  /// the canvas.
+  pub fn draw_sdf(&mut self, sdf: impl Fn(S2<f64>) -> f64 + Sync, bound: Cap<f64>, color: Rgba<u8>) -> &mut Self {
+    use rayon::prelude::*;
+
+    let (width, height) = self.image.dimensions();
+    if width == 0 || height == 0 {
+      return self;
+    }
+    let pixel_size = PI / height as f64;
+    let bound = Cap::new(bound.center, bound.radius + pixel_size);
+    let (latitude, _) = sphere::to_lat_long(bound.center);
+    // rows, within the latitudes of the bound
+    let row_of = |latitude: f64| ((0.5 - latitude / PI) * height as f64).clamp(0.0, height as f64);
+    let rows = row_of(latitude + bound.radius).floor() as usize..row_of(latitude - bound.radius).ceil() as usize;
+    self.image.par_chunks_mut(4 * width as usize)
+      .enumerate()
+      .filter(|(y, _)| rows.contains(y))
+      .for_each(|(y, row)| row.chunks_mut(4).enumerate().for_each(|(x, pixel)| {
+        let uv = Point2D::new((x as f64 + 0.5) / width as f64, (y as f64 + 0.5) / height as f64);
+        let p = sphere::from_equirectangular(uv);
+        if bound.sdf(p) > 0.0 {
+          return;
+        }
+        let coverage = (0.5 - sdf(p) / pixel_size).clamp(0.0, 1.0);
+        if coverage > 0.0 {
+          let mut color = color;
+          color.0[3] = (color.0[3] as f64 * coverage).round() as u8;
+          Rgba::from_slice_mut(pixel).blend(&color);
+        }
+      }));
+    self
+  }
+
+  pub fn draw_cap(&mut self, cap: Cap<f64>, color: Rgba<u8>) -> &mut Self {
+    self.draw_sdf(|p| cap.sdf(p), cap, color)
+  }
+}
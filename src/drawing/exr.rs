@@ -0,0 +1,81 @@
+//! Minimal OpenEXR encoder: single part, scanlines, uncompressed, 32-bit float channels.
+
+use {
+  std::{io::Write, ops::Deref},
+  image::{ImageBuffer, Pixel, Primitive},
+  num_traits::ToPrimitive
+};
+
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+/// Write `image` as OpenEXR, with channels normalized to `[0, 1]` (floating-point channels are
+/// written as is). EXR is conventionally linear light, hence the render should be linear as
+/// well, e.g. an `Rgba32FImage`, rather than sRGB encoded `u8` or `u16`.
+pub fn write_exr<Px, C>(mut writer: impl Write, image: &ImageBuffer<Px, C>) -> anyhow::Result<()>
+  where Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]>
+{
+  let names: &[&str] = match Px::COLOR_MODEL {
+    "RGBA" => &["R", "G", "B", "A"],
+    "RGB" => &["R", "G", "B"],
+    "YA" => &["Y", "A"],
+    "Y" => &["Y"],
+    model => anyhow::bail!("unsupported color model {model}")
+  };
+  // channels are stored in alphabetical order
+  let mut channels: Vec<_> = names.iter().copied().enumerate().collect();
+  channels.sort_by_key(|&(_, name)| name);
+  let (width, height) = image.dimensions();
+  let max = Px::Subpixel::DEFAULT_MAX_VALUE.to_f32().unwrap();
+
+  let mut header = vec![];
+  header.extend([0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+  let mut chlist = vec![];
+  channels.iter().for_each(|(_, name)| {
+    chlist.extend(name.as_bytes());
+    chlist.push(0);
+    chlist.extend(PIXEL_TYPE_FLOAT.to_le_bytes());
+    chlist.extend([0; 4]); // pLinear, reserved
+    chlist.extend([1i32, 1].map(i32::to_le_bytes).concat()); // sampling
+  });
+  chlist.push(0);
+  let window = [0, 0, width as i32 - 1, height as i32 - 1].map(i32::to_le_bytes).concat();
+  attribute(&mut header, "channels", "chlist", &chlist);
+  attribute(&mut header, "compression", "compression", &[0]);
+  attribute(&mut header, "dataWindow", "box2i", &window);
+  attribute(&mut header, "displayWindow", "box2i", &window);
+  attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+  attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+  attribute(&mut header, "screenWindowCenter", "v2f", &[0f32, 0.0].map(f32::to_le_bytes).concat());
+  attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+  header.push(0);
+
+  let line_size = width as usize * channels.len() * 4;
+  let chunk_size = 8 + line_size;
+  let first_chunk = header.len() + height as usize * 8;
+  (0..height as usize).for_each(|y| header.extend(((first_chunk + y * chunk_size) as u64).to_le_bytes()));
+  writer.write_all(&header)?;
+
+  let mut chunk = Vec::with_capacity(chunk_size);
+  for (y, row) in image.rows().enumerate() {
+    chunk.clear();
+    chunk.extend((y as i32).to_le_bytes());
+    chunk.extend((line_size as i32).to_le_bytes());
+    let row: Vec<_> = row.collect();
+    channels.iter().for_each(|&(channel, _)| row.iter().for_each(|px| {
+      let value = px.channels()[channel].to_f32().unwrap() / max;
+      chunk.extend(value.to_le_bytes());
+    }));
+    writer.write_all(&chunk)?;
+  }
+  Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+  header.extend(name.as_bytes());
+  header.push(0);
+  header.extend(kind.as_bytes());
+  header.push(0);
+  header.extend((value.len() as i32).to_le_bytes());
+  header.extend(value);
+}
@@ -0,0 +1,190 @@
+use {
+  euclid::{Size2D, Vector2D as V2},
+  num_traits::{Float, AsPrimitive, FloatConst},
+  crate::{
+    drawing::vector::{Outline, VectorShape, contours},
+    geometry::{Shape, P2}
+  }
+};
+
+// rings, the first one is the exterior, the rest are holes
+type Polygon = Vec<Vec<P2<f64>>>;
+
+/// Polygons of shapes, in world coordinates, for GIS and laser-cutter toolchains: a GeoJSON
+/// `FeatureCollection`, or WKT, with a feature per shape. Polygon outlines are kept as they
+/// are, circles and segments are approximated by polygons of `segments` sides, and shapes
+/// without a [`VectorShape::outline`] are traced along the zero iso-contour of their SDF, as
+/// in [`SvgBackend`](super::SvgBackend). Exterior rings have a positive signed area, holes a
+/// negative one; `y` points down, as in the rest of the world.
+#[derive(Debug, Clone)]
+pub struct GeoExport {
+  /// Sides of the polygons, approximating a circle
+  pub segments: u32,
+  /// Cells of the iso-contour tracing grid per unit of world
+  pub contour_density: f64,
+  shapes: Vec<Vec<Polygon>>
+}
+
+impl Default for GeoExport {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl GeoExport {
+  pub fn new() -> Self {
+    Self { segments: 64, contour_density: 512.0, shapes: vec![] }
+  }
+
+  pub fn with_segments(mut self, segments: u32) -> Self {
+    self.segments = segments.max(3);
+    self
+  }
+
+  pub fn with_contour_density(mut self, contour_density: f64) -> Self {
+    self.contour_density = contour_density;
+    self
+  }
+
+  pub fn len(&self) -> usize {
+    self.shapes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.shapes.is_empty()
+  }
+
+  /// Add a placed shape; empty ones are skipped.
+  pub fn push<P>(&mut self, shape: &(impl Shape<P> + VectorShape<P>)) -> &mut Self
+    where P: Float + AsPrimitive<f64>
+  {
+    let polygons = match shape.outline() {
+      Some(outline) => vec![vec![self.ring(outline.cast())]],
+      None => {
+        let bounding_box = shape.bounding_box().to_f64();
+        let cells = (bounding_box.size() * self.contour_density).ceil()
+          .clamp(Size2D::splat(4.0), Size2D::splat(4096.0))
+          .to_usize();
+        nest(contours(|p| shape.sdf(p.cast()).as_(), bounding_box, cells))
+      }
+    };
+    let polygons: Vec<Polygon> = polygons.into_iter()
+      .map(|rings| rings.into_iter().filter(|ring| ring.len() >= 3).collect::<Polygon>())
+      .filter(|rings| !rings.is_empty())
+      .collect();
+    if !polygons.is_empty() {
+      self.shapes.push(polygons);
+    }
+    self
+  }
+
+  // vertices of an outline, counter-clockwise
+  fn ring(&self, outline: Outline<f64>) -> Vec<P2<f64>> {
+    let n = self.segments;
+    let arc = |center: P2<f64>, radius: f64, from: f64, steps: u32| (0..steps).map(move |k| {
+      let angle = from + f64::TAU() * k as f64 / n as f64;
+      center + V2::new(angle.cos(), angle.sin()) * radius
+    });
+    let ring: Vec<_> = match outline {
+      Outline::Circle { center, radius } => arc(center, radius, 0.0, n).collect(),
+      Outline::Polygon(vertices) => vertices,
+      // two half circles, around the ends of the segment
+      Outline::Line { a, b, thickness } => {
+        let from = (b - a).angle_from_x_axis().radians - f64::FRAC_PI_2();
+        arc(b, thickness / 2.0, from, n / 2 + 1)
+          .chain(arc(a, thickness / 2.0, from + f64::PI(), n / 2 + 1))
+          .collect()
+      }
+    };
+    if signed_area(&ring) < 0.0 { ring.into_iter().rev().collect() } else { ring }
+  }
+
+  /// GeoJSON `FeatureCollection`, of a `Polygon` or a `MultiPolygon` per shape.
+  pub fn to_geojson(&self) -> String {
+    let ring = |ring: &Vec<P2<f64>>| {
+      let points: Vec<_> = closed(ring).map(|p| format!("[{},{}]", p.x, p.y)).collect();
+      format!("[{}]", points.join(","))
+    };
+    let polygon = |polygon: &Polygon| format!("[{}]", polygon.iter().map(ring).collect::<Vec<_>>().join(","));
+    let features: Vec<_> = self.shapes.iter().map(|polygons| {
+      let geometry = match &polygons[..] {
+        [single] => format!(r#"{{"type":"Polygon","coordinates":{}}}"#, polygon(single)),
+        polygons => format!(
+          r#"{{"type":"MultiPolygon","coordinates":[{}]}}"#,
+          polygons.iter().map(polygon).collect::<Vec<_>>().join(",")
+        )
+      };
+      format!(r#"{{"type":"Feature","properties":{{}},"geometry":{geometry}}}"#)
+    }).collect();
+    format!(r#"{{"type":"FeatureCollection","features":[{}]}}"#, features.join(","))
+  }
+
+  /// WKT, a `POLYGON` or a `MULTIPOLYGON` per shape, one per line.
+  pub fn to_wkt(&self) -> String {
+    let ring = |ring: &Vec<P2<f64>>| {
+      let points: Vec<_> = closed(ring).map(|p| format!("{} {}", p.x, p.y)).collect();
+      format!("({})", points.join(", "))
+    };
+    let polygon = |polygon: &Polygon| format!("({})", polygon.iter().map(ring).collect::<Vec<_>>().join(", "));
+    self.shapes.iter()
+      .map(|polygons| match &polygons[..] {
+        [single] => format!("POLYGON {}\n", polygon(single)),
+        polygons => format!("MULTIPOLYGON ({})\n", polygons.iter().map(polygon).collect::<Vec<_>>().join(", "))
+      })
+      .collect()
+  }
+
+  pub fn save_geojson(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+    std::fs::write(path, self.to_geojson())?;
+    Ok(())
+  }
+
+  pub fn save_wkt(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+    std::fs::write(path, self.to_wkt())?;
+    Ok(())
+  }
+}
+
+// the ring, repeating the first vertex at the end
+fn closed(ring: &[P2<f64>]) -> impl Iterator<Item = &P2<f64>> {
+  ring.iter().chain(ring.first())
+}
+
+fn signed_area(ring: &[P2<f64>]) -> f64 {
+  (0..ring.len())
+    .map(|i| ring[i].to_vector().cross(ring[(i + 1) % ring.len()].to_vector()))
+    .sum::<f64>() / 2.0
+}
+
+fn contains(ring: &[P2<f64>], p: P2<f64>) -> bool {
+  let mut inside = false;
+  (0..ring.len()).for_each(|i| {
+    let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+    if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+      inside = !inside;
+    }
+  });
+  inside
+}
+
+// polygons of traced contours, filled by the even-odd rule: rings nested in an even number of
+// others are exteriors, the rest are holes of the innermost exterior around them
+fn nest(rings: Vec<Vec<P2<f64>>>) -> Vec<Polygon> {
+  let parents: Vec<Vec<usize>> = rings.iter().enumerate()
+    .map(|(i, ring)| (0..rings.len())
+      .filter(|&j| j != i && ring.first().is_some_and(|&p| contains(&rings[j], p)))
+      .collect())
+    .collect();
+  let exterior = |i: usize| parents[i].len().is_multiple_of(2);
+  let oriented = |i: usize, positive: bool| {
+    let ring = rings[i].clone();
+    if (signed_area(&ring) > 0.0) == positive { ring } else { ring.into_iter().rev().collect() }
+  };
+  (0..rings.len()).filter(|&i| exterior(i))
+    .map(|i| std::iter::once(oriented(i, true))
+      .chain((0..rings.len())
+        .filter(|&j| !exterior(j) && parents[j].len() == parents[i].len() + 1 && parents[j].contains(&i))
+        .map(|j| oriented(j, false)))
+      .collect())
+    .collect()
+}
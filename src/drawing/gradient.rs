@@ -0,0 +1,88 @@
+//! Gradient fills for [`Texture`](super::Texture), in normalized coordinates of the bounding
+//! box of a shape: `(0, 0)` is its top left corner, `(1, 1)` the bottom right one.
+
+use {
+  euclid::Box2D,
+  image::{Pixel, Primitive},
+  num_traits::{NumCast, ToPrimitive},
+  crate::geometry::{P2, WorldSpace}
+};
+
+/// Colors at positions along a gradient, ascending; interpolated linearly between the stops,
+/// and clamped beyond the first and the last one. Must not be empty.
+pub type ColorStops<Px> = Vec<(f64, Px)>;
+
+/// Colored along the axis from `start` to `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearGradient<Px> {
+  pub start: P2<f64>,
+  pub end: P2<f64>,
+  pub stops: ColorStops<Px>
+}
+
+/// Colored by the distance from `center`, reaching the last stop at `radius`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradient<Px> {
+  pub center: P2<f64>,
+  pub radius: f64,
+  pub stops: ColorStops<Px>
+}
+
+/// Colored by the distance to the edge of the shape, i.e. along its iso-contours; the first
+/// stop is the edge, and `depth` is relative to half of the shorter side of the bounding box,
+/// so that 1 reaches the center of a circle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdfGradient<Px> {
+  pub depth: f64,
+  pub stops: ColorStops<Px>
+}
+
+pub(crate) trait Gradient<Px> {
+  /// Color at `p`, given the bounding box of the shape, and its `sdf` at `p`.
+  fn sample(&self, p: P2<f64>, bounding_box: Box2D<f64, WorldSpace>, sdf: f64) -> Px;
+}
+
+fn normalize(p: P2<f64>, bounding_box: Box2D<f64, WorldSpace>) -> P2<f64> {
+  let size = bounding_box.size().to_vector();
+  (p - bounding_box.min).component_div(size).to_point()
+}
+
+impl<Px: Pixel> Gradient<Px> for LinearGradient<Px> {
+  fn sample(&self, p: P2<f64>, bounding_box: Box2D<f64, WorldSpace>, _: f64) -> Px {
+    let axis = self.end - self.start;
+    let t = (normalize(p, bounding_box) - self.start).dot(axis) / axis.square_length();
+    interpolate(&self.stops, t)
+  }
+}
+
+impl<Px: Pixel> Gradient<Px> for RadialGradient<Px> {
+  fn sample(&self, p: P2<f64>, bounding_box: Box2D<f64, WorldSpace>, _: f64) -> Px {
+    let t = (normalize(p, bounding_box) - self.center).length() / self.radius;
+    interpolate(&self.stops, t)
+  }
+}
+
+impl<Px: Pixel> Gradient<Px> for SdfGradient<Px> {
+  fn sample(&self, _: P2<f64>, bounding_box: Box2D<f64, WorldSpace>, sdf: f64) -> Px {
+    let size = bounding_box.size();
+    let t = -sdf / (self.depth * size.width.min(size.height) / 2.0);
+    interpolate(&self.stops, t)
+  }
+}
+
+// channel-wise linear interpolation of the stops at `t`
+pub(crate) fn interpolate<Px: Pixel>(stops: &[(f64, Px)], t: f64) -> Px {
+  let (first, last) = match (stops.first(), stops.last()) {
+    (Some(first), Some(last)) => (first, last),
+    _ => panic!("gradient has no color stops")
+  };
+  if t.is_nan() || t <= first.0 { return first.1; }
+  if t >= last.0 { return last.1; }
+  let i = stops.partition_point(|&(x, _)| x <= t);
+  let ((x0, c0), (x1, c1)) = (stops[i - 1], stops[i]);
+  let k = (t - x0) / (x1 - x0);
+  c0.map2(&c1, |a, b| {
+    let (a, b) = (a.to_f64().unwrap(), b.to_f64().unwrap());
+    <Px::Subpixel as NumCast>::from(a + (b - a) * k).unwrap_or(Px::Subpixel::DEFAULT_MAX_VALUE)
+  })
+}
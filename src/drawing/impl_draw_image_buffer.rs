@@ -0,0 +1,535 @@
+#![allow(non_snake_case)]
+
+use num_traits::Float;
+use {
+  std::{sync::Arc, ops::{Deref, DerefMut, Fn}},
+  euclid::{Point2D, Rect, Size2D, Box2D, Vector2D as V2},
+  image::{
+    DynamicImage, GenericImageView, ImageBuffer, Pixel, Primitive, Luma, LumaA, Rgb, Rgba, RgbaImage,
+    imageops::FilterType
+  },
+  num_traits::{NumCast, AsPrimitive, ToPrimitive},
+  crate::{
+    drawing::{
+      BlendMode, Cmyk, Draw, DrawTile, DrawViewport, RenderOptions, Rendered, Shaded, Shape, Texture, Tile, Viewport, rescale_bounding_box,
+      gradient::{Gradient, LinearGradient, RadialGradient, SdfGradient},
+      texture_cache::CachedImage
+    },
+    geometry::{BoundingBox, PixelSpace, WorldSpace},
+    sdf::SDF
+  }
+};
+
+impl<Ty, P> SDF<P> for Ty where Ty: AsRef<dyn Draw<P, RgbaImage>> {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P { self.as_ref().sdf(pixel) }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> where P: Float { self.as_ref().gradient(pixel) } }
+impl<Ty, P> BoundingBox<P> for Ty where Ty: AsRef<dyn Draw<P, RgbaImage>> { fn bounding_box(&self) -> Box2D<P, WorldSpace> { self.as_ref().bounding_box() } }
+
+// every texture renders tiles, the whole image being a tile of itself
+impl <Cutie, T, P, Px, C> DrawViewport<P, ImageBuffer<Px, C>> for Texture<Cutie, T>
+  where Texture<Cutie, T>: DrawTile<P, ImageBuffer<Px, C>>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]>
+{
+  fn draw_viewport(&self, image: &mut ImageBuffer<Px, C>, viewport: Viewport) {
+    self.draw_tile(whole(image).with_viewport(viewport));
+  }
+}
+
+impl <Cutie, T, P, Px, C> Draw<P, ImageBuffer<Px, C>> for Texture<Cutie, T>
+  where Texture<Cutie, T>: DrawTile<P, ImageBuffer<Px, C>>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]>
+{
+  fn draw(&self, image: &mut ImageBuffer<Px, C>) {
+    let viewport = Viewport::fit(image.dimensions().into());
+    self.draw_viewport(image, viewport);
+  }
+}
+
+// a solid color, for every pixel type of `image`
+macro_rules! impl_draw_color {
+  ($($px: ty),*) => { $(
+    impl <Cutie, P: Float, C> DrawTile<P, ImageBuffer<$px, C>> for Texture<Cutie, $px>
+      where Cutie: Shape<P>,
+            P: NumCast + AsPrimitive<f64>,
+            C: Deref<Target = [<$px as Pixel>::Subpixel]> + DerefMut
+    {
+      fn draw_tile(&self, tile: Tile<&mut ImageBuffer<$px, C>>) {
+        draw_fn(&self.shape, &|_| self.texture, None, tile);
+      }
+    }
+
+    impl <Cutie, P: Float, C> DrawTile<P, ImageBuffer<$px, C>> for Texture<Cutie, Rendered<$px>>
+      where Cutie: Shape<P>,
+            P: NumCast + AsPrimitive<f64>,
+            C: Deref<Target = [<$px as Pixel>::Subpixel]> + DerefMut
+    {
+      fn draw_tile(&self, tile: Tile<&mut ImageBuffer<$px, C>>) {
+        draw_fn(&self.shape, &|_| self.texture.texture, Some(self.texture.options), tile);
+      }
+    }
+  )* };
+}
+
+impl_draw_color!(
+  Rgba<u8>, Rgba<u16>, Rgba<f32>,
+  Rgb<u8>, Rgb<u16>, Rgb<f32>,
+  LumaA<u8>, LumaA<u16>, LumaA<f32>,
+  Luma<u8>, Luma<u16>, Luma<f32>
+);
+
+// a gradient of colors of the pixel type of `image`
+macro_rules! impl_draw_gradient {
+  ($($gradient: ident),*) => { $(
+    impl <Cutie, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, $gradient<Px>>
+      where Cutie: Shape<P>,
+            P: Float + AsPrimitive<f64>,
+            Px: Pixel,
+            C: Deref<Target = [Px::Subpixel]> + DerefMut
+    {
+      fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+        draw_gradient(&self.shape, &self.texture, None, tile);
+      }
+    }
+
+    impl <Cutie, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, Rendered<$gradient<Px>>>
+      where Cutie: Shape<P>,
+            P: Float + AsPrimitive<f64>,
+            Px: Pixel,
+            C: Deref<Target = [Px::Subpixel]> + DerefMut
+    {
+      fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+        draw_gradient(&self.shape, &self.texture.texture, Some(self.texture.options), tile);
+      }
+    }
+  )* };
+}
+
+impl_draw_gradient!(LinearGradient, RadialGradient, SdfGradient);
+
+fn draw_gradient<P, Px, C>(
+  shape: &impl Shape<P>,
+  gradient: &impl Gradient<Px>,
+  options: Option<RenderOptions>,
+  tile: Tile<&mut ImageBuffer<Px, C>>
+) where P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  let bounding_box = shape.bounding_box().to_f64();
+  draw_fn(shape, &|fragment| gradient.sample(fragment.p, bounding_box, fragment.sdf), options, tile);
+}
+
+impl <Cutie, P: Float> DrawTile<P, RgbaImage> for Texture<Cutie, Cmyk>
+  where Cutie: Shape<P> + Clone,
+        P: NumCast + AsPrimitive<f64>
+{
+  fn draw_tile(&self, tile: Tile<&mut RgbaImage>) {
+    self.shape.clone()
+      .texture(self.texture.to_rgba())
+      .draw_tile(tile);
+  }
+}
+
+impl <Cutie, P> DrawTile<P, RgbaImage> for Texture<Cutie, &DynamicImage>
+  where Cutie: Shape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw_tile(&self, tile: Tile<&mut RgbaImage>) {
+    draw_image(&self.shape, self.texture, resize(self.texture), tile);
+  }
+}
+
+/// See [`TextureCache`](crate::drawing::TextureCache).
+impl <Cutie, P> DrawTile<P, RgbaImage> for Texture<Cutie, CachedImage>
+  where Cutie: Shape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw_tile(&self, tile: Tile<&mut RgbaImage>) {
+    draw_image(&self.shape, &self.texture.image, resize_cached(&self.texture), tile);
+  }
+}
+
+fn resize(texture: &DynamicImage) -> impl FnOnce(Size2D<u32, PixelSpace>) -> Arc<RgbaImage> + '_ {
+  |size| Arc::new(rescale_texture(texture, size).to_rgba8())
+}
+
+fn resize_cached(texture: &CachedImage) -> impl FnOnce(Size2D<u32, PixelSpace>) -> Arc<RgbaImage> + '_ {
+  |size| texture.cache.get(&texture.image, size, |image, size| rescale_texture(image, size).to_rgba8())
+}
+
+// `resize` covers the bounding box with the texture, at least as large as the given size
+fn draw_image<P>(
+  shape: &impl Shape<P>,
+  texture: &DynamicImage,
+  resize: impl FnOnce(Size2D<u32, PixelSpace>) -> Arc<RgbaImage>,
+  tile: Tile<&mut RgbaImage>
+) where P: Float + AsPrimitive<f64>
+{
+  let (width, height) = texture.dimensions();
+  // nothing to sample
+  if width == 0 || height == 0 { return; }
+  let Tile { image, origin, canvas, viewport } = tile;
+  let (bounding_box, offset, scale) =
+    rescale_bounding_box(shape.bounding_box().to_f64(), viewport, canvas);
+  let bounding_box = match bounding_box {
+    Some(x) => x,
+    None => return
+  };
+  if is_subpixel(shape.bounding_box().to_f64(), scale) {
+    let color = texture.get_pixel(width / 2, height / 2);
+    draw_subpixel(shape, Tile { image, origin, canvas, viewport }, offset, scale, color, overlay);
+    return;
+  }
+  let Some(region) = bounding_box.intersection(&tile_region(image, origin)) else { return };
+  let Δp = 1.0 / scale;
+  let tex = resize(bounding_box.size());
+  let tex_scale = Size2D::<_, PixelSpace>::from(tex.dimensions()).to_f64().to_vector()
+    .component_div(bounding_box.size().to_f64().to_vector());
+
+  itertools::iproduct!(region.y_range(), region.x_range())
+    .map(|(y, x)| Point2D::<_, PixelSpace>::from([x, y]))
+    .for_each(|pixel| {
+      let pixel_world = ((pixel.to_f64() - offset).to_vector() / scale)
+        .cast_unit().to_point();
+      let tex_px = ((pixel - bounding_box.min).to_f64() + V2::splat(0.5)).component_mul(tex_scale);
+      let tex_px = *tex.get_pixel(
+        (tex_px.x as u32).min(tex.width() - 1),
+        (tex_px.y as u32).min(tex.height() - 1)
+      );
+
+      let sdf = shape.sdf(pixel_world.cast::<P>()).as_();
+      let pixel = image.get_pixel_mut(pixel.x - origin.x, pixel.y - origin.y);
+      *pixel = sdf_overlay_aa(sdf, Δp, *pixel, tex_px);
+    });
+}
+
+/// `F: Fn(v: Point2D) -> Px`
+/// where `v` is in normalized texture coordinates, and `Px` is the pixel type of `image`.
+impl <Cutie, F, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, F>
+  where Cutie: Shape<P>,
+        F: Fn(Point2D<P, WorldSpace>) -> Px,
+        P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+    draw_fn(&self.shape, &|fragment| (self.texture)(fragment.uv), None, tile);
+  }
+}
+
+impl <Cutie, F, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, Rendered<F>>
+  where Cutie: Shape<P>,
+        F: Fn(Point2D<P, WorldSpace>) -> Px,
+        P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+    draw_fn(&self.shape, &|fragment| (self.texture.texture)(fragment.uv), Some(self.texture.options), tile);
+  }
+}
+
+/// `F: Fn(v: Point2D, sdf: P, gradient: Vector2D) -> Px`
+/// where `v` is in normalized texture coordinates, `sdf` and its `gradient` are sampled at the
+/// pixel, and `Px` is the pixel type of `image`. See [`Shape::texture_shaded`].
+impl <Cutie, F, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, Shaded<F>>
+  where Cutie: Shape<P>,
+        F: Fn(Point2D<P, WorldSpace>, P, V2<P, WorldSpace>) -> Px,
+        P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+    draw_shaded(&self.shape, &self.texture.0, None, tile);
+  }
+}
+
+impl <Cutie, F, P, Px, C> DrawTile<P, ImageBuffer<Px, C>> for Texture<Cutie, Rendered<Shaded<F>>>
+  where Cutie: Shape<P>,
+        F: Fn(Point2D<P, WorldSpace>, P, V2<P, WorldSpace>) -> Px,
+        P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  fn draw_tile(&self, tile: Tile<&mut ImageBuffer<Px, C>>) {
+    draw_shaded(&self.shape, &self.texture.texture.0, Some(self.texture.options), tile);
+  }
+}
+
+// the gradient by `SDF::gradient`, analytic for most of the shapes
+fn draw_shaded<P, Px, C>(
+  shape: &impl Shape<P>,
+  shader: &impl Fn(Point2D<P, WorldSpace>, P, V2<P, WorldSpace>) -> Px,
+  options: Option<RenderOptions>,
+  tile: Tile<&mut ImageBuffer<Px, C>>
+) where P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  draw_fn(shape, &|fragment| {
+    let Fragment { uv, p, sdf, .. } = *fragment;
+    shader(uv, P::from(sdf).unwrap(), shape.gradient(p.cast()))
+  }, options, tile);
+}
+
+// a pixel, as seen by the texture
+#[derive(Debug, Copy, Clone)]
+struct Fragment<P> {
+  // normalized texture coordinates
+  uv: Point2D<P, WorldSpace>,
+  // world coordinates
+  p: Point2D<f64, WorldSpace>,
+  sdf: f64
+}
+
+// the shape, colored by `texture` of a fragment, rendered with `options`, or overlaid if `None`
+fn draw_fn<P, Px, C>(
+  shape: &impl Shape<P>,
+  texture: &impl Fn(&Fragment<P>) -> Px,
+  options: Option<RenderOptions>,
+  tile: Tile<&mut ImageBuffer<Px, C>>
+) where P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  let Tile { image, origin, canvas, viewport } = tile;
+  let (bounding_box, offset, scale) =
+    rescale_bounding_box(shape.bounding_box().to_f64(), viewport, canvas);
+  let bounding_box = match bounding_box {
+    Some(x) => x,
+    None => return // bounding box has no intersection with screen at all
+  };
+  let blend = |col1, col2, coverage| match options {
+    Some(options) => composite(col1, col2, coverage, options),
+    None => overlay(col1, col2, coverage)
+  };
+  if is_subpixel(shape.bounding_box().to_f64(), scale) {
+    let p = shape.bounding_box().to_f64().center();
+    let color = texture(&Fragment {
+      uv: Point2D::splat(P::one() / (P::one() + P::one())),
+      p,
+      sdf: shape.sdf(p.cast::<P>()).as_()
+    });
+    draw_subpixel(shape, Tile { image, origin, canvas, viewport }, offset, scale, color, blend);
+    return;
+  }
+  let Some(region) = bounding_box.intersection(&tile_region(image, origin)) else { return };
+  let n = options.map_or(1, |options| options.samples);
+  let Δp = 1.0 / scale;
+  let tex_scale = bounding_box.size().width.min(bounding_box.size().height) as f64;
+  let sdf = |p: Point2D<f64, WorldSpace>| shape.sdf(p.cast::<P>()).as_();
+
+  itertools::iproduct!(region.y_range(), region.x_range())
+    .map(|(y, x)| Point2D::<_, PixelSpace>::from([x, y]))
+    .for_each(|pixel| {
+      let pixel_world = ((pixel.to_f64() - offset).to_vector() / scale)
+        .cast_unit().to_point();
+      let d = sdf(pixel_world);
+      // otherwise, the pixel is entirely inside or outside
+      if n > 1 && d.abs() < Δp {
+        let inside = itertools::iproduct!(0..n, 0..n)
+          .filter(|&(x, y)| {
+            let subpixel = V2::new(x as f64 + 0.5, y as f64 + 0.5) / n as f64 - V2::splat(0.5);
+            sdf(pixel_world + subpixel * Δp) <= 0.0
+          })
+          .count();
+        if inside == 0 { return; }
+        let coverage = inside as f64 / (n * n) as f64;
+        let uv = ((pixel - bounding_box.min.to_vector()).to_f64() / tex_scale).cast_unit();
+        let tex_px = texture(&Fragment { uv: uv.cast::<P>(), p: pixel_world, sdf: d });
+        let pixel = image.get_pixel_mut(pixel.x - origin.x, pixel.y - origin.y);
+        *pixel = blend(*pixel, tex_px, coverage);
+        return;
+      }
+
+      let uv = ((pixel - bounding_box.min.to_vector()).to_f64() / tex_scale).cast_unit();
+      let tex_px = texture(&Fragment { uv: uv.cast::<P>(), p: pixel_world, sdf: d });
+
+      let pixel = image.get_pixel_mut(pixel.x - origin.x, pixel.y - origin.y);
+      *pixel = blend(*pixel, tex_px, sdf_coverage_aa(d, Δp));
+    });
+}
+
+impl <Cutie, P> DrawTile<P, RgbaImage> for Texture<Cutie, DynamicImage>
+  where Cutie: Shape<P> + Clone,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw_tile(&self, tile: Tile<&mut RgbaImage>) {
+    Texture {
+      shape: self.shape.clone(),
+      texture: &self.texture
+    }.draw_tile(tile)
+  }
+}
+
+impl <Cutie, P> DrawTile<P, RgbaImage> for Texture<Cutie, Arc<DynamicImage>>
+  where Cutie: Shape<P> + Clone,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw_tile(&self, tile: Tile<&mut RgbaImage>) {
+    Texture {
+      shape: self.shape.clone(),
+      texture: self.texture.as_ref()
+    }.draw_tile(tile)
+  }
+}
+
+// resize the image to cover the entire container,
+// even if it has to cut off one of the edges
+fn rescale_texture(texture: &DynamicImage, size: Size2D<u32, PixelSpace>) -> DynamicImage {
+  let tex_size = Size2D::from(texture.dimensions()).to_f32();
+  let scaling_factor = tex_size.to_vector()
+    .component_div(size.to_f32().to_vector());
+  let scaling_factor = scaling_factor.x.min(scaling_factor.y);
+  let bound_inner = size.to_f32() * scaling_factor;
+  let bound_inner = Rect::new(
+    ((tex_size - bound_inner) / 2.0).to_vector().to_point(),
+    bound_inner
+  ).to_u32();
+  texture.crop_imm(
+    bound_inner.origin.x,
+    bound_inner.origin.y,
+    bound_inner.size.width,
+    bound_inner.size.height
+  ).resize_exact(size.width, size.height, FilterType::Triangle)
+}
+
+// the whole image, as a tile of itself
+fn whole<Px, C>(image: &mut ImageBuffer<Px, C>) -> Tile<&mut ImageBuffer<Px, C>>
+  where Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]>
+{
+  let canvas = image.dimensions().into();
+  Tile { image, origin: Point2D::origin(), canvas, viewport: Viewport::fit(canvas) }
+}
+
+// pixels of the canvas, covered by `image`
+fn tile_region<Px, C>(image: &ImageBuffer<Px, C>, origin: Point2D<u32, PixelSpace>) -> Box2D<u32, PixelSpace>
+  where Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]>
+{
+  Box2D::new(origin, origin + Size2D::from(image.dimensions()))
+}
+
+fn is_subpixel(bounding_box: Box2D<f64, WorldSpace>, scale: f64) -> bool {
+  let size = bounding_box.size() * scale;
+  size.width <= 1.0 && size.height <= 1.0
+}
+
+// A shape smaller than one pixel is blended as a single pixel, with alpha given by the
+// fraction of the pixel it covers (estimated on a small lattice inside the bounding box).
+fn draw_subpixel<P, Px, C>(
+  shape: &impl Shape<P>,
+  tile: Tile<&mut ImageBuffer<Px, C>>,
+  offset: V2<f64, PixelSpace>,
+  scale: f64,
+  color: Px,
+  blend: impl Fn(Px, Px, f64) -> Px
+) where P: Float + AsPrimitive<f64>,
+        Px: Pixel,
+        C: Deref<Target = [Px::Subpixel]> + DerefMut
+{
+  const N: u32 = 4;
+  let bounding_box = shape.bounding_box().to_f64();
+  let size = bounding_box.size();
+  let inside = itertools::iproduct!(0..N, 0..N)
+    .filter(|&(x, y)| {
+      let p = bounding_box.min + V2::new(x as f64 + 0.5, y as f64 + 0.5)
+        .component_mul(size.to_vector()) / N as f64;
+      shape.sdf(p.cast::<P>()).as_() <= 0.0
+    })
+    .count();
+  let coverage = inside as f64 / (N * N) as f64 * size.area() * scale.powi(2);
+
+  let pixel = (bounding_box.center().to_vector() * scale).cast_unit() + offset;
+  let Tile { image, origin, .. } = tile;
+  let region = tile_region(image, origin).to_f64();
+  if pixel.x < region.min.x || pixel.y < region.min.y || pixel.x >= region.max.x || pixel.y >= region.max.y {
+    return;
+  }
+  let pixel = image.get_pixel_mut(pixel.x as u32 - origin.x, pixel.y as u32 - origin.y);
+  *pixel = blend(*pixel, color, coverage.min(1.0));
+}
+
+fn sdf_overlay_aa<Px: Pixel>(sdf: f64, Δp: f64, col1: Px, col2: Px) -> Px {
+  overlay(col1, col2, sdf_coverage_aa(sdf, Δp))
+}
+
+fn sdf_coverage_aa(sdf: f64, Δp: f64) -> f64 {
+  let Δf = (0.5 * Δp - sdf) // antialias
+    .clamp(0.0, Δp);
+  Δf / Δp
+}
+
+// overlay blending with premultiplied alpha; pixels without an alpha channel are interpolated
+fn overlay<Px: Pixel>(mut col1: Px, mut col2: Px, alpha: f64) -> Px {
+  let cast = |x: f64| <Px::Subpixel as NumCast>::from(x).unwrap_or(Px::Subpixel::DEFAULT_MAX_VALUE);
+  if matches!(Px::COLOR_MODEL, "RGBA" | "YA") {
+    let a = col2.channels_mut().last_mut().unwrap();
+    *a = cast(a.to_f64().unwrap() * alpha);
+    col1.blend(&col2);
+    col1
+  } else {
+    col1.map2(&col2, |a, b| {
+      let (a, b) = (a.to_f64().unwrap(), b.to_f64().unwrap());
+      cast(a + (b - a) * alpha)
+    })
+  }
+}
+
+// blend `col2` over `col1` (see `BlendMode`), and composite with premultiplied alpha, in
+// linear light if requested
+pub(crate) fn composite<Px: Pixel>(col1: Px, col2: Px, coverage: f64, options: RenderOptions) -> Px {
+  if options == (RenderOptions { samples: options.samples, ..Default::default() }) {
+    return overlay(col1, col2, coverage);
+  }
+  let max = Px::Subpixel::DEFAULT_MAX_VALUE.to_f64().unwrap();
+  let has_alpha = matches!(Px::COLOR_MODEL, "RGBA" | "YA");
+  let color_channels = Px::CHANNEL_COUNT as usize - has_alpha as usize;
+  let decode = |x: Px::Subpixel| {
+    let x = x.to_f64().unwrap() / max;
+    if options.linear_light { srgb_to_linear(x) } else { x }
+  };
+  // integer channels are rounded, so that decoding and encoding round-trips
+  let integer = max > 1.0;
+  let encode = |x: f64| {
+    let x = if options.linear_light { linear_to_srgb(x) } else { x };
+    let x = (x * max).max(0.0);
+    <Px::Subpixel as NumCast>::from(if integer { x.round() } else { x })
+      .unwrap_or(Px::Subpixel::DEFAULT_MAX_VALUE)
+  };
+  let (d, s) = (col1.channels(), col2.channels());
+  let alpha = |px: &[Px::Subpixel]| if has_alpha { px[color_channels].to_f64().unwrap() / max } else { 1.0 };
+  let a_s = alpha(s) * coverage;
+  let a_d = alpha(d);
+  let a_o = a_s + a_d * (1.0 - a_s);
+
+  let mut result = col1;
+  let out = result.channels_mut();
+  (0..color_channels).for_each(|i| {
+    let (c_d, c_s) = (decode(d[i]), decode(s[i]));
+    let blended = match options.blend {
+      BlendMode::Over => c_s,
+      BlendMode::Add => c_d + c_s,
+      BlendMode::Multiply => c_d * c_s,
+      BlendMode::Max => c_d.max(c_s)
+    };
+    let c_o = (1.0 - a_s) * a_d * c_d + a_s * ((1.0 - a_d) * c_s + a_d * blended);
+    out[i] = encode(if a_o > 0.0 { c_o / a_o } else { 0.0 });
+  });
+  if has_alpha {
+    let a_o = if integer { (a_o * max).round() } else { a_o * max };
+    out[color_channels] = <Px::Subpixel as NumCast>::from(a_o).unwrap_or(Px::Subpixel::DEFAULT_MAX_VALUE);
+  }
+  result
+}
+
+fn srgb_to_linear(x: f64) -> f64 {
+  if x <= 0.04045 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(x: f64) -> f64 {
+  if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1.0 / 2.4) - 0.055 }
+}
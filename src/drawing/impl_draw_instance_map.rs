@@ -0,0 +1,89 @@
+use {
+  euclid::{Point2D, Size2D},
+  image::{ImageBuffer, Luma},
+  num_traits::{Float, AsPrimitive},
+  crate::{
+    drawing::{Draw, DrawViewport, Texture, Viewport, rescale_bounding_box},
+    geometry::{PixelSpace, Shape}
+  }
+};
+
+/// Per pixel index of the topmost shape, instead of its color; for picking, post-processing
+/// of individual shapes, or their vector reconstruction. Shapes are drawn with an
+/// [`InstanceId`] as their texture, and cover the pixels where their SDF is non-positive,
+/// without antialiasing. Shapes smaller than a pixel claim the pixel of their center.
+#[derive(Debug, Clone)]
+pub struct InstanceMap {
+  pub ids: ImageBuffer<Luma<u32>, Vec<u32>>
+}
+
+/// Index of a shape, drawn into an [`InstanceMap`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceId(pub u32);
+
+impl InstanceMap {
+  /// Id of the pixels not covered by any shape.
+  pub const BACKGROUND: u32 = u32::MAX;
+
+  pub fn new(width: u32, height: u32) -> Self {
+    Self { ids: ImageBuffer::from_pixel(width, height, Luma([Self::BACKGROUND])) }
+  }
+
+  /// The shape at a pixel, if any.
+  pub fn get(&self, x: u32, y: u32) -> Option<InstanceId> {
+    let id = self.ids.get_pixel_checked(x, y)?.0[0];
+    (id != Self::BACKGROUND).then_some(InstanceId(id))
+  }
+
+  /// Pixels, covered by the shape `id`.
+  pub fn pixels_of(&self, id: InstanceId) -> impl Iterator<Item = Point2D<u32, PixelSpace>> + '_ {
+    self.ids.enumerate_pixels()
+      .filter(move |(.., px)| px.0[0] == id.0)
+      .map(|(x, y, _)| Point2D::new(x, y))
+  }
+
+  fn draw<P>(&mut self, shape: &impl Shape<P>, id: InstanceId, viewport: Viewport)
+    where P: Float + AsPrimitive<f64>
+  {
+    let resolution: Size2D<u32, PixelSpace> = self.ids.dimensions().into();
+    let bounding_box = shape.bounding_box().to_f64();
+    let (Some(region), offset, scale) = rescale_bounding_box(bounding_box, viewport, resolution) else {
+      return // bounding box has no intersection with the canvas
+    };
+    let size = bounding_box.size() * scale;
+    if size.width <= 1.0 && size.height <= 1.0 {
+      let pixel = (bounding_box.center().to_vector() * scale).cast_unit() + offset;
+      if pixel.x >= 0.0 && pixel.y >= 0.0 && pixel.x < resolution.width as f64 && pixel.y < resolution.height as f64 {
+        self.ids.put_pixel(pixel.x as u32, pixel.y as u32, Luma([id.0]));
+      }
+      return;
+    }
+    itertools::iproduct!(region.y_range(), region.x_range())
+      .for_each(|(y, x)| {
+        let pixel_world = ((Point2D::new(x, y).to_f64() - offset).to_vector() / scale)
+          .cast_unit().to_point();
+        if shape.sdf(pixel_world.cast::<P>()).as_() <= 0.0 {
+          self.ids.put_pixel(x, y, Luma([id.0]));
+        }
+      });
+  }
+}
+
+impl <Cutie, P> Draw<P, InstanceMap> for Texture<Cutie, InstanceId>
+  where Cutie: Shape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw(&self, map: &mut InstanceMap) {
+    let viewport = Viewport::fit(map.ids.dimensions().into());
+    self.draw_viewport(map, viewport);
+  }
+}
+
+impl <Cutie, P> DrawViewport<P, InstanceMap> for Texture<Cutie, InstanceId>
+  where Cutie: Shape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw_viewport(&self, map: &mut InstanceMap, viewport: Viewport) {
+    map.draw(&self.shape, self.texture, viewport);
+  }
+}
@@ -0,0 +1,180 @@
+use {
+  std::fmt,
+  euclid::{Box2D, Point2D, Size2D},
+  image::Rgba,
+  num_traits::{Float, AsPrimitive},
+  crate::{
+    drawing::{Cmyk, Draw, Texture, vector::{Outline, VectorShape, contours, num}},
+    geometry::{BoundingBox, Shape, WorldSpace, P2}
+  }
+};
+
+/// PostScript points per millimetre
+const PT_PER_MM: f64 = 72.0 / 25.4;
+
+/// Print-oriented PostScript document, in physical units. The unit square is fit into the center
+/// of the page, inside of the margin. Shapes are emitted as in [`SvgBackend`](super::SvgBackend);
+/// colors are either RGB or [`Cmyk`], alpha is ignored (fully transparent shapes are skipped).
+#[derive(Debug, Clone)]
+pub struct PostScriptBackend {
+  /// Page size, in millimetres
+  pub page: Size2D<f64, WorldSpace>,
+  /// In millimetres
+  pub margin: f64,
+  /// Cells of the iso-contour tracing grid per millimetre
+  pub contour_density: f64,
+  body: String
+}
+
+/// Color of a shape, in the color space it was specified.
+#[derive(Copy, Clone)]
+enum Color {
+  Rgb(Rgba<u8>),
+  Cmyk(Cmyk)
+}
+
+impl PostScriptBackend {
+  /// Page of `width` by `height` millimetres.
+  pub fn new(width: f64, height: f64) -> Self {
+    Self { page: Size2D::new(width, height), margin: 0.0, contour_density: 4.0, body: String::new() }
+  }
+
+  /// A4 portrait, 210 by 297 millimetres.
+  pub fn a4() -> Self {
+    Self::new(210.0, 297.0)
+  }
+
+  pub fn with_margin(mut self, margin: f64) -> Self {
+    self.margin = margin;
+    self
+  }
+
+  pub fn with_contour_density(mut self, contour_density: f64) -> Self {
+    self.contour_density = contour_density;
+    self
+  }
+
+  pub fn save(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+    std::fs::write(path, self.to_string())?;
+    Ok(())
+  }
+
+  // side of the unit square, and its top-left corner, in millimetres
+  fn placement(&self) -> (f64, Point2D<f64, WorldSpace>) {
+    let side = (self.page.width.min(self.page.height) - 2.0 * self.margin).max(0.0);
+    (side, ((self.page.to_vector() - Size2D::splat(side).to_vector()) / 2.0).to_point())
+  }
+
+  // world space to points, y pointing up
+  fn to_pt(&self, p: P2<f64>) -> P2<f64> {
+    let (side, origin) = self.placement();
+    P2::new(origin.x + p.x * side, self.page.height - origin.y - p.y * side) * PT_PER_MM
+  }
+
+  fn push(&mut self, shape: &(impl Shape<f64> + VectorShape<f64>), color: Color) {
+    if let Color::Rgb(Rgba([.., 0])) = color {
+      return;
+    }
+    let bounding_box = shape.bounding_box();
+    if !bounding_box.intersects(&Box2D::new(Point2D::origin(), Point2D::splat(1.0))) {
+      return;
+    }
+    let (side, _) = self.placement();
+    let scale = side * PT_PER_MM;
+    let mut ops = match color {
+      Color::Rgb(Rgba([r, g, b, _])) =>
+        format!("{} {} {} setrgbcolor\n", num(r as f64 / 255.0), num(g as f64 / 255.0), num(b as f64 / 255.0)),
+      Color::Cmyk(Cmyk(cmyk)) => {
+        let [c, m, y, k] = cmyk.map(|x| num(x.clamp(0.0, 1.0) as f64));
+        format!("{c} {m} {y} {k} setcmykcolor\n")
+      }
+    };
+    match shape.outline() {
+      Some(Outline::Circle { center, radius }) => {
+        let c = self.to_pt(center);
+        ops += &format!("newpath {} {} {} 0 360 arc closepath fill\n", num(c.x), num(c.y), num(radius * scale));
+      },
+      Some(Outline::Polygon(vertices)) => {
+        ops += &format!("newpath {} closepath fill\n", self.polyline(&vertices));
+      },
+      Some(Outline::Line { a, b, thickness }) => {
+        ops += &format!("newpath {} {} setlinewidth 1 setlinecap stroke\n", self.polyline(&[a, b]), num(thickness * scale));
+      },
+      None => {
+        let cells = (bounding_box.size() * side * self.contour_density).ceil()
+          .clamp(Size2D::splat(4.0), Size2D::splat(4096.0))
+          .to_usize();
+        let path: Vec<_> = contours(|p| shape.sdf(p), bounding_box, cells).iter()
+          .map(|contour| format!("{} closepath", self.polyline(contour)))
+          .collect();
+        if path.is_empty() {
+          return;
+        }
+        ops += &format!("newpath {} eofill\n", path.join(" "));
+      }
+    }
+    self.body += &ops;
+  }
+
+  fn polyline(&self, points: &[P2<f64>]) -> String {
+    points.iter().enumerate()
+      .map(|(i, &p)| {
+        let p = self.to_pt(p);
+        format!("{} {} {}", num(p.x), num(p.y), if i == 0 { "moveto" } else { "lineto" })
+      })
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+}
+
+impl fmt::Display for PostScriptBackend {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let page = self.page * PT_PER_MM;
+    writeln!(f, "%!PS-Adobe-3.0")?;
+    writeln!(f, "%%BoundingBox: 0 0 {} {}", page.width.ceil(), page.height.ceil())?;
+    writeln!(f, "%%Pages: 1")?;
+    writeln!(f, "%%EndComments")?;
+    writeln!(f, "<< /PageSize [{} {}] >> setpagedevice", num(page.width), num(page.height))?;
+    f.write_str(&self.body)?;
+    f.write_str("showpage\n%%EOF\n")
+  }
+}
+
+// the shape in double precision
+struct Cast<'a, S, P>(&'a S, std::marker::PhantomData<P>);
+
+impl<S: Shape<P>, P: Float + AsPrimitive<f64>> crate::sdf::SDF<f64> for Cast<'_, S, P> {
+  fn sdf(&self, pixel: P2<f64>) -> f64 {
+    self.0.sdf(pixel.cast()).as_()
+  }
+}
+
+impl<S: Shape<P>, P: Float + AsPrimitive<f64>> BoundingBox<f64> for Cast<'_, S, P> {
+  fn bounding_box(&self) -> Box2D<f64, WorldSpace> {
+    self.0.bounding_box().to_f64()
+  }
+}
+
+impl<S: VectorShape<P>, P: Float> VectorShape<f64> for Cast<'_, S, P> {
+  fn outline(&self) -> Option<Outline<f64>> {
+    self.0.outline().map(Outline::cast)
+  }
+}
+
+impl <Cutie, P> Draw<P, PostScriptBackend> for Texture<Cutie, Rgba<u8>>
+  where Cutie: Shape<P> + VectorShape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw(&self, ps: &mut PostScriptBackend) {
+    ps.push(&Cast(&self.shape, Default::default()), Color::Rgb(self.texture));
+  }
+}
+
+impl <Cutie, P> Draw<P, PostScriptBackend> for Texture<Cutie, Cmyk>
+  where Cutie: Shape<P> + VectorShape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw(&self, ps: &mut PostScriptBackend) {
+    ps.push(&Cast(&self.shape, Default::default()), Color::Cmyk(self.texture));
+  }
+}
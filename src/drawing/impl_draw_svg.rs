@@ -0,0 +1,207 @@
+use {
+  std::fmt,
+  euclid::{Box2D, Point2D, Size2D},
+  image::Rgba,
+  num_traits::{Float, AsPrimitive, Signed},
+  crate::{
+    drawing::{Cmyk, Draw, Texture, Viewport, rescale_bounding_box, vector::{Outline, VectorShape, contours, num}},
+    geometry::{BoundingBox, Shape, PixelSpace, WorldSpace, P2},
+    solver::adf::{ADF, Primitive, quadtree::Quadtree}
+  }
+};
+
+/// SVG document, scaled the same way as `RgbaImage`: the unit square fits the center of the
+/// canvas. Shapes with a [`VectorShape::outline`] become the respective vector elements, while
+/// others are traced along the zero iso-contour of their SDF.
+#[derive(Debug, Clone)]
+pub struct SvgBackend {
+  pub width: u32,
+  pub height: u32,
+  /// Cells of the iso-contour tracing grid per pixel of the canvas
+  pub contour_density: f64,
+  body: String
+}
+
+impl SvgBackend {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self { width, height, contour_density: 1.0, body: String::new() }
+  }
+
+  pub fn with_contour_density(mut self, contour_density: f64) -> Self {
+    self.contour_density = contour_density;
+    self
+  }
+
+  pub fn save(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+    std::fs::write(path, self.to_string())?;
+    Ok(())
+  }
+
+  /// Rectangle in world space, filled and/or outlined by a hairline.
+  pub fn push_rect(&mut self, rect: Box2D<f64, WorldSpace>, fill: Option<Rgba<u8>>, stroke: Option<Rgba<u8>>) {
+    let (min, max) = (self.to_px(rect.min), self.to_px(rect.max));
+    self.body += &format!(
+      r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+      num(min.x), num(min.y), num(max.x - min.x), num(max.y - min.y), paint(fill, stroke)
+    );
+    self.body += "\n";
+  }
+
+  /// Polyline through points in world space, e.g. [`LineSearch::trajectory`](crate::solver::LineSearch::trajectory).
+  pub fn push_polyline(&mut self, points: impl IntoIterator<Item = P2<f64>>, stroke: Rgba<u8>) {
+    let points: Vec<_> = points.into_iter()
+      .map(|p| { let p = self.to_px(p); format!("{},{}", num(p.x), num(p.y)) })
+      .collect();
+    self.body += &format!(r#"<polyline points="{}" {}/>"#, points.join(" "), paint(None, Some(stroke)));
+    self.body += "\n";
+  }
+
+  /// Outline of the bounding box of a shape.
+  pub fn push_bounding_box<P: Float>(&mut self, shape: &impl BoundingBox<P>, stroke: Rgba<u8>) {
+    self.push_rect(shape.bounding_box().to_f64(), None, Some(stroke));
+  }
+
+  // world to canvas, see `Viewport::fit`
+  fn to_px(&self, p: P2<f64>) -> Point2D<f64, PixelSpace> {
+    Viewport::fit(Size2D::new(self.width, self.height)).to_pixel(p)
+  }
+
+  fn push_outline(&mut self, outline: Outline<f64>, to_px: impl Fn(P2<f64>) -> Point2D<f64, PixelSpace>, min_side: f64, color: Rgba<u8>) {
+    let element = match outline {
+      Outline::Circle { center, radius } => {
+        let c = to_px(center);
+        format!(r#"<circle cx="{}" cy="{}" r="{}" {}/>"#, num(c.x), num(c.y), num(radius * min_side), fill(color))
+      },
+      Outline::Polygon(vertices) => {
+        let points: Vec<_> = vertices.into_iter()
+          .map(|p| { let p = to_px(p); format!("{},{}", num(p.x), num(p.y)) })
+          .collect();
+        format!(r#"<polygon points="{}" {}/>"#, points.join(" "), fill(color))
+      },
+      Outline::Line { a, b, thickness } => {
+        let (a, b) = (to_px(a), to_px(b));
+        format!(
+          r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke-linecap="round" {}/>"#,
+          num(a.x), num(a.y), num(b.x), num(b.y), num(thickness * min_side), fill(color).replace("fill", "stroke")
+        )
+      }
+    };
+    self.body += &element;
+    self.body += "\n";
+  }
+
+  fn push_contour(&mut self, sdf: impl Fn(P2<f64>) -> f64, bounding_box: Box2D<f64, WorldSpace>, to_px: impl Fn(P2<f64>) -> Point2D<f64, PixelSpace>, min_side: f64, color: Rgba<u8>) {
+    let cells = (bounding_box.size() * min_side * self.contour_density).ceil()
+      .clamp(Size2D::splat(4.0), Size2D::splat(4096.0))
+      .to_usize();
+    let path: Vec<_> = contours(sdf, bounding_box, cells).into_iter()
+      .map(|contour| {
+        let points: Vec<_> = contour.into_iter()
+          .map(|p| { let p = to_px(p); format!("{} {}", num(p.x), num(p.y)) })
+          .collect();
+        format!("M{}Z", points.join(" L"))
+      })
+      .collect();
+    if !path.is_empty() {
+      self.body += &format!(r#"<path d="{}" fill-rule="evenodd" {}/>"#, path.join(" "), fill(color));
+      self.body += "\n";
+    }
+  }
+}
+
+impl fmt::Display for SvgBackend {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f,
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+      self.width, self.height
+    )?;
+    f.write_str(&self.body)?;
+    f.write_str("</svg>\n")
+  }
+}
+
+fn fill(Rgba([r, g, b, a]): Rgba<u8>) -> String {
+  match a {
+    255 => format!(r##"fill="#{r:02x}{g:02x}{b:02x}""##),
+    a => format!(r##"fill="#{r:02x}{g:02x}{b:02x}" fill-opacity="{}""##, num(a as f64 / 255.0))
+  }
+}
+
+// fill and hairline stroke, `none` if absent
+fn paint(fill: Option<Rgba<u8>>, stroke: Option<Rgba<u8>>) -> String {
+  let fill = fill.map_or(r#"fill="none""#.to_string(), self::fill);
+  match stroke {
+    Some(stroke) => format!(
+      r#"{fill} {} stroke-width="1" vector-effect="non-scaling-stroke""#,
+      self::fill(stroke).replace("fill", "stroke")
+    ),
+    None => fill
+  }
+}
+
+impl <Data, _Float: Float> Quadtree<Data, _Float> {
+  /// Vector counterpart of [`Quadtree::draw_layout`], which may be inspected at any zoom level.
+  pub fn draw_layout_svg(&self, svg: &mut SvgBackend) -> &Self {
+    self.traverse(&mut |node| {
+      if node.children.is_some() { return Ok(()) };
+      let alpha = 1.0 - (node.depth as f64 / self.max_depth as f64);
+      let color = Rgba([
+        ((1.0 - alpha).powi(2) * 255.0) as u8,
+        0,
+        128,
+        ((1.0 - alpha).powf(0.5) * 255.0) as u8
+      ]);
+      svg.push_rect(node.rect.to_box2d().to_f64(), None, Some(color));
+      Ok(())
+    }).ok();
+    self
+  }
+}
+
+impl <_Float: Float + Signed, Prim: Primitive<_Float>> ADF<_Float, Prim> {
+  /// Vector counterpart of [`ADF::draw_bucket_weights`].
+  pub fn draw_bucket_weights_svg(&self, svg: &mut SvgBackend) -> &Self {
+    self.tree.traverse(&mut |node| {
+      if node.children.is_none() {
+        let alpha = (((node.data.len().max(1) - 1) as f64 / 3.0).powf(1.75)
+          * 0.33 * 255.0) as u8;
+        svg.push_rect(node.rect.to_box2d().to_f64(), Some(Rgba([0x7F, 0xFF, 0, alpha])), None);
+      }
+      Ok(())
+    }).ok();
+    self
+  }
+}
+
+impl <Cutie, P> Draw<P, SvgBackend> for Texture<Cutie, Rgba<u8>>
+  where Cutie: Shape<P> + VectorShape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw(&self, svg: &mut SvgBackend) {
+    draw(&self.shape, self.texture, svg);
+  }
+}
+
+impl <Cutie, P> Draw<P, SvgBackend> for Texture<Cutie, Cmyk>
+  where Cutie: Shape<P> + VectorShape<P>,
+        P: Float + AsPrimitive<f64>
+{
+  fn draw(&self, svg: &mut SvgBackend) {
+    draw(&self.shape, self.texture.to_rgba(), svg);
+  }
+}
+
+fn draw<P>(shape: &(impl Shape<P> + VectorShape<P>), color: Rgba<u8>, svg: &mut SvgBackend)
+  where P: Float + AsPrimitive<f64>
+{
+  let resolution = Size2D::new(svg.width, svg.height);
+  let bounding_box = shape.bounding_box().to_f64();
+  let (Some(_), offset, min_side) = rescale_bounding_box(bounding_box, Viewport::fit(resolution), resolution) else {
+    return // bounding box has no intersection with the canvas
+  };
+  let to_px = |p: P2<f64>| (p.to_vector() * min_side).cast_unit().to_point() + offset;
+  match shape.outline() {
+    Some(outline) => svg.push_outline(outline.cast(), to_px, min_side, color),
+    None => svg.push_contour(|p| shape.sdf(p.cast()).as_(), bounding_box, to_px, min_side, color)
+  }
+}
@@ -2,12 +2,12 @@
 use {
   crate::{
     solver::{
-      Argmax2D, adf::{ADF, quadtree::Quadtree}
+      Argmax2D, adf::{ADF, Primitive, quadtree::Quadtree}
     },
     geometry::{
       self, BoundingBox, Shape,
       PixelSpace, WorldSpace, DistPoint,
-      Translation, Rotation, Scale
+      Translation, Rotation, Scale, Transform2D, Mirror, Repeat, RepeatPolar, Offset, Warp, Displace, Stroke, Anisotropic
     },
     sdf::SDF
   },
@@ -18,13 +18,137 @@ use {
   num_traits::{Float, AsPrimitive, Signed}
 };
 
-mod impl_draw_rgbaimage;
+pub mod animation;
+mod impl_draw_image_buffer;
+mod impl_draw_svg;
+mod gradient;
+mod impl_draw_postscript;
+mod impl_draw_instance_map;
+mod vector;
+mod geo;
+mod scene;
+mod colormap;
+mod texture_cache;
+mod exr;
+mod equirectangular;
+pub use {
+  exr::write_exr,
+  texture_cache::{CachedImage, TextureCache},
+  colormap::{Colormap, SdfDisplay},
+  scene::{DynDraw, Layer, Scene},
+  gradient::{ColorStops, LinearGradient, RadialGradient, SdfGradient},
+  impl_draw_svg::SvgBackend,
+  impl_draw_postscript::PostScriptBackend,
+  impl_draw_instance_map::{InstanceId, InstanceMap},
+  vector::{VectorShape, Outline},
+  geo::GeoExport,
+  equirectangular::Equirectangular
+};
 #[cfg(test)] mod tests;
 
 pub trait Draw<Float, Backend>: Shape<Float> {
   fn draw(&self, image: &mut Backend);
 }
 
+/// Shapes of the raster backends, drawn by the tiled renderers, such as [`draw_parallel`].
+/// Implemented per texture; [`Draw`] and [`DrawViewport`] of an image buffer follow from it.
+pub trait DrawTile<Float, Backend>: Draw<Float, Backend> {
+  /// Draw the part of the shape, which lies within `tile` of a larger canvas.
+  fn draw_tile(&self, tile: Tile<&mut Backend>);
+}
+
+/// Shapes of the raster backends and [`InstanceMap`], drawn through a [`Viewport`].
+/// [`Draw::draw`] is `draw_viewport` with [`Viewport::fit`]; image buffers get it from [`DrawTile`].
+pub trait DrawViewport<Float, Backend>: Draw<Float, Backend> {
+  /// Draw the shape, mapped onto `image` by `viewport`, instead of [`Viewport::fit`].
+  fn draw_viewport(&self, image: &mut Backend, viewport: Viewport);
+}
+
+/// A region of a canvas, rendered on its own: `image` covers the pixels of the canvas, starting
+/// at `origin`.
+#[derive(Debug, Clone)]
+pub struct Tile<B> {
+  pub image: B,
+  pub origin: Point2D<u32, PixelSpace>,
+  pub canvas: Size2D<u32, PixelSpace>,
+  pub viewport: Viewport
+}
+
+impl<B> Tile<B> {
+  pub fn as_mut(&mut self) -> Tile<&mut B> {
+    Tile { image: &mut self.image, origin: self.origin, canvas: self.canvas, viewport: self.viewport }
+  }
+
+  pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+    self.viewport = viewport;
+    self
+  }
+}
+
+/// Mapping of the world onto a canvas: `world` is scaled uniformly, to fit into `screen`, and
+/// centered. Shapes outside of `world` are still drawn, wherever they fall onto the canvas.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+  pub world: Box2D<f64, WorldSpace>,
+  pub screen: Box2D<f64, PixelSpace>
+}
+
+impl Viewport {
+  pub fn new(world: Box2D<f64, WorldSpace>, screen: Box2D<f64, PixelSpace>) -> Self {
+    Self { world, screen }
+  }
+
+  /// The unit square, letterboxed into the center of a canvas of `resolution`; the mapping
+  /// of [`Draw::draw`].
+  pub fn fit(resolution: Size2D<u32, PixelSpace>) -> Self {
+    let min_side = resolution.width.min(resolution.height) as f64;
+    let offset = (resolution.to_vector().to_f64() - V2::splat(min_side)) / 2.0;
+    Self {
+      world: Box2D::new(Point2D::origin(), Point2D::splat(1.0)),
+      screen: Box2D::from_size(Size2D::splat(min_side)).translate(offset)
+    }
+  }
+
+  /// The whole `world`, fitted into the whole canvas of `resolution`.
+  pub fn crop(world: Box2D<f64, WorldSpace>, resolution: Size2D<u32, PixelSpace>) -> Self {
+    Self { world, screen: Box2D::from_size(resolution.to_f64()) }
+  }
+
+  /// Magnify by `zoom`, around the center of `world`.
+  pub fn with_zoom(mut self, zoom: f64) -> Self {
+    let center = self.world.center();
+    self.world = self.world.translate(-center.to_vector())
+      .scale(1.0 / zoom, 1.0 / zoom)
+      .translate(center.to_vector());
+    self
+  }
+
+  /// Move `world` by `offset`.
+  pub fn with_pan(mut self, offset: V2<f64, WorldSpace>) -> Self {
+    self.world = self.world.translate(offset);
+    self
+  }
+
+  /// Pixels per unit of the world.
+  pub fn scale(&self) -> f64 {
+    let scale = self.screen.size().to_vector().component_div(self.world.size().cast_unit().to_vector());
+    scale.x.min(scale.y)
+  }
+
+  /// Position of the origin of the world on the canvas.
+  pub fn offset(&self) -> V2<f64, PixelSpace> {
+    self.screen.center().to_vector() - (self.world.center().to_vector() * self.scale()).cast_unit()
+  }
+
+  pub fn to_pixel(&self, p: Point2D<f64, WorldSpace>) -> Point2D<f64, PixelSpace> {
+    (p.to_vector() * self.scale()).cast_unit().to_point() + self.offset()
+  }
+
+  pub fn to_world(&self, p: Point2D<f64, PixelSpace>) -> Point2D<f64, WorldSpace> {
+    ((p - self.offset()).to_vector() / self.scale()).cast_unit().to_point()
+  }
+}
+
 static MSG: &str = "Draw is only implemented for Texture";
 
 // Rust doesn't support trait upcasting yet
@@ -34,58 +158,290 @@ impl <B, S, P> Draw<P, B> for Rotation<S, P> where Rotation<S, P>: Shape<P> {
   fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
 impl <B, S, P> Draw<P, B> for Scale<S, P> where Scale<S, P>: Shape<P> {
   fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Transform2D<S, P> where Transform2D<S, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Mirror<S> where Mirror<S>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Repeat<S, P> where Repeat<S, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for RepeatPolar<S> where RepeatPolar<S>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Offset<S, P> where Offset<S, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, F, P> Draw<P, B> for Warp<S, F, P> where Warp<S, F, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, F, P> Draw<P, B> for Displace<S, F, P> where Displace<S, F, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Stroke<S, P> where Stroke<S, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, S, P> Draw<P, B> for Anisotropic<S, P> where Anisotropic<S, P>: Shape<P> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
 
 impl <B, P> Draw<P, B> for geometry::Line<P> where geometry::Line<P>: Shape<P> {
   fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
 impl <B, P, U> Draw<P, B> for geometry::Polygon<U> where P: Float, U: AsRef<[Point2D<P, WorldSpace>]> {
   fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, P> Draw<P, B> for geometry::ConvexHull<P> where P: Float {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
+impl <B, P, U> Draw<P, B> for geometry::Polyline<P, U> where P: Float, U: AsRef<[Point2D<P, WorldSpace>]> {
+  fn draw(&self, _: &mut B) { unreachable!("{}", MSG) } }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Texture<S, T> {
   pub shape: S,
   pub texture: T
 }
+impl<S, T> Texture<S, T> {
+  /// Antialias with `n`x`n` samples per pixel along the edges, instead of a single sample of the
+  /// SDF, which blurs thin features and sharp corners. Raster backends only.
+  /// Shorthand for [`Texture::render`].
+  pub fn supersample(self, n: u32) -> Texture<S, Rendered<T>> {
+    self.render(RenderOptions { samples: n, ..Default::default() })
+  }
+
+  /// Rasterize with `options`, instead of the single-sample antialiasing, and overlay blending
+  /// of the raw channel values. Raster backends only.
+  pub fn render(self, options: RenderOptions) -> Texture<S, Rendered<T>> {
+    let options = RenderOptions { samples: options.samples.max(1), ..options };
+    Texture { shape: self.shape, texture: Rendered { texture: self.texture, options } }
+  }
+}
+
+/// Texture, shaded by the SDF of the shape, see [`Shape::texture_shaded`].
+#[derive(Debug, Copy, Clone)]
+pub struct Shaded<F>(pub F);
+
+/// See [`Texture::render`].
+#[derive(Debug, Copy, Clone)]
+pub struct Rendered<T> {
+  pub texture: T,
+  pub options: RenderOptions
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderOptions {
+  /// `n`x`n` samples per pixel along the edges; 1 is the single-sample SDF antialiasing
+  pub samples: u32,
+  pub blend: BlendMode,
+  /// The image holds sRGB encoded channels (except for alpha), to be composited in linear
+  /// light, avoiding dark fringes on the edges; `false` for linear images, e.g. `Rgba32FImage`
+  pub linear_light: bool
+}
+
+impl Default for RenderOptions {
+  fn default() -> Self {
+    Self { samples: 1, blend: BlendMode::Over, linear_light: false }
+  }
+}
+
+/// Blending of a color `s` of the shape over `d` of the image, composited with the coverage
+/// and alpha of the shape.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BlendMode {
+  /// `s`
+  #[default]
+  Over,
+  /// `d + s`
+  Add,
+  /// `d * s`
+  Multiply,
+  /// `max(d, s)`
+  Max
+}
+
+/// Process color, components in `[0, 1]`. Native to [`PostScriptBackend`]; other backends
+/// convert it to RGB naively, without a color profile.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cmyk(pub [f32; 4]);
+
+impl Cmyk {
+  pub fn to_rgba(self) -> Rgba<u8> {
+    let [c, m, y, k] = self.0.map(|x| x.clamp(0.0, 1.0));
+    let channel = |x: f32| ((1.0 - x) * (1.0 - k) * 255.0).round() as u8;
+    Rgba([channel(c), channel(m), channel(y), 255])
+  }
+}
+
 impl <P, S, T> SDF<P> for Texture<S, T> where S: SDF<P> {
-  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P { self.shape.sdf(pixel) } }
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P { self.shape.sdf(pixel) }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> where P: Float { self.shape.gradient(pixel) } }
 impl <P, S, T> BoundingBox<P> for Texture<S, T> where S: BoundingBox<P> {
-  fn bounding_box(&self) -> Box2D<P, WorldSpace> { self.shape.bounding_box() } }
+  fn bounding_box(&self) -> Box2D<P, WorldSpace> { self.shape.bounding_box() }
+  fn tight_bounding_box(&self, transform: &geometry::Affine<P>) -> Box2D<P, WorldSpace> where P: Float {
+    self.shape.tight_bounding_box(transform)
+  } }
 
-// try to fit world in the center of image, preserving aspect ratio
+// map world to the image, see `Viewport`
 fn rescale_bounding_box(
   bounding_box: Box2D<f64, WorldSpace>,
+  viewport: Viewport,
   resolution: Size2D<u32, PixelSpace>
 ) -> (
   Option<Box2D<u32, PixelSpace>>, // bounding_box,
   V2<f64, PixelSpace>, // offset
-  f64 // min_side
+  f64 // scale
 ) {
-  let min_side = resolution.width.min(resolution.height) as f64;
-  let offset = (resolution.to_vector().to_f64() - V2::splat(min_side)) / 2.0;
+  let (offset, scale) = (viewport.offset(), viewport.scale());
+  // cull degenerate shapes before touching the screen space
+  if bounding_box.is_empty() {
+    return (None, offset, scale);
+  }
   let bounding_box = bounding_box
-    .scale(min_side, min_side).cast_unit()
+    .scale(scale, scale).cast_unit()
     .round_out()
     .translate(offset)
     .intersection(&Box2D::from_size(resolution.to_f64()))
     .map(|x| x.cast::<u32>());
-  (bounding_box, offset, min_side)
+  (bounding_box, offset, scale)
 }
 
-/// Draw shapes, parallel.
-/// May cause undefined behaviour.
-pub fn draw_parallel<Float, Backend, Sh>(
-  framebuffer: &mut Backend,
-  shapes: impl rayon::iter::ParallelIterator<Item =Sh>
-) -> &mut Backend
-  where Backend: Sync + Send,
-        Sh: AsRef<dyn Draw<Float, Backend> + Send + Sync>
+/// Rows per tile of [`draw_parallel`].
+pub const TILE_ROWS: u32 = 64;
+
+/// Draw shapes, parallel. The framebuffer is split into horizontal tiles of [`TILE_ROWS`], and
+/// the shapes are binned by their bounding boxes; each tile is rendered by a single thread,
+/// drawing the shapes in the order of `shapes`, hence the result is the same as drawing them
+/// sequentially.
+pub fn draw_parallel<Float, Px, Sh>(
+  framebuffer: &mut ImageBuffer<Px, Vec<Px::Subpixel>>,
+  shapes: impl rayon::iter::ParallelIterator<Item = Sh>
+) -> &mut ImageBuffer<Px, Vec<Px::Subpixel>>
+  where Float: num_traits::Float,
+        Px: Pixel + Send + Sync,
+        Px::Subpixel: Send + Sync,
+        Sh: AsRef<dyn DrawTile<Float, ImageBuffer<Px, Vec<Px::Subpixel>>> + Send + Sync> + Send + Sync
 {
-  let ptr = framebuffer as *mut _ as usize;
-  shapes.for_each(|shape|
-    shape.as_ref().draw(unsafe { &mut *(ptr as *mut Backend) })
-  );
+  let viewport = Viewport::fit(framebuffer.dimensions().into());
+  draw_parallel_with(framebuffer, viewport, shapes)
+}
+
+/// Like [`draw_parallel`], mapped onto the framebuffer by `viewport`.
+pub fn draw_parallel_with<Float, Px, Sh>(
+  framebuffer: &mut ImageBuffer<Px, Vec<Px::Subpixel>>,
+  viewport: Viewport,
+  shapes: impl rayon::iter::ParallelIterator<Item = Sh>
+) -> &mut ImageBuffer<Px, Vec<Px::Subpixel>>
+  where Float: num_traits::Float,
+        Px: Pixel + Send + Sync,
+        Px::Subpixel: Send + Sync,
+        Sh: AsRef<dyn DrawTile<Float, ImageBuffer<Px, Vec<Px::Subpixel>>> + Send + Sync> + Send + Sync
+{
+  use rayon::prelude::*;
+
+  let canvas: Size2D<u32, PixelSpace> = framebuffer.dimensions().into();
+  let shapes: Vec<Sh> = shapes.collect();
+  let bins = bin_shapes(shapes.iter().map(|shape| shape.as_ref().bounding_box()), viewport, canvas);
+
+  let row = canvas.width as usize * Px::CHANNEL_COUNT as usize;
+  framebuffer.par_chunks_mut(row * TILE_ROWS as usize)
+    .zip(bins)
+    .enumerate()
+    .filter(|(_, (_, bin))| !bin.is_empty())
+    .for_each(|(i, (pixels, bin))| {
+      let image = ImageBuffer::from_raw(canvas.width, (pixels.len() / row) as u32, pixels.to_vec())
+        .expect("whole rows of the framebuffer");
+      let mut tile = Tile { image, origin: Point2D::new(0, i as u32 * TILE_ROWS), canvas, viewport };
+      bin.into_iter().for_each(|shape| shapes[shape].as_ref().draw_tile(tile.as_mut()));
+      pixels.copy_from_slice(&tile.image);
+    });
   framebuffer
 }
 
+// indices of the shapes, overlapping each tile of `TILE_ROWS`, ascending
+fn bin_shapes<Float: num_traits::Float>(
+  bounding_boxes: impl Iterator<Item = Box2D<Float, WorldSpace>>,
+  viewport: Viewport,
+  canvas: Size2D<u32, PixelSpace>
+) -> Vec<Vec<usize>> {
+  let tile_count = canvas.height.div_ceil(TILE_ROWS) as usize;
+  let mut bins = vec![vec![]; tile_count];
+  bounding_boxes.enumerate().for_each(|(i, bounding_box)| {
+    if let (Some(bounding_box), ..) = rescale_bounding_box(bounding_box.to_f64(), viewport, canvas) {
+      let last = (bounding_box.max.y / TILE_ROWS).min(tile_count as u32 - 1);
+      (bounding_box.min.y / TILE_ROWS..=last).for_each(|tile| bins[tile as usize].push(i));
+    }
+  });
+  bins
+}
+
+/// Draw shapes into a PNG of `resolution`, streamed into `writer` tile by tile (see
+/// [`draw_parallel`]), so that only a few tiles are held in memory at once, rather than the
+/// whole image. Tiles are rendered in parallel, in batches of the thread count.
+pub fn draw_png_streaming<Float, Sh>(
+  writer: impl std::io::Write,
+  resolution: Size2D<u32, PixelSpace>,
+  background: Rgba<u8>,
+  shapes: impl IntoIterator<Item = Sh>
+) -> anyhow::Result<()>
+  where Float: num_traits::Float,
+        Sh: AsRef<dyn DrawTile<Float, RgbaImage> + Send + Sync> + Sync
+{
+  use {rayon::prelude::*, std::io::Write};
+
+  let shapes: Vec<Sh> = shapes.into_iter().collect();
+  let viewport = Viewport::fit(resolution);
+  let bins = bin_shapes(shapes.iter().map(|shape| shape.as_ref().bounding_box()), viewport, resolution);
+
+  let mut encoder = png::Encoder::new(writer, resolution.width, resolution.height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header()?;
+  let mut stream = writer.stream_writer()?;
+  let bins: Vec<_> = bins.iter().enumerate().collect();
+  for batch in bins.chunks(rayon::current_num_threads()) {
+    let tiles: Vec<RgbaImage> = batch.par_iter()
+      .map(|&(i, bin)| {
+        let origin = Point2D::new(0, i as u32 * TILE_ROWS);
+        let rows = TILE_ROWS.min(resolution.height - origin.y);
+        let image = RgbaImage::from_pixel(resolution.width, rows, background);
+        let mut tile = Tile { image, origin, canvas: resolution, viewport };
+        bin.iter().for_each(|&shape| shapes[shape].as_ref().draw_tile(tile.as_mut()));
+        tile.image
+      })
+      .collect();
+    tiles.iter().try_for_each(|tile| stream.write_all(tile.as_raw()))?;
+  }
+  stream.finish()?;
+  Ok(())
+}
+
+/// Draw shapes while they are still being generated: `shapes` runs on the current thread, and
+/// a single consumer thread owns `framebuffer`, drawing the shapes queued so far (up to
+/// `buffer` of them) in batches, each one by the tiled renderer of [`draw_parallel`]. The
+/// result is the same as drawing the shapes sequentially. See also
+/// [`util::pipeline`](crate::util::pipeline).
+pub fn draw_pipelined<Float, Px, Sh>(
+  framebuffer: &mut ImageBuffer<Px, Vec<Px::Subpixel>>,
+  shapes: impl Iterator<Item = Sh>,
+  buffer: usize
+) -> anyhow::Result<&mut ImageBuffer<Px, Vec<Px::Subpixel>>>
+  where Float: num_traits::Float,
+        Px: Pixel + Send + Sync,
+        Px::Subpixel: Send + Sync,
+        Sh: AsRef<dyn DrawTile<Float, ImageBuffer<Px, Vec<Px::Subpixel>>> + Send + Sync> + Send + Sync
+{
+  use rayon::prelude::*;
+
+  let buffer = buffer.max(1);
+  let (tx, rx) = std::sync::mpsc::sync_channel::<Sh>(buffer);
+  std::thread::scope(|scope| {
+    let image = &mut *framebuffer;
+    let consumer = scope.spawn(move || {
+      while let Ok(shape) = rx.recv() {
+        let batch: Vec<Sh> = std::iter::once(shape).chain(rx.try_iter().take(buffer - 1)).collect();
+        draw_parallel(image, batch.par_iter());
+      }
+    });
+    for shape in shapes {
+      if tx.send(shape).is_err() { break; }
+    }
+    drop(tx);
+    consumer.join()
+      .unwrap_or_else(|e| std::panic::resume_unwind(e));
+  });
+  Ok(framebuffer)
+}
+
 pub fn display_sdf(sdf: impl Fn(Point2D<f64, WorldSpace>) -> f64, image: &mut RgbaImage, brightness: f64) {
   let resolution = image.width();
   let Δp = 1.0 / resolution as f64;
@@ -105,7 +461,44 @@ pub fn display_sdf(sdf: impl Fn(Point2D<f64, WorldSpace>) -> f64, image: &mut Rg
     });
 }
 
+/// Like [`display_sdf`], with a configurable colormap, range and contours.
+pub fn display_sdf_with(sdf: impl Fn(Point2D<f64, WorldSpace>) -> f64, image: &mut RgbaImage, options: &SdfDisplay) {
+  let resolution = image.width();
+  let Δp = 1.0 / resolution as f64;
+  // antialiased line along the iso-contour at `level`
+  let line = |sdf: f64, level: f64| (Δp - (sdf - level).abs()).clamp(0.0, Δp) / Δp;
+
+  image.enumerate_pixels_mut()
+    .for_each(|(x, y, pixel)| {
+      let pixel_world = Point2D::new(x, y).to_f64() / resolution as f64;
+      let sdf = sdf(pixel_world);
+      let mut color = options.colormap.map(options.normalize(sdf));
+      if let Some(interval) = options.contour_interval {
+        let alpha = line(sdf, (sdf / interval).round() * interval);
+        color.blend(&Rgba([0, 0, 0, (alpha * 96.0) as u8]));
+      }
+      if options.zero_contour {
+        color.blend(&Rgba([255, 0, 0, (line(sdf, 0.0) * 128.0) as u8]));
+      }
+      *pixel = color;
+    });
+}
+
 impl Argmax2D {
+  /// Distances relative to the global maxima, mapped by `colormap`.
+  pub fn display_debug_with(&self, colormap: Colormap) -> image::RgbImage {
+    let mut image = image::RgbImage::new(
+      self.dist_map.resolution as u32,
+      self.dist_map.resolution as u32
+    );
+    let max_dist = self.find_max().distance;
+    self.dist_map.pixels().for_each(|DistPoint { distance, point }| {
+      let color = colormap.map((distance / max_dist) as f64);
+      *image.get_pixel_mut(point.x as u32, point.y as u32) = color.to_rgb();
+    });
+    image
+  }
+
   pub fn display_debug(&self) -> image::RgbImage {
     let mut image = ImageBuffer::<image::Rgb<u8>, _>::new(
       self.dist_map.resolution as u32,
@@ -118,6 +511,17 @@ impl Argmax2D {
     });
     image
   }
+
+  /// Use a grayscale image, stretched over the unit square, as the weight map.
+  /// See [`Argmax2D::set_weight_map`].
+  pub fn set_weight_image(&mut self, image: &image::GrayImage) {
+    let (width, height) = image.dimensions();
+    self.set_weight_map(|p| {
+      let x = ((p.x * width as f32) as u32).min(width - 1);
+      let y = ((p.y * height as f32) as u32).min(height - 1);
+      image.get_pixel(x, y).0[0] as f32 / 255.0
+    });
+  }
 }
 
 impl <Data, _Float: Float> Quadtree<Data, _Float> {
@@ -168,11 +572,15 @@ impl <Data, _Float: Float> Quadtree<Data, _Float> {
   }
 }
 
-impl <_Float: Float + Signed + AsPrimitive<f64>> ADF<_Float> {
+impl <_Float: Float + Signed + AsPrimitive<f64>, Prim: Primitive<_Float>> ADF<_Float, Prim> {
   pub fn display_sdf(&self, image: &mut RgbaImage, brightness: f64) -> &Self {
     display_sdf(|p| self.sdf(p.cast()).to_f64().unwrap(), image, brightness);
     self
   }
+  pub fn display_sdf_with(&self, image: &mut RgbaImage, options: &SdfDisplay) -> &Self {
+    display_sdf_with(|p| self.sdf(p.cast()).to_f64().unwrap(), image, options);
+    self
+  }
   pub fn draw_bucket_weights(&self, image: &mut RgbaImage) -> &Self {
     self.tree.traverse(&mut |node| {
       if node.children.is_none() {
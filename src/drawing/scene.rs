@@ -0,0 +1,94 @@
+use {
+  std::collections::BTreeMap,
+  image::{ImageBuffer, Pixel},
+  num_traits::Float,
+  crate::drawing::{
+    BlendMode, DrawTile, RenderOptions, draw_parallel,
+    impl_draw_image_buffer::composite
+  }
+};
+
+/// Shapes of a raster image, drawn onto a framebuffer of `Px`.
+pub type DynDraw<_Float, Px> =
+  Box<dyn DrawTile<_Float, ImageBuffer<Px, Vec<<Px as Pixel>::Subpixel>>> + Send + Sync>;
+
+/// Shapes, grouped into [`Layer`]s by their `z`. Layers are composited from the lowest `z`
+/// upwards, and shapes of a layer are drawn in the order of insertion, regardless of drawing
+/// them in parallel.
+pub struct Scene<_Float, Px: Pixel> {
+  layers: BTreeMap<i32, Layer<_Float, Px>>
+}
+
+pub struct Layer<_Float, Px: Pixel> {
+  /// Blending of the layer with the ones below; except for [`BlendMode::Over`], the layer is
+  /// drawn onto a transparent image first, hence `Px` must have an alpha channel.
+  pub blend: BlendMode,
+  pub shapes: Vec<DynDraw<_Float, Px>>
+}
+
+impl<_Float, Px: Pixel> Default for Layer<_Float, Px> {
+  fn default() -> Self {
+    Self { blend: BlendMode::Over, shapes: vec![] }
+  }
+}
+
+impl<_Float, Px: Pixel> Default for Scene<_Float, Px> {
+  fn default() -> Self {
+    Self { layers: BTreeMap::new() }
+  }
+}
+
+impl<_Float, Px: Pixel> Scene<_Float, Px> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Blend the layer `z` with `blend`, see [`Layer::blend`].
+  pub fn with_blend(mut self, z: i32, blend: BlendMode) -> Self {
+    self.layer_mut(z).blend = blend;
+    self
+  }
+
+  pub fn push(&mut self, z: i32, shape: DynDraw<_Float, Px>) -> &mut Self {
+    self.layer_mut(z).shapes.push(shape);
+    self
+  }
+
+  pub fn layer_mut(&mut self, z: i32) -> &mut Layer<_Float, Px> {
+    self.layers.entry(z).or_default()
+  }
+
+  /// Layers, ascending by `z`.
+  pub fn layers(&self) -> impl Iterator<Item = (i32, &Layer<_Float, Px>)> {
+    self.layers.iter().map(|(&z, layer)| (z, layer))
+  }
+}
+
+impl<_Float, Px> Scene<_Float, Px>
+  where _Float: Float,
+        Px: Pixel + Send + Sync,
+        Px::Subpixel: Send + Sync
+{
+  /// Draw every layer onto `framebuffer`, parallel, see [`draw_parallel`].
+  pub fn render<'a>(&self, framebuffer: &'a mut ImageBuffer<Px, Vec<Px::Subpixel>>) -> &'a mut ImageBuffer<Px, Vec<Px::Subpixel>> {
+    use rayon::prelude::*;
+
+    self.layers.values().for_each(|layer| {
+      if layer.blend == BlendMode::Over {
+        draw_parallel(framebuffer, layer.shapes.par_iter());
+        return;
+      }
+      assert!(matches!(Px::COLOR_MODEL, "RGBA" | "YA"), "blending of layers requires an alpha channel");
+      let mut image = ImageBuffer::new(framebuffer.width(), framebuffer.height());
+      draw_parallel(&mut image, layer.shapes.par_iter());
+      let options = RenderOptions { blend: layer.blend, ..Default::default() };
+      framebuffer.par_chunks_mut(Px::CHANNEL_COUNT as usize)
+        .zip(image.par_chunks(Px::CHANNEL_COUNT as usize))
+        .for_each(|(dst, src)| {
+          let dst = Px::from_slice_mut(dst);
+          *dst = composite(*dst, *Px::from_slice(src), 1.0, options);
+        });
+    });
+    framebuffer
+  }
+}
@@ -1,7 +1,9 @@
 use {
   super::*,
   crate::{
-    geometry::{Circle, Square}
+    geometry::{self, Circle, Square, Star, Pentagon},
+    sdf::tests::close,
+    util
   },
   euclid::Angle,
   anyhow::Result,
@@ -65,4 +67,596 @@ use {
     .draw(&mut image);
   image.save("test/test_texture_fn.png")?;
   Ok(())
-}
\ No newline at end of file
+}
+
+#[test] fn subpixel() {
+  let mut image = RgbaImage::new(128, 128);
+  Circle
+    .translate(V2::splat(0.5))
+    .scale(0.5 / 128.0)
+    .texture(Luma([255u8]).to_rgba())
+    .draw(&mut image);
+  let lit: Vec<_> = image.enumerate_pixels()
+    .filter(|(_, _, pixel)| pixel.0[3] > 0)
+    .collect();
+  assert_eq!(lit.len(), 1);
+  let (x, y, pixel) = lit[0];
+  assert_eq!([x, y], [64, 64]);
+  // a circle inscribed in a pixel covers ~π/4 of its area
+  assert!((150..=220).contains(&pixel.0[3]));
+}
+
+#[test] fn empty_texture() {
+  let mut image = RgbaImage::new(128, 128);
+  let texture = image::DynamicImage::new_rgba8(0, 0);
+  // sub-pixel and regular
+  [0.5 / 128.0, 0.5].into_iter().for_each(|scale| Circle
+    .translate(V2::splat(0.5))
+    .scale(scale)
+    .texture(&texture)
+    .draw(&mut image)
+  );
+  assert!(image.pixels().all(|pixel| pixel.0 == [0; 4]));
+}
+
+#[test] fn pipelined() -> Result<()> {
+  // overlapping, hence the order of drawing matters
+  let shapes = || (0..16).map(|i| Box::new(Circle
+    .translate(V2::new(i % 4, i / 4).to_f64() / 4.0 + V2::splat(0.125))
+    .scale(0.2)
+    .texture(Rgba([(i * 16) as u8, 255 - (i * 16) as u8, 0, 192]))
+  ) as Box<dyn DrawTile<_, _> + Send + Sync>);
+
+  let mut sequential = RgbaImage::new(128, 128);
+  shapes().for_each(|shape| shape.draw(&mut sequential));
+  let mut image = RgbaImage::new(128, 128);
+  draw_pipelined(&mut image, shapes(), 4)?;
+  assert!(image == sequential);
+  Ok(())
+}
+
+#[test] fn svg() -> Result<()> {
+  let mut svg = SvgBackend::new(128, 128);
+  Circle
+    .translate(V2::splat(0.25))
+    .scale(0.25)
+    .texture(Rgba([255, 0, 0, 255]))
+    .draw(&mut svg);
+  let square = Square
+    .translate(V2::new(0.75, 0.25))
+    .scale(0.2)
+    .rotate(Angle::degrees(30.0));
+  square.texture(Rgba([0, 255, 0, 128])).draw(&mut svg);
+  Star { n: 5, m: 3.0 }
+    .translate(V2::new(0.5, 0.75))
+    .scale(0.2)
+    .texture(Rgba([0, 0, 255, 255]))
+    .draw(&mut svg);
+  // outside of the canvas
+  Circle.translate(V2::splat(4.0)).texture(Rgba([255; 4])).draw(&mut svg);
+  svg.save("test/test_svg.svg")?;
+
+  let svg = svg.to_string();
+  assert!(svg.contains(r##"<circle cx="32" cy="32" r="32" fill="#ff0000"/>"##));
+  assert_eq!(svg.matches("<polygon").count(), 1);
+  assert!(svg.contains(r#"fill-opacity="0.502""#));
+  assert_eq!(svg.matches("<path").count(), 1);
+
+  // vertices of outlines lie on the boundary
+  let pentagon = Pentagon {}.translate(V2::splat(0.5)).scale(0.5);
+  let on_boundary = |shape: &dyn SDF<f64>, outline| match outline {
+    Some(Outline::Polygon(vertices)) => vertices.iter().all(|&p| shape.sdf(p).abs() < 1e-9),
+    _ => false
+  };
+  assert!(on_boundary(&square, square.outline()));
+  assert!(on_boundary(&pentagon, pentagon.outline()));
+  Ok(())
+}
+
+#[test] fn postscript() -> Result<()> {
+  let mut ps = PostScriptBackend::a4().with_margin(10.0);
+  Circle
+    .translate(V2::splat(0.5))
+    .scale(0.5)
+    .texture(Cmyk([0.0, 1.0, 1.0, 0.0]))
+    .draw(&mut ps);
+  Star { n: 5, m: 3.0 }
+    .translate(V2::splat(0.5))
+    .scale(0.25)
+    .texture(Rgba([255, 255, 255, 255]))
+    .draw(&mut ps);
+  ps.save("test/test_postscript.ps")?;
+
+  let ps = ps.to_string();
+  assert!(ps.starts_with("%!PS-Adobe-3.0") && ps.ends_with("showpage\n%%EOF\n"));
+  // 190mm circle, centered on the page
+  assert!(ps.contains("0 1 1 0 setcmykcolor\nnewpath 297.638 420.945 269.291 0 360 arc closepath fill"));
+  assert!(ps.contains("1 1 1 setrgbcolor\nnewpath") && ps.contains("eofill"));
+  assert_eq!(Cmyk([0.0, 1.0, 1.0, 0.0]).to_rgba(), Rgba([255, 0, 0, 255]));
+  Ok(())
+}
+
+#[test] fn image_buffer() {
+  let circle = || Circle.translate(V2::splat(0.5)).scale(0.25);
+
+  let mut mask = image::GrayImage::new(64, 64);
+  circle().texture(Luma([255u8])).draw(&mut mask);
+  assert_eq!(mask.get_pixel(32, 32).0, [255]);
+  assert_eq!(mask.get_pixel(0, 0).0, [0]);
+  // antialiased edge
+  assert!(mask.pixels().any(|px| (1..255).contains(&px.0[0])));
+
+  let mut ids = ImageBuffer::<Luma<u16>, _>::new(64, 64);
+  circle().texture(Luma([1000u16])).draw(&mut ids);
+  assert_eq!(ids.get_pixel(32, 32).0, [1000]);
+
+  let mut hdr = image::Rgb32FImage::new(64, 64);
+  circle().texture(|_: Point2D<f64, _>| image::Rgb([4.0f32, 0.5, 0.0])).draw(&mut hdr);
+  assert_eq!(hdr.get_pixel(32, 32).0, [4.0, 0.5, 0.0]);
+
+  let mut rgba = image::Rgba32FImage::new(64, 64);
+  circle().texture(Rgba([1.0f32, 1.0, 1.0, 0.5])).draw(&mut rgba);
+  assert!((rgba.get_pixel(32, 32).0[3] - 0.5).abs() < 1e-6);
+}
+
+#[test] fn supersample() {
+  // a line, thinner than a pixel
+  let line = || geometry::Line { a: Point2D::new(0.1, 0.1), b: Point2D::new(0.9, 0.4), thickness: 0.25 / 128.0 };
+  let area = 0.8f64.hypot(0.3) * 0.25 * 128.0;
+  let coverage = |image: &image::GrayImage| image.pixels().map(|px| px.0[0] as f64 / 255.0).sum::<f64>();
+
+  let mut supersampled = image::GrayImage::new(128, 128);
+  line().texture(Luma([255u8])).supersample(4).draw(&mut supersampled);
+  assert!((coverage(&supersampled) / area - 1.0).abs() < 0.2, "{} {area}", coverage(&supersampled));
+
+  // n = 1 is the plain antialiasing
+  let (mut a, mut b) = (image::GrayImage::new(128, 128), image::GrayImage::new(128, 128));
+  line().texture(Luma([255u8])).draw(&mut a);
+  line().texture(Luma([255u8])).supersample(1).draw(&mut b);
+  assert!(a == b);
+}
+
+#[test] fn compositing() {
+  let square = || Square.translate(V2::splat(0.5)).scale(0.5);
+  let draw = |background: u8, color: u8, options| {
+    let mut image = image::GrayImage::from_pixel(8, 8, Luma([background]));
+    square().texture(Luma([color])).render(options).draw(&mut image);
+    image.get_pixel(4, 4).0[0]
+  };
+  let blend = |blend| RenderOptions { blend, ..Default::default() };
+  assert_eq!(draw(100, 200, blend(BlendMode::Over)), 200);
+  assert_eq!(draw(100, 100, blend(BlendMode::Add)), 200);
+  assert_eq!(draw(200, 100, blend(BlendMode::Add)), 255);
+  assert_eq!(draw(255, 128, blend(BlendMode::Multiply)), 128);
+  assert_eq!(draw(100, 50, blend(BlendMode::Max)), 100);
+
+  // half-transparent white over black is brighter in linear light
+  let mut srgb = RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 255]));
+  let mut linear = srgb.clone();
+  square().texture(Rgba([255, 255, 255, 128])).render(RenderOptions::default()).draw(&mut srgb);
+  square().texture(Rgba([255, 255, 255, 128]))
+    .render(RenderOptions { linear_light: true, ..Default::default() })
+    .draw(&mut linear);
+  assert_eq!(srgb.get_pixel(4, 4).0[..3], [128, 128, 128]);
+  assert_eq!(linear.get_pixel(4, 4).0, [188, 188, 188, 255]);
+}
+
+#[test] fn gradient() {
+  let square = || Square.translate(V2::splat(0.5)).scale(0.5);
+  let stops = vec![(0.0, Luma([0u8])), (1.0, Luma([200u8]))];
+  let mut image = image::GrayImage::new(100, 100);
+  square().texture(LinearGradient { start: Point2D::new(0.0, 0.0), end: Point2D::new(1.0, 0.0), stops: stops.clone() })
+    .draw(&mut image);
+  assert_eq!(image.get_pixel(0, 50).0[0], 0);
+  assert!(image.get_pixel(50, 10).0[0].abs_diff(100) <= 2);
+  assert!(image.get_pixel(50, 10) == image.get_pixel(50, 90));
+  assert!(image.get_pixel(99, 50).0[0] >= 196);
+
+  square().texture(RadialGradient { center: Point2D::splat(0.5), radius: 0.5, stops: stops.clone() })
+    .draw(&mut image);
+  assert!(image.get_pixel(50, 50).0[0] <= 4);
+  assert!(image.get_pixel(50, 25).0[0].abs_diff(100) <= 4);
+  assert_eq!(image.get_pixel(1, 1).0[0], 200);
+
+  // iso-distance, brightest at the center of a circle
+  let mut image = image::GrayImage::new(100, 100);
+  Circle.translate(V2::splat(0.5)).scale(0.5)
+    .texture(SdfGradient { depth: 1.0, stops: vec![(0.0, Luma([0u8])), (1.0, Luma([200]))] })
+    .draw(&mut image);
+  assert!(image.get_pixel(50, 50).0[0] >= 196);
+  assert!(image.get_pixel(50, 25).0[0].abs_diff(100) <= 4);
+  assert!(image.get_pixel(25, 50) == image.get_pixel(50, 25));
+}
+
+#[test] fn texture_shaded() {
+  let mut image = image::GrayImage::new(100, 100);
+  // brighter towards the right, and a dark outline
+  Circle.translate(V2::splat(0.5)).scale(0.5)
+    .texture_shaded(|_, sdf: f64, gradient: V2<f64, WorldSpace>| match sdf > -0.05 {
+      true => Luma([10u8]),
+      false => Luma([(128.0 + 100.0 * gradient.x) as u8])
+    })
+    .draw(&mut image);
+  assert_eq!(image.get_pixel(50, 2).0[0], 10);
+  assert!(image.get_pixel(50, 30).0[0].abs_diff(128) <= 10);
+  assert!(image.get_pixel(80, 50).0[0] >= 220);
+  assert!(image.get_pixel(20, 50).0[0] <= 36);
+}
+
+#[test] fn stroke() {
+  let mut image = image::GrayImage::new(100, 100);
+  // unlike the subtraction of a scaled copy, the band has the same width along both axes
+  geometry::Rect { size: Point2D::new(0.8, 0.4) }
+    .translate(V2::splat(0.5))
+    .stroke(0.1)
+    .texture(Luma([255u8]))
+    .draw(&mut image);
+  assert_eq!(image.get_pixel(50, 50).0[0], 0);
+  assert_eq!(image.get_pixel(10, 50).0[0], 255);
+  assert_eq!(image.get_pixel(50, 30).0[0], 255);
+  assert_eq!(image.get_pixel(3, 50).0[0], 0);
+  assert_eq!(image.get_pixel(50, 23).0[0], 0);
+
+  let mut image = image::GrayImage::new(100, 100);
+  Circle.translate(V2::splat(0.5)).scale(0.4).stroke(0.1).texture(Luma([255u8])).draw(&mut image);
+  assert_eq!(image.get_pixel(50, 50).0[0], 0);
+  assert_eq!(image.get_pixel(50, 10).0[0], 255);
+  assert_eq!(image.get_pixel(50, 3).0[0], 0);
+  assert_eq!(image.get_pixel(50, 17).0[0], 0);
+}
+
+#[test] fn draw_parallel_tiles() {
+  use rayon::prelude::*;
+  // overlapping, translucent, some crossing the tile boundaries and the canvas
+  let shapes = || (0..200).into_par_iter().map(|i| {
+    let p = util::halton(i);
+    let shape = Circle.translate(V2::new(p.x * 1.2 - 0.1, p.y)).scale(0.02 + 0.1 * p.x)
+      .texture(Rgba([(i * 37 % 256) as u8, (i * 11 % 256) as u8, 255, 160]));
+    Box::new(shape) as Box<dyn DrawTile<f64, RgbaImage> + Send + Sync>
+  });
+  let mut sequential = RgbaImage::new(300, 150);
+  shapes().collect::<Vec<_>>().iter().for_each(|shape| shape.draw(&mut sequential));
+  let mut parallel = RgbaImage::new(300, 150);
+  draw_parallel(&mut parallel, shapes());
+  assert!(sequential == parallel);
+}
+
+#[test] fn scene() {
+  let square = |color: Rgba<u8>| Box::new(Square.translate(V2::splat(0.5)).scale(0.5).texture(color)) as DynDraw<f64, _>;
+  let circle = |color: Rgba<u8>| Box::new(Circle.translate(V2::splat(0.5)).scale(0.25).texture(color)) as DynDraw<f64, _>;
+  let mut scene = Scene::new()
+    .with_blend(2, BlendMode::Multiply);
+  // pushed out of order
+  scene
+    .push(2, circle(Rgba([128, 255, 255, 255])))
+    .push(1, square(Rgba([0, 0, 200, 255])))
+    .push(0, square(Rgba([200, 200, 200, 255])))
+    .push(1, circle(Rgba([200, 100, 0, 255])));
+  assert_eq!(scene.layers().map(|(z, layer)| (z, layer.shapes.len())).collect::<Vec<_>>(), [(0, 1), (1, 2), (2, 1)]);
+
+  let mut image = RgbaImage::new(64, 64);
+  scene.render(&mut image);
+  assert_eq!(image.get_pixel(2, 2).0, [0, 0, 200, 255]);
+  assert_eq!(image.get_pixel(32, 32).0, [100, 100, 0, 255]);
+}
+
+#[test] fn colormap() {
+  // the fits are within a few levels of the reference
+  let close = |a: Rgba<u8>, b: [u8; 3]| a.0.iter().zip(b).all(|(&a, b)| a.abs_diff(b) <= 4);
+  assert!(close(Colormap::Viridis.map(0.0), [68, 1, 84]));
+  assert!(close(Colormap::Viridis.map(1.0), [253, 231, 37]));
+  assert!(close(Colormap::Turbo.map(0.0), [35, 23, 27]));
+  assert!(close(Colormap::Diverging.map(0.5), [222, 222, 222]));
+  assert_eq!(Colormap::Grayscale.map(2.0), Rgba([255, 255, 255, 255]));
+  assert_eq!(Colormap::Grayscale.map(f64::NAN), Rgba([0, 0, 0, 255]));
+
+  // a cone around the center, contours every 0.1
+  let sdf = |p: Point2D<f64, WorldSpace>| (p - Point2D::splat(0.5)).length() - 0.25;
+  let options = SdfDisplay::signed(0.2).with_zero_contour(false);
+  let (mut plain, mut contours) = (RgbaImage::new(100, 100), RgbaImage::new(100, 100));
+  display_sdf_with(sdf, &mut plain, &options);
+  display_sdf_with(sdf, &mut contours, &options.with_contour_interval(0.1));
+  assert!(contours.get_pixel(65, 50).0[0] < plain.get_pixel(65, 50).0[0] - 30);
+  assert_eq!(contours.get_pixel(70, 50), plain.get_pixel(70, 50));
+  // beyond the range
+  assert_eq!(plain.get_pixel(50, 50).0, Colormap::Diverging.map(0.0).0);
+}
+
+#[test] fn svg_debug() -> Result<()> {
+  use {std::sync::Arc, crate::solver::{ADF, LineSearch}};
+
+  let mut adf = ADF::new(5, vec![Arc::new(crate::sdf::boundary_rect)]);
+  let circle = |x: f64, y: f64| Circle.translate(V2::new(x, y)).scale(0.1);
+  for (x, y) in [(0.2, 0.2), (0.7, 0.3), (0.3, 0.8), (0.75, 0.75), (0.5, 0.5)] {
+    let domain = util::domain_empirical(DistPoint { distance: 0.1, point: Point2D::new(x, y) });
+    assert!(adf.insert_sdf_domain(domain, Arc::new(move |p| circle(x, y).sdf(p))));
+  }
+
+  let mut svg = SvgBackend::new(200, 100);
+  adf.draw_bucket_weights_svg(&mut svg);
+  adf.tree.draw_layout_svg(&mut svg);
+  svg.push_bounding_box(&Circle.translate(V2::splat(0.5)).scale(0.25), Rgba([0, 0, 255, 255]));
+  let trajectory = LineSearch::default().trajectory(|p| adf.gradient(p), Point2D::new(0.1, 0.2));
+  svg.push_polyline(trajectory.iter().copied(), Rgba([255, 0, 0, 255]));
+  svg.save("test/test_svg_debug.svg")?;
+
+  let svg = svg.to_string();
+  let mut leaves = 0;
+  adf.tree.traverse(&mut |node| { leaves += node.children.is_none() as usize; Ok(()) })?;
+  assert!(leaves > 1);
+  // leaves, the bounding box, and the trajectory
+  assert_eq!(svg.matches(r#"fill="none" stroke="#).count(), leaves + 2);
+  assert_eq!(svg.matches(r##"fill="#7fff00""##).count(), leaves);
+  // centered on the wider canvas
+  assert!(svg.contains(r##"<rect x="75" y="25" width="50" height="50" fill="none" stroke="#0000ff""##));
+  assert_eq!(svg.matches("<polyline").count(), 1);
+  Ok(())
+}
+
+#[test] fn animation() -> Result<()> {
+  use animation::{FrameOutput, FrameRecorder, RecordFrames};
+
+  let circles = || (0..10).map(util::halton);
+  let draw = |p: &Point2D<f64, WorldSpace>, canvas: &mut RgbaImage| Circle
+    .translate(p.to_vector())
+    .scale(0.05)
+    .texture(Rgba([255; 4]))
+    .draw(canvas);
+
+  let dir = std::path::PathBuf::from("test/test_animation");
+  std::fs::remove_dir_all(&dir).ok();
+  let mut recorder = FrameRecorder::new(RgbaImage::new(64, 64), FrameOutput::Numbered(dir.clone()))
+    .with_stride(3);
+  assert_eq!(circles().record(&mut recorder, draw).count(), 10);
+  assert_eq!(recorder.frame_count(), 3);
+  let canvas = recorder.finish()?;
+  // the last step is always captured
+  assert_eq!(std::fs::read_dir(&dir)?.count(), 4);
+  assert!(image::open(dir.join("00003.png"))?.to_rgba8() == canvas);
+
+  let path = std::path::PathBuf::from("test/test_animation.png");
+  let mut recorder = FrameRecorder::new(RgbaImage::new(64, 64), FrameOutput::Apng(path.clone()))
+    .with_stride(2);
+  circles().record(&mut recorder, draw).for_each(drop);
+  recorder.finish()?;
+  let decoder = png::Decoder::new(std::fs::File::open(path)?).read_info()?;
+  assert_eq!(decoder.info().animation_control.map(|control| control.num_frames), Some(5));
+  Ok(())
+}
+
+#[test] fn texture_cache() -> Result<()> {
+  let source = std::sync::Arc::new(image::open("doc/embedded.jpg")?);
+  let cache = TextureCache::new();
+  let mut cached = RgbaImage::new(256, 256);
+  let mut plain = RgbaImage::new(256, 256);
+  // similar sizes share a bucket, with the same aspect ratio
+  for (i, scale) in [0.1, 0.11, 0.12].into_iter().enumerate() {
+    let circle = Circle.translate(V2::new(0.2 + 0.3 * i as f64, 0.5)).scale(scale);
+    circle.texture(cache.texture(source.clone())).draw(&mut cached);
+    circle.texture(source.clone()).draw(&mut plain);
+  }
+  assert_eq!(cache.len(), 1);
+  // another aspect ratio
+  let rect = geometry::Rect { size: Point2D::new(0.3, 0.1) }.translate(V2::new(0.5, 0.8));
+  rect.texture(cache.texture(source.clone())).draw(&mut cached);
+  rect.texture(source.clone()).draw(&mut plain);
+  assert_eq!(cache.len(), 2);
+
+  // close to resizing each shape individually
+  let diff = cached.pixels().zip(plain.pixels())
+    .map(|(a, b)| a.0.iter().zip(b.0).map(|(&a, b)| a.abs_diff(b) as f64).sum::<f64>())
+    .sum::<f64>() / (256.0 * 256.0 * 4.0);
+  assert!(diff < 2.0, "{diff}");
+
+  cache.clear();
+  assert!(cache.is_empty());
+  Ok(())
+}
+
+#[test] fn png_streaming() -> Result<()> {
+  use rayon::prelude::*;
+  let shapes = || (0..100).map(|i| {
+    let p = util::halton(i);
+    Box::new(Circle.translate(V2::new(p.x, p.y * 2.0)).scale(0.05 + 0.1 * p.x)
+      .texture(Rgba([(i * 37 % 256) as u8, 128, 255, 200]))) as Box<dyn DrawTile<f64, RgbaImage> + Send + Sync>
+  });
+  let background = Rgba([0, 0, 0, 255]);
+  let resolution = Size2D::new(100, 230);
+
+  let mut png = vec![];
+  draw_png_streaming(&mut png, resolution, background, shapes())?;
+  let streamed = image::load_from_memory(&png)?.to_rgba8();
+
+  let mut image = RgbaImage::from_pixel(resolution.width, resolution.height, background);
+  draw_parallel(&mut image, shapes().collect::<Vec<_>>().into_par_iter());
+  assert!(streamed == image);
+  Ok(())
+}
+
+#[test] fn high_depth() -> Result<()> {
+  use std::collections::HashSet;
+  // a soft gradient over the whole canvas, which bands at 8 bits
+  fn gradient<Px: Pixel>(stops: ColorStops<Px>) -> Texture<Scale<Translation<Square, f64>, f64>, LinearGradient<Px>> {
+    Square.translate(V2::splat(0.5)).scale(0.5)
+      .texture(LinearGradient { start: Point2D::splat(0.0), end: Point2D::new(1.0, 0.0), stops })
+  }
+  let mut image = image::ImageBuffer::<Rgba<u16>, _>::new(512, 512);
+  gradient(vec![(0.0, Rgba([0, 0, 0, 65535])), (1.0, Rgba([2048, 0, 0, 65535]))]).draw(&mut image);
+  // more levels than 8 bits could hold, within 1/32 of the range
+  assert!(image.rows().nth(256).unwrap().map(|px| px.0[0]).collect::<HashSet<_>>().len() > 256);
+  let mut image = image::Rgba32FImage::new(256, 256);
+  gradient(vec![(0.0, Rgba([0.0, 0.25, 0.5, 1.0])), (1.0, Rgba([2.0, 0.25, 0.5, 1.0]))]).draw(&mut image);
+
+  let mut exr = vec![];
+  write_exr(&mut exr, &image)?;
+  let u32_at = |at: usize| u32::from_le_bytes(exr[at..at + 4].try_into().unwrap());
+  let f32_at = |at: usize| f32::from_le_bytes(exr[at..at + 4].try_into().unwrap());
+  assert_eq!(u32_at(0), 20000630);
+  // the last attribute, the end of the header, and the offsets of both scanlines
+  let last = b"screenWindowWidth\0float\0";
+  let offsets = exr.windows(last.len()).position(|w| w == last).unwrap() + last.len() + 9;
+  let first = u32_at(offsets) as usize;
+  assert_eq!(exr.len(), first + 256 * (8 + 256 * 16));
+  let line = u32_at(offsets + 128 * 8) as usize;
+  assert_eq!(line, first + 128 * (8 + 256 * 16));
+  assert_eq!([u32_at(line), u32_at(line + 4)], [128, 256 * 16]);
+  // channels A, B, G, R, of the pixel (128, 128)
+  let channel = |c: usize| f32_at(line + 8 + (c * 256 + 128) * 4);
+  assert_eq!([channel(0), channel(1), channel(2)], [1.0, 0.5, 0.25]);
+  assert!((channel(3) - 1.0).abs() < 0.02, "{}", channel(3));
+  Ok(())
+}
+
+#[test] fn viewport() {
+  use rayon::prelude::*;
+  let circle = || Circle.translate(V2::new(1.5, 0.5)).scale(0.25).texture(Rgba([255, 0, 0, 255]));
+  // the default mapping letterboxes the unit square
+  let viewport = Viewport::fit(Size2D::new(200, 100));
+  assert_eq!(viewport.to_pixel(Point2D::new(1.0, 1.0)), Point2D::new(150.0, 100.0));
+  assert_eq!(viewport.to_world(Point2D::new(50.0, 0.0)), Point2D::origin());
+  let (mut default, mut fit) = (RgbaImage::new(200, 100), RgbaImage::new(200, 100));
+  Circle.translate(V2::splat(0.5)).scale(0.25).texture(Rgba([255, 0, 0, 255])).draw(&mut default);
+  Circle.translate(V2::splat(0.5)).scale(0.25).texture(Rgba([255, 0, 0, 255])).draw_viewport(&mut fit, viewport);
+  assert!(default == fit);
+
+  // a wider world, onto the whole of a non-square canvas
+  let viewport = Viewport::crop(Box2D::new(Point2D::origin(), Point2D::new(2.0, 1.0)), Size2D::new(200, 100));
+  let mut image = RgbaImage::new(200, 100);
+  circle().draw_viewport(&mut image, viewport);
+  assert_eq!(image.get_pixel(150, 50).0, [255, 0, 0, 255]);
+  assert_eq!(image.get_pixel(150, 80).0, [0; 4]);
+
+  // zoomed in on the circle, at full resolution
+  let viewport = viewport.with_pan(V2::new(0.5, 0.0)).with_zoom(2.0);
+  assert_eq!(viewport.world, Box2D::new(Point2D::new(1.0, 0.25), Point2D::new(2.0, 0.75)));
+  let mut zoomed = RgbaImage::new(200, 100);
+  circle().draw_viewport(&mut zoomed, viewport);
+  assert_eq!(zoomed.get_pixel(100, 50).0, [255, 0, 0, 255]);
+  assert_eq!(zoomed.get_pixel(100, 3).0, [255, 0, 0, 255]);
+  assert_eq!(zoomed.get_pixel(45, 50).0, [0; 4]);
+  let mut parallel = RgbaImage::new(200, 100);
+  draw_parallel_with(&mut parallel, viewport, (0..1).into_par_iter()
+    .map(|_| Box::new(circle()) as Box<dyn DrawTile<f64, RgbaImage> + Send + Sync>));
+  assert!(zoomed == parallel);
+}
+
+#[test] fn instance_map() {
+  let mut map = InstanceMap::new(128, 64);
+  let shapes = [
+    Circle.translate(V2::new(0.5, 0.5)).scale(0.25).texture(InstanceId(0)),
+    Circle.translate(V2::new(0.7, 0.5)).scale(0.25).texture(InstanceId(1)),
+    // smaller than a pixel
+    Circle.translate(V2::new(0.1, 0.1)).scale(0.001).texture(InstanceId(2))
+  ];
+  shapes.iter().for_each(|shape| shape.draw(&mut map));
+  // the unit square is centered, 64 pixels wide
+  assert_eq!(map.get(48, 32), Some(InstanceId(0)));
+  assert_eq!(map.get(64, 32), Some(InstanceId(1)));
+  assert_eq!(map.get(38, 32), None);
+  assert_eq!(map.get(128, 0), None);
+  assert_eq!(map.pixels_of(InstanceId(2)).collect::<Vec<_>>(), [Point2D::new(38, 6)]);
+  // no antialiasing, nor blending of ids along the edges
+  assert!(map.ids.pixels().all(|px| px.0[0] <= 2 || px.0[0] == InstanceMap::BACKGROUND));
+}
+
+#[test] fn arc_pie() -> Result<()> {
+  use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+  let arc = geometry::Arc { aperture: Angle::radians(FRAC_PI_2), thickness: 0.2 };
+  let pie = geometry::Pie { aperture: Angle::radians(FRAC_PI_4) };
+  let mut image = RgbaImage::new(128, 128);
+  arc.translate(V2::splat(0.5)).scale(0.4).texture(Rgba([255, 0, 0, 255])).draw(&mut image);
+  pie.translate(V2::splat(0.5)).scale(0.3).texture(Rgba([0, 0, 255, 255])).draw(&mut image);
+  image.save("test/test_arc_pie.png")?;
+  assert_eq!(image.get_pixel(64, 17).0, [255, 0, 0, 255]);
+  assert_eq!(image.get_pixel(64, 40).0, [0, 0, 255, 255]);
+  assert_eq!(image.get_pixel(64, 80).0, [0; 4]);
+  Ok(())
+}
+
+#[test] fn polyline() {
+  let vertices = [Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0)];
+  let open = geometry::Polyline { vertices, thickness: 0.2, closed: false };
+  let mut image = RgbaImage::new(64, 64);
+  open.translate(V2::splat(0.2)).scale(0.5).texture(Rgba([255; 4])).draw(&mut image);
+  assert!(image.pixels().any(|px| px.0 == [255; 4]));
+}
+
+#[test] fn outline() {
+  use {euclid::Transform2D, vector::{Outline, VectorShape}};
+  // circles stay circles under a similarity, and offsets
+  let similarity = Transform2D::scale(0.5, 0.5)
+    .then_rotate(Angle::degrees(30.0))
+    .then_translate(V2::new(0.5, 0.25));
+  assert!(matches!(Circle.transform(similarity).outline(), Some(Outline::Circle { radius, .. }) if close(radius, 0.5)));
+  assert!(matches!(Circle.offset(0.5).outline(), Some(Outline::Circle { radius, .. }) if close(radius, 1.5)));
+  // but not under a shear, which maps polygons to polygons
+  let shear = Transform2D::new(2.0, 0.0, 1.0, 1.0, 0.0, 0.0);
+  assert!(VectorShape::<f64>::outline(&Circle.transform(shear)).is_none());
+  assert!(matches!(Square.transform(shear).outline(), Some(Outline::Polygon(vertices)) if vertices.contains(&Point2D::new(3.0, 1.0))));
+}
+
+#[test] fn warp_displace() {
+  let wavy = Circle.displace(|p| (p.x * 20.0).sin() * 0.05, 0.05);
+  let mut image = RgbaImage::new(64, 64);
+  wavy.translate(V2::splat(0.5)).scale(0.4).texture(Rgba([255; 4])).draw(&mut image);
+  assert!(image.pixels().any(|px| px.0 == [255; 4]));
+}
+
+#[test] fn texture_gradient() {
+  let vertices = [Point2D::new(-0.5, -0.4), Point2D::new(0.6, -0.2), Point2D::new(0.1, 0.7)];
+  let polygon = geometry::Polygon { vertices: &vertices[..] };
+  let textured = polygon.texture(Rgba([255; 4]));
+  // forwarded to the shape
+  itertools::iproduct!(0..9, 0..9)
+    .map(|(x, y)| Point2D::new(x as f64 / 3.7 - 1.3, y as f64 / 3.3 - 1.1))
+    .for_each(|p| assert_eq!(textured.gradient(p), polygon.gradient(p), "{p:?}"));
+}
+
+#[test] fn geo_export() {
+  let area = |ring: &[[f64; 2]]| ring.windows(2).map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1]).sum::<f64>() / 2.0;
+  let mut export = GeoExport::new().with_segments(256);
+  export
+    .push(&Circle.scale(0.25).translate(V2::splat(0.5)))
+    .push(&geometry::Line { a: Point2D::new(0.1, 0.1), b: Point2D::new(0.9, 0.1), thickness: 0.1 })
+    .push(&Square.scale(0.1).translate(V2::splat(0.8)))
+    // traced, a ring with a hole, and nothing
+    .push(&Circle.scale(0.1).translate(V2::new(0.2, 0.8)).stroke(0.05))
+    .push(&Circle.scale(0.1).translate(V2::splat(5.0)).subtraction(Circle.scale(0.2).translate(V2::splat(5.0))));
+  assert_eq!(export.len(), 4);
+
+  let wkt = export.to_wkt();
+  let lines: Vec<_> = wkt.lines().collect();
+  assert!(lines.iter().all(|line| line.starts_with("POLYGON ((") && line.ends_with("))")));
+  assert_eq!(lines[3].matches('(').count(), 3, "exterior and hole");
+
+  let json: serde_json::Value = serde_json::from_str(&export.to_geojson()).unwrap();
+  let features = json["features"].as_array().unwrap();
+  assert_eq!(features.len(), 4);
+  let rings = |i: usize| -> Vec<Vec<[f64; 2]>> {
+    serde_json::from_value(features[i]["geometry"]["coordinates"].clone()).unwrap()
+  };
+  // closed, exteriors positive, holes negative
+  (0..4).flat_map(rings).for_each(|ring| assert_eq!(ring.first(), ring.last()));
+  assert!((area(&rings(0)[0]) - std::f64::consts::PI * 0.0625).abs() < 1e-3);
+  assert!((area(&rings(1)[0]) - (0.08 + std::f64::consts::PI * 0.0025)).abs() < 1e-4);
+  assert!((area(&rings(2)[0]) - 0.04).abs() < 1e-12);
+  let (exterior, hole) = (area(&rings(3)[0]), area(&rings(3)[1]));
+  assert!(exterior > 0.0 && hole < 0.0);
+  assert!((exterior + hole - std::f64::consts::PI * (0.125f64.powi(2) - 0.075f64.powi(2))).abs() < 1e-3);
+}
+
+#[test] fn equirectangular() -> Result<()> {
+  use {crate::solver::sphere::{self, Cap}, std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI}};
+  let mut canvas = Equirectangular::new(64);
+  let red = Rgba([255, 0, 0, 255]);
+  // the northern polar cap, down to the latitude of 45°
+  canvas.draw_cap(Cap::new(sphere::from_lat_long(FRAC_PI_2, 0.0), FRAC_PI_4), red);
+  assert_eq!(canvas.image.dimensions(), (128, 64));
+  assert!((0..128).all(|x| canvas.image[(x, 0)] == red && canvas.image[(x, 14)] == red));
+  assert!((0..128).all(|x| canvas.image[(x, 18)].0[3] == 0 && canvas.image[(x, 63)].0[3] == 0));
+  // across the antimeridian
+  canvas.draw_cap(Cap::new(sphere::from_lat_long(0.0, PI), 0.2), red);
+  assert!(canvas.image[(0, 32)] == red && canvas.image[(127, 32)] == red && canvas.image[(64, 32)].0[3] == 0);
+  canvas.image.save("test/test_equirectangular.png")?;
+  Ok(())
+}
@@ -0,0 +1,76 @@
+use {
+  std::{collections::HashMap, sync::{Arc, PoisonError, RwLock}},
+  euclid::Size2D,
+  image::{DynamicImage, RgbaImage},
+  crate::geometry::PixelSpace
+};
+
+// address of the source image, and the size of the bucket
+type Key = (usize, Size2D<u32, PixelSpace>);
+
+/// Image textures, cropped and resized once per source image and size bucket, and shared
+/// between draw calls and threads. See [`TextureCache::texture`].
+///
+/// Buckets preserve the aspect ratio of the target, and round its longer side up to a power
+/// of two, so that shapes of similar size reuse the same texture. Entries keep their source
+/// image alive, till [`TextureCache::clear`].
+#[derive(Default)]
+pub struct TextureCache {
+  entries: RwLock<HashMap<Key, (Arc<DynamicImage>, Arc<RgbaImage>)>>
+}
+
+/// Texture of an image, resized through a [`TextureCache`].
+#[derive(Clone)]
+pub struct CachedImage {
+  pub image: Arc<DynamicImage>,
+  pub cache: Arc<TextureCache>
+}
+
+impl TextureCache {
+  pub fn new() -> Arc<Self> {
+    Arc::new(Self::default())
+  }
+
+  /// `image` as a texture, cached by `self`.
+  pub fn texture(self: &Arc<Self>, image: Arc<DynamicImage>) -> CachedImage {
+    CachedImage { image, cache: self.clone() }
+  }
+
+  /// `image`, covering the bucket of `size`, see [`TextureCache`]. Concurrent misses on the
+  /// same entry may resize the image more than once.
+  pub fn get(
+    &self,
+    image: &Arc<DynamicImage>,
+    size: Size2D<u32, PixelSpace>,
+    resize: impl FnOnce(&DynamicImage, Size2D<u32, PixelSpace>) -> RgbaImage
+  ) -> Arc<RgbaImage> {
+    let bucket = bucket(size);
+    let key = (Arc::as_ptr(image) as usize, bucket);
+    if let Some((_, texture)) = self.entries.read().unwrap_or_else(PoisonError::into_inner).get(&key) {
+      return texture.clone();
+    }
+    let texture = Arc::new(resize(image, bucket));
+    self.entries.write().unwrap_or_else(PoisonError::into_inner)
+      .entry(key)
+      .or_insert((image.clone(), texture))
+      .1.clone()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.read().unwrap_or_else(PoisonError::into_inner).len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn clear(&self) {
+    self.entries.write().unwrap_or_else(PoisonError::into_inner).clear();
+  }
+}
+
+fn bucket(size: Size2D<u32, PixelSpace>) -> Size2D<u32, PixelSpace> {
+  let longer = size.width.max(size.height).max(1);
+  let scale = longer.next_power_of_two() as f64 / longer as f64;
+  (size.to_f64() * scale).round().max(Size2D::splat(1.0)).to_u32()
+}
@@ -0,0 +1,223 @@
+//! Shared by the vector backends, [`SvgBackend`](super::SvgBackend) and
+//! [`PostScriptBackend`](super::PostScriptBackend).
+
+use {
+  euclid::{Box2D, Point2D, Rotation2D, Size2D, Vector2D as V2},
+  num_traits::{Float, FloatConst},
+  crate::{
+    geometry::{
+      self, BoundingBox, WorldSpace, P2,
+      Translation, Rotation, Scale, Transform2D, Mirror, Repeat, RepeatPolar, Offset, Warp, Displace, Stroke, Anisotropic
+    },
+    sdf::{Union, Subtraction, Intersection, Xor, Morph, SmoothMin, SmoothMinPoly},
+    util
+  }
+};
+
+/// Exact outline of a shape, in world space.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outline<T> {
+  Circle { center: P2<T>, radius: T },
+  Polygon(Vec<P2<T>>),
+  /// A segment with round caps, see [`geometry::Line`]
+  Line { a: P2<T>, b: P2<T>, thickness: T }
+}
+
+impl<T: Float> Outline<T> {
+  /// Apply a similarity transform `f`, scaling lengths by `scale`.
+  pub fn map(self, f: impl Fn(P2<T>) -> P2<T>, scale: T) -> Self {
+    match self {
+      Self::Circle { center, radius } => Self::Circle { center: f(center), radius: radius * scale },
+      Self::Polygon(vertices) => Self::Polygon(vertices.into_iter().map(f).collect()),
+      Self::Line { a, b, thickness } => Self::Line { a: f(a), b: f(b), thickness: thickness * scale }
+    }
+  }
+
+  pub(crate) fn cast<U: Float>(self) -> Outline<U> {
+    let cast = |x: T| U::from(x).unwrap();
+    match self {
+      Self::Circle { center, radius } => Outline::Circle { center: center.cast(), radius: cast(radius) },
+      Self::Polygon(vertices) => Outline::Polygon(vertices.into_iter().map(|p| p.cast()).collect()),
+      Self::Line { a, b, thickness } => Outline::Line { a: a.cast(), b: b.cast(), thickness: cast(thickness) }
+    }
+  }
+}
+
+/// Shape, which may have an exact vector [`Outline`], for [`SvgBackend`]. The default of `None`
+/// falls back to tracing the iso-contour, so custom shapes may implement it with an empty body.
+pub trait VectorShape<T> {
+  fn outline(&self) -> Option<Outline<T>> { None }
+}
+
+impl<T: Float> VectorShape<T> for geometry::Circle {
+  fn outline(&self) -> Option<Outline<T>> {
+    Some(Outline::Circle { center: Point2D::origin(), radius: T::one() })
+  }
+}
+
+impl<T: Float> VectorShape<T> for geometry::Rect<T, WorldSpace> {
+  fn outline(&self) -> Option<Outline<T>> {
+    let half = self.size.to_vector() / (T::one() + T::one());
+    Some(Outline::Polygon(vec![
+      P2::new(-half.x, -half.y),
+      P2::new(half.x, -half.y),
+      P2::new(half.x, half.y),
+      P2::new(-half.x, half.y)
+    ]))
+  }
+}
+
+impl<T: Float> VectorShape<T> for geometry::Square {
+  fn outline(&self) -> Option<Outline<T>> {
+    geometry::Rect { size: Point2D::splat(T::one() + T::one()) }.outline()
+  }
+}
+
+impl<T: Float> VectorShape<T> for geometry::Line<T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    Some(Outline::Line { a: self.a, b: self.b, thickness: self.thickness })
+  }
+}
+
+impl<T: Float, U: AsRef<[P2<T>]>> VectorShape<T> for geometry::Polygon<U> {
+  fn outline(&self) -> Option<Outline<T>> {
+    Some(Outline::Polygon(self.vertices.as_ref().to_vec()))
+  }
+}
+
+impl<T: Float> VectorShape<T> for geometry::ConvexHull<T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    (self.vertices().len() > 2).then(|| Outline::Polygon(self.vertices().to_vec()))
+  }
+}
+
+// vertices of a regular polygon, matching the SDF of `NGonC`
+fn ngon<T: Float + FloatConst>(n: u64) -> Option<Outline<T>> {
+  let n_ = T::from(n).unwrap();
+  Some(Outline::Polygon((0..n).map(|k| {
+    let angle = -T::FRAC_PI_2() + T::PI() / n_ + T::TAU() * T::from(k).unwrap() / n_;
+    P2::new(angle.cos(), angle.sin())
+  }).collect()))
+}
+
+impl<T: Float + FloatConst, const N: usize> VectorShape<T> for geometry::NGonC<N> {
+  fn outline(&self) -> Option<Outline<T>> {
+    ngon(N as u64)
+  }
+}
+
+impl<T: Float + FloatConst> VectorShape<T> for geometry::NGonR {
+  fn outline(&self) -> Option<Outline<T>> {
+    ngon(self.n)
+  }
+}
+
+impl<T: Float, S: VectorShape<T>> VectorShape<T> for Translation<S, T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    Some(self.shape.outline()?.map(|p| p + self.offset, T::one()))
+  }
+}
+
+impl<T: Float, S: VectorShape<T> + BoundingBox<T>> VectorShape<T> for Rotation<S, T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    let pivot = self.shape.bounding_box().center();
+    let rotation = Rotation2D::new(-self.angle);
+    Some(self.shape.outline()?.map(|p| rotation.transform_point((p - pivot).to_point()) + pivot.to_vector(), T::one()))
+  }
+}
+
+impl<T: Float, S: VectorShape<T> + BoundingBox<T>> VectorShape<T> for Scale<S, T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    let c = self.shape.bounding_box().center();
+    Some(self.shape.outline()?.map(|p| c + (p - c) * self.scale, self.scale))
+  }
+}
+
+impl<T: Float, S: VectorShape<T>> VectorShape<T> for Transform2D<S, T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    let t = &self.transform;
+    let eps = (t.m11.abs() + t.m12.abs() + t.m21.abs() + t.m22.abs()) * T::epsilon() * T::from(4).unwrap();
+    let similarity = (t.m11 - t.m22).abs() + (t.m12 + t.m21).abs() <= eps
+      || (t.m11 + t.m22).abs() + (t.m12 - t.m21).abs() <= eps;
+    // circles and round caps are only preserved by similarities
+    match self.shape.outline()? {
+      outline @ Outline::Polygon(_) => Some(outline),
+      _ if !similarity => None,
+      outline => Some(outline)
+    }.map(|outline| outline.map(|p| t.transform_point(p), self.min_scale()))
+  }
+}
+
+impl<T> VectorShape<T> for geometry::Star<T> {}
+impl<T> VectorShape<T> for geometry::Moon<T> {}
+impl<T> VectorShape<T> for geometry::Kakera<T> {}
+impl<T> VectorShape<T> for geometry::Cross<T> {}
+impl<T> VectorShape<T> for geometry::Ring<T> {}
+impl<T> VectorShape<T> for geometry::RoundedRect<T> {}
+impl<T> VectorShape<T> for geometry::Bezier2<T> {}
+impl<T> VectorShape<T> for geometry::Bezier3<T> {}
+impl<T, U> VectorShape<T> for geometry::Polyline<T, U> {}
+impl<T> VectorShape<T> for geometry::Bitmap {}
+impl<T> VectorShape<T> for geometry::Path<T> {}
+impl<T> VectorShape<T> for geometry::Group<T> {}
+impl<T> VectorShape<T> for geometry::HalfPlane<T> {}
+impl<T> VectorShape<T> for geometry::Strip<T> {}
+#[cfg(feature = "text")] impl<T> VectorShape<T> for geometry::Glyph<T> {}
+#[cfg(feature = "text")] impl<T> VectorShape<T> for geometry::Text<T> {}
+#[cfg(feature = "svg")] impl<T> VectorShape<T> for geometry::Svg<T> {}
+impl<T> VectorShape<T> for geometry::Arc<T> {}
+impl<T> VectorShape<T> for geometry::Pie<T> {}
+impl<T> VectorShape<T> for geometry::Pentagram {}
+impl<T> VectorShape<T> for geometry::Hexagram {}
+impl<T, S> VectorShape<T> for Mirror<S> {}
+impl<T, S> VectorShape<T> for Repeat<S, T> {}
+impl<T, S> VectorShape<T> for RepeatPolar<S> {}
+impl<T: Float, S: VectorShape<T>> VectorShape<T> for Offset<S, T> {
+  fn outline(&self) -> Option<Outline<T>> {
+    // exact for round outlines only
+    match self.shape.outline()? {
+      Outline::Circle { center, radius } if radius + self.radius > T::zero() =>
+        Some(Outline::Circle { center, radius: radius + self.radius }),
+      Outline::Line { a, b, thickness } if thickness + self.radius * (T::one() + T::one()) > T::zero() =>
+        Some(Outline::Line { a, b, thickness: thickness + self.radius * (T::one() + T::one()) }),
+      _ => None
+    }
+  }
+}
+impl<T, S, F> VectorShape<T> for Warp<S, F, T> {}
+impl<T, S, F> VectorShape<T> for Displace<S, F, T> {}
+impl<T, S> VectorShape<T> for Stroke<S, T> {}
+impl<T, S> VectorShape<T> for Anisotropic<S, T> {}
+impl<T, S1, S2> VectorShape<T> for Union<S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for Subtraction<S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for Intersection<S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for Xor<S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for Morph<T, S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for SmoothMin<T, S1, S2> {}
+impl<T, S1, S2> VectorShape<T> for SmoothMinPoly<T, S1, S2> {}
+
+// closed iso-contours of `sdf` at zero (marching squares), traced on a grid of `cells` over the
+// bounding box, enlarged by a cell so that every contour is closed
+pub(crate) fn contours(sdf: impl Fn(P2<f64>) -> f64, bounding_box: Box2D<f64, WorldSpace>, cells: Size2D<usize, WorldSpace>) -> Vec<Vec<P2<f64>>> {
+  let cell = bounding_box.size().to_vector().component_div(cells.to_f64().to_vector());
+  let Size2D { width: nx, height: ny, .. } = cells + Size2D::splat(2);
+  let origin = bounding_box.min - cell;
+  let corner = |i: usize, j: usize| origin + V2::new(i as f64 * cell.x, j as f64 * cell.y);
+  let values: Vec<f64> = itertools::iproduct!(0..=ny, 0..=nx)
+    .map(|(j, i)| sdf(corner(i, j)))
+    .collect();
+  util::trace_contours(&values, nx, ny, corner).into_iter()
+    .map(|(contour, _)| contour)
+    .collect()
+}
+
+// a coordinate in the units of the backend (pixels, points), to a thousandth, without
+// trailing zeros
+pub(crate) fn num(x: f64) -> String {
+  let s = format!("{x:.3}");
+  let s = s.trim_end_matches('0').trim_end_matches('.');
+  match s {
+    "-0" => "0".to_string(),
+    s => s.to_string()
+  }
+}
@@ -0,0 +1,243 @@
+use {
+  super::{BoundingBox, Circle, ConvexHull, Line, Polygon, Rect, Rotation, Scale, Shape, Square, Translation, WorldSpace, P2},
+  euclid::{Point2D, Vector2D as V2},
+  num_traits::{Float, FloatConst}
+};
+
+/// Area, perimeter and centroid of a shape, e.g. for coverage statistics of a distribution.
+/// Analytic for the primitives, and estimated from the SDF by [`Sampled`] otherwise.
+pub trait Measure<T> {
+  fn area(&self) -> T;
+  fn perimeter(&self) -> T;
+  /// Center of mass of the interior
+  fn centroid(&self) -> P2<T>;
+}
+
+impl<T: Float + FloatConst> Measure<T> for Circle {
+  fn area(&self) -> T {
+    T::PI()
+  }
+  fn perimeter(&self) -> T {
+    T::TAU()
+  }
+  fn centroid(&self) -> P2<T> {
+    Point2D::origin()
+  }
+}
+
+impl<T: Float> Measure<T> for Rect<T, WorldSpace> {
+  fn area(&self) -> T {
+    self.size.x * self.size.y
+  }
+  fn perimeter(&self) -> T {
+    (self.size.x + self.size.y) * (T::one() + T::one())
+  }
+  fn centroid(&self) -> P2<T> {
+    Point2D::origin()
+  }
+}
+
+impl<T: Float> Measure<T> for Square {
+  fn area(&self) -> T {
+    T::from(4).unwrap()
+  }
+  fn perimeter(&self) -> T {
+    T::from(8).unwrap()
+  }
+  fn centroid(&self) -> P2<T> {
+    Point2D::origin()
+  }
+}
+
+/// A capsule, the segment with round caps.
+impl<T: Float + FloatConst> Measure<T> for Line<T> {
+  fn area(&self) -> T {
+    let r = self.thickness / (T::one() + T::one());
+    (self.b - self.a).length() * self.thickness + T::PI() * r * r
+  }
+  fn perimeter(&self) -> T {
+    (self.b - self.a).length() * (T::one() + T::one()) + T::PI() * self.thickness
+  }
+  fn centroid(&self) -> P2<T> {
+    self.a.lerp(self.b, T::one() / (T::one() + T::one()))
+  }
+}
+
+/// A simple polygon, in either winding.
+impl<T, U> Measure<T> for Polygon<U>
+  where T: Float,
+        U: AsRef<[P2<T>]> {
+  fn area(&self) -> T {
+    self.edges().fold(T::zero(), |sum, (a, b)| sum + a.to_vector().cross(b.to_vector())).abs()
+      / (T::one() + T::one())
+  }
+  fn perimeter(&self) -> T {
+    self.edges().fold(T::zero(), |sum, (a, b)| sum + (b - a).length())
+  }
+  fn centroid(&self) -> P2<T> {
+    let v = self.vertices.as_ref();
+    let (area, moment) = self.edges().fold((T::zero(), V2::zero()), |(area, moment), (a, b)| {
+      let cross = a.to_vector().cross(b.to_vector());
+      (area + cross, moment + (a.to_vector() + b.to_vector()) * cross)
+    });
+    if area != T::zero() {
+      (moment / (area * T::from(3).unwrap())).to_point()
+    } else {
+      // degenerate, the mean of the vertices
+      let sum = v.iter().fold(V2::zero(), |sum, p| sum + p.to_vector());
+      (sum / T::from(v.len().max(1)).unwrap()).to_point()
+    }
+  }
+}
+
+impl<U> Polygon<U> {
+  fn edges<'a, T>(&'a self) -> impl Iterator<Item = (P2<T>, P2<T>)> + 'a
+    where T: Float + 'a,
+          U: AsRef<[P2<T>]>
+  {
+    let v = self.vertices.as_ref();
+    (0..v.len()).map(move |i| (v[i], v[(i + 1) % v.len()]))
+  }
+}
+
+impl<T: Float> Measure<T> for ConvexHull<T> {
+  fn area(&self) -> T {
+    Polygon { vertices: self.vertices() }.area()
+  }
+  fn perimeter(&self) -> T {
+    Polygon { vertices: self.vertices() }.perimeter()
+  }
+  fn centroid(&self) -> P2<T> {
+    Polygon { vertices: self.vertices() }.centroid()
+  }
+}
+
+impl<S, T> Measure<T> for Translation<S, T>
+  where S: Measure<T>,
+        T: Float {
+  fn area(&self) -> T {
+    self.shape.area()
+  }
+  fn perimeter(&self) -> T {
+    self.shape.perimeter()
+  }
+  fn centroid(&self) -> P2<T> {
+    self.shape.centroid() + self.offset
+  }
+}
+
+impl<S, T> Measure<T> for Rotation<S, T>
+  where S: Measure<T> + BoundingBox<T>,
+        T: Float {
+  fn area(&self) -> T {
+    self.shape.area()
+  }
+  fn perimeter(&self) -> T {
+    self.shape.perimeter()
+  }
+  fn centroid(&self) -> P2<T> {
+    self.to_world().transform_point(self.shape.centroid())
+  }
+}
+
+impl<S, T> Measure<T> for Scale<S, T>
+  where S: Measure<T> + BoundingBox<T>,
+        T: Float {
+  fn area(&self) -> T {
+    self.shape.area() * self.scale * self.scale
+  }
+  fn perimeter(&self) -> T {
+    self.shape.perimeter() * self.scale
+  }
+  fn centroid(&self) -> P2<T> {
+    let c = self.shape.bounding_box().center();
+    c + (self.shape.centroid() - c) * self.scale
+  }
+}
+
+/// [`Measure`] of an arbitrary shape, estimated from its SDF on a grid of square cells,
+/// `resolution` of them along the longer side of the bounding box. The area and centroid
+/// weigh each cell by its antialiased coverage; the perimeter integrates a band of a cell
+/// around the edge, hence assumes an exact distance field. The error is about a cell.
+#[derive(Debug, Copy, Clone)]
+pub struct Sampled<S> {
+  pub shape: S,
+  pub resolution: u32
+}
+
+impl<S> Sampled<S> {
+  // area, perimeter, and the first moment of the area
+  fn integrate<T>(&self) -> (T, T, V2<T, WorldSpace>)
+    where S: Shape<T>,
+          T: Float
+  {
+    let bounding_box = self.shape.bounding_box();
+    let size = bounding_box.size();
+    let resolution = T::from(self.resolution.max(1)).unwrap();
+    let cell = size.width.max(size.height) / resolution;
+    if cell <= T::zero() {
+      return (T::zero(), T::zero(), V2::zero());
+    }
+    let p5 = T::one() / (T::one() + T::one());
+    // with a margin of a cell, for the band around the edge
+    let cells = |side: T| (side / cell).ceil().to_usize().unwrap() + 2;
+    let origin = bounding_box.min - V2::splat(cell);
+    let (area, perimeter, moment) = itertools::iproduct!(0..cells(size.height), 0..cells(size.width))
+      .map(|(y, x)| origin + V2::new(T::from(x).unwrap() + p5, T::from(y).unwrap() + p5) * cell)
+      .fold((T::zero(), T::zero(), V2::zero()), |(area, perimeter, moment), p| {
+        let sdf = self.shape.sdf(p) / cell;
+        let coverage = (p5 - sdf).max(T::zero()).min(T::one());
+        let band = (T::one() - sdf.abs()).max(T::zero());
+        (area + coverage, perimeter + band, moment + p.to_vector() * coverage)
+      });
+    // sums over the cells, into world units
+    (area * cell * cell, perimeter * cell, moment * cell * cell)
+  }
+}
+
+impl<S, T> Measure<T> for Sampled<S>
+  where S: Shape<T>,
+        T: Float {
+  fn area(&self) -> T {
+    self.integrate().0
+  }
+  fn perimeter(&self) -> T {
+    self.integrate().1
+  }
+  fn centroid(&self) -> P2<T> {
+    let (area, _, moment) = self.integrate();
+    if area > T::zero() { (moment / area).to_point() } else { self.shape.bounding_box().center() }
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::tests::close, euclid::Angle};
+
+  #[test] fn analytic() {
+    let vertices = [Point2D::new(0.0, 0.0), Point2D::new(2.0, 0.0), Point2D::new(0.0, 1.0)];
+    let triangle = Polygon { vertices: &vertices[..] };
+    assert!(close(triangle.area(), 1.0));
+    assert!(close(triangle.perimeter(), 3.0 + 5f64.sqrt()));
+    assert_eq!(triangle.centroid(), Point2D::new(2.0 / 3.0, 1.0 / 3.0));
+    let capsule = Line { a: Point2D::new(0.0, 0.0), b: Point2D::new(2.0, 0.0), thickness: 1.0 };
+    assert!(close(capsule.area(), 2.0 + std::f64::consts::PI / 4.0));
+    let moved = triangle.rotate(Angle::degrees(90.0)).scale(2.0).translate(V2::new(1.0, 0.0));
+    assert!(close(moved.area(), 4.0) && close(moved.perimeter(), 2.0 * triangle.perimeter()));
+    // the centroid is mapped as the shape, about the center of its bounding box
+    let c = moved.centroid();
+    assert!(close(c.x, 5.0 / 3.0) && close(c.y, 7.0 / 6.0), "{c:?}");
+  }
+
+  #[test] fn sampled() {
+    // sampled estimates agree with the analytic ones
+    let circle = Circle.scale(0.5).translate(V2::new(0.25, -0.5));
+    let sampled = Sampled { shape: circle, resolution: 200 };
+    assert!((Measure::<f64>::area(&sampled) - circle.area()).abs() < 1e-3);
+    assert!((Measure::<f64>::perimeter(&sampled) - circle.perimeter()).abs() < 1e-2);
+    assert!((Measure::<f64>::centroid(&sampled) - circle.centroid()).length() < 1e-3);
+    let capsule = Line { a: Point2D::new(-0.5, 0.0), b: Point2D::new(0.5, 0.5), thickness: 0.2 };
+    let sampled = Sampled { shape: capsule, resolution: 200 };
+    assert!((sampled.area() - capsule.area()).abs() < 1e-3);
+    assert!((sampled.perimeter() - capsule.perimeter()).abs() < 1e-2);
+  }
+}
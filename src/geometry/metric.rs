@@ -0,0 +1,113 @@
+use {
+  super::{BoundingBox, WorldSpace, P2, update_bounding_box},
+  euclid::{Box2D, Rect, Size2D, Vector2D as V2},
+  num_traits::Float
+};
+
+/// Constant anisotropic metric, given by a symmetric positive definite tensor `M`:
+/// `|v|_M = sqrt(vᵀ M v)`.
+///
+/// Distances under `M` are Euclidean distances in the "metric space", obtained from the world
+/// by the linear map `A`, where `M = AᵀA`. A unit circle in the metric space is an ellipse in
+/// the world, hence filling with [`Anisotropic`] circles packs elongated shapes tightly along
+/// the preferred direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Metric<T> {
+  tensor: [[T; 2]; 2],
+  /// `A`, an upper triangular factor of `M`
+  factor: [[T; 2]; 2]
+}
+
+impl<T: Float> Metric<T> {
+  /// Euclidean metric.
+  pub fn isotropic() -> Self {
+    Self::axes(T::one(), T::one())
+  }
+
+  /// Per-axis scaling: distances along `x` are multiplied by `sx`, along `y` by `sy`.
+  pub fn axes(sx: T, sy: T) -> Self {
+    Self::tensor([[sx * sx, T::zero()], [T::zero(), sy * sy]])
+  }
+
+  /// Arbitrary metric tensor. Panics if `m` is not symmetric positive definite.
+  pub fn tensor(m: [[T; 2]; 2]) -> Self {
+    assert!(m[0][1] == m[1][0], "metric tensor must be symmetric");
+    // Cholesky decomposition, M = L Lᵀ, A = Lᵀ
+    let l11 = m[0][0].sqrt();
+    let l21 = m[1][0] / l11;
+    let l22 = (m[1][1] - l21 * l21).sqrt();
+    assert!(l11 > T::zero() && l22 > T::zero(), "metric tensor must be positive definite");
+    Self {
+      tensor: m,
+      factor: [[l11, l21], [T::zero(), l22]]
+    }
+  }
+
+  pub fn length(&self, v: V2<T, WorldSpace>) -> T {
+    self.to_metric(v).length()
+  }
+
+  pub fn distance(&self, a: P2<T>, b: P2<T>) -> T {
+    self.length(b - a)
+  }
+
+  /// Apply `A`, mapping world into the metric space.
+  pub fn to_metric(&self, v: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let a = self.factor;
+    V2::new(a[0][0] * v.x + a[0][1] * v.y, a[1][1] * v.y)
+  }
+
+  /// Apply `A⁻¹`, mapping the metric space back into world.
+  pub fn from_metric(&self, v: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let a = self.factor;
+    let y = v.y / a[1][1];
+    V2::new((v.x - a[0][1] * y) / a[0][0], y)
+  }
+
+  /// Apply `Aᵀ`, pulling a gradient in the metric space back into world.
+  pub fn gradient_from_metric(&self, g: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let a = self.factor;
+    V2::new(a[0][0] * g.x, a[0][1] * g.x + a[1][1] * g.y)
+  }
+
+  /// Half-size of the axis-aligned box in world, containing a metric ball of radius `r`.
+  pub fn ball_extent(&self, r: T) -> Size2D<T, WorldSpace> {
+    let m = self.tensor;
+    let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+    // diagonal of M⁻¹
+    Size2D::new((m[1][1] / det).sqrt(), (m[0][0] / det).sqrt()) * r
+  }
+
+  /// Distance to the edges of image, measured in this metric.
+  pub fn boundary_rect(&self, p: P2<T>) -> T {
+    let extent = self.ball_extent(T::one());
+    (p.x.min(T::one() - p.x) / extent.width)
+      .min(p.y.min(T::one() - p.y) / extent.height)
+  }
+
+  /// Bounding box of a metric ball with radius `r` around `center`.
+  pub fn ball_bounding_box(&self, center: P2<T>, r: T) -> Rect<T, WorldSpace> {
+    let extent = self.ball_extent(r);
+    Rect::new(center - extent.to_vector(), extent * (T::one() + T::one()))
+  }
+}
+
+/// A shape, defined in the metric space (see [`Metric`]). Its signed distance is measured
+/// in the units of the metric.
+#[derive(Debug, Copy, Clone)]
+pub struct Anisotropic<S, T> {
+  pub shape: S,
+  pub metric: Metric<T>
+}
+
+impl<S, T> BoundingBox<T> for Anisotropic<S, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    update_bounding_box(
+      self.shape.bounding_box(),
+      |p| self.metric.from_metric(p.to_vector()).to_point()
+    )
+  }
+}
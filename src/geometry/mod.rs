@@ -4,26 +4,54 @@
 //! interval `[-1, 1]`, and center in the origin.
 
 use {
-  std::ops::Add,
+  std::{fmt::Debug, ops::Add},
   euclid::{Point2D, Box2D, Vector2D as V2, Rotation2D, Angle},
-  num_traits::Float,
-  crate::sdf::{SDF, Union, Subtraction, Intersection, SmoothMin}
+  num_traits::{Float, FloatConst},
+  crate::sdf::{SDF, Union, Subtraction, Intersection, Xor, Morph, SmoothMin, SmoothMinPoly, Polynomial}
 };
 
 pub mod shapes;
 pub use shapes::*;
+pub mod metric;
+pub use metric::*;
+pub mod measure;
+pub use measure::*;
 
 /// Pixel coordinate basis
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PixelSpace;
 /// Normalized coordinate basis
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorldSpace;
 
 pub type P2<P> = Point2D<P, WorldSpace>;
 
+/// Affine map of world, see [`BoundingBox::tight_bounding_box`].
+pub type Affine<T> = euclid::Transform2D<T, WorldSpace, WorldSpace>;
+
 pub trait BoundingBox<T> {
   fn bounding_box(&self) -> Box2D<T, WorldSpace>;
+  /// Bounding box of the shape, mapped by `transform`. By default, the box of the mapped
+  /// corners of [`bounding_box`](BoundingBox::bounding_box), which is loose under rotation for
+  /// all but rectangles; primitives and transform wrappers override it with an exact one.
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> where T: Float {
+    update_bounding_box(self.bounding_box(), |p| transform.transform_point(p))
+  }
+}
+
+// bounding box of `points`, mapped by `transform`, and inflated by the image of a disk of
+// `radius` around each of them
+pub(crate) fn transformed_hull<T: Float>(
+  points: impl IntoIterator<Item = P2<T>>,
+  radius: T,
+  transform: &Affine<T>
+) -> Box2D<T, WorldSpace> {
+  let t = transform;
+  let hull = Box2D::from_points(points.into_iter().map(|p| t.transform_point(p)));
+  let extent = V2::new(t.m11.hypot(t.m21), t.m12.hypot(t.m22)) * radius;
+  Box2D::new(hull.min - extent, hull.max + extent)
 }
 
 /// Something inside a rectangular area.
@@ -39,6 +67,33 @@ pub trait Shape<T>: SDF<T> + BoundingBox<T> {
   fn scale(self, scale: T) -> Scale<Self, T> where Self: Sized {
     Scale { shape: self, scale }
   }
+  /// Apply an arbitrary affine `transform`, mapping the points of the shape into world. Unlike
+  /// [`rotate`](Shape::rotate) and [`scale`](Shape::scale), there is no implicit pivot.
+  fn transform(self, transform: euclid::Transform2D<T, WorldSpace, WorldSpace>) -> Transform2D<Self, T> where Self: Sized {
+    Transform2D { shape: self, transform }
+  }
+  /// Reflect across the `y` axis: the half of the shape at `x < 0` is replaced by the mirror
+  /// image of the half at `x > 0`.
+  fn mirror_x(self) -> Mirror<Self> where Self: Sized {
+    Mirror { shape: self, x: true, y: false }
+  }
+  /// Reflect across the `x` axis, see [`mirror_x`](Shape::mirror_x).
+  fn mirror_y(self) -> Mirror<Self> where Self: Sized {
+    Mirror { shape: self, x: false, y: true }
+  }
+  /// Reflect across both axes: the quadrant at `x > 0, y > 0` is repeated in the other three.
+  fn symmetric(self) -> Mirror<Self> where Self: Sized {
+    Mirror { shape: self, x: true, y: true }
+  }
+  /// Grid of `counts` copies, offset by multiples of `spacing`, starting with the shape itself.
+  /// Evaluated in constant time, see [`Repeat`].
+  fn repeat(self, counts: [u32; 2], spacing: V2<T, WorldSpace>) -> Repeat<Self, T> where Self: Sized {
+    Repeat { shape: self, counts, spacing }
+  }
+  /// Ring of `n` copies, rotated around the origin, see [`RepeatPolar`].
+  fn repeat_polar(self, n: u32) -> RepeatPolar<Self> where Self: Sized {
+    RepeatPolar { shape: self, n }
+  }
   /// Union of two SDFs.
   fn union<U>(self, other: U) -> Union<Self, U> where Self: Sized {
     Union { s1: self, s2: other }
@@ -52,21 +107,70 @@ pub trait Shape<T>: SDF<T> + BoundingBox<T> {
   fn intersection<U>(self, other: U) -> Intersection<Self, U> where Self: Sized {
     Intersection { s1: self, s2: other }
   }
+  /// Symmetric difference of two SDFs.
+  fn xor<U>(self, other: U) -> Xor<Self, U> where Self: Sized {
+    Xor { s1: self, s2: other }
+  }
+  /// Interpolate from this SDF at `t = 0` to `other` at `t = 1`, see [`Morph`].
+  fn morph<U>(self, other: U, t: T) -> Morph<T, Self, U> where Self: Sized {
+    Morph { s1: self, s2: other, t }
+  }
   /// Takes the minimum of two SDFs, smoothing between them when they are close.
   ///
   /// `k` controls the radius/distance of the smoothing. 32 is a good default value.
   fn smooth_min<U>(self, other: U, k: T) -> SmoothMin<T, Self, U> where Self: Sized {
     SmoothMin { s1: self, s2: other, k }
   }
+  /// Polynomial smooth minimum of two SDFs, at most `radius` below the plain one, see
+  /// [`SmoothMinPoly`]. Same as [`smooth_min`](Shape::smooth_min) of `k = 1 / radius`, in depth.
+  fn smooth_min_poly<U>(self, other: U, radius: T, polynomial: Polynomial) -> SmoothMinPoly<T, Self, U> where Self: Sized {
+    SmoothMinPoly { s1: self, s2: other, radius, polynomial }
+  }
+  /// Outline of the shape, a band of `width` centered on its edge.
+  fn stroke(self, width: T) -> Stroke<Self, T> where Self: Sized {
+    Stroke { shape: self, width }
+  }
+  /// Dilate the shape by `radius`, rounding its corners; a negative `radius` erodes it instead.
+  fn offset(self, radius: T) -> Offset<Self, T> where Self: Sized {
+    Offset { shape: self, radius }
+  }
+  /// Shell of the shape, `thickness` to either side of its edge: `|sdf| - thickness`. Same as
+  /// [`stroke`](Shape::stroke) of twice the width.
+  fn onion(self, thickness: T) -> Stroke<Self, T> where Self: Sized, T: Float {
+    Stroke { shape: self, width: thickness + thickness }
+  }
+  /// Evaluate the shape at `warp(p)` instead of `p`: twists, waves and the like. `inflate`
+  /// bounds how far the warp moves the edge, `|warp(p) - p|`, to enlarge the bounding box.
+  fn warp<F>(self, warp: F, inflate: T) -> Warp<Self, F, T> where Self: Sized, F: Fn(P2<T>) -> P2<T> {
+    Warp { shape: self, warp, inflate }
+  }
+  /// Add `displace(p)` to the SDF, perturbing the edge, e.g. by noise. `inflate` bounds
+  /// `-displace(p)`, to enlarge the bounding box.
+  fn displace<F>(self, displace: F, inflate: T) -> Displace<Self, F, T> where Self: Sized, F: Fn(P2<T>) -> T {
+    Displace { shape: self, displace, inflate }
+  }
+  /// Interpret the shape in the metric space of `metric`, see [`Metric`].
+  fn anisotropic(self, metric: Metric<T>) -> Anisotropic<Self, T> where Self: Sized {
+    Anisotropic { shape: self, metric }
+  }
   #[cfg(feature = "drawing")]
-  #[cfg_attr(doc, doc(cfg(feature = "drawing")))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "drawing")))]
   fn texture<Tex>(self, texture: Tex) -> crate::drawing::Texture<Self, Tex> where Self: Sized {
     crate::drawing::Texture { shape: self, texture }
   }
+  /// Like a closure [`texture`](Shape::texture), which also receives the SDF and its gradient at
+  /// the pixel: `|v, sdf, gradient| -> Px`. Enables glows, outlines and shading, without
+  /// evaluating the SDF once more inside of the closure.
+  #[cfg(feature = "drawing")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "drawing")))]
+  fn texture_shaded<F>(self, shader: F) -> crate::drawing::Texture<Self, crate::drawing::Shaded<F>> where Self: Sized {
+    crate::drawing::Texture { shape: self, texture: crate::drawing::Shaded(shader) }
+  }
 }
 impl <T, Sh> Shape<T> for Sh where Sh: SDF<T> + BoundingBox<T> {}
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Translation<S, T> {
   pub shape: S,
   pub offset: V2<T, WorldSpace>
@@ -77,10 +181,14 @@ impl <S, P> BoundingBox<P> for Translation<S, P>
   fn bounding_box(&self) -> Box2D<P, WorldSpace> {
     self.shape.bounding_box().translate(self.offset)
   }
+  fn tight_bounding_box(&self, transform: &Affine<P>) -> Box2D<P, WorldSpace> where P: Float {
+    self.shape.tight_bounding_box(&Affine::translation(self.offset.x, self.offset.y).then(transform))
+  }
 }
 
 /// Rotate around the center of shape's bounding box
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rotation<S, T> {
   pub shape: S,
   pub angle: Angle<T>
@@ -90,17 +198,31 @@ impl <T, S> BoundingBox<T> for Rotation<S, T>
         T: Float
 {
   fn bounding_box(&self) -> Box2D<T, WorldSpace> {
-    let bounding = self.shape.bounding_box();
-    let pivot = bounding.center();
-    let rot = |point: Point2D<_, _>| Rotation2D::new(self.angle)
-      .transform_point( (point - pivot).to_point())
-      + pivot.to_vector();
-    update_bounding_box(bounding, rot)
+    self.tight_bounding_box(&Affine::identity())
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    self.shape.tight_bounding_box(&self.to_world().then(transform))
+  }
+}
+
+impl<S: BoundingBox<T>, T: Float> Rotation<S, T> {
+  // the shape into world, opposite to the rotation of the sample point in `SDF::sdf`
+  fn to_world(&self) -> Affine<T> {
+    rotation_to_world(self.shape.bounding_box().center(), self.angle)
   }
 }
 
+// rotation by `angle` around `pivot`, as the one of `Rotation`
+pub(crate) fn rotation_to_world<T: Float>(pivot: P2<T>, angle: Angle<T>) -> Affine<T> {
+  let (sin, cos) = (-angle.radians).sin_cos();
+  Affine::translation(-pivot.x, -pivot.y)
+    .then(&Affine::new(cos, sin, -sin, cos, T::zero(), T::zero()))
+    .then_translate(pivot.to_vector())
+}
+
 /// Scale around the center of shape's bounding box
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scale<S, T> {
   pub shape: S,
   pub scale: T
@@ -116,6 +238,201 @@ impl <T, S> BoundingBox<T> for Scale<S, T>
       .scale(self.scale, self.scale)
       .translate(c)
   }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    let c = self.shape.bounding_box().center().to_vector();
+    let to_world = Affine::translation(-c.x, -c.y)
+      .then_scale(self.scale, self.scale)
+      .then_translate(c);
+    self.shape.tight_bounding_box(&to_world.then(transform))
+  }
+}
+
+/// Affine transform of a shape: rotation, shear, non-uniform scale and translation, mapping
+/// the points of the shape into world.
+///
+/// The distance is exact for similarity transforms, and is otherwise a lower bound, scaled by
+/// the least stretch of the transform (see [`Transform2D::min_scale`]). Degenerate transforms
+/// collapse the shape, which is then infinitely far.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform2D<S, T> {
+  pub shape: S,
+  pub transform: euclid::Transform2D<T, WorldSpace, WorldSpace>
+}
+
+impl<S: Debug, T: Float + Debug> Debug for Transform2D<S, T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Transform2D")
+      .field("shape", &self.shape)
+      .field("transform", &self.transform)
+      .finish()
+  }
+}
+
+impl<S, T: Float> Transform2D<S, T> {
+  /// The least factor, by which the transform scales lengths: the smaller singular value of
+  /// its linear part. For a similarity transform, this is its scale.
+  pub fn min_scale(&self) -> T {
+    let t = &self.transform;
+    let two = T::one() + T::one();
+    let sum = t.m11 * t.m11 + t.m12 * t.m12 + t.m21 * t.m21 + t.m22 * t.m22;
+    let det = t.determinant().abs();
+    let max_scale = ((sum + (sum * sum - two * two * det * det).max(T::zero()).sqrt()) / two).sqrt();
+    if max_scale > T::zero() { det / max_scale } else { T::zero() }
+  }
+}
+
+impl <T, S> BoundingBox<T> for Transform2D<S, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.shape.tight_bounding_box(&self.transform)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    self.shape.tight_bounding_box(&self.transform.then(transform))
+  }
+}
+
+/// Reflection of a shape across the axes of world, see [`Shape::mirror_x`]. The sample point
+/// is folded into the positive half-plane of each mirrored axis, before evaluating the shape.
+#[derive(Debug, Copy, Clone)]
+pub struct Mirror<S> {
+  pub shape: S,
+  /// Reflect across the `y` axis
+  pub x: bool,
+  /// Reflect across the `x` axis
+  pub y: bool
+}
+
+impl <T, S> BoundingBox<T> for Mirror<S>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let Box2D { min, max, .. } = self.shape.bounding_box();
+    let (min_x, max_x) = if self.x { (min.x.min(-max.x), max.x.max(-min.x)) } else { (min.x, max.x) };
+    let (min_y, max_y) = if self.y { (min.y.min(-max.y), max.y.max(-min.y)) } else { (min.y, max.y) };
+    Box2D::new(Point2D::new(min_x, min_y), Point2D::new(max_x, max_y))
+  }
+}
+
+/// Grid of copies of a shape, see [`Shape::repeat`]. Only the copy with the nearest center of
+/// bounding box, and its neighbours towards the sample point, are evaluated; the distance is
+/// exact for convex shapes, as long as the copies do not overlap.
+#[derive(Debug, Copy, Clone)]
+pub struct Repeat<S, T> {
+  pub shape: S,
+  pub counts: [u32; 2],
+  pub spacing: V2<T, WorldSpace>
+}
+
+impl <T, S> BoundingBox<T> for Repeat<S, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let bounding = self.shape.bounding_box();
+    let last = V2::new(
+      T::from(self.counts[0].max(1) - 1).unwrap() * self.spacing.x,
+      T::from(self.counts[1].max(1) - 1).unwrap() * self.spacing.y
+    );
+    bounding.union(&bounding.translate(last))
+  }
+}
+
+/// Ring of `n` copies of a shape, each rotated by `τ / n` around the origin of world, see
+/// [`Shape::repeat_polar`]. Like [`Repeat`], only the nearest copies are evaluated.
+#[derive(Debug, Copy, Clone)]
+pub struct RepeatPolar<S> {
+  pub shape: S,
+  pub n: u32
+}
+
+impl<S> RepeatPolar<S> {
+  pub(crate) fn rotation<T: Float + FloatConst>(&self, k: T) -> Rotation2D<T, WorldSpace, WorldSpace> {
+    Rotation2D::new(Angle::radians(T::TAU() * k / T::from(self.n.max(1)).unwrap()))
+  }
+}
+
+impl <T, S> BoundingBox<T> for RepeatPolar<S>
+  where S: BoundingBox<T>,
+        T: Float + FloatConst
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let bounding = self.shape.bounding_box();
+    (0..self.n.max(1))
+      .map(|k| update_bounding_box(bounding, |p| self.rotation(T::from(k).unwrap()).transform_point(p)))
+      .reduce(|a, b| a.union(&b))
+      .unwrap()
+  }
+}
+
+/// Shape, dilated by `radius`, see [`Shape::offset`]
+#[derive(Debug, Copy, Clone)]
+pub struct Offset<S, T> {
+  pub shape: S,
+  pub radius: T
+}
+impl <T, S> BoundingBox<T> for Offset<S, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    // eroded shapes keep the original bounds
+    let r = self.radius.max(T::zero());
+    self.shape.bounding_box().inflate(r, r)
+  }
+}
+
+/// Domain warp of a shape, see [`Shape::warp`]. The SDF is no longer an exact distance, unless
+/// the warp is an isometry; steep warps may require a smaller step of the solvers.
+#[derive(Debug, Copy, Clone)]
+pub struct Warp<S, F, T> {
+  pub shape: S,
+  pub warp: F,
+  pub inflate: T
+}
+impl <T, S, F> BoundingBox<T> for Warp<S, F, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.shape.bounding_box().inflate(self.inflate, self.inflate)
+  }
+}
+
+/// Displacement of the SDF of a shape, see [`Shape::displace`]. Like [`Warp`], the result is
+/// not an exact distance.
+#[derive(Debug, Copy, Clone)]
+pub struct Displace<S, F, T> {
+  pub shape: S,
+  pub displace: F,
+  pub inflate: T
+}
+impl <T, S, F> BoundingBox<T> for Displace<S, F, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.shape.bounding_box().inflate(self.inflate, self.inflate)
+  }
+}
+
+/// Outline of a shape, a band of `width` centered on its edge
+#[derive(Debug, Copy, Clone)]
+pub struct Stroke<S, T> {
+  pub shape: S,
+  pub width: T
+}
+impl <T, S> BoundingBox<T> for Stroke<S, T>
+  where S: BoundingBox<T>,
+        T: Float
+{
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let half = self.width / (T::one() + T::one());
+    self.shape.bounding_box().inflate(half, half)
+  }
 }
 
 fn update_bounding_box<T>(
@@ -136,6 +453,7 @@ fn update_bounding_box<T>(
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DistPoint<D, P, Space> {
   pub distance: D,
   pub point: Point2D<P, Space>
@@ -177,4 +495,183 @@ impl<P, S> std::cmp::Ord for DistPoint<f32, P, S> {
     }
     total_cmp(self.distance, other.distance)
   }
-}
\ No newline at end of file
+}
+/// A shape, placed into world by an affine `transform`: a record of a generated distribution.
+/// With the `serde` feature, a list of placements may be saved, e.g. as JSON, for reuse in
+/// other tools.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Placement<S, T> {
+  pub shape: S,
+  pub transform: Affine<T>
+}
+
+impl<S: Debug, T: Float + Debug> Debug for Placement<S, T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Placement")
+      .field("shape", &self.shape)
+      .field("transform", &self.transform)
+      .finish()
+  }
+}
+
+impl<S, T> Placement<S, T> {
+  /// The placed shape, see [`Shape::transform`].
+  pub fn into_shape(self) -> Transform2D<S, T> {
+    Transform2D { shape: self.shape, transform: self.transform }
+  }
+}
+
+/// A unit [`Circle`], scaled by `distance` and centered at `point`.
+impl<T: Float> From<DistPoint<T, T, WorldSpace>> for Placement<Circle, T> {
+  fn from(circle: DistPoint<T, T, WorldSpace>) -> Self {
+    Self {
+      shape: Circle,
+      transform: Affine::scale(circle.distance, circle.distance).then_translate(circle.point.to_vector())
+    }
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::tests::close};
+
+  #[test] fn transform() {
+    // a similarity is exact, and equals the chain of combinators
+    let similarity = Affine::scale(0.5, 0.5)
+      .then_rotate(Angle::degrees(30.0))
+      .then_translate(V2::new(0.5, 0.25));
+    let chain = Square.scale(0.5).rotate(Angle::degrees(-30.0)).translate(V2::new(0.5, 0.25));
+    let transformed = Square.transform(similarity);
+    assert!(close(transformed.min_scale(), 0.5));
+    itertools::iproduct!(0..8, 0..8)
+      .map(|(x, y)| Point2D::new(x as f64 / 4.0 - 0.5, y as f64 / 4.0 - 0.75))
+      .for_each(|p| assert!(close(transformed.sdf(p), chain.sdf(p)), "{p:?}"));
+
+    // shear, and non-uniform scale, bound the distance from below
+    let shear = Affine::new(2.0, 0.0, 1.0, 1.0, 0.0, 0.0);
+    let sheared = Square.transform(shear);
+    assert_eq!(sheared.bounding_box(), Box2D::new(Point2D::new(-3.0, -1.0), Point2D::new(3.0, 1.0)));
+    assert!(sheared.sdf(Point2D::new(0.0, 2.0)) <= 1.0 && sheared.sdf(Point2D::new(0.0, 2.0)) > 0.0);
+    assert!(sheared.sdf(Point2D::origin()) < 0.0);
+    // degenerate
+    assert!(Square.transform(Affine::scale(1.0, 0.0)).sdf(Point2D::origin()) > 1e100);
+  }
+
+  #[test] fn mirror() {
+    let circle = Circle.scale(0.25).translate(V2::new(0.5, 0.25));
+    // two circles, and four
+    let pair = circle.mirror_x();
+    let four = circle.symmetric();
+    assert_eq!(pair.bounding_box(), Box2D::new(Point2D::new(-0.75, 0.0), Point2D::new(0.75, 0.5)));
+    assert_eq!(four.bounding_box(), Box2D::new(Point2D::new(-0.75, -0.5), Point2D::new(0.75, 0.5)));
+    assert!(close(pair.sdf(Point2D::new(-0.5, 0.25)), -0.25));
+    assert!(close(pair.sdf(Point2D::new(-0.5, -0.25)), 0.25));
+    assert!(close(four.sdf(Point2D::new(-0.5, -0.25)), -0.25));
+    assert!(close(circle.mirror_y().sdf(Point2D::new(0.5, -0.25)), -0.25));
+    assert!(close(circle.mirror_y().sdf(Point2D::new(-0.5, 0.25)), 0.75));
+  }
+
+  #[test] fn repeat() {
+    use std::f64::consts::TAU;
+    let circle = Circle.scale(0.1).translate(V2::new(0.2, 0.3));
+    // explicit unions of the copies
+    let grid = circle.repeat([3, 2], V2::new(0.25, -0.3));
+    let copies = (0..3).flat_map(|i| (0..2).map(move |j| V2::new(i as f64 * 0.25, j as f64 * -0.3)))
+      .map(|offset| circle.translate(offset))
+      .collect::<Vec<_>>();
+    assert_eq!(grid.bounding_box(), Box2D::new(Point2D::new(0.1, -0.1), Point2D::new(0.8, 0.4)));
+    let ring = Circle.scale(0.1).translate(V2::new(0.5, 0.0)).repeat_polar(5);
+    let spokes = (0..5).map(|k| TAU * k as f64 / 5.0)
+      .map(|angle| Point2D::<_, WorldSpace>::new(angle.cos(), angle.sin()) * 0.5)
+      .collect::<Vec<_>>();
+    let bounding_box = ring.bounding_box();
+    assert!(close(bounding_box.max.x, 0.6) && bounding_box.min.x < -0.45 && bounding_box.max.y > 0.5);
+    itertools::iproduct!(0..12, 0..12)
+      .map(|(x, y)| Point2D::new(x as f64 / 8.0 - 0.7, y as f64 / 8.0 - 0.7))
+      .for_each(|p| {
+        let expected = copies.iter().map(|c| c.sdf(p)).fold(f64::MAX, f64::min);
+        assert!(close(grid.sdf(p), expected), "{p:?}");
+        let expected = spokes.iter().map(|c| (p - *c).length() - 0.1).fold(f64::MAX, f64::min);
+        assert!(close(ring.sdf(p), expected), "{p:?}");
+      });
+  }
+
+  #[test] fn offset_onion() {
+    let rounded = Square.offset(0.25);
+    assert_eq!(rounded.bounding_box(), Box2D::new(Point2D::splat(-1.25), Point2D::splat(1.25)));
+    assert!(close(rounded.sdf(Point2D::new(1.25, 0.0)), 0.0));
+    assert!(close(rounded.sdf(Point2D::splat(1.0) + V2::splat(0.25 / 2f64.sqrt())), 0.0));
+    let eroded = Square.offset(-0.25);
+    assert_eq!(eroded.bounding_box(), Square.bounding_box());
+    assert!(close(eroded.sdf(Point2D::new(0.75, 0.0)), 0.0));
+
+    let shell = Circle.onion(0.1);
+    assert_eq!(shell.bounding_box(), Box2D::new(Point2D::splat(-1.1), Point2D::splat(1.1)));
+    assert!(close(shell.sdf(Point2D::origin()), 0.9));
+    assert!(close(shell.sdf(Point2D::new(0.0, 1.05)), -0.05));
+  }
+
+  #[test] fn warp_displace() {
+    // a square, twisted by a quarter turn at its corners
+    let twisted = Square.warp(|p| {
+      let angle = Angle::radians(p.to_vector().length() * std::f64::consts::FRAC_PI_4 / 2f64.sqrt());
+      Rotation2D::new(angle).transform_point(p)
+    }, 2f64.sqrt() - 1.0);
+    assert!(close(twisted.sdf(Point2D::origin()), -1.0));
+    let bounding_box = twisted.bounding_box();
+    // the corners reach the axes, at the distance of `√2`
+    assert!(close(twisted.sdf(Point2D::new(0.0, 2f64.sqrt())), 0.0));
+    assert!(bounding_box.contains_box(&Box2D::new(Point2D::splat(-2f64.sqrt()), Point2D::splat(2f64.sqrt()))));
+
+    let wavy = Circle.displace(|p| (p.x * 20.0).sin() * 0.05, 0.05);
+    assert!(close(wavy.sdf(Point2D::new(0.0, 1.0)), 0.0));
+    assert!(close(wavy.sdf(Point2D::new(1.0, 0.0)), 20f64.sin() * 0.05));
+    assert_eq!(wavy.bounding_box(), Box2D::new(Point2D::splat(-1.05), Point2D::splat(1.05)));
+  }
+
+  #[test] fn tight_bounding_box() {
+    let close_box = |a: Box2D<f64, WorldSpace>, b: Box2D<f64, WorldSpace>| (a.min - b.min).length() + (a.max - b.max).length() < 1e-9;
+    // a thin diagonal capsule, turned horizontal
+    let line = Line { a: Point2D::new(-1.0, -1.0), b: Point2D::new(1.0, 1.0), thickness: 0.1 };
+    let s = 2f64.sqrt();
+    let horizontal = Box2D::new(Point2D::new(-s - 0.05, -0.05), Point2D::new(s + 0.05, 0.05));
+    assert!(close_box(line.rotate(Angle::degrees(45.0)).bounding_box(), horizontal));
+    // through nested wrappers, and for circles and polygons
+    let nested = line.translate(V2::new(1.0, 0.0)).scale(2.0).rotate(Angle::degrees(45.0));
+    assert!((nested.bounding_box().height() - 0.2).abs() < 1e-9);
+    assert!(close_box(Circle.translate(V2::splat(1.0)).rotate(Angle::degrees(30.0)).bounding_box(), Box2D::new(Point2D::splat(0.0), Point2D::splat(2.0))));
+    let vertices = [Point2D::new(-1.0, -1.0), Point2D::new(1.0, 1.0), Point2D::new(1.0, 0.9)];
+    let polygon = Polygon { vertices: &vertices[..] };
+    assert!(polygon.rotate(Angle::degrees(45.0)).bounding_box().height() < 0.1);
+    assert!(close_box(Square.rotate(Angle::degrees(45.0)).bounding_box(), Box2D::new(Point2D::splat(-s), Point2D::splat(s))));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test] fn serde_shapes() -> anyhow::Result<()> {
+    // a distribution, saved and restored
+    let circles = [
+      DistPoint { distance: 0.25, point: Point2D::new(0.5, 0.5) },
+      DistPoint { distance: 0.1, point: Point2D::new(0.1, 0.8) }
+    ];
+    let placements: Vec<Placement<Circle, f64>> = circles.iter().map(|&c| c.into()).collect();
+    let json = serde_json::to_string(&placements)?;
+    let restored: Vec<Placement<Circle, f64>> = serde_json::from_str(&json)?;
+    restored.into_iter().zip(circles).for_each(|(placement, circle)| {
+      let shape = placement.into_shape();
+      assert!((shape.sdf(circle.point) + circle.distance).abs() < 1e-12);
+    });
+    // primitives and wrappers
+    let shape = Star { n: 5, m: 2.5 }.rotate(Angle::degrees(30.0)).scale(0.5).translate(V2::splat(0.5));
+    let restored: Translation<Scale<Rotation<Star<f64>, f64>, f64>, f64> =
+      serde_json::from_str(&serde_json::to_string(&shape)?)?;
+    assert_eq!(shape.sdf(Point2D::new(0.4, 0.6)), restored.sdf(Point2D::new(0.4, 0.6)));
+    let vertices = vec![Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(0.0, 1.0)];
+    let hull = ConvexHull::from_points(vertices.clone());
+    let restored: ConvexHull<f64> = serde_json::from_str(&serde_json::to_string(&hull)?)?;
+    assert_eq!(restored.vertices(), hull.vertices());
+    let polygon: Polygon<Vec<Point2D<f64, WorldSpace>>> =
+      serde_json::from_str(&serde_json::to_string(&Polygon { vertices: &vertices[..] })?)?;
+    assert_eq!(polygon.vertices, vertices);
+    Ok(())
+  }
+}
@@ -1,12 +1,17 @@
 #![allow(non_upper_case_globals)]
 use {
-  super::{Shape, BoundingBox, WorldSpace, Translation},
+  super::{Affine, Shape, BoundingBox, WorldSpace, Translation, transformed_hull},
   crate::sdf::{SDF, Union},
-  euclid::{Box2D, Point2D, Vector2D as V2},
+  euclid::{Angle, Box2D, Point2D, Vector2D as V2},
   num_traits::{Float, Signed, FloatConst},
   std::marker::PhantomData
 };
 
+// unit vector, or zero where the gradient is undefined
+fn normalize<T: Float>(v: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+  if v.square_length() > T::zero() { v.normalize() } else { V2::zero() }
+}
+
 fn clamp<T: Float>(mut x: T, min: T, max: T) -> T {
   if x < min { x = min; }
   if x > max { x = max; }
@@ -15,6 +20,7 @@ fn clamp<T: Float>(mut x: T, min: T, max: T) -> T {
 
 /// Unit circle
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Circle;
 
 impl<T: Float> BoundingBox<T> for Circle {
@@ -22,16 +28,23 @@ impl<T: Float> BoundingBox<T> for Circle {
     Box2D::new(
       Point2D::splat(-T::one()),
       Point2D::splat(T::one())
-    )}}
+    )}
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull([Point2D::origin()], T::one(), transform)
+  }}
 
 impl <T: Float> SDF<T> for Circle {
   fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
     pixel.to_vector().length() - T::one()
   }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    normalize(pixel.to_vector())
+  }
 }
 
 /// Rectangle with center at `[0, 0]`
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect<T, S> {
   pub size: Point2D<T, S>
 }
@@ -57,9 +70,61 @@ impl<T> SDF<T> for Rect<T, WorldSpace>
       .max(dist.y)
       .min(T::zero());
     outside_dist + inside_dist
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let two = T::one() + T::one();
+    let dist = pixel.to_vector().abs() - (self.size.to_vector() / two);
+    let g = if dist.x.max(dist.y) > T::zero() {
+      normalize(dist.max(V2::splat(T::zero())))
+    } else if dist.x > dist.y {
+      V2::new(T::one(), T::zero())
+    } else {
+      V2::new(T::zero(), T::one())
+    };
+    g.component_mul(V2::new(pixel.x.signum(), pixel.y.signum()))
   }}
 
+/// Rectangle with center at `[0, 0]`, and rounded corners. `radii` are of the top-left,
+/// top-right, bottom-right and bottom-left corner, at most half of the shorter side.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundedRect<T> {
+  pub size: Point2D<T, WorldSpace>,
+  pub radii: [T; 4]
+}
+
+impl<T: Float> BoundingBox<T> for RoundedRect<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let two = T::one() + T::one();
+    Box2D::new(
+      -self.size / two,
+      self.size / two
+    )}}
+
+impl<T> SDF<T> for RoundedRect<T>
+  where T: Float + Signed
+{
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let two = T::one() + T::one();
+    let [top_left, top_right, bottom_right, bottom_left] = self.radii;
+    let r = match (pixel.x > T::zero(), pixel.y > T::zero()) {
+      (false, false) => top_left,
+      (true, false) => top_right,
+      (true, true) => bottom_right,
+      (false, true) => bottom_left
+    };
+    let dist = pixel.to_vector().abs() - (self.size.to_vector() / two) + V2::splat(r);
+    let outside_dist = dist
+      .max(V2::splat(T::zero()))
+      .length();
+    let inside_dist = dist.x
+      .max(dist.y)
+      .min(T::zero());
+    outside_dist + inside_dist - r
+  }}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line<T> {
   pub a: Point2D<T, WorldSpace>,
   pub b: Point2D<T, WorldSpace>,
@@ -72,6 +137,9 @@ impl<T: Float> BoundingBox<T> for Line<T> {
     let ret = Box2D::from_points([self.a, self.b]);
     let t = V2::splat(self.thickness / two);
     Box2D::new(ret.min - t, ret.max + t)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull([self.a, self.b], self.thickness / (T::one() + T::one()), transform)
   }}
 
 impl<T: Float> SDF<T> for Line<T> {
@@ -81,10 +149,159 @@ impl<T: Float> SDF<T> for Line<T> {
     let h = clamp(pa.dot(ba) / ba.dot(ba), T::zero(), T::one());
     (pa - ba * h).length() - self.thickness / (T::one() + T::one())
   }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let ba = self.b - self.a;
+    let pa = pixel - self.a;
+    let h = clamp(pa.dot(ba) / ba.dot(ba), T::zero(), T::one());
+    normalize(pa - ba * h)
+  }
+}
+
+/// Quadratic Bézier segment with control points `a`, `b`, `c`, and round caps.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bezier2<T> {
+  pub a: Point2D<T, WorldSpace>,
+  pub b: Point2D<T, WorldSpace>,
+  pub c: Point2D<T, WorldSpace>,
+  pub thickness: T
+}
+
+impl<T: Float> Bezier2<T> {
+  /// Point of the curve at `t` in `0..=1`.
+  pub fn point(&self, t: T) -> Point2D<T, WorldSpace> {
+    let two = T::one() + T::one();
+    let s = T::one() - t;
+    let p = self.a.to_vector() * (s * s)
+      + self.b.to_vector() * (two * s * t)
+      + self.c.to_vector() * (t * t);
+    p.to_point()
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Bezier2<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let two = T::one() + T::one();
+    let ret = Box2D::from_points([self.a, self.b, self.c]);
+    let t = V2::splat(self.thickness / two);
+    Box2D::new(ret.min - t, ret.max + t)
+  }
+  // the curve lies in the hull of its control points, which affine maps preserve
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull([self.a, self.b, self.c], self.thickness / (T::one() + T::one()), transform)
+  }}
+
+impl<T: Float> SDF<T> for Bezier2<T> {
+  // roots of the derivative of the squared distance, a cubic, in closed form
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let (two, three) = (T::one() + T::one(), T::one() + T::one() + T::one());
+    let a = self.b - self.a;
+    let b = self.a.to_vector() - self.b.to_vector() * two + self.c.to_vector();
+    let c = a * two;
+    let d = self.a - pixel;
+    if b.square_length() <= T::epsilon() * a.square_length() {
+      // the control points are collinear, and evenly spaced
+      return Line { a: self.a, b: self.c, thickness: self.thickness }.sdf(pixel);
+    }
+    let dist = |t: T| (d + (c + b * t) * t).square_length();
+    let kk = T::one() / b.dot(b);
+    let kx = kk * a.dot(b);
+    let ky = kk * (two * a.dot(a) + d.dot(b)) / three;
+    let kz = kk * d.dot(a);
+    let p = ky - kx * kx;
+    let q = kx * (two * kx * kx - three * ky) + kz;
+    let h = q * q + T::from(4).unwrap() * p * p * p;
+    let dist = if h >= T::zero() {
+      let h = h.sqrt();
+      let (u, v) = (((h - q) / two).cbrt(), ((-h - q) / two).cbrt());
+      dist(clamp(u + v - kx, T::zero(), T::one()))
+    } else {
+      let z = (-p).sqrt();
+      let v = (q / (p * z * two)).acos() / three;
+      let (m, n) = (v.cos(), v.sin() * three.sqrt());
+      // the third root can not be the closest
+      dist(clamp((m + m) * z - kx, T::zero(), T::one()))
+        .min(dist(clamp((-n - m) * z - kx, T::zero(), T::one())))
+    };
+    dist.sqrt() - self.thickness / two
+  }
+}
+
+/// Cubic Bézier segment with control points `a`, `b`, `c`, `d`, and round caps.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bezier3<T> {
+  pub a: Point2D<T, WorldSpace>,
+  pub b: Point2D<T, WorldSpace>,
+  pub c: Point2D<T, WorldSpace>,
+  pub d: Point2D<T, WorldSpace>,
+  pub thickness: T
+}
+
+impl<T: Float> Bezier3<T> {
+  /// Point of the curve at `t` in `0..=1`.
+  pub fn point(&self, t: T) -> Point2D<T, WorldSpace> {
+    let three = T::one() + T::one() + T::one();
+    let s = T::one() - t;
+    let p = self.a.to_vector() * (s * s * s)
+      + self.b.to_vector() * (three * s * s * t)
+      + self.c.to_vector() * (three * s * t * t)
+      + self.d.to_vector() * (t * t * t);
+    p.to_point()
+  }
+
+  fn derivative(&self, t: T) -> (V2<T, WorldSpace>, V2<T, WorldSpace>) {
+    let (two, three, six) = (T::one() + T::one(), T::one() + T::one() + T::one(), T::from(6).unwrap());
+    let s = T::one() - t;
+    let first = (self.b - self.a) * (three * s * s)
+      + (self.c - self.b) * (six * s * t)
+      + (self.d - self.c) * (three * t * t);
+    let second = (self.c.to_vector() - self.b.to_vector() * two + self.a.to_vector()) * (six * s)
+      + (self.d.to_vector() - self.c.to_vector() * two + self.b.to_vector()) * (six * t);
+    (first, second)
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Bezier3<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let two = T::one() + T::one();
+    let ret = Box2D::from_points([self.a, self.b, self.c, self.d]);
+    let t = V2::splat(self.thickness / two);
+    Box2D::new(ret.min - t, ret.max + t)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull([self.a, self.b, self.c, self.d], self.thickness / (T::one() + T::one()), transform)
+  }}
+
+impl<T: Float> SDF<T> for Bezier3<T> {
+  // closest of a few samples, refined by Newton's iterations on the derivative of the
+  // squared distance
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    const SAMPLES: usize = 16;
+    const ITERATIONS: usize = 4;
+    let n = T::from(SAMPLES).unwrap();
+    let dist = |t: T| (self.point(t) - pixel).square_length();
+    let mut t = (0..=SAMPLES)
+      .map(|i| T::from(i).unwrap() / n)
+      .fold((T::zero(), T::infinity()), |best, t| {
+        let d = dist(t);
+        if d < best.1 { (t, d) } else { best }
+      }).0;
+    for _ in 0..ITERATIONS {
+      let (first, second) = self.derivative(t);
+      let delta = self.point(t) - pixel;
+      let f = delta.dot(first);
+      let df = first.dot(first) + delta.dot(second);
+      if df <= T::zero() { break; }
+      t = clamp(t - f / df, T::zero(), T::one());
+    }
+    dist(t).sqrt() - self.thickness / (T::one() + T::one())
+  }
 }
 
 /// Regular polygon with N sides, inscribed in a unit circle. Partially evaluated at compile-time.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NGonC<const N: usize>;
 
 impl<T: Float, const N: usize> BoundingBox<T> for NGonC<N> {
@@ -107,6 +324,7 @@ impl<T: Float + FloatConst, const N: usize> SDF<T> for NGonC<N> {
 
 /// Regular polygon with N sides, inscribed in a unit circle. Evaluated at runtime.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NGonR {
   pub n: u64
 }
@@ -132,6 +350,7 @@ impl<T: Float + FloatConst> SDF<T> for NGonR {
 /// N-pointed regular star polygon, inscibed in a unit circle.
 /// `m` is density, must be between `2..=n`
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Star<T> {
   pub n: u64,
   pub m: T
@@ -164,6 +383,7 @@ impl<T: Float + FloatConst> SDF<T> for Star<T> {
 
 /// `phase` in `-1..=1`.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Moon<T> {
   pub phase: T
 }
@@ -194,6 +414,7 @@ impl<T: Float> SDF<T> for Moon<T> {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Kakera<T> {
   pub width: T
 }
@@ -219,6 +440,7 @@ impl<T: Float + Signed> SDF<T> for Kakera<T> {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cross<T> {
   pub thickness: T
 }
@@ -242,6 +464,7 @@ impl<T: Float + Signed> SDF<T> for Cross<T>  {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ring<T> {
   pub inner_r: T
 }
@@ -260,7 +483,65 @@ impl<T: Float> SDF<T> for Ring<T>  {
   }
 }
 
+/// Arc of the unit circle, `thickness` wide, with round caps. Spans `aperture` to either side
+/// of the upward direction, `0..=PI`.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arc<T> {
+  pub aperture: Angle<T>,
+  pub thickness: T
+}
+
+impl<T: Float> BoundingBox<T> for Arc<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    Box2D::new(
+      Point2D::splat(-T::one()),
+      Point2D::splat(T::one())
+    )}}
+
+impl<T: Float> SDF<T> for Arc<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let rb = self.thickness / (T::one() + T::one());
+    let ra = T::one() - rb;
+    let (sin, cos) = self.aperture.sin_cos();
+    let sc = V2::<_, WorldSpace>::new(sin, cos);
+    let pixel = V2::new(pixel.x.abs(), -pixel.y);
+    if cos * pixel.x > sin * pixel.y {
+      (pixel - sc * ra).length() - rb
+    } else {
+      (pixel.length() - ra).abs() - rb
+    }
+  }
+}
+
+/// Circular sector of the unit circle. Spans `aperture` to either side of the upward
+/// direction, `0..=PI`.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pie<T> {
+  pub aperture: Angle<T>
+}
+
+impl<T: Float> BoundingBox<T> for Pie<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    Box2D::new(
+      Point2D::splat(-T::one()),
+      Point2D::splat(T::one())
+    )}}
+
+impl<T: Float> SDF<T> for Pie<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let (sin, cos) = self.aperture.sin_cos();
+    let sc = V2::<_, WorldSpace>::new(sin, cos);
+    let pixel = V2::new(pixel.x.abs(), -pixel.y);
+    let l = pixel.length() - T::one();
+    let m = (pixel - sc * clamp(pixel.dot(sc), T::zero(), T::one())).length();
+    l.max(m * (cos * pixel.x - sin * pixel.y).signum())
+  }
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon<T> {
   pub vertices: T
 }
@@ -270,17 +551,34 @@ impl<T, U> BoundingBox<T> for Polygon<U>
         U: AsRef<[Point2D<T, WorldSpace>]> {
   fn bounding_box(&self) -> Box2D<T, WorldSpace> {
     Box2D::from_points(self.vertices.as_ref())
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull(self.vertices.as_ref().iter().copied(), T::zero(), transform)
   }}
 
 impl<T, U> SDF<T> for Polygon<U>
   where T: Float,
         U: AsRef<[Point2D<T, WorldSpace>]> {
   fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    match self.nearest(pixel) {
+      Some((s, b)) => s * b.length(),
+      None => T::max_value() / (T::one() + T::one())
+    }
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    self.nearest(pixel).map_or(V2::zero(), |(s, b)| normalize(b) * s)
+  }
+}
+
+impl<U> Polygon<U> {
+  // the sign, and the vector from the nearest point of the edges
+  fn nearest<T>(&self, pixel: Point2D<T, WorldSpace>) -> Option<(T, V2<T, WorldSpace>)>
+    where T: Float,
+          U: AsRef<[Point2D<T, WorldSpace>]>
+  {
     let v = self.vertices.as_ref();
-    let mut d = match v.get(0) {
-      Some(&v) => (pixel - v).dot(pixel - v),
-      None => return T::max_value() / (T::one() + T::one())
-    };
+    let mut nearest = pixel - *v.first()?;
+    let mut d = nearest.dot(nearest);
     let mut s = T::one();
     let n = v.len();
     (0..n).zip(std::iter::once(n - 1).chain(0..n - 1))
@@ -288,7 +586,10 @@ impl<T, U> SDF<T> for Polygon<U>
         let e = v[j] - v[i];
         let w = pixel - v[i];
         let b = w - e * clamp(w.dot(e) / e.dot(e), T::zero(), T::one());
-        d = d.min(b.dot(b));
+        if b.dot(b) < d {
+          d = b.dot(b);
+          nearest = b;
+        }
         let c = euclid::BoolVector3D {
           x: pixel.y >= v[i].y,
           y: pixel.y < v[j].y,
@@ -298,12 +599,248 @@ impl<T, U> SDF<T> for Polygon<U>
           s = s.neg();
         }
       });
-    s * d.sqrt()
+    Some((s, nearest))
+  }
+}
+
+/// Convex hull of a set of points, as a convex polygon. Inside, the distance is the maximum over
+/// the half-planes of the edges, hence faster and more robust than the winding test of
+/// [`Polygon`]; it is exact everywhere. Fewer than three points, or collinear ones, give a
+/// segment or a point, without an interior.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConvexHull<T> {
+  // counter-clockwise, with `y` pointing up
+  vertices: Vec<Point2D<T, WorldSpace>>,
+  // outward unit normals of the edges from each vertex, if there is an interior
+  normals: Vec<V2<T, WorldSpace>>
+}
+
+impl<T: Float> ConvexHull<T> {
+  /// Andrew's monotone chain, in `O(n log n)`. Points with non-finite coordinates are ignored.
+  pub fn from_points(points: impl IntoIterator<Item = Point2D<T, WorldSpace>>) -> Self {
+    let mut points: Vec<_> = points.into_iter()
+      .filter(|p| p.x.is_finite() && p.y.is_finite())
+      .collect();
+    points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap().then(a.y.partial_cmp(&b.y).unwrap()));
+    points.dedup();
+    let turn = |chain: &[Point2D<T, WorldSpace>], p: Point2D<T, WorldSpace>| {
+      let (a, b) = (chain[chain.len() - 2], chain[chain.len() - 1]);
+      (b - a).cross(p - a)
+    };
+    let mut vertices: Vec<Point2D<T, WorldSpace>> = vec![];
+    // the lower chain, then the upper one
+    [points.clone(), points.iter().rev().copied().collect()].into_iter().for_each(|points| {
+      let start = vertices.len();
+      points.into_iter().for_each(|p| {
+        while vertices.len() >= start + 2 && turn(&vertices, p) <= T::zero() {
+          vertices.pop();
+        }
+        vertices.push(p);
+      });
+      // the last point starts the other chain
+      vertices.pop();
+    });
+    if vertices.is_empty() {
+      vertices.extend(points.first());
+    }
+    let normals = if vertices.len() < 3 { vec![] } else {
+      (0..vertices.len()).map(|i| {
+        let e = vertices[(i + 1) % vertices.len()] - vertices[i];
+        V2::new(e.y, -e.x).normalize()
+      }).collect()
+    };
+    Self { vertices, normals }
+  }
+
+  pub fn vertices(&self) -> &[Point2D<T, WorldSpace>] {
+    &self.vertices
+  }
+
+  // the distance, and its gradient
+  fn field(&self, pixel: Point2D<T, WorldSpace>) -> Option<(T, V2<T, WorldSpace>)> {
+    let v = &self.vertices;
+    let inside = self.normals.iter().zip(v)
+      .map(|(&n, &a)| (n.dot(pixel - a), n))
+      .reduce(|a, b| if b.0 > a.0 { b } else { a });
+    match inside {
+      Some((d, n)) if d <= T::zero() => Some((d, n)),
+      _ => (0..v.len())
+        .map(|i| {
+          let (e, w) = (v[(i + 1) % v.len()] - v[i], pixel - v[i]);
+          let h = if e == V2::zero() { T::zero() } else { clamp(w.dot(e) / e.dot(e), T::zero(), T::one()) };
+          w - e * h
+        })
+        .reduce(|a, b| if b.square_length() < a.square_length() { b } else { a })
+        .map(|b| (b.length(), normalize(b)))
+    }
+  }
+}
+
+impl<T: Float> BoundingBox<T> for ConvexHull<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    Box2D::from_points(&self.vertices)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull(self.vertices.iter().copied(), T::zero(), transform)
+  }}
+
+impl<T: Float> SDF<T> for ConvexHull<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    self.field(pixel).map_or(T::max_value() / (T::one() + T::one()), |(d, _)| d)
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    self.field(pixel).map_or(V2::zero(), |(_, g)| g)
+  }
+}
+
+/// Chain of segments through `vertices`, with round joins and caps; `closed` connects the
+/// last vertex to the first.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polyline<T, U> {
+  pub vertices: U,
+  pub thickness: T,
+  pub closed: bool
+}
+
+impl<T, U> Polyline<T, U>
+  where T: Float,
+        U: AsRef<[Point2D<T, WorldSpace>]> {
+  /// Point at the fraction `t` in `0..=1` of the length of the chain, including the closing
+  /// segment.
+  pub fn point(&self, t: T) -> Point2D<T, WorldSpace> {
+    let v = self.vertices.as_ref();
+    let closing = (self.closed && v.len() > 2).then(|| (v[v.len() - 1], v[0]));
+    let edges = || v.windows(2).map(|w| (w[0], w[1])).chain(closing);
+    let length = edges().fold(T::zero(), |sum, (a, b)| sum + (b - a).length());
+    let mut left = t.max(T::zero()).min(T::one()) * length;
+    for (a, b) in edges() {
+      let edge = (b - a).length();
+      if left <= edge && edge > T::zero() {
+        return a.lerp(b, left / edge);
+      }
+      left = left - edge;
+    }
+    match closing {
+      Some(_) => v[0],
+      None => v.last().copied().unwrap_or_else(Point2D::origin)
+    }
+  }
+}
+
+impl<T, U> BoundingBox<T> for Polyline<T, U>
+  where T: Float,
+        U: AsRef<[Point2D<T, WorldSpace>]> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let two = T::one() + T::one();
+    let ret = Box2D::from_points(self.vertices.as_ref());
+    let t = V2::splat(self.thickness / two);
+    Box2D::new(ret.min - t, ret.max + t)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    transformed_hull(self.vertices.as_ref().iter().copied(), self.thickness / (T::one() + T::one()), transform)
+  }}
+
+impl<T, U> SDF<T> for Polyline<T, U>
+  where T: Float,
+        U: AsRef<[Point2D<T, WorldSpace>]> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let v = self.vertices.as_ref();
+    let mut d = match v.first() {
+      Some(&v) => (pixel - v).dot(pixel - v),
+      None => return T::max_value() / (T::one() + T::one())
+    };
+    let closing = (self.closed && v.len() > 2).then(|| (v[v.len() - 1], v[0]));
+    v.windows(2).map(|w| (w[0], w[1]))
+      .chain(closing)
+      .for_each(|(a, b)| {
+        let (ba, pa) = (b - a, pixel - a);
+        let h = if ba == V2::zero() { T::zero() } else { clamp(pa.dot(ba) / ba.dot(ba), T::zero(), T::one()) };
+        let e = pa - ba * h;
+        d = d.min(e.dot(e));
+      });
+    d.sqrt() - self.thickness / (T::one() + T::one())
+  }
+}
+
+// the image, clipped to the half-planes `n · p <= offset`, by Sutherland-Hodgman
+fn clip_domain<T: Float>(half_planes: &[(V2<T, WorldSpace>, T)]) -> Box2D<T, WorldSpace> {
+  let corners = [(0, 0), (1, 0), (1, 1), (0, 1)].iter()
+    .map(|&(x, y)| Point2D::new(T::from(x).unwrap(), T::from(y).unwrap()))
+    .collect();
+  let polygon = half_planes.iter().fold(corners, |polygon: Vec<Point2D<T, WorldSpace>>, &(n, offset)| {
+    let dist = |p: Point2D<T, WorldSpace>| n.dot(p.to_vector()) - offset;
+    (0..polygon.len()).flat_map(|i| {
+      let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+      let (da, db) = (dist(a), dist(b));
+      let crossing = ((da < T::zero()) != (db < T::zero())).then(|| a.lerp(b, da / (da - db)));
+      (da <= T::zero()).then_some(a).into_iter().chain(crossing)
+    }).collect()
+  });
+  if polygon.is_empty() { Box2D::zero() } else { Box2D::from_points(polygon) }
+}
+
+/// Half of the plane, the points `p` at `normal · p <= offset`: with `normal` pointing outwards,
+/// and the edge at `offset` from the origin, for a unit `normal`. Infinite, hence the bounding
+/// box is the part of the image `[0, 1]²` it covers, in its own coordinates; transforms move
+/// this box along, so prefer placing it by the parameters.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfPlane<T> {
+  pub normal: V2<T, WorldSpace>,
+  pub offset: T
+}
+
+impl<T: Float> BoundingBox<T> for HalfPlane<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    clip_domain(&[(self.normal, self.offset)])
+  }}
+
+impl<T: Float> SDF<T> for HalfPlane<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    (self.normal.dot(pixel.to_vector()) - self.offset) / self.normal.length()
+  }
+  fn gradient(&self, _: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    normalize(self.normal)
+  }
+}
+
+/// Infinite band of `width`, along `direction`, through the origin. The bounding box is the
+/// part of the image `[0, 1]²` it covers, see [`HalfPlane`].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Strip<T> {
+  pub direction: V2<T, WorldSpace>,
+  pub width: T
+}
+
+impl<T: Float> Strip<T> {
+  // unit normal
+  fn normal(&self) -> V2<T, WorldSpace> {
+    normalize(V2::new(-self.direction.y, self.direction.x))
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Strip<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let (n, half) = (self.normal(), self.width / (T::one() + T::one()));
+    clip_domain(&[(n, half), (-n, half)])
+  }}
+
+impl<T: Float> SDF<T> for Strip<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    self.normal().dot(pixel.to_vector()).abs() - self.width / (T::one() + T::one())
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    let n = self.normal();
+    if n.dot(pixel.to_vector()) < T::zero() { -n } else { n }
   }
 }
 
 /// `= Rect { size: [2.0, 2.0] }`
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Square;
 
 impl<T: Float> BoundingBox<T> for Square {
@@ -320,6 +857,7 @@ impl<T> SDF<T> for Square
 
 /// `= Star { n: 5, m: 10.0 / 3.0 }`
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pentagram;
 
 impl<T: Float> BoundingBox<T> for Pentagram {
@@ -340,6 +878,7 @@ impl<T> SDF<T> for Pentagram
 
 /// `= Star { n: 6, m: 3.0 }`
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hexagram;
 
 impl<T: Float> BoundingBox<T> for Hexagram {
@@ -378,4 +917,162 @@ pub static HolyCross: Union <
     shape: Rect { size: Point2D {  x: 1.432, y: 0.4, _unit: PhantomData::<WorldSpace> } },
     offset: V2 { x: 0.0, y: -0.3, _unit: PhantomData::<WorldSpace> }
   }
-};
\ No newline at end of file
+};
+mod bitmap;
+pub use bitmap::Bitmap;
+
+mod path;
+pub use path::{FillRule, Path, PathBuilder};
+
+mod group;
+pub use group::Group;
+
+#[cfg(feature = "text")] mod text;
+#[cfg(feature = "text")]
+#[cfg_attr(docsrs, doc(cfg(feature = "text")))]
+pub use text::{Glyph, Text};
+
+#[cfg(feature = "svg")] mod svg;
+#[cfg(feature = "svg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
+pub use svg::Svg;
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::tests::close};
+
+  #[test] fn arc_pie() {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+    let arc = Arc { aperture: Angle::radians(FRAC_PI_2), thickness: 0.2 };
+    assert!(close(arc.sdf(Point2D::new(0.0, -0.9)), -0.1));
+    assert!(close(arc.sdf(Point2D::new(0.9, 0.0)), -0.1));
+    assert!(close(arc.sdf(Point2D::new(0.0, 0.9)), 0.9 * 2f64.sqrt() - 0.1));
+    assert!(close(arc.sdf(Point2D::origin()), 0.8));
+    let pie = Pie { aperture: Angle::radians(FRAC_PI_4) };
+    assert!(close(pie.sdf(Point2D::origin()), 0.0));
+    assert!(close(pie.sdf(Point2D::new(0.0, -0.5)), -0.5 * FRAC_PI_4.sin()));
+    assert!(close(pie.sdf(Point2D::new(0.0, 0.5)), 0.5));
+    assert!(close(pie.sdf(Point2D::new(0.0, -1.5)), 0.5));
+  }
+
+  #[test] fn rounded_rect() {
+    let rect = RoundedRect { size: Point2D::new(2.0, 1.0), radii: [0.0, 0.1, 0.5, 0.25] };
+    // sharp top-left corner
+    assert!(close(rect.sdf(Point2D::new(-1.0, -0.5)), 0.0));
+    assert!(close(rect.sdf(Point2D::new(-1.1, -0.6)), 0.1 * 2f64.sqrt()));
+    // rounded corners, along the diagonals from the centers of their arcs
+    let diagonal = |x: f64, y: f64, length: f64| V2::new(x, y) * length / 2f64.sqrt();
+    assert!(close(rect.sdf(Point2D::new(0.9, -0.4) + diagonal(1.0, -1.0, 0.3)), 0.2));
+    assert!(close(rect.sdf(Point2D::new(0.5, 0.0) + diagonal(1.0, 1.0, 0.5)), 0.0));
+    assert!(close(rect.sdf(Point2D::new(-0.75, 0.25) + diagonal(-1.0, 1.0, 0.35)), 0.1));
+    // flat edges
+    assert!(close(rect.sdf(Point2D::new(0.0, -0.5)), 0.0));
+    assert!(close(rect.sdf(Point2D::origin()), -0.5));
+  }
+
+  #[test] fn bezier() {
+    let p = |x: f64, y: f64| Point2D::new(x, y);
+    // distance to the curve, by dense sampling
+    let reference = |curve: &dyn Fn(f64) -> Point2D<f64, WorldSpace>, q: Point2D<f64, WorldSpace>|
+      (0..=10000).map(|i| (curve(i as f64 / 10000.0) - q).length()).fold(f64::INFINITY, f64::min);
+
+    let quadratic = Bezier2 { a: p(0.0, 0.0), b: p(0.5, 1.0), c: p(1.0, 0.0), thickness: 0.1 };
+    let curve2 = |t: f64| (quadratic.a.to_vector() * (1.0 - t).powi(2)
+      + quadratic.b.to_vector() * (2.0 * t * (1.0 - t))
+      + quadratic.c.to_vector() * t.powi(2)).to_point();
+    let cubic = Bezier3 { a: p(0.0, 0.0), b: p(0.0, 1.0), c: p(1.0, -0.5), d: p(1.0, 0.5), thickness: 0.1 };
+    itertools::iproduct!(0..10, 0..10)
+      .map(|(x, y)| p(x as f64 / 6.0 - 0.3, y as f64 / 6.0 - 0.7))
+      .for_each(|q| {
+        assert!((quadratic.sdf(q) - (reference(&curve2, q) - 0.05)).abs() < 1e-4, "{q:?}");
+        assert!((cubic.sdf(q) - (reference(&|t| cubic.point(t), q) - 0.05)).abs() < 1e-4, "{q:?}");
+      });
+    // degenerate, a straight segment
+    let line = Bezier2 { a: p(0.0, 0.0), b: p(0.5, 0.0), c: p(1.0, 0.0), thickness: 0.0 };
+    assert!(close(line.sdf(p(0.5, 0.3)), 0.3));
+    assert!(close(line.sdf(p(2.0, 0.0)), 1.0));
+  }
+
+  #[test] fn polyline() {
+    let vertices = [Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0)];
+    let open = Polyline { vertices, thickness: 0.2, closed: false };
+    let closed = Polyline { closed: true, ..open };
+    // round joins, and the distance to the nearest segment
+    assert!(close(open.sdf(Point2D::new(1.3, -0.4)), 0.4));
+    assert!(close(open.sdf(Point2D::new(0.5, 0.2)), 0.1));
+    assert!(close(open.sdf(Point2D::new(0.0, 1.0)), 0.9));
+    assert!(close(closed.sdf(Point2D::new(0.0, 1.0)), 0.5f64.sqrt() - 0.1));
+    assert!(close(closed.sdf(Point2D::new(0.5, 0.5)), -0.1));
+    // a single vertex is a dot
+    let dot = Polyline { vertices: &vertices[..1], thickness: 0.2, closed: true };
+    assert!(close(dot.sdf(Point2D::new(0.0, 0.5)), 0.4));
+    assert_eq!(dot.bounding_box(), Box2D::new(Point2D::splat(-0.1), Point2D::splat(0.1)));
+  }
+
+  #[test] fn group() {
+    let group = Group::new()
+      .with(Circle.scale(0.5).translate(V2::new(-0.5, 0.0)))
+      .with(Square.scale(0.25).translate(V2::new(0.75, 0.0)))
+      .with(Line { a: Point2D::new(0.0, -1.0), b: Point2D::new(0.0, 1.0), thickness: 0.1 });
+    assert_eq!(group.0.len(), 3);
+    assert_eq!(group.bounding_box(), Box2D::new(Point2D::new(-1.0, -1.05), Point2D::new(1.0, 1.05)));
+    assert!(close(group.sdf(Point2D::new(-0.5, 0.0)), -0.5));
+    assert!(close(group.sdf(Point2D::new(0.75, 0.0)), -0.25));
+    assert!(close(group.sdf(Point2D::new(0.0, 0.5)), -0.05));
+    assert!(close(group.sdf(Point2D::new(-1.5, 0.0)), 0.5));
+    assert_eq!(group.gradient(Point2D::new(-1.5, 0.0)), V2::new(-1.0, 0.0));
+    // placed as a whole
+    let placed = group.scale(0.5).translate(V2::new(1.0, 1.0));
+    assert!(close(placed.sdf(Point2D::new(0.75, 1.0)), -0.25));
+    assert!(Group::<f64>::default().sdf(Point2D::origin()) > 1e100);
+  }
+
+  #[test] fn convex_hull() {
+    // a hexagon, with points inside, on the edges, and repeated
+    let corners: Vec<Point2D<f64, WorldSpace>> = [(1.0, 0.0), (0.5, 0.8), (-0.5, 0.8), (-1.0, 0.0), (-0.5, -0.8), (0.5, -0.8)]
+      .iter().map(|&(x, y)| Point2D::new(x, y))
+      .collect();
+    let points = corners.iter().copied()
+      .chain(corners.iter().map(|p| p.lerp(Point2D::origin(), 0.5)))
+      .chain([Point2D::new(0.0, 0.8), corners[2], Point2D::new(f64::NAN, 0.0)]);
+    let hull = ConvexHull::from_points(points);
+    assert_eq!(hull.vertices().len(), 6);
+    let polygon = Polygon { vertices: &corners[..] };
+    assert_eq!(hull.bounding_box(), polygon.bounding_box());
+    itertools::iproduct!(0..13, 0..13)
+      .map(|(x, y)| Point2D::new(x as f64 / 5.1 - 1.2, y as f64 / 5.3 - 1.1))
+      .for_each(|p| {
+        assert!(close(hull.sdf(p), polygon.sdf(p)), "{p:?}");
+        let expected = crate::sdf::central_difference(|p| hull.sdf(p), p, 1e-6);
+        assert!((hull.gradient(p) - expected).length() < 1e-4, "{p:?}");
+      });
+    // without an interior
+    let segment = ConvexHull::from_points([Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(2.0, 2.0)]);
+    assert_eq!(segment.vertices(), &[Point2D::new(0.0, 0.0), Point2D::new(2.0, 2.0)]);
+    assert!(close(segment.sdf(Point2D::new(2.0, 0.0)), 2f64.sqrt()));
+    let point = ConvexHull::from_points([Point2D::new(1.0, 1.0)]);
+    assert_eq!(point.sdf(Point2D::new(1.0, 2.0)), 1.0);
+    assert!(ConvexHull::<f64>::from_points([]).sdf(Point2D::origin()) > 1e100);
+  }
+
+  #[test] fn half_plane_strip() {
+    // the left half of the image, and the lower-right triangle
+    let left = HalfPlane { normal: V2::new(2.0, 0.0), offset: 1.0 };
+    assert!(close(left.sdf(Point2D::new(0.25, 3.0)), -0.25));
+    assert!(close(left.sdf(Point2D::new(1.0, -3.0)), 0.5));
+    assert_eq!(left.bounding_box(), Box2D::new(Point2D::new(0.0, 0.0), Point2D::new(0.5, 1.0)));
+    let triangle = HalfPlane { normal: V2::new(1.0, -1.0), offset: 0.0 };
+    assert_eq!(triangle.bounding_box(), Box2D::new(Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0)));
+    assert!(triangle.sdf(Point2D::new(0.2, 0.8)) < 0.0);
+    let outside = HalfPlane { normal: V2::new(0.0, 1.0), offset: -1.0 };
+    assert_eq!(outside.bounding_box(), Box2D::zero());
+    // a diagonal band
+    let strip = Strip { direction: V2::new(1.0, 1.0), width: 0.2 };
+    assert!(close(strip.sdf(Point2D::new(5.0, 5.0)), -0.1));
+    assert!(close(strip.sdf(Point2D::new(0.0, 1.0)), 0.5f64.sqrt() - 0.1));
+    let h = 0.1 * 2f64.sqrt();
+    assert!(close(strip.bounding_box().max.x, 1.0) && close(strip.bounding_box().min.y, 0.0));
+    assert!(close(strip.bounding_box().max.y, 1.0) && close(strip.bounding_box().min.x, 0.0));
+    assert!(close(Strip { direction: V2::new(1.0, 0.0), width: 2.0 * h }.bounding_box().max.y, h));
+    assert_eq!(strip.gradient(Point2D::new(1.0, 0.0)), -strip.gradient(Point2D::new(0.0, 1.0)));
+  }
+}
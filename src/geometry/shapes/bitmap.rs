@@ -0,0 +1,165 @@
+use {
+  crate::{geometry::{BoundingBox, WorldSpace}, sdf::SDF},
+  euclid::{Box2D, Point2D, Size2D, Vector2D as V2},
+  num_traits::Float
+};
+
+// distance of the pixels not covered by the feature
+const FAR: f32 = 1e20;
+
+/// SDF of a raster mask, such as a logo or silhouette, by the Euclidean distance transform of
+/// its pixels, sampled bilinearly. The longer side of the mask spans `-1..=1`, centered at
+/// `[0, 0]`; outside of it, the distance is a lower bound, extrapolated from the edge.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bitmap {
+  size: Size2D<u32, WorldSpace>,
+  // signed distances at the centers of the pixels, in pixels
+  distances: Vec<f32>
+}
+
+impl Bitmap {
+  /// `inside(x, y)` of each pixel of a mask of `width` by `height`.
+  pub fn new(width: u32, height: u32, inside: impl Fn(u32, u32) -> bool) -> Self {
+    assert!(width > 0 && height > 0, "empty mask");
+    let mask: Vec<bool> = itertools::iproduct!(0..height, 0..width)
+      .map(|(y, x)| inside(x, y))
+      .collect();
+    let outside = distance_transform(width, height, |i| mask[i]);
+    let inside = distance_transform(width, height, |i| !mask[i]);
+    // the edge lies halfway between the pixels inside and outside
+    let distances = outside.iter().zip(inside)
+      .map(|(&outside, inside)| if outside > 0.0 { outside - 0.5 } else { 0.5 - inside })
+      .collect();
+    Self { size: Size2D::new(width, height), distances }
+  }
+
+  /// Pixels brighter than `threshold` are inside.
+  #[cfg(feature = "drawing")]
+  pub fn from_image(image: &image::GrayImage, threshold: u8) -> Self {
+    Self::new(image.width(), image.height(), |x, y| image.get_pixel(x, y).0[0] > threshold)
+  }
+
+  // size of a pixel in the world
+  fn pixel(&self) -> f64 {
+    2.0 / self.size.width.max(self.size.height) as f64
+  }
+
+  fn get(&self, x: u32, y: u32) -> f64 {
+    self.distances[(y * self.size.width + x) as usize] as f64
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Bitmap {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let half = (self.size.to_f64() * self.pixel() / 2.0).cast::<T>().to_vector().to_point();
+    Box2D::new(-half, half)
+  }}
+
+impl<T: Float> SDF<T> for Bitmap {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let px = self.pixel();
+    let max = (self.size - Size2D::splat(1)).to_f64().to_vector();
+    // in pixels, relative to the center of the first one
+    let p = pixel.cast::<f64>().to_vector() / px + self.size.to_f64().to_vector() / 2.0 - V2::splat(0.5);
+    let clamped = p.max(V2::zero()).min(max);
+    let (x0, y0) = (clamped.x.floor(), clamped.y.floor());
+    let (tx, ty) = (clamped.x - x0, clamped.y - y0);
+    let (x0, y0) = (x0 as u32, y0 as u32);
+    let (x1, y1) = ((x0 + 1).min(self.size.width - 1), (y0 + 1).min(self.size.height - 1));
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    let d = lerp(
+      lerp(self.get(x0, y0), self.get(x1, y0), tx),
+      lerp(self.get(x0, y1), self.get(x1, y1), tx),
+      ty
+    );
+    // the shape lies within the mask, hence beyond its edge, in the direction away from `p`
+    let outside = (p - clamped).length();
+    let d = if outside > 0.0 { d.max(0.0).hypot(outside) } else { d };
+    T::from(d * px).unwrap()
+  }
+}
+
+/// Distance of each pixel to the nearest one of the feature, in two passes of the exact 1D
+/// transform of Felzenszwalb and Huttenlocher.
+fn distance_transform(width: u32, height: u32, feature: impl Fn(usize) -> bool) -> Vec<f32> {
+  let (width, height) = (width as usize, height as usize);
+  let mut grid: Vec<f32> = (0..width * height)
+    .map(|i| if feature(i) { 0.0 } else { FAR })
+    .collect();
+  let mut line = vec![0.0; width.max(height)];
+  (0..width).for_each(|x| {
+    (0..height).for_each(|y| line[y] = grid[y * width + x]);
+    let transformed = transform_1d(&line[..height]);
+    (0..height).for_each(|y| grid[y * width + x] = transformed[y]);
+  });
+  (0..height).for_each(|y| {
+    let transformed = transform_1d(&grid[y * width..(y + 1) * width]);
+    grid[y * width..(y + 1) * width].copy_from_slice(&transformed);
+  });
+  grid.iter().map(|d| d.sqrt()).collect()
+}
+
+// squared distances, by the lower envelope of parabolas rooted at each sample
+fn transform_1d(f: &[f32]) -> Vec<f32> {
+  let n = f.len();
+  let mut v = vec![0; n];
+  let mut z = vec![0.0; n + 1];
+  let mut k = 0;
+  z[0] = f32::NEG_INFINITY;
+  z[1] = f32::INFINITY;
+  let intersection = |q: usize, p: usize| {
+    let (q_, p_) = (q as f32, p as f32);
+    ((f[q] + q_ * q_) - (f[p] + p_ * p_)) / (2.0 * q_ - 2.0 * p_)
+  };
+  for q in 1..n {
+    let mut s = intersection(q, v[k]);
+    while s <= z[k] {
+      k -= 1;
+      s = intersection(q, v[k]);
+    }
+    k += 1;
+    v[k] = q;
+    z[k] = s;
+    z[k + 1] = f32::INFINITY;
+  }
+  k = 0;
+  (0..n).map(|q| {
+    while z[k + 1] < q as f32 {
+      k += 1;
+    }
+    let d = q as f32 - v[k] as f32;
+    d * d + f[v[k]]
+  }).collect()
+}
+
+#[cfg(test)] mod tests {
+  use super::*;
+
+  #[test] fn distance_transform_exact() {
+    // a single pixel, in the corner
+    let d = distance_transform(5, 4, |i| i == 0);
+    assert_eq!(d[0], 0.0);
+    assert_eq!(d[4], 4.0);
+    assert_eq!(d[3 * 5 + 4], 5.0);
+    assert_eq!(d[5 + 1], 2f32.sqrt());
+  }
+
+  #[test] fn bitmap() {
+    // a disk of radius 20 pixels, on a wider mask
+    let disk = Bitmap::new(80, 64, |x, y| {
+      let p = V2::<_, WorldSpace>::new(x as f32 + 0.5 - 40.0, y as f32 + 0.5 - 32.0);
+      p.length() <= 20.0
+    });
+    assert_eq!(disk.bounding_box(), Box2D::new(Point2D::new(-1.0, -0.8), Point2D::new(1.0, 0.8)));
+    // a pixel is 1 / 40 of the world, the error is within about a pixel
+    let circle = |p: Point2D<f64, WorldSpace>| p.to_vector().length() - 0.5;
+    itertools::iproduct!(0..21, 0..21)
+      .map(|(x, y)| Point2D::new(x as f64 / 10.0 - 1.0, y as f64 / 10.0 - 1.0))
+      .for_each(|p| if p.y.abs() < 0.8 {
+        assert!((disk.sdf(p) - circle(p)).abs() < 1.5 / 40.0, "{p:?}");
+      } else {
+        assert!(disk.sdf(p) > 0.0 && disk.sdf(p) < circle(p) + 1.0 / 40.0, "{p:?}");
+      });
+  }
+}
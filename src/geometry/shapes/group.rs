@@ -0,0 +1,69 @@
+use {
+  crate::{geometry::{Affine, BoundingBox, Shape, WorldSpace, P2}, sdf::SDF},
+  euclid::{Box2D, Vector2D as V2},
+  num_traits::Float,
+  std::fmt::{self, Debug}
+};
+
+/// Union of shapes of different types, as a single one: a compound object, which may be
+/// placed, scaled, and inserted into a solver as a whole. Unlike nested
+/// [`Union`](crate::sdf::Union)s, the members are boxed, hence any number of them, and known
+/// at runtime only. An empty group is nowhere. Unlike the other shapes, not serializable.
+pub struct Group<T>(pub Vec<Box<dyn Shape<T> + Send + Sync>>);
+
+impl<T> Group<T> {
+  pub fn new() -> Self {
+    Self(vec![])
+  }
+
+  pub fn push(&mut self, shape: impl Shape<T> + Send + Sync + 'static) -> &mut Self {
+    self.0.push(Box::new(shape));
+    self
+  }
+
+  pub fn with(mut self, shape: impl Shape<T> + Send + Sync + 'static) -> Self {
+    self.push(shape);
+    self
+  }
+}
+
+impl<T> Default for Group<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Debug for Group<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Group").field(&format_args!("[{} shapes]", self.0.len())).finish()
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Group<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.0.iter()
+      .map(|shape| shape.bounding_box())
+      .reduce(|a, b| a.union(&b))
+      .unwrap_or_else(Box2D::zero)
+  }
+  fn tight_bounding_box(&self, transform: &Affine<T>) -> Box2D<T, WorldSpace> {
+    self.0.iter()
+      .map(|shape| shape.tight_bounding_box(transform))
+      .reduce(|a, b| a.union(&b))
+      .unwrap_or_else(Box2D::zero)
+  }}
+
+impl<T: Float> SDF<T> for Group<T> {
+  fn sdf(&self, pixel: P2<T>) -> T {
+    self.0.iter()
+      .map(|shape| shape.sdf(pixel))
+      .fold(T::max_value() / (T::one() + T::one()), T::min)
+  }
+  // of the nearest member
+  fn gradient(&self, pixel: P2<T>) -> V2<T, WorldSpace> {
+    self.0.iter()
+      .map(|shape| (shape.sdf(pixel), shape))
+      .reduce(|a, b| if b.0 < a.0 { b } else { a })
+      .map_or_else(V2::zero, |(_, shape)| shape.gradient(pixel))
+  }
+}
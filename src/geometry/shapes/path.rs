@@ -0,0 +1,225 @@
+use {
+  super::{Bezier2, Bezier3, Line},
+  crate::{geometry::{BoundingBox, WorldSpace}, sdf::SDF},
+  euclid::{Box2D, Point2D, Vector2D as V2},
+  num_traits::Float
+};
+
+/// Curves are flattened into this many edges, for the winding number
+const FLATTEN: usize = 8;
+
+type P2<T> = Point2D<T, WorldSpace>;
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Segment<T> {
+  Line(Line<T>),
+  Quad(Bezier2<T>),
+  Cubic(Bezier3<T>)
+}
+
+impl<T: Float> SDF<T> for Segment<T> {
+  fn sdf(&self, pixel: P2<T>) -> T {
+    match self {
+      Self::Line(s) => s.sdf(pixel),
+      Self::Quad(s) => s.sdf(pixel),
+      Self::Cubic(s) => s.sdf(pixel)
+    }
+  }
+}
+
+/// Which regions, enclosed by the contours of a [`Path`], are inside.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillRule {
+  #[default]
+  NonZero,
+  EvenOdd
+}
+
+/// Closed contours of line and Bézier segments, such as a font glyph or vector artwork. The
+/// distance is exact to the segments, while the inside is given by the [`FillRule`]. Build
+/// with a [`PathBuilder`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path<T> {
+  segments: Vec<Segment<T>>,
+  // the contours, with curves flattened
+  edges: Vec<(P2<T>, P2<T>)>,
+  bounding_box: Box2D<T, WorldSpace>,
+  rule: FillRule
+}
+
+impl<T: Float> Path<T> {
+  pub fn is_empty(&self) -> bool {
+    self.segments.is_empty()
+  }
+
+  /// `p * scale + offset` of every point, for a positive `scale`.
+  pub fn transform(&self, scale: T, offset: V2<T, WorldSpace>) -> Self {
+    let f = |p: P2<T>| p * scale + offset;
+    Self {
+      segments: self.segments.iter().map(|segment| match *segment {
+        Segment::Line(s) => Segment::Line(Line { a: f(s.a), b: f(s.b), ..s }),
+        Segment::Quad(s) => Segment::Quad(Bezier2 { a: f(s.a), b: f(s.b), c: f(s.c), ..s }),
+        Segment::Cubic(s) => Segment::Cubic(Bezier3 { a: f(s.a), b: f(s.b), c: f(s.c), d: f(s.d), ..s })
+      }).collect(),
+      edges: self.edges.iter().map(|&(a, b)| (f(a), f(b))).collect(),
+      bounding_box: Box2D::new(f(self.bounding_box.min), f(self.bounding_box.max)),
+      rule: self.rule
+    }
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Path<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.bounding_box
+  }}
+
+impl<T: Float> SDF<T> for Path<T> {
+  fn sdf(&self, pixel: P2<T>) -> T {
+    let dist = self.segments.iter()
+      .map(|segment| segment.sdf(pixel))
+      .fold(T::max_value() / (T::one() + T::one()), T::min);
+    let cross = |a: P2<T>, b: P2<T>| (b - a).cross(pixel - a);
+    let winding = self.edges.iter().fold(0, |winding, &(a, b)| {
+      if a.y <= pixel.y && b.y > pixel.y && cross(a, b) > T::zero() {
+        winding + 1
+      } else if a.y > pixel.y && b.y <= pixel.y && cross(a, b) < T::zero() {
+        winding - 1
+      } else {
+        winding
+      }
+    });
+    let inside = match self.rule {
+      FillRule::NonZero => winding != 0,
+      FillRule::EvenOdd => winding % 2 != 0
+    };
+    if inside { -dist } else { dist }
+  }
+}
+
+/// Contours of a [`Path`], in world coordinates. Open contours are closed by a line.
+#[derive(Debug, Clone)]
+pub struct PathBuilder<T> {
+  start: P2<T>,
+  current: P2<T>,
+  segments: Vec<Segment<T>>,
+  edges: Vec<(P2<T>, P2<T>)>,
+  points: Vec<P2<T>>,
+  rule: FillRule
+}
+
+impl<T: Float> Default for PathBuilder<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Float> PathBuilder<T> {
+  pub fn new() -> Self {
+    Self {
+      start: Point2D::origin(),
+      current: Point2D::origin(),
+      segments: vec![],
+      edges: vec![],
+      points: vec![],
+      rule: FillRule::NonZero
+    }
+  }
+
+  pub fn with_fill_rule(self, rule: FillRule) -> Self {
+    Self { rule, ..self }
+  }
+
+  /// Begin a contour, closing the previous one.
+  pub fn move_to(&mut self, p: P2<T>) -> &mut Self {
+    self.close();
+    self.points.push(p);
+    self.start = p;
+    self.current = p;
+    self
+  }
+
+  pub fn line_to(&mut self, b: P2<T>) -> &mut Self {
+    let a = self.current;
+    self.points.push(b);
+    self.edges.push((a, b));
+    self.segments.push(Segment::Line(Line { a, b, thickness: T::zero() }));
+    self.current = b;
+    self
+  }
+
+  /// Quadratic Bézier to `c`, with the control point `b`.
+  pub fn quad_to(&mut self, b: P2<T>, c: P2<T>) -> &mut Self {
+    let a = self.current;
+    let two = T::one() + T::one();
+    let curve = |t: T| (a.to_vector() * (T::one() - t).powi(2) + b.to_vector() * (two * t * (T::one() - t))
+      + c.to_vector() * t.powi(2)).to_point();
+    self.points.extend([b, c]);
+    self.push(Segment::Quad(Bezier2 { a, b, c, thickness: T::zero() }), c, curve)
+  }
+
+  /// Cubic Bézier to `d`, with the control points `b` and `c`.
+  pub fn cubic_to(&mut self, b: P2<T>, c: P2<T>, d: P2<T>) -> &mut Self {
+    let curve = Bezier3 { a: self.current, b, c, d, thickness: T::zero() };
+    self.points.extend([b, c, d]);
+    self.push(Segment::Cubic(curve), d, |t| curve.point(t))
+  }
+
+  /// Close the current contour by a line to its start, if needed.
+  pub fn close(&mut self) -> &mut Self {
+    if self.current != self.start {
+      self.line_to(self.start);
+    }
+    self
+  }
+
+  pub fn build(mut self) -> Path<T> {
+    self.close();
+    let bounding_box = if self.points.is_empty() { Box2D::zero() } else { Box2D::from_points(&self.points) };
+    Path { segments: self.segments, edges: self.edges, bounding_box, rule: self.rule }
+  }
+
+  fn push(&mut self, segment: Segment<T>, end: P2<T>, curve: impl Fn(T) -> P2<T>) -> &mut Self {
+    let n = T::from(FLATTEN).unwrap();
+    let mut a = self.current;
+    (1..=FLATTEN).for_each(|i| {
+      let b = if i == FLATTEN { end } else { curve(T::from(i).unwrap() / n) };
+      self.edges.push((a, b));
+      a = b;
+    });
+    self.segments.push(segment);
+    self.current = end;
+    self
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::tests::close};
+
+  // a square, with a square hole of the same winding
+  fn square_with_hole(rule: FillRule) -> Path<f64> {
+    let mut builder = PathBuilder::new().with_fill_rule(rule);
+    builder.move_to(Point2D::new(0.0, 0.0));
+    [(1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].iter().for_each(|&(x, y)| { builder.line_to(Point2D::new(x, y)); });
+    builder.move_to(Point2D::new(0.25, 0.25));
+    [(0.75, 0.25), (0.75, 0.75), (0.25, 0.75)].iter().for_each(|&(x, y)| { builder.line_to(Point2D::new(x, y)); });
+    builder.build()
+  }
+
+  #[test] fn fill_rule() {
+    let (non_zero, even_odd) = (square_with_hole(FillRule::NonZero), square_with_hole(FillRule::EvenOdd));
+    assert_eq!(non_zero.segments.len(), 8);
+    assert!(close(non_zero.sdf(Point2D::new(0.5, 0.5)), -0.25));
+    assert!(close(even_odd.sdf(Point2D::new(0.5, 0.5)), 0.25));
+    [&non_zero, &even_odd].iter().for_each(|path| {
+      assert!(close(path.sdf(Point2D::new(0.1, 0.5)), -0.1));
+      assert!(close(path.sdf(Point2D::new(1.5, 0.5)), 0.5));
+    });
+    let moved = even_odd.transform(2.0, V2::new(-1.0, -1.0));
+    assert_eq!(moved.bounding_box(), Box2D::new(Point2D::new(-1.0, -1.0), Point2D::new(1.0, 1.0)));
+    assert!(close(moved.sdf(Point2D::new(-0.8, 0.0)), -0.2));
+    assert!(close(moved.sdf(Point2D::origin()), 0.5));
+  }
+}
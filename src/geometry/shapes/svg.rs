@@ -0,0 +1,123 @@
+use {
+  super::{FillRule, Path, PathBuilder},
+  crate::{geometry::{BoundingBox, WorldSpace}, sdf::SDF},
+  euclid::{Box2D, Point2D},
+  num_traits::Float,
+  usvg::{Group, Node, Options, Tree, tiny_skia_path::{PathSegment, Point}}
+};
+
+/// Vector artwork: the union of the filled paths of an SVG document, in its user units, with
+/// `y` pointing down, as in the rest of the world. Strokes, images, text, clipping and masks
+/// are ignored; convert them to paths beforehand. See [`Svg::fit`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Svg<T> {
+  pub paths: Vec<Path<T>>
+}
+
+impl<T: Float> Svg<T> {
+  /// Parse an SVG document.
+  pub fn parse(svg: &str) -> anyhow::Result<Self> {
+    let tree = Tree::from_str(svg, &Options::default())?;
+    let mut paths = vec![];
+    collect(tree.root(), &mut paths);
+    Ok(Self { paths })
+  }
+
+  /// Parse the `d` attribute of a path, filled by the non-zero rule.
+  pub fn from_path_data(d: &str) -> anyhow::Result<Self> {
+    let d = d.replace('&', "&amp;").replace('<', "&lt;").replace('"', "&quot;");
+    Self::parse(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="{d}"/></svg>"#))
+  }
+
+  /// Scaled and centered, such that the longer side spans `-1..=1`, like the rest of the
+  /// primitives.
+  pub fn fit(self) -> Self {
+    let bounding_box = self.bounding_box();
+    let size = bounding_box.size();
+    if size.width.max(size.height) <= T::zero() {
+      return self;
+    }
+    let scale = (T::one() + T::one()) / size.width.max(size.height);
+    let offset = -bounding_box.center().to_vector() * scale;
+    Self { paths: self.paths.iter().map(|path| path.transform(scale, offset)).collect() }
+  }
+}
+
+fn collect<T: Float>(group: &Group, paths: &mut Vec<Path<T>>) {
+  group.children().iter().for_each(|node| match node {
+    Node::Group(group) => collect(group, paths),
+    Node::Path(path) if path.is_visible() => if let Some(fill) = path.fill() {
+      let rule = match fill.rule() {
+        usvg::FillRule::NonZero => FillRule::NonZero,
+        usvg::FillRule::EvenOdd => FillRule::EvenOdd
+      };
+      let transform = path.abs_transform();
+      let point = |mut p: Point| {
+        transform.map_point(&mut p);
+        Point2D::new(T::from(p.x).unwrap(), T::from(p.y).unwrap())
+      };
+      let mut builder = PathBuilder::new().with_fill_rule(rule);
+      path.data().segments().for_each(|segment| { match segment {
+        PathSegment::MoveTo(p) => builder.move_to(point(p)),
+        PathSegment::LineTo(p) => builder.line_to(point(p)),
+        PathSegment::QuadTo(b, c) => builder.quad_to(point(b), point(c)),
+        PathSegment::CubicTo(b, c, d) => builder.cubic_to(point(b), point(c), point(d)),
+        PathSegment::Close => builder.close()
+      }; });
+      let path = builder.build();
+      if !path.is_empty() {
+        paths.push(path);
+      }
+    },
+    _ => ()
+  });
+}
+
+impl<T: Float> BoundingBox<T> for Svg<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.paths.iter()
+      .map(|path| path.bounding_box())
+      .reduce(|a, b| a.union(&b))
+      .unwrap_or_else(Box2D::zero)
+  }}
+
+impl<T: Float> SDF<T> for Svg<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    self.paths.iter()
+      .map(|path| path.sdf(pixel))
+      .fold(T::max_value() / (T::one() + T::one()), T::min)
+  }
+}
+
+#[cfg(test)] mod tests {
+  use super::*;
+
+  #[test] fn svg() -> anyhow::Result<()> {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-4;
+    // a 100 x 50 rectangle with an even-odd hole, and a circle, moved by a group
+    let art = Svg::<f64>::parse(r#"
+      <svg xmlns="http://www.w3.org/2000/svg" width="400" height="200">
+        <path fill-rule="evenodd" d="M0 0 H100 V50 H0 Z M25 10 h50 v30 h-50 Z"/>
+        <g transform="translate(150 25)"><circle r="25"/></g>
+        <path d="M0 100 H100" fill="none" stroke="black"/>
+      </svg>
+    "#)?;
+    assert_eq!(art.paths.len(), 2);
+    assert_eq!(art.bounding_box(), Box2D::new(Point2D::new(0.0, 0.0), Point2D::new(175.0, 50.0)));
+    assert!(close(art.sdf(Point2D::new(5.0, 25.0)), -5.0));
+    assert!(close(art.sdf(Point2D::new(50.0, 25.0)), 15.0));
+    assert!((art.sdf(Point2D::new(150.0, 25.0)) + 25.0).abs() < 0.1);
+    assert!(close(art.sdf(Point2D::new(50.0, 100.0)), 50.0));
+
+    let art = art.fit();
+    assert!(close(art.bounding_box().min.x, -1.0) && close(art.bounding_box().max.x, 1.0));
+    assert!(close(art.sdf(Point2D::new(-1.0 + 5.0 / 87.5, 0.0)), -5.0 / 87.5));
+
+    let triangle = Svg::<f32>::from_path_data("M0 0 L10 0 L0 10 z")?;
+    assert!(triangle.sdf(Point2D::new(1.0, 1.0)) < 0.0);
+    assert!(triangle.sdf(Point2D::new(9.0, 9.0)) > 0.0);
+    assert!(Svg::<f32>::parse("<svg").is_err());
+    Ok(())
+  }
+}
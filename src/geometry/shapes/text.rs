@@ -0,0 +1,176 @@
+use {
+  super::{Path, PathBuilder},
+  crate::{geometry::{BoundingBox, WorldSpace}, sdf::SDF},
+  euclid::{Box2D, Point2D, Vector2D as V2},
+  num_traits::Float,
+  ttf_parser::{Face, GlyphId, OutlineBuilder}
+};
+
+/// Outline of a font glyph, in ems: the origin is on the baseline, and `y` points down, as in
+/// the rest of the world. The outline is a [`Path`], filled by the non-zero winding rule.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Glyph<T> {
+  pub path: Path<T>,
+  /// Horizontal advance
+  pub advance: T
+}
+
+impl<T: Float> Glyph<T> {
+  /// The glyph of `c`, if the font has one.
+  pub fn new(face: &Face, c: char) -> Option<Self> {
+    face.glyph_index(c).map(|id| Self::from_id(face, id))
+  }
+
+  /// The glyph `id`; glyphs without an outline, such as a space, are empty.
+  pub fn from_id(face: &Face, id: GlyphId) -> Self {
+    let mut builder = Builder::new(T::one() / T::from(face.units_per_em()).unwrap());
+    face.outline_glyph(id, &mut builder);
+    let advance = T::from(face.glyph_hor_advance(id).unwrap_or(0)).unwrap() * builder.scale;
+    Self { path: builder.path.build(), advance }
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Glyph<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.path.bounding_box()
+  }}
+
+impl<T: Float> SDF<T> for Glyph<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    self.path.sdf(pixel)
+  }
+}
+
+/// A line of text, laid out by the horizontal advances of the glyphs, without kerning. In ems,
+/// with the origin on the baseline, see [`Glyph`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Text<T> {
+  pub glyphs: Vec<(V2<T, WorldSpace>, Glyph<T>)>
+}
+
+impl<T: Float> Text<T> {
+  /// Characters missing from the font are drawn as its `.notdef` glyph.
+  pub fn new(face: &Face, text: &str) -> Self {
+    let mut pen = T::zero();
+    let glyphs = text.chars()
+      .map(|c| {
+        let glyph = Glyph::from_id(face, face.glyph_index(c).unwrap_or(GlyphId(0)));
+        let offset = V2::new(pen, T::zero());
+        pen = pen + glyph.advance;
+        (offset, glyph)
+      })
+      .collect();
+    Self { glyphs }
+  }
+
+  /// Horizontal advance of the whole line.
+  pub fn advance(&self) -> T {
+    self.glyphs.last().map_or(T::zero(), |(offset, glyph)| offset.x + glyph.advance)
+  }
+}
+
+impl<T: Float> BoundingBox<T> for Text<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.glyphs.iter()
+      .map(|(offset, glyph)| glyph.bounding_box().translate(*offset))
+      .filter(|bounding_box| !bounding_box.is_empty())
+      .reduce(|a, b| a.union(&b))
+      .unwrap_or_else(Box2D::zero)
+  }}
+
+impl<T: Float> SDF<T> for Text<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    self.glyphs.iter()
+      .map(|(offset, glyph)| glyph.sdf(pixel - *offset))
+      .fold(T::max_value() / (T::one() + T::one()), T::min)
+  }
+}
+
+// font units to ems, flipping `y`
+struct Builder<T> {
+  scale: T,
+  path: PathBuilder<T>
+}
+
+impl<T: Float> Builder<T> {
+  fn new(scale: T) -> Self {
+    Self { scale, path: PathBuilder::new() }
+  }
+
+  fn point(&self, x: f32, y: f32) -> Point2D<T, WorldSpace> {
+    Point2D::new(T::from(x).unwrap(), -T::from(y).unwrap()) * self.scale
+  }
+}
+
+impl<T: Float> OutlineBuilder for Builder<T> {
+  fn move_to(&mut self, x: f32, y: f32) {
+    self.path.move_to(self.point(x, y));
+  }
+
+  fn line_to(&mut self, x: f32, y: f32) {
+    self.path.line_to(self.point(x, y));
+  }
+
+  fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+    self.path.quad_to(self.point(x1, y1), self.point(x, y));
+  }
+
+  fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+    self.path.cubic_to(self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+  }
+
+  fn close(&mut self) {
+    self.path.close();
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::tests::close};
+
+  // a square of 100 font units, with a square hole of the opposite winding
+  fn square_with_hole() -> Glyph<f64> {
+    let mut builder = Builder::new(0.01);
+    builder.move_to(0.0, 0.0);
+    [(100.0, 0.0), (100.0, 100.0), (0.0, 100.0)].iter().for_each(|&(x, y)| builder.line_to(x, y));
+    builder.close();
+    builder.move_to(25.0, 25.0);
+    [(25.0, 75.0), (75.0, 75.0), (75.0, 25.0)].iter().for_each(|&(x, y)| builder.line_to(x, y));
+    builder.close();
+    Glyph { path: builder.path.build(), advance: 1.2 }
+  }
+
+  #[test] fn glyph() {
+    let glyph = square_with_hole();
+    assert_eq!(glyph.bounding_box(), Box2D::new(Point2D::new(0.0, -1.0), Point2D::new(1.0, 0.0)));
+    assert!(close(glyph.sdf(Point2D::new(0.1, -0.5)), -0.1));
+    assert!(close(glyph.sdf(Point2D::new(0.5, -0.5)), 0.25));
+    assert!(close(glyph.sdf(Point2D::new(1.5, -0.5)), 0.5));
+  }
+
+  #[test] fn curves() {
+    // a circle of quadratic arcs, and of cubic ones
+    let k = 4.0 / 3.0 * (2f32.sqrt() - 1.0);
+    let mut quad = Builder::new(1.0);
+    quad.move_to(1.0, 0.0);
+    quad.quad_to(1.0, 1.0, 0.0, 1.0);
+    quad.quad_to(-1.0, 1.0, -1.0, 0.0);
+    quad.quad_to(-1.0, -1.0, 0.0, -1.0);
+    quad.quad_to(1.0, -1.0, 1.0, 0.0);
+    quad.close();
+    let mut cubic = Builder::new(1.0);
+    cubic.move_to(1.0, 0.0);
+    cubic.curve_to(1.0, k, k, 1.0, 0.0, 1.0);
+    cubic.curve_to(-k, 1.0, -1.0, k, -1.0, 0.0);
+    cubic.curve_to(-1.0, -k, -k, -1.0, 0.0, -1.0);
+    cubic.curve_to(k, -1.0, 1.0, -k, 1.0, 0.0);
+    cubic.close();
+    [quad.path.build(), cubic.path.build()].iter().for_each(|glyph| {
+      assert_eq!(glyph.bounding_box(), Box2D::new(Point2D::new(-1.0, -1.0), Point2D::new(1.0, 1.0)));
+      assert!((glyph.sdf(Point2D::origin()) + 1.0).abs() < 1e-3);
+      assert!((glyph.sdf(Point2D::new(0.0, 2.0)) - 1.0).abs() < 1e-6);
+      assert!((glyph.sdf(Point2D::new(1.5, 0.0)) - 0.5).abs() < 1e-6);
+    });
+  }
+}
@@ -46,13 +46,12 @@
     //!     .translate(global_max.point.to_vector())
     //!     .scale(global_max.distance / 4.0);
     //!   /** Update the field.
-    //!    * `Circle` impletemens the `SDF` trait. Additionally, it has been concluded that
-    //!    * only a certain part of the field is being changed every time, depending on the
-    //!    * current maximum distance. To be exact - a square region with a side of
-    //!    * max_dist * 4.0 * sqrt(2.0), which made possible achieving
-    //!    * greater speed of computation. **/
+    //!    * `Circle` impletemens the `SDF` trait. Additionally, only a certain part of the
+    //!    * field is being changed every time: the new shape can't be closer than the current
+    //!    * maximum distance outside of its bounding box, inflated by this distance. Updating
+    //!    * only this region made possible achieving greater speed of computation. **/
     //!   representation.insert_sdf_domain(
-    //!     util::domain_empirical(global_max),
+    //!     util::changed_domain(&circle, global_max.distance),
     //!     |v| circle.sdf(v)
     //!   );
     //!   circle
@@ -69,26 +68,27 @@
 //! #   space_filling::{
 //! #     geometry::{Shape, Circle, Translation, Scale, P2},
 //! #     sdf::{self, SDF},
-//! #     solver::{line_search::LineSearch, adf::ADF},
+//! #     solver::{line_search::LineSearch, adf::{ADF, SharedADF}},
 //! #     drawing::Draw,
 //! #     util
 //! #   },
 //! #   image::{Luma, Pixel},
 //! #   anyhow::Result,
 //! #   rand::prelude::*,
-//! #   std::sync::{Arc, RwLock}
+//! #   std::sync::Arc
 //! # };
 //! #
 //! # fn main() -> Result<()> {
     //! let path = "out.png";
-    //! let mut representation = RwLock::new(ADF::<f64>::new(5, vec![Arc::new(sdf::boundary_rect)]));
+    //! // `SharedADF` may be sampled and updated concurrently
+    //! let representation = SharedADF::new(ADF::<f64>::new(5, vec![Arc::new(sdf::boundary_rect)]));
     //! let mut image = image::RgbaImage::new(2048, 2048);
     //! // In case of GD-ADF, it is adviced to use `util::local_maxima_iter`,
     //! // as it is capable of finding multiple local maxima in parallel.
     //! // By default, this is an infinite iterator.
     //! util::local_maxima_iter(
     //!   // provide a closure for sampling distance field
-    //!   Box::new(|p| representation.read().unwrap().sdf(p)),
+    //!   Box::new(|p| representation.sdf(p)),
     //!   32, 0, LineSearch::default()
     //! ).filter_map(|local_max| {
     //!   let circle = Circle
@@ -96,10 +96,10 @@
     //!     .scale(local_max.distance / 4.0);
     //!   // Update distance field. Since the precision is not perfect, sometimes update may fail -
     //!   // thus Option is returned
-    //!   representation.write().unwrap().insert_sdf_domain(
+    //!   representation.insert_sdf_domain(
     //!     util::domain_empirical(local_max),
     //!     Arc::new(move |p| circle.sdf(p))
-    //!   ).then(|| circle)
+    //!   ).then_some(circle)
     //! }).take(1000) // stop, once 1000 circles were successfully added
     //!   .for_each(|shape| shape
     //!     .texture(Luma([255u8]).to_rgba())
@@ -149,7 +149,7 @@
 //!
 //! Lastly, there is: [`draw_parallel`](drawing::draw_parallel), which is convenient when shapes
 //! require heavy computations to draw, such as texture loading. It accepts an iterator on
-//! `dyn Draw<RgbaImage> + Send + Sync`, constructed via trait object casting, exactly as above.
+//! `dyn DrawTile<RgbaImage> + Send + Sync`, constructed via trait object casting, as above.
 //! See `examples/argmax2d/03_embedded.rs`, `examples/gd_adf/04_polymorphic.rs` and
 //! `drawing/tests::polymorphic_*` for more details.
 //!
@@ -162,7 +162,7 @@
 
 #![allow(clippy::type_complexity)]
 
-#![cfg_attr(doc, feature(doc_cfg))]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![allow(rustdoc::private_intra_doc_links)]
 
 pub mod util;
@@ -170,5 +170,8 @@ pub mod sdf;
 pub mod solver;
 pub mod geometry;
 #[cfg(feature = "drawing")]
-#[cfg_attr(doc, doc(cfg(feature = "drawing")))]
-pub mod drawing;
\ No newline at end of file
+#[cfg_attr(docsrs, doc(cfg(feature = "drawing")))]
+pub mod drawing;
+#[cfg(feature = "drawing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "drawing")))]
+pub mod patterns;
\ No newline at end of file
@@ -0,0 +1,100 @@
+//! High-level helpers for common layouts, built on top of [`solver`](crate::solver) and
+//! [`drawing`](crate::drawing).
+
+use {
+  crate::{
+    geometry::{Shape, Circle, DistPoint, WorldSpace},
+    sdf::SDF,
+    solver::Argmax2D,
+    drawing::Draw,
+    util
+  },
+  anyhow::Result,
+  image::{Rgba, RgbaImage},
+  rand::prelude::*
+};
+
+#[cfg(test)] mod tests;
+
+pub use crate::util::FillStrategy;
+
+#[derive(Debug, Clone)]
+pub struct PosterConfig {
+  /// Side of the output image, in pixels
+  pub resolution: u32,
+  /// Resolution of the [`Argmax2D`] field; must be divisible by `chunk_size`
+  pub field_resolution: u64,
+  pub chunk_size: u64,
+  pub strategy: FillStrategy,
+  /// Clearance around the title
+  pub margin: f32,
+  /// Stop once there is no room for a circle of this radius
+  pub min_radius: f32,
+  pub max_shapes: usize,
+  pub background: Rgba<u8>,
+  pub fill_color: Rgba<u8>,
+  pub title_color: Rgba<u8>
+}
+
+impl Default for PosterConfig {
+  fn default() -> Self {
+    Self {
+      resolution: 2048,
+      field_resolution: 1024,
+      chunk_size: 16,
+      strategy: FillStrategy::Fractal { scale: 0.25 },
+      margin: 0.01,
+      min_radius: 1.0 / 1024.0,
+      max_shapes: 10000,
+      background: Rgba([0, 0, 0, 255]),
+      fill_color: Rgba([255, 255, 255, 255]),
+      title_color: Rgba([255, 64, 64, 255])
+    }
+  }
+}
+
+/// Fill the interior of `body_mask` with circles, except for the region around `title` (a logo
+/// or text), then render both layers: the fill first, and the title on top of it.
+/// Returns the image, and the placed circles.
+pub fn poster<T>(
+  title: T,
+  body_mask: impl SDF<f32> + Send + Sync,
+  config: &PosterConfig
+) -> Result<(RgbaImage, Vec<DistPoint<f32, f32, WorldSpace>>)>
+  where T: Shape<f32> + Clone + Send + Sync
+{
+  let mut argmax = Argmax2D::new(config.field_resolution, config.chunk_size)?
+    .with_boundary(body_mask);
+  {
+    let title = title.clone();
+    let margin = config.margin;
+    argmax.insert_sdf(move |p| title.sdf(p) - margin);
+  }
+
+  let mut rng = rand_pcg::Pcg64::seed_from_u64(config.strategy.seed());
+  let mut circles = vec![];
+  while circles.len() < config.max_shapes {
+    let Some(global_max) = argmax.find_max_above(config.min_radius) else { break };
+    let Some(circle) = config.strategy.place(global_max, &mut rng) else { break };
+    let shape = Circle
+      .translate(circle.point.to_vector())
+      .scale(circle.distance);
+    argmax.insert_sdf_domain(
+      util::changed_domain(&shape, global_max.distance),
+      move |p| shape.sdf(p)
+    );
+    circles.push(circle);
+  }
+
+  let mut image = RgbaImage::from_pixel(config.resolution, config.resolution, config.background);
+  circles.iter().for_each(|circle| Circle
+    .translate(circle.point.to_vector())
+    .scale(circle.distance)
+    .texture(config.fill_color)
+    .draw(&mut image)
+  );
+  title
+    .texture(config.title_color)
+    .draw(&mut image);
+  Ok((image, circles))
+}
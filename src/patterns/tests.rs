@@ -0,0 +1,30 @@
+use {
+  super::*,
+  crate::geometry::Square,
+  euclid::Vector2D as V2
+};
+
+#[test] fn poster() -> Result<()> {
+  let title = Square.translate(V2::splat(0.5)).scale(0.2);
+  let mask = Circle.translate(V2::splat(0.5)).scale(0.45);
+  for strategy in [FillStrategy::Fractal { scale: 0.5 }, FillStrategy::Random { seed: 0 }] {
+    let config = PosterConfig {
+      resolution: 128,
+      field_resolution: 256,
+      min_radius: 1.0 / 64.0,
+      strategy,
+      ..Default::default()
+    };
+    let (image, circles) = super::poster(title, mask, &config)?;
+    assert!(circles.len() > 10);
+    // one pixel of tolerance for the discrete field
+    let px = 1.0 / 256.0;
+    circles.iter().for_each(|circle| {
+      assert!(title.sdf(circle.point) - circle.distance > config.margin - px);
+      assert!(-mask.sdf(circle.point) - circle.distance > -px);
+    });
+    assert_eq!(*image.get_pixel(64, 64), config.title_color);
+    assert_eq!(*image.get_pixel(1, 1), config.background);
+  }
+  Ok(())
+}
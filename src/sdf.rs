@@ -1,15 +1,25 @@
 use {
   euclid::{Point2D, Vector2D as V2, Rotation2D, Box2D},
   crate::{
-    geometry::{self, WorldSpace, Shape, Rotation, Scale, Translation, BoundingBox},
+    geometry::{self, WorldSpace, Shape, Rotation, Scale, Stroke, Translation, Transform2D, Mirror, Repeat, RepeatPolar, Offset, Warp, Displace, BoundingBox, Anisotropic, P2},
   },
-  num_traits::{Float, Signed},
+  num_traits::{Float, FloatConst, Signed},
   std::ops::{Neg, Sub}
 };
 
 /// Signed distance function
 pub trait SDF<T> {
   fn sdf(&self, p: Point2D<T, WorldSpace>) -> T;
+  /// Gradient of the field at `p`, of unit length almost everywhere for exact distances.
+  /// Central difference by default, overridden where it is known in closed form.
+  fn gradient(&self, p: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> where T: Float {
+    central_difference(|p| self.sdf(p), p, T::epsilon().cbrt())
+  }
+}
+
+pub(crate) fn central_difference<T: Float>(f: impl Fn(Point2D<T, WorldSpace>) -> T, p: Point2D<T, WorldSpace>, delta: T) -> V2<T, WorldSpace> {
+  let (dx, dy) = (V2::new(delta, T::zero()), V2::new(T::zero(), delta));
+  V2::new(f(p + dx) - f(p - dx), f(p + dy) - f(p - dy)) / (delta + delta)
 }
 
 impl <S, P: Float> SDF<P> for Translation<S, P>
@@ -18,6 +28,9 @@ impl <S, P: Float> SDF<P> for Translation<S, P>
   fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
     self.shape.sdf(pixel - self.offset)
   }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    self.shape.gradient(pixel - self.offset)
+  }
 }
 
 impl <S, P> SDF<P> for Rotation<S, P>
@@ -31,6 +44,12 @@ impl <S, P> SDF<P> for Rotation<S, P>
 
     self.shape.sdf(pixel)
   }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let pivot = self.shape.bounding_box().center();
+    let rotation = Rotation2D::new(self.angle);
+    let local = rotation.transform_point((pixel - pivot).to_point()) + pivot.to_vector();
+    rotation.inverse().transform_vector(self.shape.gradient(local))
+  }
 }
 
 impl <S, P> SDF<P> for Scale<S, P>
@@ -42,6 +61,131 @@ impl <S, P> SDF<P> for Scale<S, P>
       .to_point();
     self.shape.sdf(pixel) * self.scale
   }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let c = self.shape.bounding_box().center();
+    self.shape.gradient(((pixel - c) / self.scale + c.to_vector()).to_point())
+  }
+}
+
+impl <S, P> SDF<P> for Transform2D<S, P>
+  where S: SDF<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    match self.transform.inverse() {
+      Some(inverse) => self.shape.sdf(inverse.transform_point(pixel)) * self.min_scale(),
+      None => P::max_value() / (P::one() + P::one())
+    }
+  }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let Some(inverse) = self.transform.inverse() else { return V2::zero() };
+    // by the inverse transpose of the linear part
+    let t = &self.transform;
+    let g = self.shape.gradient(inverse.transform_point(pixel)) * (self.min_scale() / t.determinant());
+    V2::new(t.m22 * g.x - t.m12 * g.y, t.m11 * g.y - t.m21 * g.x)
+  }
+}
+
+impl <S, P> SDF<P> for Mirror<S>
+  where S: SDF<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    let x = if self.x { pixel.x.abs() } else { pixel.x };
+    let y = if self.y { pixel.y.abs() } else { pixel.y };
+    self.shape.sdf(Point2D::new(x, y))
+  }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let fold = |x: P, mirror: bool| if mirror && x < P::zero() { -P::one() } else { P::one() };
+    let sign = V2::new(fold(pixel.x, self.x), fold(pixel.y, self.y));
+    self.shape.gradient(Point2D::new(pixel.x * sign.x, pixel.y * sign.y)).component_mul(sign)
+  }
+}
+
+impl <S, P> SDF<P> for Repeat<S, P>
+  where S: Shape<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    let c = self.shape.bounding_box().center();
+    // index of the nearest copy along an axis, and of its neighbour towards the pixel
+    let cells = |x: P, spacing: P, count: u32| {
+      if spacing == P::zero() { return (P::zero(), P::zero()) }
+      let last = P::from(count.max(1) - 1).unwrap();
+      let i = (x / spacing).round().max(P::zero()).min(last);
+      let j = (i + (x / spacing - i).signum()).max(P::zero()).min(last);
+      (i, j)
+    };
+    let (ix, jx) = cells(pixel.x - c.x, self.spacing.x, self.counts[0]);
+    let (iy, jy) = cells(pixel.y - c.y, self.spacing.y, self.counts[1]);
+    [(ix, iy), (jx, iy), (ix, jy), (jx, jy)].iter()
+      .map(|&(x, y)| self.shape.sdf(pixel - V2::new(x * self.spacing.x, y * self.spacing.y)))
+      .fold(P::max_value(), P::min)
+  }
+}
+
+impl <S, P> SDF<P> for RepeatPolar<S>
+  where S: Shape<P>,
+        P: Float + FloatConst {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    let c = self.shape.bounding_box().center();
+    // the sector of the nearest copy, and its neighbour towards the pixel
+    let angle = pixel.y.atan2(pixel.x) - c.y.atan2(c.x);
+    let sector = angle * P::from(self.n.max(1)).unwrap() / P::TAU();
+    let k = sector.round();
+    [k, k + (sector - k).signum()].iter()
+      .map(|&k| self.shape.sdf(self.rotation(-k).transform_point(pixel)))
+      .fold(P::max_value(), P::min)
+  }
+}
+
+impl <S, P> SDF<P> for Offset<S, P>
+  where S: SDF<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    self.shape.sdf(pixel) - self.radius
+  }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    self.shape.gradient(pixel)
+  }
+}
+
+impl <S, F, P> SDF<P> for Warp<S, F, P>
+  where S: SDF<P>,
+        F: Fn(P2<P>) -> P2<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    self.shape.sdf((self.warp)(pixel))
+  }
+}
+
+impl <S, F, P> SDF<P> for Displace<S, F, P>
+  where S: SDF<P>,
+        F: Fn(P2<P>) -> P,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    self.shape.sdf(pixel) + (self.displace)(pixel)
+  }
+}
+
+impl <S, P> SDF<P> for Stroke<S, P>
+  where S: SDF<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    self.shape.sdf(pixel).abs() - self.width / (P::one() + P::one())
+  }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    self.shape.gradient(pixel) * self.shape.sdf(pixel).signum()
+  }
+}
+
+impl <S, P> SDF<P> for Anisotropic<S, P>
+  where S: SDF<P>,
+        P: Float {
+  fn sdf(&self, pixel: Point2D<P, WorldSpace>) -> P {
+    self.shape.sdf(self.metric.to_metric(pixel.to_vector()).to_point())
+  }
+  fn gradient(&self, pixel: Point2D<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let g = self.shape.gradient(self.metric.to_metric(pixel.to_vector()).to_point());
+    self.metric.gradient_from_metric(g)
+  }
 }
 
 /// Distance to the edges of image.
@@ -52,6 +196,27 @@ pub fn boundary_rect<T: Float + Signed>(pixel: Point2D<T, WorldSpace>) -> T {
     .sdf(pixel)
 }
 
+/// Distance to the edge of the circle inscribed into image.
+pub fn boundary_circle<T: Float>(pixel: Point2D<T, WorldSpace>) -> T {
+  let p5 = T::one() / (T::one() + T::one());
+  -geometry::Circle
+    .scale(p5)
+    .translate(V2::splat(p5))
+    .sdf(pixel)
+}
+
+/// Distance to the edges of image, inset by `margin`.
+pub fn boundary_frame<T: Float + Signed>(margin: T) -> impl Fn(Point2D<T, WorldSpace>) -> T + Copy {
+  move |pixel| boundary_rect(pixel) - margin
+}
+
+/// Distance to the edge of an arbitrary `shape`, such as a glyph or a country outline, positive
+/// inside of it; as [`boundary_rect`], for `insert_sdf` or [`ADF::new`](crate::solver::ADF::new).
+pub fn boundary_of<T, S: SDF<T>>(shape: S) -> impl Fn(Point2D<T, WorldSpace>) -> T
+  where T: Neg<Output = T> {
+  move |pixel| -shape.sdf(pixel)
+}
+
 /// Union of two SDFs.
 #[derive(Clone, Copy, Debug)]
 pub struct Union<S1, S2> {
@@ -65,6 +230,9 @@ impl<T, S1, S2> SDF<T> for Union<S1, S2>
         S2: SDF<T> {
   fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
     self.s1.sdf(pixel).min(self.s2.sdf(pixel))
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    if self.s1.sdf(pixel) <= self.s2.sdf(pixel) { self.s1.gradient(pixel) } else { self.s2.gradient(pixel) }
   }}
 
 impl<T, S1, S2> BoundingBox<T> for Union<S1, S2>
@@ -89,6 +257,9 @@ impl<T, S1, S2> SDF<T> for Subtraction<S1, S2>
     S2: SDF<T> {
   fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
     (-self.s2.sdf(pixel)).max(self.s1.sdf(pixel))
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    if -self.s2.sdf(pixel) >= self.s1.sdf(pixel) { -self.s2.gradient(pixel) } else { self.s1.gradient(pixel) }
   }}
 
 impl<T, S1, S2> BoundingBox<T> for Subtraction<S1, S2>
@@ -112,6 +283,9 @@ impl<T, S1, S2> SDF<T> for Intersection<S1, S2>
         S2: SDF<T> {
   fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
     self.s1.sdf(pixel).max(self.s2.sdf(pixel))
+  }
+  fn gradient(&self, pixel: Point2D<T, WorldSpace>) -> V2<T, WorldSpace> {
+    if self.s1.sdf(pixel) >= self.s2.sdf(pixel) { self.s1.gradient(pixel) } else { self.s2.gradient(pixel) }
   }}
 
 impl<T, S1, S2> BoundingBox<T> for Intersection<S1, S2>
@@ -127,9 +301,69 @@ impl<T, S1, S2> BoundingBox<T> for Intersection<S1, S2>
       })
   }}
 
+/// Symmetric difference of two SDFs: either one of the shapes, but not both.
+#[derive(Clone, Copy, Debug)]
+pub struct Xor<S1, S2> {
+  pub s1: S1,
+  pub s2: S2,
+}
+
+impl<T, S1, S2> SDF<T> for Xor<S1, S2>
+  where T: Float,
+        S1: SDF<T>,
+        S2: SDF<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let (s1, s2) = (self.s1.sdf(pixel), self.s2.sdf(pixel));
+    s1.min(s2).max(-s1.max(s2))
+  }}
+
+impl<T, S1, S2> BoundingBox<T> for Xor<S1, S2>
+  where T: Copy + PartialOrd,
+        S1: BoundingBox<T>,
+        S2: BoundingBox<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    self.s1.bounding_box().union(&self.s2.bounding_box())
+  }}
+
+/// Linear interpolation between two SDFs, from `s1` at `t = 0` to `s2` at `t = 1`. The result
+/// is a bound, rather than an exact distance, in between.
+#[derive(Clone, Copy, Debug)]
+pub struct Morph<T, S1, S2> {
+  pub s1: S1,
+  pub s2: S2,
+  pub t: T
+}
+
+impl<T, S1, S2> SDF<T> for Morph<T, S1, S2>
+  where T: Float,
+        S1: SDF<T>,
+        S2: SDF<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let (s1, s2) = (self.s1.sdf(pixel), self.s2.sdf(pixel));
+    s1 + (s2 - s1) * self.t
+  }}
+
+/// Covers the morph for `t` in `[0, 1]`; beyond that, it is extrapolated and may leave the union.
+impl<T, S1, S2> BoundingBox<T> for Morph<T, S1, S2>
+  where T: Float,
+        S1: BoundingBox<T>,
+        S2: BoundingBox<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    if self.t <= T::zero() {
+      self.s1.bounding_box()
+    } else if self.t >= T::one() {
+      self.s2.bounding_box()
+    } else {
+      self.s1.bounding_box().union(&self.s2.bounding_box())
+    }
+  }}
+
 /// Takes the minimum of two SDFs, smoothing between them when they are close.
 ///
 /// `k` controls the radius/distance of the smoothing. 32 is a good default value.
+///
+/// Exponential: the blend never vanishes, and shifts the whole edge by up to `1 / k`, see
+/// [`SmoothMin::radius`]. [`SmoothMinPoly`] is cheaper, and exact outside of the blend.
 #[derive(Clone, Copy, Debug)]
 pub struct SmoothMin<T, S1, S2> {
   pub s1: S1,
@@ -137,6 +371,14 @@ pub struct SmoothMin<T, S1, S2> {
   pub k: T
 }
 
+impl<T: Float, S1, S2> SmoothMin<T, S1, S2> {
+  /// The deepest the blend gets below the plain minimum, where both SDFs are equal: `1 / k`.
+  /// Same convention as [`SmoothMinPoly::radius`].
+  pub fn radius(&self) -> T {
+    self.k.recip()
+  }
+}
+
 impl<T, S1, S2> SDF<T> for SmoothMin<T, S1, S2>
   where T: Float,
         S1: SDF<T>,
@@ -153,4 +395,142 @@ impl<T, S1, S2> BoundingBox<T> for SmoothMin<T, S1, S2>
         S2: BoundingBox<T> {
   fn bounding_box(&self) -> Box2D<T, WorldSpace> {
     self.s1.bounding_box().union(&self.s2.bounding_box())
-  }}
\ No newline at end of file
+  }}
+
+/// Falloff of [`SmoothMinPoly`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Polynomial {
+  /// C¹ continuous
+  Quadratic,
+  /// C² continuous, slightly wider
+  Cubic
+}
+
+/// Polynomial smooth minimum of two SDFs: equal to the plain minimum, unless they are within
+/// `4 * radius` (quadratic) or `6 * radius` (cubic) of each other, and at most `radius` below it.
+/// No transcendental functions, hence cheaper and stable for any radius, unlike [`SmoothMin`].
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothMinPoly<T, S1, S2> {
+  pub s1: S1,
+  pub s2: S2,
+  pub radius: T,
+  pub polynomial: Polynomial
+}
+
+impl<T, S1, S2> SDF<T> for SmoothMinPoly<T, S1, S2>
+  where T: Float,
+        S1: SDF<T>,
+        S2: SDF<T> {
+  fn sdf(&self, pixel: Point2D<T, WorldSpace>) -> T {
+    let (s1, s2) = (self.s1.sdf(pixel), self.s2.sdf(pixel));
+    if self.radius <= T::zero() {
+      return s1.min(s2);
+    }
+    let (width, degree) = match self.polynomial {
+      Polynomial::Quadratic => (T::from(4).unwrap(), 2),
+      Polynomial::Cubic => (T::from(6).unwrap(), 3)
+    };
+    let k = self.radius * width;
+    let h = ((k - (s1 - s2).abs()).max(T::zero()) / k).powi(degree);
+    s1.min(s2) - h * self.radius
+  }}
+
+impl<T, S1, S2> BoundingBox<T> for SmoothMinPoly<T, S1, S2>
+  where T: Float,
+        S1: BoundingBox<T>,
+        S2: BoundingBox<T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    let r = self.radius.max(T::zero());
+    self.s1.bounding_box().union(&self.s2.bounding_box()).inflate(r, r)
+  }}
+
+/// The quadratic blend of the same [`radius`](SmoothMin::radius).
+impl<T: Float, S1, S2> From<SmoothMin<T, S1, S2>> for SmoothMinPoly<T, S1, S2> {
+  fn from(smooth: SmoothMin<T, S1, S2>) -> Self {
+    let radius = smooth.radius();
+    Self { s1: smooth.s1, s2: smooth.s2, radius, polynomial: Polynomial::Quadratic }
+  }
+}
+#[cfg(test)] pub(crate) mod tests {
+  use {super::*, euclid::Angle, geometry::{Circle, Square}};
+
+  /// Equality up to rounding, of distances computed in different ways.
+  pub(crate) fn close(a: f64, b: f64) -> bool { (a - b).abs() < 1e-9 }
+
+  #[test] fn smooth_min_poly() {
+    let (left, right) = (Circle.translate(V2::new(-1.0, 0.0)), Circle.translate(V2::new(1.0, 0.0)));
+    let quadratic = left.smooth_min_poly(right, 0.1, Polynomial::Quadratic);
+    let cubic = left.smooth_min_poly(right, 0.1, Polynomial::Cubic);
+    // at most `radius` deeper, where both are equal
+    let middle = Point2D::new(0.0, 0.5);
+    let plain = left.sdf(middle);
+    assert!(close(quadratic.sdf(middle), plain - 0.1));
+    assert!(close(cubic.sdf(middle), plain - 0.1));
+    // exact, away from the blend
+    let p = Point2D::new(-2.5, 0.0);
+    assert!(close(quadratic.sdf(p), 0.5) && close(cubic.sdf(p), 0.5));
+    // the exponential one, converted, has the same depth
+    let exponential = left.smooth_min(right, 10.0);
+    assert!(close(exponential.radius(), 0.1));
+    assert!(close(exponential.sdf(middle), plain - 0.1));
+    let converted: SmoothMinPoly<_, _, _> = exponential.into();
+    assert!(close(converted.sdf(middle), quadratic.sdf(middle)));
+    assert_eq!(quadratic.bounding_box(), Box2D::new(Point2D::new(-2.1, -1.1), Point2D::new(2.1, 1.1)));
+  }
+
+  #[test] fn xor_morph() {
+    let (left, right) = (Circle.translate(V2::new(-0.5, 0.0)), Circle.translate(V2::new(0.5, 0.0)));
+    let xor = left.xor(right);
+    // the lens, covered by both, is outside
+    assert!(close(xor.sdf(Point2D::origin()), 0.5));
+    assert!(close(xor.sdf(Point2D::new(-1.25, 0.0)), -0.25));
+    assert!(close(xor.sdf(Point2D::new(2.0, 0.0)), 0.5));
+    assert_eq!(xor.bounding_box(), Box2D::new(Point2D::new(-1.5, -1.0), Point2D::new(1.5, 1.0)));
+
+    let morph = |t| Circle.scale(0.5).morph(Square, t);
+    let p = Point2D::new(0.0, 2.0);
+    assert!(close(morph(0.0).sdf(p), 1.5) && close(morph(1.0).sdf(p), 1.0) && close(morph(0.5).sdf(p), 1.25));
+    assert_eq!(morph(0.0).bounding_box(), Box2D::new(Point2D::splat(-0.5), Point2D::splat(0.5)));
+    assert_eq!(morph(0.5).bounding_box(), Square.bounding_box());
+  }
+
+  #[test] fn analytic_gradient() {
+    fn check(shape: &impl SDF<f64>) {
+      itertools::iproduct!(0..9, 0..9)
+        .map(|(x, y)| Point2D::new(x as f64 / 3.7 - 1.3, y as f64 / 3.3 - 1.1))
+        .for_each(|p| {
+          let expected = central_difference(|p| shape.sdf(p), p, 1e-6);
+          assert!((shape.gradient(p) - expected).length() < 1e-4, "{p:?} {:?} {expected:?}", shape.gradient(p));
+        });
+    }
+    let rect = geometry::Rect { size: Point2D::new(1.0, 0.6) };
+    let line = geometry::Line { a: Point2D::new(-0.3, 0.2), b: Point2D::new(0.4, -0.5), thickness: 0.1 };
+    let vertices = [Point2D::new(-0.5, -0.4), Point2D::new(0.6, -0.2), Point2D::new(0.1, 0.7)];
+    let polygon = geometry::Polygon { vertices: &vertices[..] };
+    check(&Circle);
+    check(&rect);
+    check(&line);
+    check(&polygon);
+    check(&rect.translate(V2::new(0.1, 0.2)).rotate(Angle::degrees(20.0)).scale(0.7));
+    check(&Circle.transform(euclid::Transform2D::new(0.9, 0.4, -0.4, 0.9, 0.1, 0.0)));
+    check(&rect.transform(euclid::Transform2D::new(2.0, 0.0, 1.0, 1.0, 0.0, 0.0)));
+    check(&line.mirror_x().offset(0.1).stroke(0.05));
+    check(&Circle.anisotropic(geometry::Metric::axes(2.0, 0.5)));
+    check(&Circle.scale(0.3).union(rect).subtraction(Circle.scale(0.2)).intersection(Square));
+  }
+
+  #[test] fn boundaries() {
+    let frame = boundary_frame(0.1);
+    let circle = boundary_of(Circle.scale(0.5).translate(V2::splat(0.5)));
+    [(0.5, 0.5), (0.1, 0.5), (0.9, 0.95), (1.2, 0.5)].iter().for_each(|&(x, y)| {
+      let p = Point2D::new(x, y);
+      assert!(close(frame(p), boundary_rect(p) - 0.1));
+      assert!(close(circle(p), boundary_circle(p)));
+    });
+    assert!(close(boundary_circle(Point2D::new(0.5, 0.5)), 0.5));
+    assert!(close(boundary_circle(Point2D::new(1.0, 1.0)), 0.5 - 0.5 * 2f64.sqrt()));
+    assert!(close(frame(Point2D::new(0.05, 0.5)), -0.05));
+    // as the initial primitives of a solver
+    let _ = crate::solver::ADF::new(3, vec![std::sync::Arc::new(frame), std::sync::Arc::new(circle)]);
+  }
+}
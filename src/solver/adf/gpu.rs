@@ -0,0 +1,205 @@
+//! Flattened representation of an [`ADF`], evaluated by a compute shader, see [`WGSL`].
+//! Enables evaluating the field for millions of points at once, e.g. for GPU-side local
+//! maxima search, or rendering. Uploading the buffers is left to the GPU API of choice.
+
+use {
+  super::{ADF, ShapePrimitive},
+  crate::geometry::{P2, WorldSpace},
+  std::collections::{HashMap, VecDeque},
+  anyhow::{Result, bail},
+  euclid::Vector2D as V2,
+  num_traits::{Float, Signed}
+};
+
+/// Compute shader, evaluating `sdf` of a [`GpuAdf`] at an array of points, one per invocation.
+/// Entry point is `main`, with `@workgroup_size(64)`; bindings of group 0:
+/// 0. `nodes: array<Node>` - [`GpuAdf::nodes`]
+/// 1. `buckets: array<u32>` - [`GpuAdf::buckets`]
+/// 2. `code: array<f32>` - [`GpuAdf::code`]
+/// 3. `points: array<vec2<f32>>`
+/// 4. `distances: array<f32>`, read-write, the output
+/// 5. `params: Params` (uniform) - amount of points, and [`GpuAdf::toroidal`] as `u32`
+pub const WGSL: &str = include_str!("gpu.wgsl");
+
+/// Maximum nesting of transforms and complements within a primitive.
+pub const MAX_NESTING: usize = 16;
+
+const OP_CIRCLE: f32 = 0.0;
+const OP_RECT: f32 = 1.0;
+const OP_POLYGON: f32 = 2.0;
+const OP_RASTER: f32 = 3.0;
+const OP_TRANSFORM: f32 = 4.0;
+const OP_COMPLEMENT: f32 = 5.0;
+
+/// A node of the tree, laid out as `Node` of [`WGSL`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GpuNode {
+  pub x: f32,
+  pub y: f32,
+  pub size: f32,
+  /// Index of the first of 4 children, 0 for leaves
+  pub children: u32,
+  pub bucket_start: u32,
+  pub bucket_len: u32
+}
+
+/// [`ADF`] of [`ShapePrimitive`], flattened into linear buffers, see [`WGSL`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuAdf {
+  /// Breadth-first, siblings are adjacent
+  pub nodes: Vec<GpuNode>,
+  /// Offsets of the primitives into `code`, `bucket_start..bucket_start + bucket_len` per node
+  pub buckets: Vec<u32>,
+  /// Primitives, each one encoded as a sequence of instructions; shared between buckets
+  pub code: Vec<f32>,
+  pub toroidal: bool
+}
+
+impl GpuAdf {
+  /// Flatten the tree, single precision. Fails on primitives nested deeper than [`MAX_NESTING`].
+  pub fn compile<T: Float + Signed>(adf: &ADF<T, ShapePrimitive<T>>) -> Result<Self> {
+    let mut gpu = Self { toroidal: adf.toroidal, ..Default::default() };
+    // offsets of the primitives already encoded, by their code
+    let mut encoded = HashMap::<Vec<u32>, u32>::new();
+    let mut queue = VecDeque::from([(&adf.tree, 0)]);
+    gpu.nodes.push(GpuNode::default());
+
+    while let Some((node, i)) = queue.pop_front() {
+      let bucket_start = gpu.buckets.len() as u32;
+      for f in &node.data {
+        let mut code = vec![];
+        encode(f, &mut code, 0)?;
+        let offset = *encoded.entry(code.iter().map(|x| x.to_bits()).collect())
+          .or_insert_with(|| {
+            gpu.code.extend_from_slice(&code);
+            (gpu.code.len() - code.len()) as u32
+          });
+        gpu.buckets.push(offset);
+      }
+      let children = match node.children.as_deref() {
+        Some(children) => {
+          let first = gpu.nodes.len();
+          gpu.nodes.extend([GpuNode::default(); 4]);
+          queue.extend(children.iter().zip(first..));
+          first as u32
+        },
+        None => 0
+      };
+      gpu.nodes[i] = GpuNode {
+        x: to_f32(node.rect.origin.x),
+        y: to_f32(node.rect.origin.y),
+        size: to_f32(node.rect.size.width),
+        children,
+        bucket_start,
+        bucket_len: node.data.len() as u32
+      };
+    }
+    Ok(gpu)
+  }
+
+  /// Reference implementation of the kernel, on the CPU.
+  pub fn eval(&self, p: P2<f32>) -> f32 {
+    let p = if self.toroidal { P2::new(p.x - p.x.floor(), p.y - p.y.floor()) } else { p };
+    let mut node = &self.nodes[0];
+    if (0.0..1.0).contains(&p.x) && (0.0..1.0).contains(&p.y) {
+      while node.children != 0 {
+        let half = node.size * 0.5;
+        let quad = (p.x >= node.x + half) as u32 + 2 * (p.y >= node.y + half) as u32;
+        node = &self.nodes[(node.children + quad) as usize];
+      }
+    }
+    let bucket = node.bucket_start as usize..(node.bucket_start + node.bucket_len) as usize;
+    self.buckets[bucket].iter()
+      .map(|&offset| self.eval_primitive(p, offset as usize))
+      .fold(f32::MAX / 2.0, f32::min)
+  }
+
+  fn eval_primitive(&self, mut p: P2<f32>, mut at: usize) -> f32 {
+    let code = &self.code;
+    let v = |at: usize| V2::<f32, WorldSpace>::new(code[at], code[at + 1]);
+    let rotate_inv = |v: V2<f32, WorldSpace>, c: f32, s: f32| V2::new(v.x * c + v.y * s, -v.x * s + v.y * c);
+    let mut k = 1.0;
+    for _ in 0..=MAX_NESTING {
+      match code[at] {
+        OP_TRANSFORM => {
+          let scale = code[at + 5];
+          p = (rotate_inv(p.to_vector() - v(at + 1), code[at + 3], code[at + 4]) / scale).to_point();
+          k *= scale;
+          at += 6;
+        },
+        OP_COMPLEMENT => {
+          k = -k;
+          at += 1;
+        },
+        OP_CIRCLE => return k * ((p.to_vector() - v(at + 1)).length() - code[at + 3]),
+        OP_RECT => {
+          let local = rotate_inv(p.to_vector() - v(at + 1), code[at + 5], code[at + 6]);
+          let d = local.abs() - v(at + 3);
+          return k * (d.max(V2::zero()).length() + d.x.max(d.y).min(0.0));
+        },
+        OP_POLYGON => {
+          let n = code[at + 1] as usize;
+          let vertices: Vec<_> = (0..n).map(|i| v(at + 2 + 2 * i).to_point()).collect();
+          return k * crate::sdf::SDF::sdf(&crate::geometry::Polygon { vertices }, p);
+        },
+        _ => {
+          let (width, height) = (code[at + 5] as usize, code[at + 6] as usize);
+          let raster = super::Raster {
+            rect: euclid::Rect::new(v(at + 1).to_point(), v(at + 3).to_size()),
+            width,
+            height,
+            samples: code[at + 7..at + 7 + width * height].to_vec()
+          };
+          return k * raster.eval(p);
+        }
+      }
+    }
+    f32::MAX / 2.0
+  }
+}
+
+fn to_f32<T: Float>(x: T) -> f32 {
+  x.to_f32().unwrap_or(f32::MAX)
+}
+
+fn encode<T: Float + Signed>(f: &ShapePrimitive<T>, code: &mut Vec<f32>, nesting: usize) -> Result<()> {
+  let v = |v: V2<T, WorldSpace>| [to_f32(v.x), to_f32(v.y)];
+  match f {
+    ShapePrimitive::Circle { center, radius } => {
+      code.push(OP_CIRCLE);
+      code.extend(v(center.to_vector()));
+      code.push(to_f32(*radius));
+    },
+    ShapePrimitive::Rect { center, half_size, angle } => {
+      code.push(OP_RECT);
+      code.extend(v(center.to_vector()));
+      code.extend(v(*half_size));
+      code.extend([to_f32(angle.cos()), to_f32(angle.sin())]);
+    },
+    ShapePrimitive::Polygon(vertices) => {
+      code.extend([OP_POLYGON, vertices.len() as f32]);
+      vertices.iter().for_each(|p| code.extend(v(p.to_vector())));
+    },
+    ShapePrimitive::Raster(raster) => {
+      code.push(OP_RASTER);
+      code.extend(v(raster.rect.origin.to_vector()));
+      code.extend(v(raster.rect.size.to_vector()));
+      code.extend([raster.width as f32, raster.height as f32]);
+      code.extend(raster.samples.iter().map(|&x| to_f32(x)));
+    },
+    ShapePrimitive::Transform { .. } | ShapePrimitive::Complement(_) if nesting == MAX_NESTING =>
+      bail!("primitive is nested deeper than {MAX_NESTING}"),
+    ShapePrimitive::Transform { shape, offset, angle, scale } => {
+      code.push(OP_TRANSFORM);
+      code.extend(v(*offset));
+      code.extend([to_f32(angle.cos()), to_f32(angle.sin()), to_f32(*scale)]);
+      encode(shape, code, nesting + 1)?;
+    },
+    ShapePrimitive::Complement(shape) => {
+      code.push(OP_COMPLEMENT);
+      encode(shape, code, nesting + 1)?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,154 @@
+// Evaluates a flattened ADF (see `solver::adf::gpu::GpuAdf`) at `points`, one per invocation.
+
+struct Node {
+  x: f32,
+  y: f32,
+  size: f32,
+  // index of the first of 4 children, 0 for leaves
+  children: u32,
+  bucket_start: u32,
+  bucket_len: u32,
+}
+
+struct Params {
+  count: u32,
+  toroidal: u32,
+}
+
+@group(0) @binding(0) var<storage, read> nodes: array<Node>;
+@group(0) @binding(1) var<storage, read> buckets: array<u32>;
+@group(0) @binding(2) var<storage, read> code: array<f32>;
+@group(0) @binding(3) var<storage, read> points: array<vec2<f32>>;
+@group(0) @binding(4) var<storage, read_write> distances: array<f32>;
+@group(0) @binding(5) var<uniform> params: Params;
+
+const OP_CIRCLE: u32 = 0u;
+const OP_RECT: u32 = 1u;
+const OP_POLYGON: u32 = 2u;
+const OP_RASTER: u32 = 3u;
+const OP_TRANSFORM: u32 = 4u;
+const OP_COMPLEMENT: u32 = 5u;
+const MAX_NESTING: u32 = 16u;
+const MAX_DIST: f32 = 1.7014117e38;
+
+// rotate `v` by the angle, whose cosine and sine are `c` and `s`, backwards
+fn rotate_inv(v: vec2<f32>, c: f32, s: f32) -> vec2<f32> {
+  return vec2<f32>(v.x * c + v.y * s, -v.x * s + v.y * c);
+}
+
+fn vec_at(at: u32) -> vec2<f32> {
+  return vec2<f32>(code[at], code[at + 1u]);
+}
+
+fn rect_sdf(p: vec2<f32>, half_size: vec2<f32>) -> f32 {
+  let d = abs(p) - half_size;
+  return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
+}
+
+fn polygon_sdf(p: vec2<f32>, start: u32, n: u32) -> f32 {
+  if n == 0u {
+    return MAX_DIST;
+  }
+  let v0 = vec_at(start);
+  var d = dot(p - v0, p - v0);
+  var s = 1.0;
+  var j = n - 1u;
+  for (var i = 0u; i < n; i++) {
+    let vi = vec_at(start + 2u * i);
+    let vj = vec_at(start + 2u * j);
+    let e = vj - vi;
+    let w = p - vi;
+    let b = w - e * clamp(dot(w, e) / dot(e, e), 0.0, 1.0);
+    d = min(d, dot(b, b));
+    let c = vec3<bool>(p.y >= vi.y, p.y < vj.y, e.x * w.y > e.y * w.x);
+    if all(c) || !any(c) {
+      s = -s;
+    }
+    j = i;
+  }
+  return s * sqrt(d);
+}
+
+fn raster_sdf(p: vec2<f32>, start: u32) -> f32 {
+  let origin = vec_at(start);
+  let size = vec_at(start + 2u);
+  let width = u32(code[start + 4u]);
+  let height = u32(code[start + 5u]);
+  let samples = start + 6u;
+  let local = (p - origin) / size;
+  let clamped = clamp(local, vec2<f32>(0.0), vec2<f32>(1.0));
+  let outside = length((local - clamped) * size);
+  let xy = clamped * vec2<f32>(f32(width - 1u), f32(height - 1u));
+  let x0 = u32(floor(xy.x));
+  let y0 = u32(floor(xy.y));
+  let x1 = min(x0 + 1u, width - 1u);
+  let y1 = min(y0 + 1u, height - 1u);
+  let t = xy - floor(xy);
+  let top = mix(code[samples + y0 * width + x0], code[samples + y0 * width + x1], t.x);
+  let bottom = mix(code[samples + y1 * width + x0], code[samples + y1 * width + x1], t.x);
+  return mix(top, bottom, t.y) + outside;
+}
+
+fn eval_primitive(point: vec2<f32>, start: u32) -> f32 {
+  var p = point;
+  var k = 1.0;
+  var at = start;
+  for (var nesting = 0u; nesting <= MAX_NESTING; nesting++) {
+    switch u32(code[at]) {
+      case OP_TRANSFORM: {
+        let scale = code[at + 5u];
+        p = rotate_inv(p - vec_at(at + 1u), code[at + 3u], code[at + 4u]) / scale;
+        k = k * scale;
+        at = at + 6u;
+      }
+      case OP_COMPLEMENT: {
+        k = -k;
+        at = at + 1u;
+      }
+      case OP_CIRCLE: {
+        return k * (length(p - vec_at(at + 1u)) - code[at + 3u]);
+      }
+      case OP_RECT: {
+        let local = rotate_inv(p - vec_at(at + 1u), code[at + 5u], code[at + 6u]);
+        return k * rect_sdf(local, vec_at(at + 3u));
+      }
+      case OP_POLYGON: {
+        return k * polygon_sdf(p, at + 2u, u32(code[at + 1u]));
+      }
+      default: {
+        return k * raster_sdf(p, at + 1u);
+      }
+    }
+  }
+  return MAX_DIST;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+  let i = id.x;
+  if i >= params.count {
+    return;
+  }
+  var p = points[i];
+  if params.toroidal != 0u {
+    p = p - floor(p);
+  }
+  // points outside of the unit square use the bucket of the root
+  var node = 0u;
+  if all(p >= vec2<f32>(0.0)) && all(p < vec2<f32>(1.0)) {
+    loop {
+      let n = nodes[node];
+      if n.children == 0u {
+        break;
+      }
+      let half = n.size * 0.5;
+      node = n.children + select(0u, 1u, p.x >= n.x + half) + select(0u, 2u, p.y >= n.y + half);
+    }
+  }
+  let n = nodes[node];
+  var d = MAX_DIST;
+  for (var j = 0u; j < n.bucket_len; j++) {
+    d = min(d, eval_primitive(p, buckets[n.bucket_start + j]));
+  }
+  distances[i] = d;
+}
@@ -1,50 +1,181 @@
 //! Adaptive Distance Field, uses quadtree as an underlying data structire.
-//! Each node (bucket) stores several `Arc<dyn Fn(Point2D) -> {float}>`
+//! Each node (bucket) stores several primitives: either opaque `Arc<dyn Fn(Point2D) -> {float}>`,
+//! or [`ShapePrimitive`], see [`Primitive`].
 
-#![allow(clippy::mut_from_ref)]
 use {
   crate::{
-    solver::LineSearch,
+    solver::{Argmax2D, LineSearch, argmax2d::z_order_storage::ChunkOrder},
     geometry::{Shape, shapes, P2, WorldSpace, BoundingBox},
     sdf::SDF,
-  },
-  quadtree::{
-    Quadtree, TraverseCommand
+    util
   },
   std::{
     sync::{
-      Arc, atomic::{AtomicBool, Ordering}
+      Arc, atomic::{AtomicBool, AtomicI64, Ordering}
     },
     fmt::{Debug, Formatter}
   },
   euclid::{Point2D, Box2D, Rect},
-  num_traits::{Float, Signed}
+  num_traits::{Float, Signed},
+  anyhow::Result
 };
 
 #[cfg(test)] mod tests;
 pub(crate) mod quadtree;
+pub use quadtree::{Quadtree, Leaves, TraverseCommand};
+pub mod gpu;
+mod shared;
+pub use shared::SharedADF;
+mod primitive;
+pub use primitive::{Primitive, DynPrimitive, ShapePrimitive, Raster};
 
+/// Cloning copies the tree, but shares the primitives, so a distribution may be branched
+/// and continued independently.
+///
+/// With the `serde` feature, an `ADF` of [`ShapePrimitive`] may be written to disk, and
+/// re-loaded for later querying or continued filling.
 #[derive(Clone)]
-pub struct ADF<Float> {
-  pub tree: Quadtree<Vec<Arc<dyn Fn(P2<Float>) -> Float>>, Float>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ADF<Float, Prim = DynPrimitive<Float>> {
+  pub tree: Quadtree<Vec<Prim>, Float>,
   /// Gradient Descent lattice density, N^2
   /// higher values improve precision
   ipm_gd_lattice_density: u32,
-  ipm_line_config: LineSearch<Float>
+  ipm_line_config: LineSearch<Float>,
+  toroidal: bool,
+  stats: FastStats,
+  /// Prune automatically after this amount of insertions, 0 to disable
+  prune_interval: u32,
+  /// Insertions since the last pruning, and their combined domain
+  prune_backlog: Option<(u32, Rect<Float, WorldSpace>)>,
+  /// Initial and boundary primitives
+  base: Vec<Prim>,
+  /// Inserted primitives (one per periodic image) with their domains, indexed by [`PrimitiveId`];
+  /// `None` unless enabled, see [`ADF::with_registry`]
+  registry: Option<Vec<Option<Vec<(Rect<Float, WorldSpace>, Prim)>>>>,
+  /// Handles issued so far
+  inserted: usize,
+  /// Periodic images within `registry`, for [`ADF::memory_fast`]
+  registered_images: usize,
+  /// Maximum error of the field, see [`ADF::with_accuracy`]
+  accuracy: Option<Float>,
+  /// See [`ADF::with_memory_budget`]
+  memory_budget: Option<usize>,
+  /// See [`ADF::with_balance`]
+  balanced: bool,
+  /// See [`ADF::with_shape_index`]
+  #[cfg_attr(feature = "serde", serde(skip))]
+  shape_index: Option<util::ShapeIndex<Float>>,
+  /// Modify the tree on the current thread only, without spawning rayon jobs; set while held
+  /// by a [`SharedADF`]
+  #[cfg_attr(feature = "serde", serde(skip))]
+  sequential: bool
 }
 
-unsafe impl<Float> Send for ADF<Float> {}
-unsafe impl<Float> Sync for ADF<Float> {}
+/// Handle of an inserted primitive, see [`ADF::remove_sdf_domain`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimitiveId(usize);
 
-impl <_Float: Float> SDF<_Float> for &[Arc<dyn Fn(P2<_Float>) -> _Float>] {
-  fn sdf(&self, pixel: P2<_Float>) -> _Float {
-    self.iter()
-      .map(|f| f(pixel))
-      .reduce(|a, b| if a <= b { a } else { b })
-      .unwrap_or(_Float::max_value() / (_Float::one() + _Float::one()))
+/// Origin of a stored primitive, see [`ADF::nearest_primitive`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PrimitiveHandle {
+  /// One of the initial or boundary primitives, by index
+  Base(usize),
+  Inserted(PrimitiveId)
+}
+
+/// Maximum amount of primitives in a bucket, before it is subdivided.
+const BUCKET_SIZE: usize = 3;
+
+/// Tree statistics, maintained incrementally during insertion. See [`ADF::stats_fast`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FastStats {
+  /// Amount of leaves at each depth, `leaves_per_depth[0]` being the root.
+  pub leaves_per_depth: Vec<u64>,
+  /// Total amount of primitives stored in the leaves.
+  pub total_primitives: u64
+}
+
+impl FastStats {
+  fn new(max_depth: u8, primitives: usize) -> Self {
+    let mut leaves_per_depth = vec![0; max_depth as usize + 1];
+    leaves_per_depth[0] = 1;
+    Self { leaves_per_depth, total_primitives: primitives as u64 }
+  }
+
+  pub fn total_leaves(&self) -> u64 {
+    self.leaves_per_depth.iter().sum()
+  }
+
+  /// Every subdivision replaces one leaf with four.
+  pub fn total_nodes(&self) -> u64 {
+    let leaves = self.total_leaves();
+    leaves + (leaves - 1) / 3
+  }
+
+  pub fn max_depth(&self) -> u8 {
+    self.leaves_per_depth.iter()
+      .rposition(|&n| n > 0)
+      .unwrap_or(0) as u8
   }
 }
 
+/// Tree statistics, collected by a full traversal. See [`ADF::stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdfStats {
+  pub total_nodes: u64,
+  pub total_leaves: u64,
+  /// Amount of leaves at each depth, `leaves_per_depth[0]` being the root.
+  pub leaves_per_depth: Vec<u64>,
+  /// Amount of leaves, holding exactly `i` primitives, at index `i`.
+  pub primitives_per_leaf: Vec<u64>,
+  /// Memory used by the tree and the registry of inserted primitives, excluding the data
+  /// primitives may share behind pointers.
+  pub bytes: usize
+}
+
+// Changes of `FastStats` during a single (parallel) traversal.
+struct StatsDelta {
+  leaves_per_depth: Vec<AtomicI64>,
+  total_primitives: AtomicI64
+}
+
+impl StatsDelta {
+  fn new(max_depth: u8) -> Self {
+    Self {
+      leaves_per_depth: (0..=max_depth).map(|_| AtomicI64::new(0)).collect(),
+      total_primitives: AtomicI64::new(0)
+    }
+  }
+
+  fn primitives(&self, delta: i64) {
+    self.total_primitives.fetch_add(delta, Ordering::Relaxed);
+  }
+
+  fn leaves(&self, depth: u8, delta: i64) {
+    self.leaves_per_depth[depth as usize].fetch_add(delta, Ordering::Relaxed);
+  }
+
+  fn apply(self, stats: &mut FastStats) {
+    let add = |x: &mut u64, delta: AtomicI64| *x = (*x as i64 + delta.into_inner()) as u64;
+    stats.leaves_per_depth.iter_mut()
+      .zip(self.leaves_per_depth)
+      .for_each(|(x, delta)| add(x, delta));
+    add(&mut stats.total_primitives, self.total_primitives);
+  }
+}
+
+// combined field of a bucket
+fn bucket_sdf<_Float: Float, Prim: Primitive<_Float>>(data: &[Prim], pixel: P2<_Float>) -> _Float {
+  data.iter()
+    .map(|f| f.eval(pixel))
+    .reduce(|a, b| if a <= b { a } else { b })
+    .unwrap_or(_Float::max_value() / (_Float::one() + _Float::one()))
+}
+
 fn sdf_partialord<_Float: Float + Signed>(
   f: impl Fn(P2<_Float>) -> _Float,
   g: impl Fn(P2<_Float>) -> _Float,
@@ -74,16 +205,311 @@ fn sdf_partialord<_Float: Float + Signed>(
   }
 }
 
-impl <_Float: Float + Signed + Sync> ADF<_Float> {
+/// Remove SDF primitives, that do not affect the field within `rect`.
+/// Primitives are eliminated one by one, so that duplicates do not cancel each other out.
+fn prune_bucket<_Float: Float + Signed, Prim: Primitive<_Float>>(
+  data: &[Prim],
+  rect: Rect<_Float, WorldSpace>,
+  lattice_density: u32,
+  line_search: LineSearch<_Float>
+) -> Vec<Prim> {
+  let mut g = data.to_vec();
+  let mut i = 0;
+  while i < g.len() {
+    let sdf_old = |p| g.iter().enumerate()
+      .filter(|&(j, _)| i != j)
+      .map(|(_, f)| f.eval(p))
+      .fold(_Float::max_value() / (_Float::one() + _Float::one()), |a, b| a.min(b));
+    // f(v) > g(v) forall v e D
+    if g.len() > 1 && sdf_partialord(|p| g[i].eval(p), sdf_old, rect, lattice_density, line_search) {
+      g.remove(i);
+    } else {
+      i += 1;
+    }
+  }
+  g
+}
+
+/// Insert `f` into a leaf `node`; returns whether the field has changed.
+/// The leaf may be subdivided, in which case its children are already pruned against `f`.
+fn insert_leaf<_Float: Float + Signed, Prim: Primitive<_Float>>(
+  node: &mut Quadtree<Vec<Prim>, _Float>,
+  f: &Prim,
+  lattice_density: u32,
+  line_search: LineSearch<_Float>,
+  stats: &StatsDelta
+) -> bool {
+  // f(v) > g(v) forall v e D, no refinement is required
+  if sdf_partialord(
+    |p| f.eval(p),
+    |p| bucket_sdf(&node.data, p),
+    node.rect,
+    lattice_density,
+    line_search
+  ) {
+    return false;
+  }
+
+  // f(v) <= g(v) forall v e D, a minor optimization
+  if sdf_partialord(
+    |p| bucket_sdf(&node.data, p),
+    |p| f.eval(p),
+    node.rect,
+    lattice_density,
+    line_search
+  ) {
+    stats.primitives(1 - node.data.len() as i64);
+    node.data = vec![f.clone()];
+    return true;
+  };
+
+  // max tree depth is reached, just append the primitive
+  if node.depth == node.max_depth || node.data.len() < BUCKET_SIZE {
+
+    node.data.push(f.clone());
+    stats.primitives(1);
+
+  } else { // max bucket size is reached, subdivide
+
+    let mut g = node.data.clone();
+    g.push(f.clone());
+    subdivide_leaf(node, &g, lattice_density, line_search, stats);
+  }
+  true
+}
+
+// split a leaf, distributing `data` over the children
+fn subdivide_leaf<_Float: Float + Signed, Prim: Primitive<_Float>>(
+  node: &mut Quadtree<Vec<Prim>, _Float>,
+  data: &[Prim],
+  lattice_density: u32,
+  line_search: LineSearch<_Float>,
+  stats: &StatsDelta
+) {
+  node.subdivide(|rect_ch| prune_bucket(data, rect_ch, lattice_density, line_search));
+  let children_primitives = node.children.iter()
+    .flat_map(|children| children.iter())
+    .map(|child| child.data.len() as i64)
+    .sum::<i64>();
+  stats.primitives(children_primitives - node.data.len() as i64);
+  stats.leaves(node.depth, -1);
+  stats.leaves(node.depth + 1, 4);
+}
+
+// subdivide a leaf, whose bucket exceeds `BUCKET_SIZE`, and its children likewise
+fn split_overfull<_Float: Float + Signed, Prim: Primitive<_Float>>(
+  node: &mut Quadtree<Vec<Prim>, _Float>,
+  lattice_density: u32,
+  line_search: LineSearch<_Float>,
+  stats: &StatsDelta
+) {
+  if node.data.len() <= BUCKET_SIZE || node.depth == node.max_depth {
+    return;
+  }
+  let data = node.data.clone();
+  subdivide_leaf(node, &data, lattice_density, line_search, stats);
+  if let Some(children) = node.children.as_deref_mut() {
+    children.iter_mut().for_each(|child| split_overfull(child, lattice_density, line_search, stats));
+  }
+}
+
+/// Whether `g` is lower than the combined field of `data` by at least `epsilon` somewhere within
+/// `rect`. Both are assumed to be 1-Lipschitz (i.e. exact distance bounds), hence their
+/// difference may change by at most the diameter of a cell, which is halved until it is
+/// conclusive. Reports `true` for differences in `[epsilon / 2, epsilon)` as well.
+fn exceeds_tolerance<_Float: Float, Prim: Primitive<_Float>>(
+  data: &[Prim],
+  g: &Prim,
+  rect: Rect<_Float, WorldSpace>,
+  epsilon: _Float
+) -> bool {
+  let two = _Float::one() + _Float::one();
+  let center = rect.center();
+  let gap = bucket_sdf(data, center) - g.eval(center);
+  let diameter = rect.size.to_vector().length();
+  if gap + diameter < epsilon {
+    return false;
+  }
+  if gap >= epsilon || diameter <= epsilon / two {
+    return true;
+  }
+  let half = rect.size / two;
+  [(0, 0), (1, 0), (0, 1), (1, 1)].into_iter()
+    .map(|(x, y)| Rect::new(
+      rect.origin + euclid::Vector2D::new(half.width * _Float::from(x).unwrap(), half.height * _Float::from(y).unwrap()),
+      half
+    ))
+    .any(|quad| exceeds_tolerance(data, g, quad, epsilon))
+}
+
+/// Add every primitive of `candidates`, exceeding the tolerance within the leaf `node`,
+/// subdividing as usual. Returns whether the leaf has changed.
+fn enforce_leaf<_Float: Float + Signed, Prim: Primitive<_Float>>(
+  node: &mut Quadtree<Vec<Prim>, _Float>,
+  candidates: &[Prim],
+  epsilon: _Float,
+  lattice_density: u32,
+  line_search: LineSearch<_Float>,
+  stats: &StatsDelta
+) -> bool {
+  let mut changed = false;
+  for g in candidates {
+    // pruning may drop some of the candidates from the new children, so they start over
+    if let Some(children) = node.children.as_deref_mut() {
+      children.iter_mut().for_each(|child| {
+        enforce_leaf(child, candidates, epsilon, lattice_density, line_search, stats);
+      });
+      return true;
+    }
+    if node.data.iter().any(|f| f.identical(g))
+      || !exceeds_tolerance(&node.data, g, node.rect, epsilon) {
+      continue;
+    }
+    changed = true;
+    if node.depth == node.max_depth || node.data.len() < BUCKET_SIZE {
+      node.data.push(g.clone());
+      stats.primitives(1);
+    } else {
+      let mut data = node.data.clone();
+      data.push(g.clone());
+      subdivide_leaf(node, &data, lattice_density, line_search, stats);
+    }
+  }
+  changed
+}
+
+/// Merge the leaves of `node` into it. The buckets of the leaves are already pruned, hence their
+/// union keeps every primitive, which may be the lowest anywhere within `node`; the bucket may
+/// exceed [`BUCKET_SIZE`], until it is subdivided on an insertion.
+fn coarsen<_Float: Float, Prim: Primitive<_Float>>(
+  node: &mut Quadtree<Vec<Prim>, _Float>,
+  stats: &StatsDelta
+) {
+  let Some(children) = node.children.take() else {
+    return;
+  };
+  let mut data: Vec<Prim> = vec![];
+  children.iter()
+    .flat_map(|child| child.data.iter())
+    .for_each(|f| if !data.iter().any(|g| f.identical(g)) {
+      data.push(f.clone())
+    });
+  let children_primitives = children.iter()
+    .map(|child| child.data.len() as i64)
+    .sum::<i64>();
+  stats.primitives(data.len() as i64 - children_primitives);
+  stats.leaves(node.depth + 1, -4);
+  stats.leaves(node.depth, 1);
+  node.data = data;
+}
+
+impl <_Float: Float + Signed + Send + Sync + 'static> ADF<_Float> {
   /// Create a new ADF instance. `max_depth` specifies maximum number of quadtree subdivisions;
   /// `init` specifies initial sdf primitives.
-  pub fn new(max_depth: u8, init: Vec<Arc<dyn Fn(P2<_Float>) -> _Float>>) -> Self {
+  pub fn new(max_depth: u8, init: Vec<DynPrimitive<_Float>>) -> Self {
+    Self::with_primitives(max_depth, init)
+  }
+  /// Restrict the field to the interior of `shape` (an arbitrary mask), in addition to the
+  /// initial primitives. Must be called before any insertion.
+  pub fn with_boundary(self, shape: impl SDF<_Float> + Send + Sync + 'static) -> Self {
+    self.with_boundary_primitive(Arc::new(move |p| -shape.sdf(p)))
+  }
+  /// Resume filling from previously generated `shapes`, e.g. imported with
+  /// [`util::import_circles`], see [`ADF::insert_shapes`].
+  pub fn from_shapes<S>(max_depth: u8, init: Vec<DynPrimitive<_Float>>, shapes: impl IntoIterator<Item = S>) -> Self
+    where S: Shape<_Float> + Send + Sync + 'static
+  {
+    let mut adf = Self::new(max_depth, init);
+    adf.insert_shapes(shapes);
+    adf
+  }
+  /// Insert many shapes as a single [`ADF::insert_sdf_batch`], each within its
+  /// [`util::changed_domain`], bounded by the field after the insertion. The bound is sampled
+  /// on a grid beforehand, the field being 1-Lipschitz.
+  pub fn insert_shapes<S>(&mut self, shapes: impl IntoIterator<Item = S>) -> Vec<Option<PrimitiveId>>
+    where S: Shape<_Float> + Send + Sync + 'static
+  {
+    use rayon::prelude::*;
+    const GRID: usize = 32;
+
+    let shapes: Vec<S> = shapes.into_iter().collect();
+    if shapes.is_empty() {
+      return vec![];
+    }
+    let cell = _Float::one() / _Float::from(GRID - 1).unwrap();
+    let sample = |i: usize| {
+      let p = Point2D::new(_Float::from(i % GRID).unwrap(), _Float::from(i / GRID).unwrap()) * cell;
+      shapes.iter().fold(self.sdf(p), |min, shape| min.min(shape.sdf(p)))
+    };
+    let field_bound = match self.sequential {
+      true => (0..GRID * GRID).map(sample).fold(_Float::neg_infinity(), _Float::max),
+      false => (0..GRID * GRID).into_par_iter().map(sample).reduce(_Float::neg_infinity, _Float::max)
+    }
+      // within half of a diagonal of a cell from a sample
+      + cell * _Float::from(std::f64::consts::FRAC_1_SQRT_2).unwrap();
+    let batch: Vec<(Rect<_Float, WorldSpace>, DynPrimitive<_Float>)> = shapes.into_iter()
+      .map(|shape| {
+        let domain = util::changed_domain(&shape, field_bound);
+        let shape = Arc::new(shape);
+        if let Some(index) = &mut self.shape_index {
+          index.insert_shared(shape.clone());
+        }
+        (domain, Arc::new(move |p| shape.sdf(p)) as DynPrimitive<_Float>)
+      })
+      .collect();
+    self.insert_sdf_batch(batch)
+  }
+  /// Same as [`ADF::insert_sdf_domain`], for a `shape`, which is recorded in the
+  /// [`ADF::shape_index`], if any.
+  pub fn insert_shape_domain<S>(&mut self, domain: Rect<_Float, WorldSpace>, shape: S) -> bool
+    where S: Shape<_Float> + Send + Sync + 'static
+  {
+    let shape = Arc::new(shape);
+    if let Some(index) = &mut self.shape_index {
+      index.insert_shared(shape.clone());
+    }
+    self.insert_sdf_domain(domain, Arc::new(move |p| shape.sdf(p)))
+  }
+}
+
+impl <_Float: Float + Signed + Send + Sync + 'static, Prim: Primitive<_Float> + Send + Sync> ADF<_Float, Prim> {
+  /// Create a new ADF instance, storing primitives of an arbitrary representation, such as
+  /// [`ShapePrimitive`].
+  pub fn with_primitives(max_depth: u8, init: Vec<Prim>) -> Self {
     Self {
+      stats: FastStats::new(max_depth, init.len()),
+      base: init.clone(),
+      registry: None,
+      inserted: 0,
+      registered_images: 0,
       tree: Quadtree::new(max_depth, init),
       ipm_gd_lattice_density: 1,
-      ipm_line_config: LineSearch::default()
+      ipm_line_config: LineSearch::default(),
+      toroidal: false,
+      prune_interval: 0,
+      prune_backlog: None,
+      accuracy: None,
+      memory_budget: None,
+      balanced: false,
+      shape_index: None,
+      sequential: false
     }
   }
+  /// Wrap the field around the edges of the unit square (i.e. compute distances on a torus).
+  /// Queries outside of the unit square are wrapped as well, so gradient descent may freely
+  /// cross the edges. See [`util::torus_images`](crate::util::torus_images).
+  pub fn with_toroidal(mut self, toroidal: bool) -> Self {
+    self.toroidal = toroidal;
+    self
+  }
+  /// Add a primitive, negative outside of the domain (e.g. [`ShapePrimitive::Complement`]).
+  /// Must be called before any insertion.
+  pub fn with_boundary_primitive(mut self, boundary: Prim) -> Self {
+    self.base.push(boundary.clone());
+    self.tree.data.push(boundary);
+    self.stats.total_primitives += 1;
+    self
+  }
   /// Controls precision of primitive pruning in a bucket.
   pub fn with_gd_lattice_density(mut self, density: u32) -> Self {
     self.ipm_gd_lattice_density = density;
@@ -94,6 +520,63 @@ impl <_Float: Float + Signed + Sync> ADF<_Float> {
     self.ipm_line_config = line_config;
     self
   }
+  /// Call [`ADF::prune`] after every `interval` successful insertions, over their combined
+  /// domain. Keeps long runs from accumulating redundant nodes and primitives; 0 disables.
+  pub fn with_prune_interval(mut self, interval: u32) -> Self {
+    self.prune_interval = interval;
+    self
+  }
+  /// Record the shapes inserted by [`ADF::insert_shape_domain`] and [`ADF::insert_shapes`]
+  /// into `index`, for nearest-shape and range queries. Not serialized.
+  pub fn with_shape_index(mut self, index: util::ShapeIndex<_Float>) -> Self {
+    self.shape_index = Some(index);
+    self
+  }
+  pub fn shape_index(&self) -> Option<&util::ShapeIndex<_Float>> {
+    self.shape_index.as_ref()
+  }
+  /// Keep the tree 2:1 balanced: depths of neighbouring leaves (including diagonal ones) differ
+  /// by at most one, coarser leaves being subdivided after each modification, which may ripple
+  /// further. Bounds the discontinuity of the field across the edges of leaves, at the cost of
+  /// a larger tree. [`ADF::with_memory_budget`] takes precedence.
+  pub fn with_balance(mut self, balanced: bool) -> Self {
+    self.balanced = balanced;
+    self
+  }
+  /// Limit the memory of the tree (see [`ADF::memory_fast`]) to approximately `bytes`.
+  /// Once exceeded, the tree is pruned; if that is not enough, the least important regions
+  /// (i.e. having the lowest field, such as the ones covered by shapes) are coarsened, until
+  /// three quarters of the budget are used. Coarsened buckets keep every primitive, which
+  /// affects the field within them, hence they are slower to sample, rather than less precise.
+  /// With [`ADF::with_accuracy`], only pruning is performed.
+  pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+    self.memory_budget = Some(bytes);
+    self
+  }
+  /// Guarantee `|ADF::sdf(p) - true_sdf(p)| < epsilon` everywhere, `true_sdf` being the
+  /// minimum of all the inserted primitives. After every modification, affected leaves are
+  /// verified against all primitives (and the others against the new one), using bounds which
+  /// hold for exact distance fields, instead of the GD lattice heuristics; violating primitives
+  /// are added back, subdividing further. Considerably slower. Should be set before any insertion.
+  ///
+  /// Every inserted primitive is retained in this mode, even if it does not change the field;
+  /// enables [`ADF::with_registry`].
+  pub fn with_accuracy(mut self, epsilon: _Float) -> Self {
+    self.accuracy = Some(epsilon);
+    self.with_registry(true)
+  }
+  /// Retain the inserted primitives, which have changed the field, along with their domains;
+  /// required by [`ADF::remove_sdf_domain`] and [`ADF::nearest_primitive`]. Holds a reference
+  /// to every such primitive, even once it no longer affects the field, hence disabled by
+  /// default. Should be set before any insertion.
+  pub fn with_registry(mut self, registry: bool) -> Self {
+    self.registry = match registry {
+      true => Some(self.registry.unwrap_or_else(|| (0..self.inserted).map(|_| None).collect())),
+      false => None
+    };
+    self.registered_images = self.registry.iter().flatten().flatten().map(Vec::len).sum();
+    self
+  }
   /*
     Upon insertion of a new SDF primitive (`f`), this function tests whether it does
     change the distance field within a certain domain (remember that it is considered changed
@@ -135,146 +618,609 @@ impl <_Float: Float + Signed + Sync> ADF<_Float> {
   }
 
   /// Add a new sdf primitive function.
-  pub fn insert_sdf_domain(&mut self, domain: Rect<_Float, WorldSpace>, f: Arc<dyn Fn(P2<_Float>) -> _Float + Send + Sync>) -> bool {
+  pub fn insert_sdf_domain(&mut self, domain: Rect<_Float, WorldSpace>, f: Prim) -> bool {
+    self.insert_sdf_domain_id(domain, f).is_some()
+  }
+
+  /// Same as [`ADF::insert_sdf_domain`], but returns a handle of the primitive, if it has
+  /// changed the field. See [`ADF::with_registry`].
+  pub fn insert_sdf_domain_id(&mut self, domain: Rect<_Float, WorldSpace>, f: Prim) -> Option<PrimitiveId> {
+    let images: Vec<_> = self.images(domain)
+      .map(|(domain, offset)| (domain, match offset {
+        Some(offset) => f.offset(offset),
+        None => f.clone()
+      }))
+      .collect();
+    let changed: Vec<_> = images.iter()
+      .map(|(domain, f)| self.insert_sdf_domain_a(*domain, f.clone()))
+      .collect();
+    self.register(domain, images, changed)
+  }
+
+  // record the images of a primitive, which have changed the field, and prune if it is due
+  fn register(
+    &mut self,
+    domain: Rect<_Float, WorldSpace>,
+    images: Vec<(Rect<_Float, WorldSpace>, Prim)>,
+    changed: Vec<bool>
+  ) -> Option<PrimitiveId> {
+    if self.accuracy.is_some() {
+      return self.register_verified(domain, images, changed);
+    }
+    let images: Vec<_> = images.into_iter()
+      .zip(changed)
+      .filter_map(|(image, changed)| changed.then_some(image))
+      .collect();
+    if images.is_empty() {
+      return None;
+    }
+    self.maintain(domain);
+    Some(self.push_registry(images))
+  }
+
+  // issue a handle, recording `images` in the registry, if enabled
+  fn push_registry(&mut self, images: Vec<(Rect<_Float, WorldSpace>, Prim)>) -> PrimitiveId {
+    if let Some(registry) = &mut self.registry {
+      self.registered_images += images.len();
+      registry.push(Some(images));
+    }
+    self.inserted += 1;
+    PrimitiveId(self.inserted - 1)
+  }
+
+  // same as `register`, but every image is retained, and verified
+  fn register_verified(
+    &mut self,
+    domain: Rect<_Float, WorldSpace>,
+    images: Vec<(Rect<_Float, WorldSpace>, Prim)>,
+    changed: Vec<bool>
+  ) -> Option<PrimitiveId> {
+    let id = self.push_registry(images.clone());
+    let mut changed = changed.into_iter().any(|changed| changed);
+    for (domain, f) in images {
+      changed |= self.enforce_accuracy(domain, &[f]);
+    }
+    if changed {
+      self.maintain(domain);
+    }
+    changed.then_some(id)
+  }
+
+  // prune once `prune_interval` insertions have accumulated, balance, and enforce the memory budget
+  fn maintain(&mut self, domain: Rect<_Float, WorldSpace>) {
+    if self.prune_interval > 0 {
+      let domain = if self.toroidal { Rect::from_size(euclid::Size2D::splat(_Float::one())) } else { domain };
+      let (count, backlog) = match self.prune_backlog {
+        Some((count, backlog)) => (count + 1, backlog.union(&domain)),
+        None => (1, domain)
+      };
+      self.prune_backlog = Some((count, backlog));
+      if count >= self.prune_interval {
+        self.prune(backlog);
+      }
+    }
+    self.balance(domain);
+    self.enforce_memory_budget();
+  }
+
+  // subdivide the leaves, neighbouring with the leaves within `domain`, which are more than
+  // one level coarser; then, the neighbours of the subdivided ones, and so on
+  fn balance(&mut self, domain: Rect<_Float, WorldSpace>) {
+    if !self.balanced {
+      return;
+    }
+    let (lattice_density, line_search) = (self.ipm_gd_lattice_density, self.ipm_line_config);
+    let domain = if self.toroidal { Rect::from_size(euclid::Size2D::splat(_Float::one())) } else { domain };
+    let mut region = Some(domain);
+
+    // depth and cell of a node, exact since the origins are dyadic
+    let key = |node: &Quadtree<Vec<Prim>, _Float>| {
+      let cell = node.rect.origin * _Float::from(1u64 << node.depth).unwrap();
+      (node.depth, cell.x.to_u64().unwrap(), cell.y.to_u64().unwrap())
+    };
+
+    while let Some(area) = region.take() {
+      let mut split = std::collections::HashSet::new();
+      for leaf in self.tree.leaves_in_region(area) {
+        // slightly enlarged, so that adjacent leaves intersect it
+        let margin = leaf.rect.size.width / _Float::from(1024).unwrap();
+        split.extend(self.tree.leaves_in_region(leaf.rect.inflate(margin, margin))
+          .filter(|neighbour| neighbour.depth + 1 < leaf.depth)
+          .map(key)
+        );
+      }
+
+      let stats = StatsDelta::new(self.tree.max_depth);
+      self.tree.traverse_managed(&mut |node| {
+        if node.children.is_some() {
+          return TraverseCommand::Recurse;
+        }
+        if split.contains(&key(node)) {
+          let data = node.data.clone();
+          subdivide_leaf(node, &data, lattice_density, line_search, &stats);
+          region = Some(region.map_or(node.rect, |region| region.union(&node.rect)));
+        }
+        TraverseCommand::SkipChildren
+      });
+      stats.apply(&mut self.stats);
+    }
+  }
+
+  fn enforce_memory_budget(&mut self) {
+    let Some(budget) = self.memory_budget else {
+      return;
+    };
+    if self.memory_fast() <= budget {
+      return;
+    }
+    self.prune(Rect::from_size(euclid::Size2D::splat(_Float::one())));
+    if self.accuracy.is_some() {
+      return;
+    }
+    let target = budget / 4 * 3;
+    let node_size = std::mem::size_of::<Quadtree<Vec<Prim>, _Float>>();
+
+    while self.memory_fast() > target {
+      // parents of 4 leaves, by the highest field at the centers of the leaves
+      let mut candidates = vec![];
+      self.tree.traverse(&mut |node| {
+        if let Some(children) = node.children.as_deref() {
+          if children.iter().all(|child| child.children.is_none()) {
+            let importance = children.iter()
+              .map(|child| bucket_sdf(&child.data, child.rect.center()))
+              .fold(_Float::min_value(), _Float::max);
+            candidates.push((importance, node.depth, node.rect.origin));
+          }
+        }
+        Ok(())
+      }).ok();
+      if candidates.is_empty() {
+        break;
+      }
+      candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+      // enough of them to reach the target, assuming that every one saves 4 nodes
+      let excess = self.memory_fast() - target;
+      let count = (excess / (4 * node_size) + 1).min(candidates.len());
+      let selected: Vec<_> = candidates[..count].iter()
+        .map(|&(_, depth, origin)| (depth, origin))
+        .collect();
+
+      let stats = StatsDelta::new(self.tree.max_depth);
+      self.tree.traverse_managed(&mut |node| {
+        let key = (node.depth, node.rect.origin);
+        if node.children.is_none() {
+          return TraverseCommand::SkipChildren;
+        }
+        if !selected.contains(&key) {
+          return TraverseCommand::Recurse;
+        }
+        coarsen(node, &stats);
+        TraverseCommand::SkipChildren
+      });
+      stats.apply(&mut self.stats);
+    }
+  }
+
+  /// Restore the accuracy contract (if set), see [`ADF::with_accuracy`]: leaves within
+  /// `domain` are verified against the initial primitives, and the inserted ones, whose stored
+  /// domains overlap them; the rest only against `new`. Returns whether any leaf has changed.
+  fn enforce_accuracy(&mut self, domain: Rect<_Float, WorldSpace>, new: &[Prim]) -> bool {
+    use rayon::prelude::*;
+
+    let Some(epsilon) = self.accuracy else {
+      return false;
+    };
+    let nearby: Vec<(Rect<_Float, WorldSpace>, Prim)> = self.registry.iter()
+      .flatten()
+      .flatten()
+      .flatten()
+      .filter(|(image, _)| image.intersects(&domain))
+      .cloned()
+      .collect();
+    let base = &self.base;
+    let (lattice_density, line_search) = (self.ipm_gd_lattice_density, self.ipm_line_config);
+    let stats = StatsDelta::new(self.tree.max_depth);
+    let enforce = |leaf: &mut Quadtree<Vec<Prim>, _Float>| {
+      if !leaf.rect.intersects(&domain) {
+        return enforce_leaf(leaf, new, epsilon, lattice_density, line_search, &stats);
+      }
+      let candidates: Vec<Prim> = base.iter().cloned()
+        .chain(nearby.iter()
+          .filter(|(image, _)| image.intersects(&leaf.rect))
+          .map(|(_, f)| f.clone())
+        )
+        .collect();
+      enforce_leaf(leaf, &candidates, epsilon, lattice_density, line_search, &stats)
+    };
+    let leaves = self.tree.leaves_planar();
+    let changed = match self.sequential {
+      true => leaves.into_iter().map(enforce).fold(false, |a, b| a | b),
+      false => leaves.into_par_iter().map(enforce).reduce(|| false, |a, b| a | b)
+    };
+    stats.apply(&mut self.stats);
+    changed
+  }
+
+  // domains of the periodic images of `domain`, and the offsets of their primitives
+  fn images(&self, domain: Rect<_Float, WorldSpace>)
+    -> impl Iterator<Item = (Rect<_Float, WorldSpace>, Option<euclid::Vector2D<_Float, WorldSpace>>)> {
+    let images: Vec<_> = if self.toroidal {
+      util::torus_images(domain)
+        .map(|offset| (domain.translate(-offset), (offset != euclid::Vector2D::zero()).then_some(offset)))
+        .collect()
+    } else {
+      vec![(domain, None)]
+    };
+    images.into_iter()
+  }
+
+  /// Delete a primitive, previously inserted with [`ADF::with_registry`]. Leaves within its
+  /// stored domain are rebuilt from the remaining primitives, subdividing the ones exceeding
+  /// the bucket size, then pruned, merged and balanced where possible.
+  /// Returns `false` if the primitive does not exist.
+  pub fn remove_sdf_domain(&mut self, id: PrimitiveId) -> bool {
+    let Some(removed) = self.registry.as_mut()
+      .and_then(|registry| registry.get_mut(id.0))
+      .and_then(Option::take) else {
+      return false;
+    };
+    self.registered_images -= removed.len();
+    let (lattice_density, line_search) = (self.ipm_gd_lattice_density, self.ipm_line_config);
+    // the inserted primitives, which may affect the rebuilt leaves
+    let nearby: Vec<(Rect<_Float, WorldSpace>, Prim)> = self.registry.iter()
+      .flatten()
+      .flatten()
+      .flatten()
+      .filter(|(domain, _)| removed.iter().any(|(removed, _)| domain.intersects(removed)))
+      .cloned()
+      .collect();
+    let base = &self.base;
+    let stats = StatsDelta::new(self.tree.max_depth);
+    let mut affected: Option<Rect<_Float, WorldSpace>> = None;
+
+    self.tree.traverse_managed(&mut |node| {
+      if !removed.iter().any(|(domain, _)| node.rect.intersects(domain)) {
+        return TraverseCommand::SkipChildren;
+      }
+      if node.children.is_some() {
+        return TraverseCommand::Recurse;
+      }
+      if !node.data.iter().any(|f| removed.iter().any(|(_, g)| f.identical(g))) {
+        return TraverseCommand::SkipChildren;
+      }
+      let candidates: Vec<Prim> = base.iter().cloned()
+        .chain(nearby.iter()
+          .filter(|(domain, _)| domain.intersects(&node.rect))
+          .map(|(_, f)| f.clone())
+        )
+        .collect();
+      let data = prune_bucket(&candidates, node.rect, lattice_density, line_search);
+      stats.primitives(data.len() as i64 - node.data.len() as i64);
+      node.data = data;
+      affected = Some(affected.map_or(node.rect, |affected| affected.union(&node.rect)));
+      split_overfull(node, lattice_density, line_search, &stats);
+      TraverseCommand::SkipChildren
+    });
+    stats.apply(&mut self.stats);
+    if let Some(affected) = affected {
+      self.prune(affected);
+      self.balance(affected);
+    }
+    true
+  }
+
+  /// Find the primitive, which defines the field at `p`, among the buckets of the leaf
+  /// containing `p` and its neighbours. Returns its distance at `p`, and where it came from;
+  /// `None` if there are no primitives at all, or an inserted one was found without
+  /// [`ADF::with_registry`].
+  pub fn nearest_primitive(&self, p: P2<_Float>) -> Option<(_Float, PrimitiveHandle)> {
+    let p = self.wrap(p);
+    let leaf = self.tree.pt_to_node(p).unwrap_or(&self.tree);
+    // slightly enlarged, so that adjacent leaves intersect it
+    let margin = leaf.rect.size.width / _Float::from(1024).unwrap();
+    let (distance, f) = self.tree.leaves_in_region(leaf.rect.inflate(margin, margin))
+      .flat_map(|leaf| leaf.data.iter())
+      .map(|f| (f.eval(p), f))
+      .reduce(|a, b| if a.0 <= b.0 { a } else { b })?;
+    let handle = match self.base.iter().position(|g| f.identical(g)) {
+      Some(i) => PrimitiveHandle::Base(i),
+      None => PrimitiveHandle::Inserted(PrimitiveId(self.registry.as_ref()?.iter()
+        .position(|images| images.iter().flatten().any(|(_, g)| f.identical(g)))?
+      ))
+    };
+    Some((distance, handle))
+  }
+
+  /// Gradient of the field at `p`, i.e. of the primitive defining it, see
+  /// [`Primitive::gradient`]. Unlike sampling [`SDF::sdf`], the leaf is only looked up once.
+  /// Use with [`LineSearch::optimize_grad`].
+  pub fn gradient(&self, p: P2<_Float>) -> euclid::Vector2D<_Float, WorldSpace> {
+    let p = self.wrap(p);
+    let data = match self.tree.pt_to_node(p) {
+      Some(node) => &node.data,
+      None => &self.tree.data
+    };
+    data.iter()
+      .map(|f| (f.eval(p), f))
+      .reduce(|a, b| if a.0 <= b.0 { a } else { b })
+      .map_or(euclid::Vector2D::zero(), |(_, f)| f.gradient(p, self.ipm_line_config.Δ))
+  }
+
+  /// Remove primitives, that do not affect the field within their buckets, and merge sibling
+  /// leaves within `domain`, whose combined bucket does not exceed the bucket size.
+  /// Returns the amount of removed nodes. See also [`ADF::with_accuracy`].
+  pub fn prune(&mut self, domain: Rect<_Float, WorldSpace>) -> u64 {
+    fn prune_a<_Float: Float + Signed, Prim: Primitive<_Float>>(
+      node: &mut Quadtree<Vec<Prim>, _Float>,
+      domain: Rect<_Float, WorldSpace>,
+      lattice_density: u32,
+      line_search: LineSearch<_Float>,
+      stats: &StatsDelta
+    ) {
+      if !node.rect.intersects(&domain) {
+        return;
+      }
+      let Some(children) = node.children.as_deref_mut() else {
+        let data = prune_bucket(&node.data, node.rect, lattice_density, line_search);
+        stats.primitives(data.len() as i64 - node.data.len() as i64);
+        node.data = data;
+        return;
+      };
+      children.iter_mut()
+        .for_each(|child| prune_a(child, domain, lattice_density, line_search, stats));
+      if children.iter().any(|child| child.children.is_some()) {
+        return;
+      }
+      let mut union: Vec<Prim> = vec![];
+      children.iter()
+        .flat_map(|child| child.data.iter())
+        .for_each(|f| if !union.iter().any(|g| f.identical(g)) {
+          union.push(f.clone())
+        });
+      let data = prune_bucket(&union, node.rect, lattice_density, line_search);
+      if data.len() <= BUCKET_SIZE {
+        let children_primitives = children.iter()
+          .map(|child| child.data.len() as i64)
+          .sum::<i64>();
+        stats.primitives(data.len() as i64 - children_primitives);
+        stats.leaves(node.depth + 1, -4);
+        stats.leaves(node.depth, 1);
+        node.data = data;
+        node.children = None;
+      }
+    }
+
+    let stats = StatsDelta::new(self.tree.max_depth);
+    prune_a(&mut self.tree, domain, self.ipm_gd_lattice_density, self.ipm_line_config, &stats);
+    let total_nodes = self.stats.total_nodes();
+    stats.apply(&mut self.stats);
+    self.prune_backlog = None;
+    let removed = total_nodes - self.stats.total_nodes();
+    self.enforce_accuracy(domain, &[]);
+    removed
+  }
+
+  fn insert_sdf_domain_a(&mut self, domain: Rect<_Float, WorldSpace>, f: Prim) -> bool {
     let change_exists = AtomicBool::new(false);
+    let stats = StatsDelta::new(self.tree.max_depth);
+    let (lattice_density, line_search) = (self.ipm_gd_lattice_density, self.ipm_line_config);
 
-    self.tree.traverse_managed_parallel(|node| {
+    let visit = |node: &mut Quadtree<Vec<Prim>, _Float>| {
       // no intersection with domain
       if !node.rect.intersects(&domain) {
-        return TraverseCommand::Skip;
+        return TraverseCommand::SkipChildren;
       }
 
       // not a leaf node
       if node.children.is_some() {
-        return TraverseCommand::Ok;
+        return TraverseCommand::Recurse;
       }
 
-      // f(v) > g(v) forall v e D, no refinement is required
-      if sdf_partialord(
-        f.as_ref(),
-        |p| node.data.as_slice().sdf(p),
-        node.rect,
-        self.ipm_gd_lattice_density,
-        self.ipm_line_config
-      ) {
-        return TraverseCommand::Skip;
+      if insert_leaf(node, &f, lattice_density, line_search, &stats) {
+        change_exists.store(true, Ordering::Relaxed);
       }
+      TraverseCommand::SkipChildren
+    };
+    match self.sequential {
+      true => self.tree.traverse_managed(&mut |node| visit(node)),
+      false => self.tree.traverse_managed_parallel(visit)
+    };
 
-      // f(v) <= g(v) forall v e D, a minor optimization
-      if sdf_partialord(
-        |p| node.data.as_slice().sdf(p),
-        f.as_ref(),
-        node.rect,
-        self.ipm_gd_lattice_density,
-        self.ipm_line_config
-      ) {
-        node.data = vec![f.clone()];
-        change_exists.store(true, Ordering::Relaxed);
-        return TraverseCommand::Skip;
-      };
+    stats.apply(&mut self.stats);
+    change_exists.load(Ordering::SeqCst)
+  }
 
-      change_exists.store(true, Ordering::Relaxed);
-      const BUCKET_SIZE: usize = 3;
-
-      // remove SDF primitives, that do not affect the field within `D`
-      let prune = |data: &[Arc<dyn Fn(P2<_Float>) -> _Float>], rect| {
-        let mut g = vec![];
-        for (i, f) in data.iter().enumerate() {
-          let sdf_old = |p|
-            data.iter().enumerate()
-              .filter_map(|(j, f)| if i != j {
-                Some(f(p))
-              } else { None })
-              .fold(_Float::max_value() / (_Float::one() + _Float::one()), |a, b| a.min(b));
-          // there exists v e D, such that f(v) < g(v)
-          if !sdf_partialord(
-            f.as_ref(),
-            sdf_old,
-            rect,
-            self.ipm_gd_lattice_density,
-            self.ipm_line_config
-          ) {
-            g.push(f.clone())
-          }
-        };
-        g
-      };
+  /// Insert several primitives within a single parallel traversal, instead of descending
+  /// from the root for each of them. Primitives are ordered along a Z-order curve, hence
+  /// the result may differ slightly from sequential insertion.
+  /// Returns handles of the primitives, which have changed the field.
+  pub fn insert_sdf_batch(&mut self, batch: impl IntoIterator<Item = (Rect<_Float, WorldSpace>, Prim)>) -> Vec<Option<PrimitiveId>> {
+    #[allow(clippy::too_many_arguments)]
+    fn insert_batch_a<_Float: Float + Signed + Send + Sync, Prim: Primitive<_Float> + Send + Sync>(
+      node: &mut Quadtree<Vec<Prim>, _Float>,
+      images: &[(usize, Rect<_Float, WorldSpace>, Prim)],
+      batch: &[usize],
+      changed: &[AtomicBool],
+      sequential: bool,
+      lattice_density: u32,
+      line_search: LineSearch<_Float>,
+      stats: &StatsDelta
+    ) {
+      use rayon::prelude::*;
 
-      // max tree depth is reached, just append the primitive
-      if node.depth == node.max_depth || node.data.len() < BUCKET_SIZE {
+      let batch: Vec<usize> = batch.iter().copied()
+        .filter(|&i| images[i].1.intersects(&node.rect))
+        .collect();
+      let mut inserted = 0;
+      while inserted < batch.len() && node.children.is_none() {
+        let i = batch[inserted];
+        if insert_leaf(node, &images[i].2, lattice_density, line_search, stats) {
+          changed[i].store(true, Ordering::Relaxed);
+        }
+        inserted += 1;
+      }
+      // the rest of batch goes to the children, including the ones just subdivided
+      if let Some(children) = node.children.as_deref_mut() {
+        let batch = &batch[inserted..];
+        if !batch.is_empty() {
+          let insert = |child: &mut Quadtree<Vec<Prim>, _Float>|
+            insert_batch_a(child, images, batch, changed, sequential, lattice_density, line_search, stats);
+          match sequential {
+            true => children.iter_mut().for_each(insert),
+            false => children.par_iter_mut().for_each(insert)
+          }
+        }
+      }
+    }
 
-        node.data.push(f.clone());
-        //node.data = prune(node.data.as_slice(), node.rect);
+    let batch: Vec<_> = batch.into_iter().collect();
+    // periodic images, tagged with the index of their primitive
+    let mut images: Vec<_> = batch.iter().enumerate()
+      .flat_map(|(i, (domain, f))| self.images(*domain)
+        .map(move |(domain, offset)| (i, domain, match offset {
+          Some(offset) => f.offset(offset),
+          None => f.clone()
+        }))
+      )
+      .collect();
+    let scale = _Float::from(u16::MAX).unwrap();
+    images.sort_by_cached_key(|(_, domain, _)| {
+      let center = domain.center();
+      let quantize = |x: _Float| (x.max(_Float::zero()).min(_Float::one()) * scale).to_u64().unwrap_or(0);
+      ChunkOrder::Morton.key([quantize(center.x), quantize(center.y)].into())
+    });
 
-      } /*else if node.data.len() < BUCKET_SIZE {
+    let changed: Vec<_> = images.iter().map(|_| AtomicBool::new(false)).collect();
+    let stats = StatsDelta::new(self.tree.max_depth);
+    let all: Vec<usize> = (0..images.len()).collect();
+    insert_batch_a(&mut self.tree, &images, &all, &changed, self.sequential, self.ipm_gd_lattice_density, self.ipm_line_config, &stats);
+    stats.apply(&mut self.stats);
 
-        //node.data = prune(node.data.as_slice(), node.rect);
-        //if !Self::higher_all(f.as_ref(), &node.sdf_vec(), node.rect) {
-        //  node.data.push(f.clone());
-        //}
+    let mut per_primitive = vec![(vec![], vec![]); batch.len()];
+    images.into_iter().zip(changed)
+      .for_each(|((i, domain, f), changed)| {
+        per_primitive[i].0.push((domain, f));
+        per_primitive[i].1.push(changed.into_inner());
+      });
+    batch.iter().zip(per_primitive)
+      .map(|((domain, _), (images, changed))| self.register(*domain, images, changed))
+      .collect()
+  }
 
-      }*/
-      else { // max bucket size is reached, subdivide
+  /// Sample the field into a discrete [`Argmax2D`], e.g. in order to continue filling with
+  /// the discrete solver, once the shapes become too dense. See also [`ADF::from_argmax2d`].
+  pub fn rasterize(&self, resolution: u64, chunk_size: u64) -> Result<Argmax2D> {
+    let mut argmax = Argmax2D::new(resolution, chunk_size)?;
+    argmax.insert_sdf(|p| self.sdf(p.cast()).to_f32().unwrap_or(f32::MAX / 2.0));
+    Ok(argmax.with_toroidal(self.toroidal))
+  }
 
-        let mut g = node.data.clone();
-        g.push(f.clone());
+  /// The largest axis-aligned rectangle within the free region, where the field is positive,
+  /// e.g. for placing images or text boxes without inscribing them into a circle. Made of the
+  /// cells of a `resolution × resolution` grid over the unit square, whose centers are further
+  /// than the half of their diagonal from the shapes; `None` if there are none. See
+  /// [`Argmax2D::max_empty_rect`].
+  pub fn max_empty_rect(&self, resolution: usize) -> Option<Rect<_Float, WorldSpace>> {
+    let size = _Float::one() / _Float::from(resolution).unwrap();
+    let half = size / (_Float::one() + _Float::one());
+    let margin = size * _Float::from(std::f64::consts::FRAC_1_SQRT_2).unwrap();
+    let cell = |x: usize, y: usize| P2::new(_Float::from(x).unwrap(), _Float::from(y).unwrap()) * size;
+    let (x, y, width, height) = util::largest_free_rect(resolution, resolution, |x, y|
+      self.sdf(cell(x, y) + euclid::Vector2D::splat(half)) >= margin
+    )?;
+    Some(Rect::new(cell(x, y), cell(width, height).to_vector().to_size()))
+  }
 
-        node.subdivide(|rect_ch| prune(g.as_slice(), rect_ch));
-        /*node.subdivide(|rect_ch| prune(&g, rect_ch))
-          .as_deref_mut()
-          .unwrap()
-          .into_iter()
-          .for_each(|child| {
-            child.insert_sdf_domain(domain, f.clone());
-          });*/
+  /// Statistics of the tree, collected by traversing it; `O(n)`. See also [`ADF::stats_fast`].
+  pub fn stats(&self) -> AdfStats {
+    let node_size = std::mem::size_of::<Quadtree<Vec<Prim>, _Float>>();
+    let prim_size = std::mem::size_of::<Prim>();
+    let mut stats = AdfStats {
+      leaves_per_depth: vec![0; self.tree.max_depth as usize + 1],
+      bytes: std::mem::size_of::<Self>()
+        + self.base.capacity() * prim_size
+        + self.registry.as_ref().map_or(0, Vec::capacity) * std::mem::size_of::<Option<Vec<(Rect<_Float, WorldSpace>, Prim)>>>()
+        + self.registry.iter()
+          .flatten()
+          .flatten()
+          .map(|images| images.capacity() * std::mem::size_of::<(Rect<_Float, WorldSpace>, Prim)>())
+          .sum::<usize>(),
+      ..Default::default()
+    };
+    self.tree.traverse(&mut |node| {
+      stats.total_nodes += 1;
+      // the root is a part of `Self`
+      if node.depth > 0 { stats.bytes += node_size; }
+      stats.bytes += node.data.capacity() * prim_size;
+      if node.children.is_none() {
+        stats.total_leaves += 1;
+        stats.leaves_per_depth[node.depth as usize] += 1;
+        if stats.primitives_per_leaf.len() <= node.data.len() {
+          stats.primitives_per_leaf.resize(node.data.len() + 1, 0);
+        }
+        stats.primitives_per_leaf[node.data.len()] += 1;
       }
-      //TODO: despite issuing Skip, newly divided node continues to be explored. Rework Traverse API
-      TraverseCommand::Skip
-    });
+      Ok(())
+    }).ok();
+    stats
+  }
 
-    change_exists.load(Ordering::SeqCst)
+  /// Statistics of the tree, maintained during insertion; `O(1)`.
+  /// Does not account for modifications made directly through [`ADF::tree`].
+  pub fn stats_fast(&self) -> &FastStats {
+    &self.stats
   }
+}
 
-  /// # Safety
-  /// Nobody is safe
-  pub unsafe fn as_mut(&self) -> &mut Self {
-    let ptr = self as *const _ as usize;
-    &mut *(ptr as *const Self as *mut _)
+impl <_Float: Float + Signed + Send + Sync + 'static> ADF<_Float, ShapePrimitive<_Float>> {
+  /// Continue with a field, previously computed by [`Argmax2D`], which becomes the initial
+  /// primitive, interpolated bilinearly. See also [`ADF::rasterize`].
+  pub fn from_argmax2d(max_depth: u8, argmax: &Argmax2D) -> Self {
+    Self::with_primitives(max_depth, vec![ShapePrimitive::Raster(Arc::new(argmax.to_raster()))])
   }
 }
 
-impl <_Float: Float> SDF<_Float> for ADF<_Float> {
+impl <_Float: Float, Prim> ADF<_Float, Prim> {
+  /// Approximate memory of the tree, its primitives and the registry of the inserted ones,
+  /// from [`ADF::stats_fast`]; `O(1)`.
+  pub fn memory_fast(&self) -> usize {
+    self.stats.total_nodes() as usize * std::mem::size_of::<Quadtree<Vec<Prim>, _Float>>()
+      + self.stats.total_primitives as usize * std::mem::size_of::<Prim>()
+      + self.registry.as_ref().map_or(0, Vec::len) * std::mem::size_of::<Option<Vec<(Rect<_Float, WorldSpace>, Prim)>>>()
+      + self.registered_images * std::mem::size_of::<(Rect<_Float, WorldSpace>, Prim)>()
+  }
+
+  // wrap queries into the unit square, in toroidal mode
+  fn wrap(&self, p: P2<_Float>) -> P2<_Float> {
+    if self.toroidal {
+      P2::new(p.x - p.x.floor(), p.y - p.y.floor())
+    } else { p }
+  }
+}
+
+impl <_Float: Float, Prim: Primitive<_Float>> SDF<_Float> for ADF<_Float, Prim> {
   fn sdf(&self, pixel: P2<_Float>) -> _Float {
+    let pixel = self.wrap(pixel);
     match self.tree.pt_to_node(pixel) {
-      Some(node) => node.data.as_slice().sdf(pixel),
-      None => self.tree.data.as_slice().sdf(pixel),
+      Some(node) => bucket_sdf(&node.data, pixel),
+      None => bucket_sdf(&self.tree.data, pixel),
     }}}
 
-impl <_Float: Float> BoundingBox<_Float> for ADF<_Float> {
+impl <_Float: Float, Prim> BoundingBox<_Float> for ADF<_Float, Prim> {
   fn bounding_box(&self) -> Box2D<_Float, WorldSpace> {
     Box2D::new(
       P2::splat(_Float::zero()),
       P2::splat(_Float::one())
     )}}
 
-impl <_Float: Float> Debug for ADF<_Float> {
+impl <_Float: Float, Prim> Debug for ADF<_Float, Prim> {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     use humansize::{FileSize, file_size_opts as options};
 
-    let mut total_nodes = 0u64;
-    let mut total_size = 0usize;
-    let mut max_depth = 0u8;
-    self.tree.traverse(&mut |node| {
-      total_nodes += 1;
-      total_size += std::mem::size_of::<Self>()
-        + node.data.capacity() * std::mem::size_of::<Arc<dyn Fn(P2<f64>) -> f64>>();
-      max_depth = (max_depth).max(node.depth);
-      Ok(())
-    }).ok();
     f.debug_struct("ADF")
-      .field("total_nodes", &total_nodes)
-      .field("max_depth", &max_depth)
-      .field("size", &total_size.file_size(options::BINARY).unwrap())
+      .field("total_nodes", &self.stats.total_nodes())
+      .field("max_depth", &self.stats.max_depth())
+      .field("size", &self.memory_fast().file_size(options::BINARY).unwrap())
       .finish()
   }
 }
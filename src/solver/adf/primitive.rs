@@ -0,0 +1,228 @@
+use {
+  crate::{
+    geometry::{self, P2, WorldSpace},
+    sdf::{SDF, central_difference}
+  },
+  std::sync::Arc,
+  anyhow::{Result, bail},
+  euclid::{Rect, Rotation2D, Angle, Vector2D as V2},
+  num_traits::{Float, Signed}
+};
+
+/// A primitive, stored in the buckets of [`ADF`](super::ADF).
+pub trait Primitive<Float>: Clone {
+  fn eval(&self, p: P2<Float>) -> Float;
+  /// Whether both refer to the same primitive, used for deduplication.
+  fn identical(&self, other: &Self) -> bool;
+  /// A primitive, evaluating `self` at `p + offset`.
+  fn offset(&self, offset: V2<Float, WorldSpace>) -> Self;
+  /// Gradient of the field at `p`; central difference with `delta` by default.
+  fn gradient(&self, p: P2<Float>, delta: Float) -> V2<Float, WorldSpace> where Float: num_traits::Float {
+    central_difference(|p| self.eval(p), p, delta)
+  }
+}
+
+/// Opaque primitive, the default representation of [`ADF`](super::ADF) buckets.
+pub type DynPrimitive<Float> = Arc<dyn Fn(P2<Float>) -> Float + Send + Sync>;
+
+impl<_Float: Float + Send + Sync + 'static> Primitive<_Float> for DynPrimitive<_Float> {
+  fn eval(&self, p: P2<_Float>) -> _Float {
+    self(p)
+  }
+
+  fn identical(&self, other: &Self) -> bool {
+    Arc::ptr_eq(self, other)
+  }
+
+  fn offset(&self, offset: V2<_Float, WorldSpace>) -> Self {
+    let f = self.clone();
+    Arc::new(move |p| f(p + offset))
+  }
+}
+
+/// Transparent primitive, an alternative representation of [`ADF`](super::ADF) buckets,
+/// which may be compared, deduplicated and inspected.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShapePrimitive<T> {
+  Circle { center: P2<T>, radius: T },
+  /// Rectangle, rotated by `angle` (radians) around its center
+  Rect { center: P2<T>, half_size: V2<T, WorldSpace>, angle: T },
+  Polygon(Arc<[P2<T>]>),
+  Raster(Arc<Raster<T>>),
+  /// `shape`, scaled by `scale` around the origin, rotated by `angle` (radians) around the
+  /// origin, then translated by `offset`
+  Transform { shape: Arc<ShapePrimitive<T>>, offset: V2<T, WorldSpace>, angle: T, scale: T },
+  /// Interior and exterior swapped, such as a boundary
+  Complement(Arc<ShapePrimitive<T>>)
+}
+
+/// Distance field samples on a regular grid over `rect`, interpolated bilinearly.
+/// Holds at least one sample per axis, see [`Raster::new`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RasterFields<T>"))]
+pub struct Raster<T> {
+  pub(crate) rect: Rect<T, WorldSpace>,
+  pub(crate) width: usize,
+  pub(crate) height: usize,
+  /// Row-major, `width * height` samples, the first and the last ones lying at the corners
+  pub(crate) samples: Vec<T>
+}
+
+// deserialized as is, then checked by `Raster::new`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RasterFields<T> {
+  rect: Rect<T, WorldSpace>,
+  width: usize,
+  height: usize,
+  samples: Vec<T>
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<RasterFields<T>> for Raster<T> {
+  type Error = anyhow::Error;
+  fn try_from(RasterFields { rect, width, height, samples }: RasterFields<T>) -> Result<Self> {
+    Self::new(rect, width, height, samples)
+  }
+}
+
+impl<T> Raster<T> {
+  /// `samples` are row-major, `width * height` of them, the first and the last ones lying at
+  /// the corners of `rect`. Fails unless `width` and `height` are at least 1.
+  pub fn new(rect: Rect<T, WorldSpace>, width: usize, height: usize, samples: Vec<T>) -> Result<Self> {
+    if width == 0 || height == 0 {
+      bail!("raster has no samples along an axis")
+    }
+    if width.checked_mul(height) != Some(samples.len()) {
+      bail!("raster has {} samples, instead of {width} x {height}", samples.len())
+    }
+    Ok(Self { rect, width, height, samples })
+  }
+
+  pub fn rect(&self) -> &Rect<T, WorldSpace> { &self.rect }
+  pub fn width(&self) -> usize { self.width }
+  pub fn height(&self) -> usize { self.height }
+  pub fn samples(&self) -> &[T] { &self.samples }
+}
+
+impl<T: Float> Raster<T> {
+  /// Outside of `rect`, the distance to `rect` is added to the nearest sample. That is an upper
+  /// bound of the true distance, not a conservative one: far from `rect`, the field may be
+  /// overestimated, e.g. around the corners.
+  pub fn eval(&self, p: P2<T>) -> T {
+    let local = (p - self.rect.origin).component_div(self.rect.size.to_vector());
+    let clamped = V2::new(
+      local.x.max(T::zero()).min(T::one()),
+      local.y.max(T::zero()).min(T::one())
+    );
+    let outside = (local - clamped).component_mul(self.rect.size.to_vector()).length();
+    let x = clamped.x * T::from(self.width - 1).unwrap();
+    let y = clamped.y * T::from(self.height - 1).unwrap();
+    let (x0, y0) = (x.floor().to_usize().unwrap(), y.floor().to_usize().unwrap());
+    let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+    let (tx, ty) = (x - x.floor(), y - y.floor());
+    let at = |x: usize, y: usize| self.samples[y * self.width + x];
+    let lerp = |a: T, b: T, t: T| a + (b - a) * t;
+    lerp(
+      lerp(at(x0, y0), at(x1, y0), tx),
+      lerp(at(x0, y1), at(x1, y1), tx),
+      ty
+    ) + outside
+  }
+}
+
+impl<T: Float + Signed> ShapePrimitive<T> {
+  pub fn circle(center: P2<T>, radius: T) -> Self {
+    Self::Circle { center, radius }
+  }
+
+  /// Same as [`sdf::boundary_rect`](crate::sdf::boundary_rect).
+  pub fn boundary_rect() -> Self {
+    let half = T::one() / (T::one() + T::one());
+    Self::Complement(Arc::new(Self::Rect {
+      center: P2::splat(half),
+      half_size: V2::splat(half),
+      angle: T::zero()
+    }))
+  }
+
+  pub fn transform(self, offset: V2<T, WorldSpace>, angle: T, scale: T) -> Self {
+    Self::Transform { shape: Arc::new(self), offset, angle, scale }
+  }
+
+  pub fn complement(self) -> Self {
+    Self::Complement(Arc::new(self))
+  }
+
+  /// Gradient of the field at `p`; analytic except for rasters, which use the central
+  /// difference with `delta`.
+  pub fn gradient(&self, p: P2<T>, delta: T) -> V2<T, WorldSpace> {
+    match self {
+      Self::Circle { center, .. } => {
+        let v = p - *center;
+        if v.square_length() > T::zero() { v.normalize() } else { V2::zero() }
+      },
+      Self::Transform { shape, offset, angle, scale } => {
+        let local = Rotation2D::new(Angle::radians(-*angle))
+          .transform_vector(p.to_vector() - *offset) / *scale;
+        Rotation2D::new(Angle::radians(*angle))
+          .transform_vector(shape.gradient(local.to_point(), delta / *scale))
+      },
+      Self::Rect { center, half_size, angle } => {
+        let two = T::one() + T::one();
+        let local = Rotation2D::new(Angle::radians(-*angle))
+          .transform_vector(p - *center)
+          .to_point();
+        Rotation2D::new(Angle::radians(*angle))
+          .transform_vector(SDF::gradient(&geometry::Rect { size: (*half_size * two).to_point() }, local))
+      },
+      Self::Polygon(vertices) => SDF::gradient(&geometry::Polygon { vertices: &vertices[..] }, p),
+      Self::Complement(shape) => -shape.gradient(p, delta),
+      Self::Raster(_) => central_difference(|p| self.sdf(p), p, delta)
+    }
+  }
+}
+
+impl<T: Float + Signed> SDF<T> for ShapePrimitive<T> {
+  fn sdf(&self, p: P2<T>) -> T {
+    match self {
+      Self::Circle { center, radius } => (p - *center).length() - *radius,
+      Self::Rect { center, half_size, angle } => {
+        let two = T::one() + T::one();
+        let local = Rotation2D::new(Angle::radians(-*angle))
+          .transform_vector(p - *center)
+          .to_point();
+        geometry::Rect { size: (*half_size * two).to_point() }.sdf(local)
+      },
+      Self::Polygon(vertices) => geometry::Polygon { vertices: &vertices[..] }.sdf(p),
+      Self::Raster(raster) => raster.eval(p),
+      Self::Transform { shape, offset, angle, scale } => {
+        let local = Rotation2D::new(Angle::radians(-*angle))
+          .transform_vector(p.to_vector() - *offset) / *scale;
+        shape.sdf(local.to_point()) * *scale
+      },
+      Self::Complement(shape) => -shape.sdf(p)
+    }
+  }
+}
+
+impl<T: Float + Signed> Primitive<T> for ShapePrimitive<T> {
+  fn eval(&self, p: P2<T>) -> T {
+    self.sdf(p)
+  }
+
+  fn identical(&self, other: &Self) -> bool {
+    self == other
+  }
+
+  fn offset(&self, offset: V2<T, WorldSpace>) -> Self {
+    // evaluating at `p + offset` moves the shape by `-offset`
+    self.clone().transform(-offset, T::zero(), T::one())
+  }
+
+  fn gradient(&self, p: P2<T>, delta: T) -> V2<T, WorldSpace> {
+    ShapePrimitive::gradient(self, p, delta)
+  }
+}
@@ -3,7 +3,10 @@ use {
   crate::{
     geometry::WorldSpace
   },
-  std::{fmt::{Debug, Formatter}},
+  std::{
+    fmt::{Debug, Formatter},
+    sync::atomic::{AtomicBool, Ordering}
+  },
   anyhow::Result,
   euclid::{Point2D, Size2D, Rect},
   num_traits::Float
@@ -12,6 +15,7 @@ use {
 type Point<T> = Point2D<T, WorldSpace>;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quadtree<Data, Float> {
   pub rect: Rect<Float, WorldSpace>,
   pub children: Option<Box<[Quadtree<Data, Float>; 4]>>,
@@ -97,10 +101,38 @@ impl Quadtrant {
   }
 }
 
-#[derive(PartialEq)]
+/// Controls [`Quadtree::traverse_managed`] after visiting a node.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TraverseCommand {
-  Ok,
-  Skip
+  /// Visit the children of the node, including the ones it has just been subdivided into
+  Recurse,
+  /// Do not visit the children of the node
+  SkipChildren,
+  /// Abort the whole traversal
+  Stop
+}
+
+/// Iterator over the leaves of a [`Quadtree`], see [`Quadtree::leaves_in_region`].
+pub struct Leaves<'a, Data, Float> {
+  stack: Vec<&'a Quadtree<Data, Float>>,
+  region: Option<Rect<Float, WorldSpace>>
+}
+
+impl<'a, Data, _Float: Float> Iterator for Leaves<'a, Data, _Float> {
+  type Item = &'a Quadtree<Data, _Float>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(node) = self.stack.pop() {
+      if self.region.is_some_and(|region| !node.rect.intersects(&region)) {
+        continue;
+      }
+      match node.children.as_deref() {
+        Some(children) => self.stack.extend(children.iter().rev()),
+        None => return Some(node)
+      }
+    }
+    None
+  }
 }
 
 impl<Data, _Float: Float> Quadtree<Data, _Float> {
@@ -134,44 +166,55 @@ impl<Data, _Float: Float> Quadtree<Data, _Float> {
   }
 
 
-  pub fn traverse_managed(&mut self, f: &mut impl FnMut(&mut Self) -> TraverseCommand) {
-    if f(self) == TraverseCommand::Ok {
-      self.traverse_managed_a(f);
+  /// Apply `f` to every node in depth-first order, `f` decides whether to descend into the
+  /// children of a node; it may also subdivide the node first. Returns `false` if the
+  /// traversal was stopped.
+  pub fn traverse_managed(&mut self, f: &mut impl FnMut(&mut Self) -> TraverseCommand) -> bool {
+    match f(self) {
+      TraverseCommand::Recurse => match self.children.as_deref_mut() {
+        Some(children) => children.iter_mut().all(|child| child.traverse_managed(f)),
+        None => true
+      },
+      TraverseCommand::SkipChildren => true,
+      TraverseCommand::Stop => false
     }
   }
 
-  fn traverse_managed_a(&mut self, f: &mut impl FnMut(&mut Self) -> TraverseCommand) {
-    if let Some(children) = &mut self.children {
-      for child in children.iter_mut() {
-        if f(child) == TraverseCommand::Ok {
-          child.traverse_managed_a(f);
+  /// Same as [`Quadtree::traverse_managed`], but siblings are visited in parallel. Once `f`
+  /// returns [`TraverseCommand::Stop`], no more nodes are visited.
+  pub fn traverse_managed_parallel(&mut self, f: impl Fn(&mut Self) -> TraverseCommand + Send + Sync) -> bool
+    where Data: Send, _Float: Send
+  {
+    let stop = AtomicBool::new(false);
+    self.traverse_managed_parallel_a(&f, &stop);
+    !stop.into_inner()
+  }
+
+  fn traverse_managed_parallel_a(&mut self, f: &(impl Fn(&mut Self) -> TraverseCommand + Send + Sync), stop: &AtomicBool)
+    where Data: Send, _Float: Send
+  {
+    // split the siblings in halves, until a single one is left
+    fn join<Node: Send>(nodes: &mut [Node], visit: &(impl Fn(&mut Node) + Sync)) {
+      match nodes {
+        [] => (),
+        [node] => visit(node),
+        _ => {
+          let (a, b) = nodes.split_at_mut(nodes.len() / 2);
+          rayon::join(|| join(a, visit), || join(b, visit));
         }
       }
     }
-  }
 
-  pub fn traverse_managed_parallel(&mut self, f: impl Fn(&mut Self) -> TraverseCommand + Send + Sync) {
-    if f(self) == TraverseCommand::Ok {
-      self.traverse_managed_parallel_a(&f);
+    if stop.load(Ordering::Relaxed) {
+      return;
+    }
+    match f(self) {
+      TraverseCommand::Recurse => (),
+      TraverseCommand::SkipChildren => return,
+      TraverseCommand::Stop => return stop.store(true, Ordering::Relaxed)
     }
-  }
-
-  fn traverse_managed_parallel_a(&mut self, f: &(impl Fn(&mut Self) -> TraverseCommand + Send + Sync)) {
-    use rayon::prelude::*;
-
     if let Some(children) = self.children.as_deref_mut() {
-      let mut children_ptr = [0; 4];
-      for i in 0..4 {
-        children_ptr[i] = &mut children[i] as *mut _ as usize;
-      };
-
-      children_ptr.into_par_iter()
-        .for_each(move |child| {
-          let child = unsafe { &mut *(child as *mut Self) };
-          if f(child) == TraverseCommand::Ok {
-            child.traverse_managed_parallel_a(f);
-          }
-        })
+      join(children, &|child: &mut Self| child.traverse_managed_parallel_a(f, stop));
     }
   }
 
@@ -199,26 +242,28 @@ impl<Data, _Float: Float> Quadtree<Data, _Float> {
     &mut self.children
   }
 
+  /// Mutable references to all the leaves, in depth-first order.
   pub fn leaves_planar(&mut self) -> Vec<&mut Quadtree<Data, _Float>> {
-
-    fn nodes_planar_a<Data, Float>(tree: &mut Quadtree<Data, Float>) -> Vec<*mut Quadtree<Data, Float>> {
-      let mut result = vec![];
-      if let Some(children) = tree.children.as_deref_mut() {
-        for child in children.iter_mut() {
-          result.append(&mut nodes_planar_a(child));
-        }
-      } else {
-        result.push(tree)
-      }
-      result
+    if self.children.is_none() {
+      return vec![self];
     }
-
-    nodes_planar_a(self)
+    self.children.as_deref_mut()
       .into_iter()
-      .map(|x| unsafe { x.as_mut().unwrap() })
+      .flat_map(|children| children.iter_mut())
+      .flat_map(Self::leaves_planar)
       .collect()
   }
 
+  /// Iterate over the leaves of the tree, in depth-first order.
+  pub fn leaves(&self) -> Leaves<'_, Data, _Float> {
+    Leaves { stack: vec![self], region: None }
+  }
+
+  /// Iterate over the leaves, intersecting with `region`; subtrees outside of it are skipped.
+  pub fn leaves_in_region(&self, region: Rect<_Float, WorldSpace>) -> Leaves<'_, Data, _Float> {
+    Leaves { stack: vec![self], region: Some(region) }
+  }
+
   /// return all nodes, containing `pt`
   pub fn path_to_pt(&self, pt: Point<_Float>) -> Vec<&Self> {
     let mut result = vec![self];
@@ -267,4 +312,54 @@ impl<Data, _Float: Float> Quadtree<Data, _Float> {
       );
     }
   }
-}
\ No newline at end of file
+
+  #[test] fn leaves() {
+    let mut tree = Quadtree::<(), f64>::new(3, ());
+    tree.subdivide(|_| ());
+    tree.children.as_deref_mut().unwrap()[0].subdivide(|_| ());
+    assert_eq!(tree.leaves().count(), 3 + 4);
+    assert!(tree.leaves().all(|leaf| leaf.children.is_none()));
+    assert_eq!(tree.leaves().next().unwrap().rect, Rect::new(Point2D::splat(0.0), Size2D::splat(0.25)));
+
+    let region = Rect::new(Point2D::splat(0.1), Size2D::splat(0.3));
+    assert_eq!(tree.leaves_in_region(region).count(), 4);
+    // across the border of quarters of different depth
+    let region = Rect::new(Point2D::new(0.45, 0.1), Size2D::splat(0.1));
+    let leaves: Vec<_> = tree.leaves_in_region(region).collect();
+    assert_eq!(leaves.iter().map(|leaf| leaf.depth).collect::<Vec<_>>(), [2, 1]);
+    assert!(leaves.iter().all(|leaf| leaf.rect.intersects(&region)));
+  }
+
+  #[test] fn traverse_managed() {
+    // subdivisions made by `f` are explored on `Recurse`
+    let mut tree = Quadtree::<(), f64>::new(3, ());
+    let mut visited = 0;
+    assert!(tree.traverse_managed(&mut |node| {
+      visited += 1;
+      node.subdivide(|_| ());
+      TraverseCommand::Recurse
+    }));
+    assert_eq!(visited, 1 + 4 + 16 + 64);
+
+    let mut visited = 0;
+    tree.traverse_managed(&mut |node| {
+      visited += 1;
+      if node.depth == 1 { TraverseCommand::SkipChildren } else { TraverseCommand::Recurse }
+    });
+    assert_eq!(visited, 1 + 4);
+
+    let mut visited = 0;
+    assert!(!tree.traverse_managed(&mut |_| {
+      visited += 1;
+      if visited == 10 { TraverseCommand::Stop } else { TraverseCommand::Recurse }
+    }));
+    assert_eq!(visited, 10);
+
+    let visited = std::sync::atomic::AtomicU64::new(0);
+    assert!(!tree.traverse_managed_parallel(|node| {
+      visited.fetch_add(1, Ordering::Relaxed);
+      if node.depth == 1 { TraverseCommand::Stop } else { TraverseCommand::Recurse }
+    }));
+    assert!(visited.into_inner() <= 1 + 4);
+  }
+}
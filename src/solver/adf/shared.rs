@@ -0,0 +1,110 @@
+use {
+  super::{ADF, DynPrimitive, Primitive, PrimitiveId},
+  crate::{
+    geometry::{P2, WorldSpace},
+    solver::{line_search::Optimizer, placer::{PlacedShape, ShapePlacer}},
+    sdf::SDF,
+    util
+  },
+  std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, PoisonError}
+  },
+  crossbeam_utils::sync::ShardedLock,
+  euclid::Rect,
+  num_traits::{Float, FloatConst, Signed}
+};
+
+/// [`ADF`], which may be sampled and modified through a shared reference, from any thread;
+/// e.g. within the closures of [`util::local_maxima_iter`](crate::util::local_maxima_iter).
+///
+/// A single lock guards the whole field. Reads only lock a shard of it, local to the current
+/// thread, so concurrent sampling does not contend; writes lock every shard, for the whole
+/// insertion. While shared, the field is modified on the locking thread alone, without
+/// spawning rayon jobs: a worker, waiting for its jobs with the lock held, could otherwise steal
+/// a job sampling the same field, and deadlock. Likewise, the guards of [`SharedADF::read`] and
+/// [`SharedADF::write`] must not be held across parallel code, which accesses the field.
+pub struct SharedADF<Float, Prim = DynPrimitive<Float>> {
+  inner: ShardedLock<ADF<Float, Prim>>
+}
+
+impl<Float, Prim> SharedADF<Float, Prim> {
+  pub fn new(mut adf: ADF<Float, Prim>) -> Self {
+    adf.sequential = true;
+    Self { inner: ShardedLock::new(adf) }
+  }
+
+  pub fn into_inner(self) -> ADF<Float, Prim> {
+    let mut adf = self.inner.into_inner().unwrap_or_else(PoisonError::into_inner);
+    adf.sequential = false;
+    adf
+  }
+
+  /// Lock for reading, e.g. to sample the field repeatedly without locking each time.
+  pub fn read(&self) -> impl Deref<Target = ADF<Float, Prim>> + '_ {
+    self.inner.read().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  /// Lock for writing, e.g. to access methods, which are not forwarded by `SharedADF`.
+  pub fn write(&self) -> impl DerefMut<Target = ADF<Float, Prim>> + '_ {
+    self.inner.write().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+impl<_Float, Prim> SharedADF<_Float, Prim>
+  where _Float: Float + Signed + Send + Sync + 'static,
+        Prim: Primitive<_Float> + Send + Sync
+{
+  /// See [`ADF::insert_sdf_domain`].
+  pub fn insert_sdf_domain(&self, domain: Rect<_Float, WorldSpace>, f: Prim) -> bool {
+    self.write().insert_sdf_domain(domain, f)
+  }
+
+  /// See [`ADF::insert_sdf_domain_id`].
+  pub fn insert_sdf_domain_id(&self, domain: Rect<_Float, WorldSpace>, f: Prim) -> Option<PrimitiveId> {
+    self.write().insert_sdf_domain_id(domain, f)
+  }
+
+  /// See [`ADF::remove_sdf_domain`].
+  pub fn remove_sdf_domain(&self, id: PrimitiveId) -> bool {
+    self.write().remove_sdf_domain(id)
+  }
+}
+
+impl<_Float> SharedADF<_Float>
+  where _Float: Float + FloatConst + Signed + Send + Sync + 'static
+{
+  /// Place circles at the local maxima of [`util::local_maxima_iter`], as decided by `placer`,
+  /// inserting each one into the field; the maxima rejected by the placer, or made obsolete by
+  /// the previous insertions, are skipped. `rng_seed` seeds both the search and the placer.
+  pub fn place_iter<'a>(
+    &'a self,
+    mut placer: impl ShapePlacer<_Float> + 'a,
+    batch_size: u64,
+    rng_seed: u64,
+    line_search: impl Optimizer<_Float> + Copy + Sync + 'a
+  ) -> impl Iterator<Item = PlacedShape<_Float>> + 'a {
+    use rand::SeedableRng;
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(util::split_seed(rng_seed, 1));
+
+    util::local_maxima_iter(|p| self.sdf(p), batch_size, rng_seed, line_search)
+      .filter_map(move |local_max| {
+        let placed = placer.place(local_max, &mut rng)?;
+        let circle = placed.circle();
+        self.insert_sdf_domain(util::domain_empirical(local_max), Arc::new(move |p| circle.sdf(p)))
+          .then_some(placed)
+      })
+  }
+}
+
+impl<Float, Prim> From<ADF<Float, Prim>> for SharedADF<Float, Prim> {
+  fn from(adf: ADF<Float, Prim>) -> Self {
+    Self::new(adf)
+  }
+}
+
+impl<_Float: Float, Prim: Primitive<_Float>> SDF<_Float> for SharedADF<_Float, Prim> {
+  fn sdf(&self, pixel: P2<_Float>) -> _Float {
+    self.read().sdf(pixel)
+  }
+}
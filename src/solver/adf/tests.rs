@@ -2,19 +2,31 @@ use {
   super::*,
   crate::{
     geometry::{Circle, Shape, P2},
-    drawing,
     sdf,
     solver::{ADF, LineSearch},
     util
   },
   anyhow::Result,
-  image::{Rgba, RgbaImage},
-  euclid::{Vector2D, Size2D},
-  std::cell::Cell
+  euclid::{Vector2D, Size2D}
 };
 use crate::geometry::DistPoint;
 
+// `batches` of circles, half the size of those inscribed at the local maxima of the field
+fn fill(adf: &mut ADF<f64>, batches: usize, batch_size: usize) {
+  for _ in 0..batches {
+    let local_maxima: Vec<_> = util::local_maxima_iter(|p| adf.sdf(p), 32, 0, LineSearch::default())
+      .take(batch_size).collect();
+    for local_max in local_maxima {
+      let circle = Circle.translate(local_max.point.to_vector()).scale(local_max.distance / 2.0);
+      adf.insert_sdf_domain(util::domain_empirical(local_max), Arc::new(move |p| circle.sdf(p)));
+    }
+  }
+}
+
+#[cfg(feature = "drawing")]
 #[test] fn draw_layout() -> Result<()> {
+  use {crate::drawing, image::RgbaImage};
+
   let mut image = RgbaImage::new(512, 512);
   let mut adf = ADF::new(8, vec![Arc::new(|_| f64::MAX / 2.0)]);
   let domain = Rect::from_size(Size2D::splat(1.0));
@@ -39,14 +51,21 @@ use crate::geometry::DistPoint;
 }
 
 // profile: 4.85s, 100k circles, adf_subdiv = 7
+#[cfg(feature = "drawing")]
 #[test] #[ignore] fn gradient_adf() -> Result<()> {
-  use rand::prelude::*;
+  use {
+    rand::prelude::*,
+    image::RgbaImage,
+    std::cell::Cell,
+    crate::solver::{PowerLawRadius, ShapePlacer}
+  };
 
   let mut image = RgbaImage::new(1024, 1024);
-  let representation = ADF::<f64>::new(7, vec![Arc::new(sdf::boundary_rect)]);
+  let representation = SharedADF::new(ADF::<f64>::new(7, vec![Arc::new(sdf::boundary_rect)]));
   let mut primitives = vec![];
   let trials = Cell::new(0u64);
   let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+  let mut placer = PowerLawRadius { exponent: 5.0, max_radius: 1.0 / 6.0 };
 
   let t0 = std::time::Instant::now();
 
@@ -55,24 +74,11 @@ use crate::geometry::DistPoint;
     32, 0, LineSearch::default()
   ).inspect(|_| trials.set(trials.get() + 1))
     .filter_map(|local_max| {
-      let circle = {
-        use std::f64::consts::PI;
-
-        let angle = rng.gen_range(-PI..=PI);
-        let r = (rng.gen_range(1e-6..1.0).powf(5.0) * local_max.distance)
-          .min(1.0 / 6.0);
-        let delta = local_max.distance - r;
-        // polar to cartesian
-        let offset = P2::from([angle.cos(), angle.sin()]) * delta;
-
-        Circle.translate(local_max.point - offset)
-          .scale(r)
-      };
-      // alternately use safe RwLock<ADF> for 1.5x slowdown
-      unsafe { representation.as_mut() }.insert_sdf_domain(
+      let circle = placer.place(local_max, &mut rng)?.circle();
+      representation.insert_sdf_domain(
         util::domain_empirical(local_max),
         Arc::new(move |p| circle.sdf(p))
-      ).then(|| circle)
+      ).then_some(circle)
     })
     .enumerate()
     .take(100000)
@@ -87,10 +93,11 @@ use crate::geometry::DistPoint;
      imperfect primitive elimination method. See `solver::adf::ADF::higher_all` for more details.
    */
   println!("adf_error_margin: {:+.3e}", trials.get() as f64 / primitives.len() as f64 - 1.0);
+  let representation = representation.into_inner();
   println!("{representation:#?}");
   //drawing::display_sdf(|p| representation.sdf(p), &mut image, 3.5);
   //representation.draw_layout(&mut image);
-  use {image::Pixel, drawing::Draw};
+  use {image::Pixel, crate::drawing::Draw};
   /*primitives.into_iter()
     .for_each(|p| p.texture(image::Luma([255]).to_rgba())
     .draw(&mut image));*/
@@ -102,11 +109,16 @@ use crate::geometry::DistPoint;
   Ok(())
 }
 
+#[cfg(feature = "drawing")]
 #[test] #[ignore] fn animation() -> Result<()> {
-  use rand::prelude::*;
-  use drawing::Draw;
+  use {
+    rand::prelude::*,
+    image::{Rgba, RgbaImage},
+    crate::solver::{RandomInscribed, ShapePlacer}
+  };
+  use crate::drawing::{Draw, animation::{FrameOutput, FrameRecorder}};
 
-  std::fs::create_dir("test\\anim").ok();
+  let mut recorder = FrameRecorder::new(RgbaImage::new(512, 512), FrameOutput::Numbered("test/anim".into()));
 
   let mut representation = ADF::new(11, vec![Arc::new(sdf::boundary_rect)]);
   let mut circles = vec![];
@@ -138,7 +150,7 @@ use crate::geometry::DistPoint;
       .draw_bucket_weights(&mut image)
       .tree
       .draw_layout(&mut image);
-    image.save(format!("test/anim/#{}_0.png", i))?;
+    recorder.record(|canvas| canvas.clone_from(&image))?;
 
 
     {
@@ -148,27 +160,17 @@ use crate::geometry::DistPoint;
         .scale(local_max.distance)
         .texture(Rgba([0x45, 0x8F, 0xF5, 0x7F]))
         .draw(&mut image);
-      image.save(format!("test/anim/#{}_1.png", i))?;
+      recorder.record(|canvas| canvas.clone_from(&image))?;
     }
 
-    let circle = {
-      use std::f64::consts::PI;
-
-      let angle = rng.gen_range::<f64, _>(-PI..=PI);
-      let r = (rng.gen_range::<f64, _>(0.0..1.0).powf(1.0) * local_max.distance)
-        .min(1.0 / 6.0);
-      let delta = local_max.distance - r;
-      let offset = Point2D::from([angle.cos(), angle.sin()]) * delta;
-
-      Circle.translate(local_max.point - offset)
-        .scale(r)
-    };
+    let Some(placed) = RandomInscribed { max_radius: 1.0 / 6.0 }.place(local_max, &mut rng) else { continue };
+    let circle = placed.circle();
     let domain = util::domain_empirical(local_max);
 
     circle.texture(Rgba([0x45, 0x8F, 0xF5, 0xFF]))
       .draw(&mut image);
 
-    image.save(format!("test/anim/#{}_2.png", i))?;
+    recorder.record(|canvas| canvas.clone_from(&image))?;
     {
       let mut image = image.clone();
       Circle
@@ -176,10 +178,10 @@ use crate::geometry::DistPoint;
         .scale(local_max.distance * 4.0)
         .texture(Rgba([0xFF, 0, 0, 0x7F]))
         .draw(&mut image);
-      image.save(format!("test/anim/#{}_3.png", i))?;
+      recorder.record(|canvas| canvas.clone_from(&image))?;
     }
     representation.tree.draw_bounding(domain, &mut image);
-    image.save(format!("test/anim/#{}_4.png", i))?;
+    recorder.record(|canvas| canvas.clone_from(&image))?;
 
     representation.insert_sdf_domain(
       domain,
@@ -191,6 +193,548 @@ use crate::geometry::DistPoint;
   };
 
   println!("{representation:#?}");
+  recorder.finish()?;
+
+  Ok(())
+}
+
+#[test] fn toroidal() {
+  let circle = Circle
+    .translate(Vector2D::new(0.98, 0.5))
+    .scale(0.05);
+  let domain = util::domain_empirical(DistPoint { distance: 0.05, point: P2::new(0.98, 0.5) });
+  let mut adf = ADF::new(5, vec![Arc::new(|_| f64::MAX / 2.0)])
+    .with_toroidal(true);
+  assert!(adf.insert_sdf_domain(domain, Arc::new(move |p| circle.sdf(p))));
+
+  assert!(adf.sdf(P2::new(0.01, 0.5)) < 0.0);
+  assert!((adf.sdf(P2::new(1.01, 0.5)) - adf.sdf(P2::new(0.01, 0.5))).abs() < 1e-9);
+  assert!((adf.sdf(P2::new(0.5, 0.5)) - 0.43).abs() < 1e-9);
+}
+
+#[test] fn stats_fast() {
+  let mut adf = ADF::<f64>::new(5, vec![Arc::new(sdf::boundary_rect)]);
+  fill(&mut adf, 8, 16);
+
+  let mut expected = FastStats { leaves_per_depth: vec![0; 6], total_primitives: 0 };
+  adf.tree.traverse(&mut |node| {
+    if node.children.is_none() {
+      expected.leaves_per_depth[node.depth as usize] += 1;
+      expected.total_primitives += node.data.len() as u64;
+    }
+    Ok(())
+  }).ok();
+  assert!(expected.max_depth() > 1);
+  assert_eq!(adf.stats_fast(), &expected);
+
+  let stats = adf.stats();
+  assert_eq!(stats.leaves_per_depth, expected.leaves_per_depth);
+  assert_eq!(stats.total_leaves, expected.total_leaves());
+  assert_eq!(stats.total_nodes, expected.total_nodes());
+  assert_eq!(stats.primitives_per_leaf.iter().sum::<u64>(), stats.total_leaves);
+  assert_eq!(
+    stats.primitives_per_leaf.iter().enumerate().map(|(n, &leaves)| n as u64 * leaves).sum::<u64>(),
+    expected.total_primitives
+  );
+  assert!(stats.bytes >= stats.total_nodes as usize * std::mem::size_of::<quadtree::Quadtree<Vec<DynPrimitive<f64>>, f64>>());
+}
+
+#[test] fn branch() {
+  let adf = ADF::<f64>::new(5, vec![Arc::new(sdf::boundary_rect)]);
+  let mut branch = adf.clone();
+  let circle = Circle.translate(Vector2D::splat(0.5)).scale(0.25);
+  branch.insert_sdf_domain(
+    util::domain_empirical(DistPoint { distance: 0.25, point: P2::splat(0.5) }),
+    Arc::new(move |p| circle.sdf(p))
+  );
+  assert!(branch.sdf(P2::splat(0.5)) < 0.0);
+  assert!((adf.sdf(P2::splat(0.5)) - 0.5).abs() < 1e-9);
+  assert_eq!(adf.stats_fast().total_primitives, 1);
+}
+
+#[test] fn prune() {
+  let mut adf = ADF::<f64>::new(6, vec![Arc::new(sdf::boundary_rect)]);
+  let local_maxima: Vec<_> = util::local_maxima_iter(|p| adf.sdf(p), 32, 0, LineSearch::default())
+    .take(64).collect();
+  for local_max in local_maxima {
+    let circle = Circle.translate(local_max.point.to_vector()).scale(local_max.distance / 2.0);
+    adf.insert_sdf_domain(util::domain_empirical(local_max), Arc::new(move |p| circle.sdf(p)));
+  }
+  // cover most of the small circles
+  let circle = Circle.translate(Vector2D::splat(0.5)).scale(0.4);
+  adf.insert_sdf_domain(Rect::from_size(Size2D::splat(1.0)), Arc::new(move |p| circle.sdf(p)));
+
+  let samples: Vec<_> = itertools::iproduct!(0..16, 0..16)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 16.0)
+    .collect();
+  let before: Vec<_> = samples.iter().map(|&p| adf.sdf(p)).collect();
+  let total_nodes = adf.stats_fast().total_nodes();
+
+  let removed = adf.prune(Rect::from_size(Size2D::splat(1.0)));
+  assert!(removed > 0);
+  assert_eq!(adf.stats_fast().total_nodes(), total_nodes - removed);
+  samples.iter().zip(before).for_each(|(&p, before)| assert!((adf.sdf(p) - before).abs() < 1e-9));
+
+  let mut expected = FastStats { leaves_per_depth: vec![0; 7], total_primitives: 0 };
+  adf.tree.traverse(&mut |node| {
+    if node.children.is_none() {
+      expected.leaves_per_depth[node.depth as usize] += 1;
+      expected.total_primitives += node.data.len() as u64;
+    }
+    Ok(())
+  }).ok();
+  assert_eq!(adf.stats_fast(), &expected);
+}
+
+#[test] fn shape_primitives() {
+  let mut opaque = ADF::<f64>::new(5, vec![Arc::new(sdf::boundary_rect)]);
+  let mut shapes = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]);
+  let circles = [([0.3, 0.3], 0.2), ([0.7, 0.6], 0.1), ([0.4, 0.8], 0.05)];
+  for (center, radius) in circles {
+    let local_max = DistPoint { distance: radius, point: P2::from(center) };
+    let circle = Circle.translate(Vector2D::from(center)).scale(radius);
+    opaque.insert_sdf_domain(util::domain_empirical(local_max), Arc::new(move |p| circle.sdf(p)));
+    shapes.insert_sdf_domain(util::domain_empirical(local_max), ShapePrimitive::circle(center.into(), radius));
+  }
+  itertools::iproduct!(0..16, 0..16)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 16.0)
+    .for_each(|p| {
+      assert!((opaque.sdf(p) - shapes.sdf(p)).abs() < 1e-9);
+      assert!((ShapePrimitive::boundary_rect().sdf(p) - sdf::boundary_rect(p)).abs() < 1e-9);
+    });
+
+  let circle = ShapePrimitive::circle(P2::new(0.5, 0.5), 0.25);
+  assert!(circle.identical(&circle.clone()));
+  assert!(!circle.identical(&ShapePrimitive::circle(P2::new(0.5, 0.5), 0.125)));
+  // moved by -offset
+  assert!(circle.offset(Vector2D::new(1.0, 0.0)).sdf(P2::new(-0.5, 0.5)) + 0.25 < 1e-9);
+}
+
+#[test] fn raster_primitive() {
+  // samples of a half-plane `x - 0.5`, exactly representable
+  let raster = Raster::new(
+    Rect::from_size(Size2D::splat(1.0)), 5, 3,
+    (0..15).map(|i| (i % 5) as f64 / 4.0 - 0.5).collect()
+  ).unwrap();
+  let raster = ShapePrimitive::Raster(Arc::new(raster));
+  assert!((raster.sdf(P2::new(0.6, 0.3)) - 0.1).abs() < 1e-9);
+  // outside of the grid, the field grows
+  assert!((raster.sdf(P2::new(1.5, 0.5)) - 1.0).abs() < 1e-9);
+}
+
+#[test] fn raster_invalid() {
+  let rect = Rect::from_size(Size2D::splat(1.0));
+  assert!(Raster::new(rect, 0, 0, vec![]).is_err());
+  assert!(Raster::new(rect, 0, 3, vec![]).is_err());
+  assert!(Raster::new(rect, 2, 2, vec![0.0; 3]).is_err());
+  assert!(Raster::new(rect, 1, 1, vec![0.0]).is_ok());
+  #[cfg(feature = "serde")] {
+    let json = serde_json::to_string(&Raster::new(rect, 1, 1, vec![0.0]).unwrap()).unwrap();
+    assert!(serde_json::from_str::<Raster<f64>>(&json).is_ok());
+    let json = json.replace(r#""width":1"#, r#""width":2"#);
+    assert!(serde_json::from_str::<Raster<f64>>(&json).is_err());
+  }
+}
+
+#[cfg(feature = "serde")]
+#[test] fn serde_roundtrip() -> Result<()> {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]);
+  for (center, radius) in [([0.3, 0.3], 0.2), ([0.7, 0.6], 0.1)] {
+    adf.insert_sdf_domain(
+      util::domain_empirical(DistPoint { distance: radius, point: P2::from(center) }),
+      ShapePrimitive::circle(center.into(), radius)
+    );
+  }
+  let json = serde_json::to_string(&adf)?;
+  let mut restored: ADF<f64, ShapePrimitive<f64>> = serde_json::from_str(&json)?;
+  assert_eq!(restored.stats_fast(), adf.stats_fast());
+  assert_eq!(serde_json::to_string(&restored)?, json);
+
+  // continue filling
+  let local_max = DistPoint { distance: 0.05, point: P2::new(0.4, 0.8) };
+  assert!(restored.insert_sdf_domain(util::domain_empirical(local_max), ShapePrimitive::circle(local_max.point, 0.05)));
+  assert!(restored.sdf(local_max.point) < 0.0);
+  Ok(())
+}
+
+#[test] fn remove() {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()])
+    .with_registry(true);
+  let circles = [([0.3, 0.3], 0.1), ([0.7, 0.6], 0.1), ([0.4, 0.8], 0.05), ([0.3, 0.3], 0.2)];
+  let inserted: Vec<_> = circles.iter().map(|&(center, radius)| {
+    let domain = util::domain_empirical(DistPoint { distance: radius, point: P2::from(center) });
+    (domain, adf.insert_sdf_domain_id(domain, ShapePrimitive::circle(center.into(), radius)).unwrap())
+  }).collect();
+
+  // the last circle shadows the first one completely
+  let (_, id) = inserted[3];
+  assert!(adf.remove_sdf_domain(id));
+  assert!(!adf.remove_sdf_domain(id));
+
+  // a leaf is affected by (at least) the primitives, whose domain intersects with it
+  itertools::iproduct!(0..16, 0..16)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 16.0)
+    .for_each(|p| {
+      let leaf = adf.tree.pt_to_node(p).unwrap();
+      let field = |filter: &dyn Fn(&Rect<f64, WorldSpace>) -> bool| circles[..3].iter().zip(&inserted)
+        .filter(|(_, (domain, _))| filter(domain))
+        .map(|(&(center, radius), _)| ShapePrimitive::circle(center.into(), radius).sdf(p))
+        .fold(sdf::boundary_rect(p), f64::min);
+      let exact = field(&|_| true);
+      let local = field(&|domain| domain.intersects(&leaf.rect));
+      assert!(exact - 1e-9 <= adf.sdf(p) && adf.sdf(p) <= local + 1e-9);
+    });
+
+  let removed = ShapePrimitive::circle(P2::new(0.3, 0.3), 0.2);
+  let mut expected = FastStats { leaves_per_depth: vec![0; 6], total_primitives: 0 };
+  adf.tree.traverse(&mut |node| {
+    if node.children.is_none() {
+      assert!(!node.data.contains(&removed));
+      expected.leaves_per_depth[node.depth as usize] += 1;
+      expected.total_primitives += node.data.len() as u64;
+    }
+    Ok(())
+  }).ok();
+  assert_eq!(adf.stats_fast(), &expected);
+}
+
+#[test] fn remove_shadowing() {
+  let mut adf = ADF::with_primitives(6, vec![ShapePrimitive::boundary_rect()])
+    .with_registry(true);
+  let insert = |adf: &mut ADF<f64, ShapePrimitive<f64>>, center: P2<f64>, radius: f64| adf.insert_sdf_domain_id(
+    util::domain_empirical(DistPoint { distance: radius, point: center }),
+    ShapePrimitive::circle(center, radius)
+  );
+  let id = insert(&mut adf, P2::splat(0.5), 0.35).unwrap();
+  // poking out of the first one, hence registered
+  let circles: Vec<_> = (0..16)
+    .map(|i| P2::splat(0.5) + Vector2D::from_angle_and_length(euclid::Angle::degrees(i as f64 * 22.5), 0.28))
+    .collect();
+  circles.iter().for_each(|&center| assert!(insert(&mut adf, center, 0.1).is_some()));
+  let removed = ShapePrimitive::circle(P2::splat(0.5), 0.35);
+  let rebuilt: Vec<_> = adf.tree.leaves()
+    .filter(|leaf| leaf.data.contains(&removed))
+    .map(|leaf| leaf.rect)
+    .collect();
+  assert!(adf.remove_sdf_domain(id));
+
+  // the leaves, shadowed by the removed circle, are subdivided until their buckets fit
+  adf.tree.leaves()
+    .filter(|leaf| rebuilt.iter().any(|rect| rect.contains_rect(&leaf.rect)))
+    .for_each(|leaf| assert!(leaf.data.len() <= BUCKET_SIZE || leaf.depth == leaf.max_depth));
+  circles.iter().for_each(|&center| assert!((adf.sdf(center) + 0.1).abs() < 1e-9));
+  assert!(adf.sdf(P2::splat(0.5)) > 0.0);
+  assert_eq!(adf.stats().total_leaves, adf.stats_fast().total_leaves());
+  // handles are issued, but removal requires the registry
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]);
+  let id = insert(&mut adf, P2::splat(0.5), 0.35).unwrap();
+  assert!(!adf.remove_sdf_domain(id) && adf.nearest_primitive(P2::splat(0.5)).is_none());
+}
+
+#[test] fn insert_batch() {
+  let circles: Vec<_> = itertools::iproduct!(0..8, 0..8)
+    .map(|(x, y)| (P2::new(x as f64 + 0.5, y as f64 + 0.5) / 8.0, 0.03))
+    .collect();
+  let mut adf = ADF::with_primitives(6, vec![ShapePrimitive::boundary_rect()])
+    .with_gd_lattice_density(3)
+    .with_registry(true);
+  let ids = adf.insert_sdf_batch(circles.iter().map(|&(center, radius)| (
+    util::domain_empirical(DistPoint { distance: radius, point: center }),
+    ShapePrimitive::circle(center, radius)
+  )));
+  assert!(ids.iter().all(Option::is_some));
 
+  let exact = |p| circles.iter()
+    .map(|&(center, radius)| ShapePrimitive::circle(center, radius).sdf(p))
+    .fold(sdf::boundary_rect(p), f64::min);
+  circles.iter().for_each(|&(center, _)| assert!(adf.sdf(center) < 0.0));
+  itertools::iproduct!(0..32, 0..32)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 32.0)
+    .for_each(|p| assert!(adf.sdf(p) >= exact(p) - 1e-9));
+
+  let mut expected = FastStats { leaves_per_depth: vec![0; 7], total_primitives: 0 };
+  adf.tree.traverse(&mut |node| {
+    if node.children.is_none() {
+      expected.leaves_per_depth[node.depth as usize] += 1;
+      expected.total_primitives += node.data.len() as u64;
+    }
+    Ok(())
+  }).ok();
+  assert_eq!(adf.stats_fast(), &expected);
+
+  // removal works as well
+  assert!(adf.remove_sdf_domain(ids[0].unwrap()));
+  assert!(adf.sdf(circles[0].0) > 0.0);
+}
+
+#[test] fn nearest_primitive() {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()])
+    .with_registry(true);
+  let ids: Vec<_> = [([0.3, 0.3], 0.1), ([0.7, 0.6], 0.15)].iter().map(|&(center, radius)| adf.insert_sdf_domain_id(
+    util::domain_empirical(DistPoint { distance: radius, point: P2::from(center) }),
+    ShapePrimitive::circle(center.into(), radius)
+  ).unwrap()).collect();
+
+  let (distance, handle) = adf.nearest_primitive(P2::new(0.3, 0.45)).unwrap();
+  assert_eq!(handle, PrimitiveHandle::Inserted(ids[0]));
+  assert!((distance - 0.05).abs() < 1e-9);
+  assert_eq!(adf.nearest_primitive(P2::new(0.7, 0.6)).unwrap().1, PrimitiveHandle::Inserted(ids[1]));
+  let (distance, handle) = adf.nearest_primitive(P2::new(0.02, 0.9)).unwrap();
+  assert_eq!(handle, PrimitiveHandle::Base(0));
+  assert!((distance - 0.02).abs() < 1e-9);
+
+  // neighbouring buckets may only lower the distance
+  itertools::iproduct!(0..16, 0..16)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 16.0)
+    .for_each(|p| assert!(adf.nearest_primitive(p).unwrap().0 <= adf.sdf(p)));
+}
+
+#[test] fn accuracy() {
+  use rand::prelude::*;
+  let epsilon = 1e-3;
+  let mut adf = ADF::with_primitives(6, vec![ShapePrimitive::boundary_rect()])
+    .with_accuracy(epsilon);
+  // overlapping circles of varying size, a hard case for the lattice heuristics
+  let mut rng = rand_pcg::Pcg64::seed_from_u64(1);
+  let circles: Vec<_> = (0..48)
+    .map(|_| (
+      P2::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)),
+      rng.gen_range(0.01..0.11)
+    ))
+    .collect();
+  circles.iter().for_each(|&(center, radius)| {
+    adf.insert_sdf_domain(
+      util::domain_empirical(DistPoint { distance: radius, point: center }),
+      ShapePrimitive::circle(center, radius)
+    );
+  });
+
+  let exact = |p| circles.iter()
+    .map(|&(center, radius)| ShapePrimitive::circle(center, radius).sdf(p))
+    .fold(sdf::boundary_rect(p), f64::min);
+  itertools::iproduct!(0..64, 0..64)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 64.0)
+    .for_each(|p| assert!((adf.sdf(p) - exact(p)).abs() < epsilon, "{p:?}"));
+
+  let mut expected = FastStats { leaves_per_depth: vec![0; 7], total_primitives: 0 };
+  adf.tree.traverse(&mut |node| {
+    if node.children.is_none() {
+      expected.leaves_per_depth[node.depth as usize] += 1;
+      expected.total_primitives += node.data.len() as u64;
+    }
+    Ok(())
+  }).ok();
+  assert_eq!(adf.stats_fast(), &expected);
+}
+
+#[test] fn gradient() {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]);
+  let square = ShapePrimitive::Rect { center: P2::new(0.0, 0.0), half_size: Vector2D::splat(0.1), angle: 0.0 }
+    .transform(Vector2D::new(0.7, 0.6), std::f64::consts::FRAC_PI_4, 1.0);
+  [ShapePrimitive::circle(P2::new(0.3, 0.3), 0.1), square].into_iter().for_each(|f| {
+    adf.insert_sdf_domain(Rect::from_size(Size2D::splat(1.0)), f);
+  });
+
+  let close = |a: Vector2D<f64, WorldSpace>, b: Vector2D<f64, WorldSpace>| (a - b).length() < 1e-4;
+  assert!(close(adf.gradient(P2::new(0.3, 0.45)), Vector2D::new(0.0, 1.0)));
+  assert!(close(adf.gradient(P2::new(0.02, 0.9)), Vector2D::new(1.0, 0.0)));
+  // away from the corners, the gradient of a rotated square is one of its normals
+  let normal = Vector2D::new(1.0, 1.0) / 2f64.sqrt();
+  assert!(close(adf.gradient(P2::new(0.7, 0.6) + normal * 0.15), normal));
+
+  let line_search = LineSearch::default();
+  let p0 = P2::new(0.5, 0.1);
+  let p_sampled = line_search.optimize(|p| adf.sdf(p), p0);
+  let p_grad = line_search.optimize_grad(|p| adf.gradient(p), p0);
+  assert!((adf.sdf(p_sampled) - adf.sdf(p_grad)).abs() < 1e-3);
+}
+
+#[test] fn rasterize() -> Result<()> {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]);
+  [([0.3, 0.3], 0.1), ([0.7, 0.6], 0.15)].iter().for_each(|&(center, radius)| {
+    adf.insert_sdf_domain(
+      util::domain_empirical(DistPoint { distance: radius, point: P2::from(center) }),
+      ShapePrimitive::circle(center.into(), radius)
+    );
+  });
+
+  let argmax = adf.rasterize(128, 32)?;
+  argmax.pixels().for_each(|DistPoint { distance, point }| {
+    let p = point.to_f64().cast_unit() / 128.0;
+    assert!((distance as f64 - adf.sdf(p)).abs() < 1e-6);
+  });
+
+  // and back, the samples are interpolated exactly
+  let mut adf = ADF::<f64, _>::from_argmax2d(5, &argmax);
+  argmax.pixels().step_by(7).for_each(|DistPoint { distance, point }| {
+    let p = point.to_f64().cast_unit() / 128.0;
+    assert!((distance as f64 - adf.sdf(p)).abs() < 1e-6);
+  });
+  let max = argmax.find_max();
+  assert!(adf.insert_sdf_domain(
+    util::domain_empirical(DistPoint { distance: max.distance as f64, point: max.point.cast() }),
+    ShapePrimitive::circle(max.point.cast(), max.distance as f64)
+  ));
+  assert!(adf.sdf(max.point.cast()) < 0.0);
+  Ok(())
+}
+
+#[test] fn gpu() -> Result<()> {
+  let mut adf = ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()])
+    .with_toroidal(true);
+  let raster = Raster::new(
+    Rect::new(P2::new(0.6, 0.1), Size2D::splat(0.2)), 2, 2,
+    vec![0.05, 0.0, -0.05, 0.0]
+  )?;
+  let primitives = [
+    ShapePrimitive::circle(P2::new(0.3, 0.3), 0.1),
+    ShapePrimitive::Rect { center: P2::new(0.0, 0.0), half_size: Vector2D::new(0.1, 0.05), angle: 0.0 }
+      .transform(Vector2D::new(0.7, 0.6), 0.5, 1.5),
+    ShapePrimitive::Polygon(vec![P2::new(0.1, 0.7), P2::new(0.3, 0.9), P2::new(0.1, 0.9)].into()),
+    ShapePrimitive::Raster(Arc::new(raster)),
+    ShapePrimitive::circle(P2::new(0.9, 0.9), 0.15).complement().complement()
+  ];
+  primitives.into_iter().for_each(|f| {
+    adf.insert_sdf_domain(Rect::from_size(Size2D::splat(1.0)), f);
+  });
+
+  let gpu = gpu::GpuAdf::compile(&adf)?;
+  itertools::iproduct!(0..64, 0..64)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 64.0 * 1.5 - Vector2D::splat(0.25))
+    .for_each(|p| assert!((gpu.eval(p.cast()) as f64 - adf.sdf(p)).abs() < 1e-5, "{p:?}"));
+
+  let module = naga::front::wgsl::parse_str(gpu::WGSL)?;
+  naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+    .validate(&module)?;
   Ok(())
-}
\ No newline at end of file
+}
+
+#[test] fn shared() {
+  use rayon::prelude::*;
+
+  let adf = SharedADF::new(ADF::with_primitives(5, vec![ShapePrimitive::boundary_rect()]));
+  let circles: Vec<_> = itertools::iproduct!(0..8, 0..8)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 8.0)
+    .collect();
+  // sampled and modified concurrently, through a shared reference
+  circles.par_iter().for_each(|&center| {
+    assert!(adf.sdf(center) > 0.0);
+    adf.insert_sdf_domain(
+      util::domain_empirical(DistPoint { distance: 0.03, point: center }),
+      ShapePrimitive::circle(center, 0.03)
+    );
+    assert!(adf.sdf(center) < 0.0);
+  });
+
+  let adf = adf.into_inner();
+  circles.iter().for_each(|&center| assert!(adf.sdf(center) < 0.0));
+  assert_eq!(adf.stats().total_leaves, adf.stats_fast().total_leaves());
+}
+
+#[test] fn memory_budget() {
+  let mut unbounded = ADF::<f64>::new(7, vec![Arc::new(sdf::boundary_rect)]);
+  fill(&mut unbounded, 8, 32);
+  let budget = unbounded.memory_fast() / 2;
+  let mut bounded = ADF::<f64>::new(7, vec![Arc::new(sdf::boundary_rect)])
+    .with_memory_budget(budget);
+  fill(&mut bounded, 8, 32);
+
+  assert!(bounded.memory_fast() <= budget);
+  let stats = bounded.stats();
+  assert_eq!(stats.leaves_per_depth, bounded.stats_fast().leaves_per_depth);
+  assert_eq!(stats.total_nodes, bounded.stats_fast().total_nodes());
+  // free space is still found
+  let mut rng = <rand_pcg::Pcg64 as rand::SeedableRng>::seed_from_u64(0);
+  assert!(!util::find_max_parallel(|p| bounded.sdf(p), 32, &mut rng, LineSearch::default()).is_empty());
+}
+
+#[test] fn coarsen_keeps_primitives() {
+  let centers = [P2::new(0.25, 0.25), P2::new(0.75, 0.25), P2::new(0.25, 0.75), P2::new(0.75, 0.75)];
+  let mut node = Quadtree::new(1, vec![]);
+  node.subdivide(|rect| centers.iter()
+    .filter(|center| rect.contains(**center))
+    .map(|&center| ShapePrimitive::circle(center, 0.1))
+    .collect());
+  coarsen(&mut node, &StatsDelta::new(1));
+
+  // each one is the lowest around its center, beyond the bucket size
+  assert!(node.children.is_none() && node.data.len() == 4);
+  centers.iter().for_each(|&center| assert_eq!(bucket_sdf(&node.data, center), -0.1));
+}
+
+#[test] fn balance() {
+  let mut adf = ADF::<f64>::new(7, vec![Arc::new(sdf::boundary_rect)])
+    .with_balance(true);
+  fill(&mut adf, 4, 32);
+
+  assert!(adf.stats_fast().max_depth() > 3);
+  adf.tree.leaves().for_each(|leaf| {
+    let margin = leaf.rect.size.width / 1024.0;
+    adf.tree.leaves_in_region(leaf.rect.inflate(margin, margin))
+      .for_each(|neighbour| assert!(neighbour.depth.abs_diff(leaf.depth) <= 1));
+  });
+  let stats = adf.stats();
+  assert_eq!(stats.leaves_per_depth, adf.stats_fast().leaves_per_depth);
+  assert_eq!(stats.total_nodes, adf.stats_fast().total_nodes());
+}
+
+#[test] fn from_shapes() {
+  let circles: Vec<_> = (0..40)
+    .map(|i| Circle.scale(0.01 + 0.002 * (i % 7) as f64).translate(Vector2D::new((i * 37 % 40) as f64 / 40.0, i as f64 / 40.0)))
+    .collect();
+  let adf = ADF::from_shapes(8, vec![Arc::new(sdf::boundary_rect)], circles.clone());
+  let exact = |p: P2<f64>| circles.iter().fold(sdf::boundary_rect(p), |min, circle| min.min(circle.sdf(p)));
+  circles.iter().for_each(|circle| assert!(adf.sdf(circle.offset.to_point()) < 0.0));
+  itertools::iproduct!(0..32, 0..32)
+    .map(|(x, y)| P2::new(x as f64 + 0.5, y as f64 + 0.5) / 32.0)
+    .for_each(|p| assert!(adf.sdf(p) >= exact(p) - 1e-9));
+  assert!(ADF::from_shapes(8, vec![Arc::new(sdf::boundary_rect)], Vec::<Circle>::new()).sdf(P2::splat(0.5)) == 0.5);
+}
+
+#[test] fn shape_index() {
+  let circles: Vec<_> = (0..4).map(|i| Circle.scale(0.05).translate(Vector2D::new(0.2 * i as f64 + 0.1, 0.5))).collect();
+  let mut adf = ADF::new(6, vec![Arc::new(sdf::boundary_rect)])
+    .with_shape_index(util::ShapeIndex::new(0.1));
+  adf.insert_shapes(circles[..2].to_vec());
+  circles[2..].iter().for_each(|&circle| { adf.insert_shape_domain(util::changed_domain(&circle, 0.5), circle); });
+  let index = adf.shape_index().unwrap();
+  assert_eq!(index.k_nearest(P2::new(0.45, 0.5), 2).iter().map(|&(id, _)| id).collect::<Vec<_>>(), [2, 1]);
+  assert_eq!(index.shapes_in(Rect::new(P2::new(0.0, 0.0), Size2D::new(0.3, 1.0))), [0, 1]);
+}
+
+#[test] fn max_empty_rect() {
+  let circle = Circle.scale(0.2).translate(Vector2D::new(0.3, 0.5));
+  let mut adf = ADF::new(6, vec![Arc::new(sdf::boundary_rect)]);
+  adf.insert_shapes([circle]);
+  // the strip to the right of the circle, less the cells along the edges
+  let rect = adf.max_empty_rect(128).unwrap();
+  assert!(rect.area() > 0.47 && rect.min_x() >= 0.5, "{rect:?}");
+  assert!(itertools::iproduct!(0..=32, 0..=32)
+    .map(|(x, y)| rect.origin + rect.size.to_vector().component_mul(Vector2D::new(x as f64, y as f64) / 32.0))
+    .all(|p| sdf::boundary_rect(p) >= -1e-9 && circle.sdf(p) >= -1e-9));
+  assert!(ADF::new(4, vec![Arc::new(|_| -1.0)]).max_empty_rect(16).is_none());
+}
+
+#[test] fn contour() {
+  let circle = Circle.scale(0.2).translate(Vector2D::splat(0.5));
+  let mut adf = ADF::new(6, vec![Arc::new(sdf::boundary_rect)]);
+  adf.insert_shapes([circle]);
+  // the free region, bounded by the edges of the unit square and the circle
+  let contours = util::contour(&adf, 0.0f64, 64);
+  let hole = contours.iter().find(|line| line.vertices.iter().all(|p| (p.x - 0.5).abs() < 0.3)).unwrap();
+  assert!(hole.closed && hole.vertices.iter().all(|p: &P2<f64>| ((*p - P2::splat(0.5)).length() - 0.2).abs() < 1e-3));
+}
+
+#[test] fn place_iter() {
+  use crate::solver::PowerLawRadius;
+
+  let representation = SharedADF::new(ADF::new(5, vec![Arc::new(sdf::boundary_rect)]));
+  let placer = PowerLawRadius { exponent: 2.0, max_radius: 0.1 };
+  let circles: Vec<_> = representation.place_iter(placer, 16, 0, LineSearch::default())
+    .take(200)
+    .map(|placed| DistPoint { distance: placed.radius, point: placed.center })
+    .collect();
+  assert!(circles.iter().all(|c| c.distance <= 0.1 && sdf::boundary_rect(c.point) >= c.distance - 1e-6));
+  assert!(util::validate_no_overlap(circles.iter().copied(), 1e-6).is_empty());
+}
+
@@ -0,0 +1,156 @@
+//! Exact circle packing of the Apollonian gasket, by Descartes' circle theorem. Instead of
+//! sampling a distance field, every circle is computed from three mutually tangent ones,
+//! hence it is much faster than the general solvers for this particular fractal.
+
+use {
+  std::{cmp::Ordering, collections::BinaryHeap},
+  euclid::Vector2D as V2,
+  num_traits::Float,
+  crate::geometry::{DistPoint, WorldSpace}
+};
+
+// a circle, by its curvature (negative for the enclosing one) and center
+#[derive(Debug, Copy, Clone)]
+struct Tangent<T> {
+  k: T,
+  z: V2<T, WorldSpace>
+}
+
+// the circle inscribed between three mutually tangent ones, on the opposite side of the
+// fourth one, tangent to them as well
+#[derive(Debug, Copy, Clone)]
+struct Gap<T> {
+  triple: [usize; 3],
+  circle: Tangent<T>
+}
+
+// the largest circle first
+impl<T: Float> Ord for Gap<T> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    other.circle.k.partial_cmp(&self.circle.k).unwrap_or(Ordering::Equal)
+  }
+}
+
+impl<T: Float> PartialOrd for Gap<T> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<T: Float> PartialEq for Gap<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl<T: Float> Eq for Gap<T> {}
+
+/// Circles of an Apollonian gasket, filling [`sdf::boundary_circle`](crate::sdf::boundary_circle),
+/// in the order of decreasing radius. The iterator is infinite; limit it with
+/// `take_while(|c| c.distance > min_radius)`.
+#[derive(Debug, Clone)]
+pub struct ApollonianGasket<T> {
+  circles: Vec<Tangent<T>>,
+  // initial circles, not yielded yet
+  seeds: std::ops::Range<usize>,
+  gaps: BinaryHeap<Gap<T>>
+}
+
+impl<T: Float> Default for ApollonianGasket<T> {
+  fn default() -> Self {
+    Self::new(T::from(0.5).unwrap())
+  }
+}
+
+impl<T: Float> ApollonianGasket<T> {
+  /// Start from two circles, tangent to each other and to the boundary along its horizontal
+  /// diameter, of `ratio` and `1 - ratio` of its radius, `ratio` being within `(0, 1)`.
+  pub fn new(ratio: T) -> Self {
+    let two = T::one() + T::one();
+    let radius = T::one() / two;
+    let center = V2::splat(radius);
+    let (ra, rb) = (ratio * radius, (T::one() - ratio) * radius);
+    let outer = Tangent { k: -T::one() / radius, z: center };
+    let a = Tangent { k: T::one() / ra, z: center - V2::new(radius - ra, T::zero()) };
+    let b = Tangent { k: T::one() / rb, z: center + V2::new(radius - rb, T::zero()) };
+    // the larger one is yielded first
+    let (a, b) = if a.k <= b.k { (a, b) } else { (b, a) };
+    // Descartes' theorem, for the two circles tangent to all three, mirrored about the diameter:
+    // along it, `ka kb + kb ko + ko ka` is 0, hence their curvatures coincide
+    let k = outer.k + a.k + b.k;
+    let [o, a_, b_] = [outer, a, b].map(|c| c.z * c.k);
+    let root = sqrt(mul(o, a_) + mul(a_, b_) + mul(b_, o)) * two;
+    let sum = o + a_ + b_;
+    let (c, d) = (Tangent { k, z: (sum + root) / k }, Tangent { k, z: (sum - root) / k });
+    let mut gasket = Self { circles: vec![outer, a, b, c, d], seeds: 1..5, gaps: BinaryHeap::new() };
+    gasket.push_gaps(3, [0, 1, 2]);
+    gasket.push_gaps(4, [0, 1, 2]);
+    gasket
+  }
+
+  // gaps between the circle `fourth` and each pair of `triple`, which it is tangent to
+  fn push_gaps(&mut self, fourth: usize, [a, b, c]: [usize; 3]) {
+    let two = T::one() + T::one();
+    for (triple, opposite) in [([a, b, fourth], c), ([a, c, fourth], b), ([b, c, fourth], a)] {
+      let [x, y, z] = triple.map(|i| self.circles[i]);
+      let e = self.circles[opposite];
+      // the other root of Descartes' theorem, for curvatures and centers alike
+      let k = (x.k + y.k + z.k) * two - e.k;
+      let kz = (x.z * x.k + y.z * y.k + z.z * z.k) * two - e.z * e.k;
+      self.gaps.push(Gap { triple, circle: Tangent { k, z: kz / k } });
+    }
+  }
+}
+
+impl<T: Float> Iterator for ApollonianGasket<T> {
+  type Item = DistPoint<T, T, WorldSpace>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let circle = match self.seeds.next() {
+      Some(i) => self.circles[i],
+      None => {
+        let gap = self.gaps.pop()?;
+        self.circles.push(gap.circle);
+        self.push_gaps(self.circles.len() - 1, gap.triple);
+        gap.circle
+      }
+    };
+    Some(DistPoint { distance: T::one() / circle.k, point: circle.z.to_point() })
+  }
+}
+
+// complex numbers, as vectors
+fn mul<T: Float>(a: V2<T, WorldSpace>, b: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+  V2::new(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x)
+}
+
+fn sqrt<T: Float>(a: V2<T, WorldSpace>) -> V2<T, WorldSpace> {
+  let two = T::one() + T::one();
+  let r = a.length();
+  let im = ((r - a.x) / two).max(T::zero()).sqrt();
+  V2::new(((r + a.x) / two).max(T::zero()).sqrt(), if a.y < T::zero() { -im } else { im })
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::{sdf, util}, euclid::Point2D};
+
+  #[test] fn apollonian_gasket() {
+    let circles: Vec<DistPoint<f64, f64, WorldSpace>> = ApollonianGasket::default()
+      .take_while(|c| c.distance > 0.002)
+      .collect();
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    assert!(close(circles[0].distance, 0.25) && close(circles[2].distance, 1.0 / 6.0));
+    assert!(close((circles[2].point - Point2D::new(0.5, 0.5)).length(), 1.0 / 3.0));
+    assert!(circles.windows(2).all(|w| w[0].distance >= w[1].distance));
+    // tangent, yet neither overlapping each other, nor the boundary
+    assert!(util::validate_no_overlap(circles.iter().copied(), 1e-9).is_empty());
+    assert!(circles.iter().all(|c| sdf::boundary_circle(c.point) >= c.distance - 1e-9));
+    let coverage: f64 = circles.iter().map(|c| c.distance * c.distance).sum::<f64>() * 4.0;
+    assert!(coverage > 0.95, "{coverage}");
+
+    let asymmetric: Vec<_> = ApollonianGasket::new(0.3).take(200).collect();
+    assert!(close(asymmetric[0].distance, 0.35) && close(asymmetric[1].distance, 0.15));
+    assert!(close(asymmetric[2].distance, asymmetric[3].distance));
+    assert!(util::validate_no_overlap(asymmetric.iter().copied(), 1e-9).is_empty());
+  }
+}
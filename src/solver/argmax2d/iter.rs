@@ -0,0 +1,71 @@
+use {
+  super::Argmax2D,
+  crate::{
+    solver::placer::{PlacedShape, ShapePlacer},
+    sdf::SDF,
+    util::{self, Budget}
+  },
+  std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant
+  }
+};
+
+/// Iterator of [`Argmax2D::place_iter`].
+pub struct ArgmaxIter<'a, P> {
+  argmax: &'a mut Argmax2D,
+  placer: P,
+  rng: rand_pcg::Pcg64,
+  budget: Option<Budget>,
+  start: Instant,
+  sdf_evals: u64
+}
+
+impl<'a, P> ArgmaxIter<'a, P> {
+  pub(super) fn new(argmax: &'a mut Argmax2D, placer: P, rng: rand_pcg::Pcg64) -> Self {
+    Self { argmax, placer, rng, budget: None, start: Instant::now(), sdf_evals: 0 }
+  }
+
+  /// Stop once `budget` is spent, yielding the shapes placed so far. The evaluations of the
+  /// SDF are those of the inserted circles, one per pixel of the updated domain.
+  pub fn with_budget(mut self, budget: Budget) -> Self {
+    self.budget = Some(budget);
+    self
+  }
+
+  /// Evaluations of the SDF so far; only counted under [`Budget::SdfEvals`].
+  pub fn sdf_evals(&self) -> u64 {
+    self.sdf_evals
+  }
+}
+
+impl<P: ShapePlacer<f32>> Iterator for ArgmaxIter<'_, P> {
+  type Item = PlacedShape<f32>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let spent = match self.budget {
+      Some(Budget::Time(timeout)) => self.start.elapsed() >= timeout,
+      Some(Budget::SdfEvals(evals)) => self.sdf_evals >= evals,
+      None => false
+    };
+    if spent {
+      return None;
+    }
+    let global_max = self.argmax.find_max();
+    let placed = self.placer.place(global_max, &mut self.rng)?;
+    let circle = placed.circle();
+    let domain = util::domain_empirical(global_max);
+    // counting contends on the atomic, hence only done when the budget depends on it
+    if matches!(self.budget, Some(Budget::SdfEvals(_))) {
+      let evals = AtomicU64::new(0);
+      self.argmax.insert_sdf_domain(domain, |p| {
+        evals.fetch_add(1, Ordering::Relaxed);
+        circle.sdf(p)
+      });
+      self.sdf_evals += evals.into_inner();
+    } else {
+      self.argmax.insert_sdf_domain(domain, |p| circle.sdf(p));
+    }
+    Some(placed)
+  }
+}
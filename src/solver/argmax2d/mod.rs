@@ -2,38 +2,98 @@
 
 use {
   crate::{
-    geometry::{DistPoint, PixelSpace, WorldSpace}
+    geometry::{DistPoint, PixelSpace, Shape, WorldSpace},
+    solver::{adf::Raster, placer::ShapePlacer},
+    sdf::SDF,
+    util
   },
-  z_order_storage::ZOrderStorage,
+  z_order_storage::{ZOrderStorage, ChunkOrder},
   anyhow::Result,
-  euclid::{Rect, Point2D, Size2D},
+  euclid::{Box2D, Rect, Point2D, Size2D, Vector2D as V2},
+  num_traits::Float
 };
 
 pub mod z_order_storage;
+mod multi;
+pub use multi::MultiArgmax2D;
+mod iter;
+pub use iter::ArgmaxIter;
+#[cfg(test)] mod tests;
 
+#[derive(Clone)]
 pub struct Argmax2D {
   pub (crate) dist_map: ZOrderStorage<Vec<f32>>,
-  chunk_argmax: Vec<DistPoint<f32, f32, WorldSpace>>
+  chunk_argmax: Vec<DistPoint<f32, f32, WorldSpace>>,
+  weight_map: Option<ZOrderStorage<Vec<f32>>>,
+  toroidal: bool,
+  shape_index: Option<util::ShapeIndex<f32>>
 }
 
+/// Saved state of [`Argmax2D`], see [`Argmax2D::snapshot`].
+#[derive(Clone)]
+pub struct Snapshot(Argmax2D);
+
 impl Argmax2D {
   pub fn new(resolution: u64, chunk_size: u64) -> Result<Self> {
     let storage = ZOrderStorage::new(resolution, chunk_size, f32::MAX / 2.0)?;
     let chunk_count = storage.chunk_count() as usize;
     Ok(Self {
       dist_map: storage,
-      chunk_argmax: vec![DistPoint::default(); chunk_count]
+      chunk_argmax: vec![DistPoint::default(); chunk_count],
+      weight_map: None,
+      toroidal: false,
+      shape_index: None
     })
   }
 
+  /// Wrap the field around the edges of the unit square (i.e. compute distances on a torus),
+  /// so that shapes near one edge also constrain the opposite one.
+  /// Useful for generating seamlessly tileable textures; `sdf::boundary_rect` should not be
+  /// inserted in this mode. See [`util::torus_images`](crate::util::torus_images).
+  pub fn with_toroidal(mut self, toroidal: bool) -> Self {
+    self.toroidal = toroidal;
+    self
+  }
+
+  /// Order of visiting chunks on insertion, see [`ChunkOrder`].
+  pub fn with_chunk_order(mut self, chunk_order: ChunkOrder) -> Self {
+    self.dist_map = self.dist_map.with_chunk_order(chunk_order);
+    // the argmax of each chunk is stored at its position, which has changed
+    self.update_domain(Self::unit_domain(), |_, _| ());
+    self
+  }
+
+  /// Resume filling from previously generated `shapes`, e.g. imported with
+  /// [`util::import_circles`], see [`Argmax2D::insert_shapes`]. The boundary may be inserted
+  /// afterwards; for a toroidal field, insert the shapes into a toroidal one instead.
+  pub fn from_shapes<S>(resolution: u64, chunk_size: u64, shapes: impl IntoIterator<Item = S>) -> Result<Self>
+    where S: Shape<f32> + Send + Sync + 'static
+  {
+    let mut argmax = Self::new(resolution, chunk_size)?;
+    argmax.insert_shapes(shapes);
+    Ok(argmax)
+  }
+
+  /// Record the shapes inserted by [`Argmax2D::insert_shape_domain`] and
+  /// [`Argmax2D::insert_shapes`] into `index`, for nearest-shape and range queries.
+  pub fn with_shape_index(mut self, index: util::ShapeIndex<f32>) -> Self {
+    self.shape_index = Some(index);
+    self
+  }
+
+  pub fn shape_index(&self) -> Option<&util::ShapeIndex<f32>> {
+    self.shape_index.as_ref()
+  }
+
   pub fn resolution(&self) -> u64 {
     self.dist_map.resolution
   }
 
-  #[inline]
-  fn write_cache(&self, id: u64, dist: DistPoint<f32, f32, WorldSpace>) {
-    let ptr = &self.chunk_argmax[id as usize] as *const _ as usize;
-    unsafe { *(ptr as *const DistPoint<f32, f32, WorldSpace> as *mut _) = dist }
+  /// Restrict the field to the interior of `shape` (an arbitrary mask, such as a circle, a
+  /// glyph or a country outline), in place of `sdf::boundary_rect`.
+  pub fn with_boundary(mut self, shape: impl SDF<f32> + Send + Sync) -> Self {
+    self.insert_sdf(move |p| -shape.sdf(p));
+    self
   }
 
   /// Find global maxima.
@@ -43,49 +103,248 @@ impl Argmax2D {
       .unwrap()
   }
 
+  /// Find global maxima, if there is still a point at least `min_dist` away from the boundary
+  /// and all inserted shapes. Returns `None` once the domain is saturated, regardless of its
+  /// shape, since everything outside of the boundary is negative.
+  pub fn find_max_above(&self, min_dist: f32) -> Option<DistPoint<f32, f32, WorldSpace>> {
+    Some(self.find_max())
+      .filter(|max| max.distance >= min_dist)
+  }
+
   pub fn insert_sdf(&mut self, sdf: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send) {
-    self.insert_sdf_domain(
-      Rect::new(
-        Point2D::splat(0.0),
-        Size2D::splat(1.0),
-      ),
-      sdf
-    );
+    self.insert_sdf_domain(Self::unit_domain(), sdf);
   }
 
   pub fn insert_sdf_domain(&mut self, domain: Rect<f32, WorldSpace>, sdf: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send) {
+    if !self.toroidal {
+      return self.insert_sdf_domain_a(domain, sdf);
+    }
+    util::torus_images(domain).for_each(|offset| self.insert_sdf_domain_a(
+      domain.translate(-offset),
+      |p| sdf(p + offset)
+    ));
+  }
+
+  /// Insert many shapes within a single parallel pass over the chunks, rather than a pass per
+  /// shape. Each pixel evaluates the shapes in order of the distance from its chunk to their
+  /// bounding boxes, until it exceeds the value of the pixel (a lower bound of the SDF outside
+  /// of a box), hence the result does not depend on the order of `shapes`.
+  pub fn insert_shapes<S>(&mut self, shapes: impl IntoIterator<Item = S>)
+    where S: Shape<f32> + Send + Sync + 'static
+  {
+    let shapes: Vec<S> = shapes.into_iter().collect();
+    if shapes.is_empty() {
+      return;
+    }
+    let offsets: Vec<V2<f32, WorldSpace>> = if self.toroidal {
+      itertools::iproduct!(-1..=1, -1..=1).map(|(x, y)| V2::new(x as f32, y as f32)).collect()
+    } else {
+      vec![V2::zero()]
+    };
+    // periodic images, as in `insert_sdf_domain`
+    let images: Vec<_> = shapes.iter()
+      .flat_map(|shape| offsets.iter().map(move |&offset| (shape.bounding_box().translate(-offset), shape, offset)))
+      .collect();
+    self.update_chunks(Self::unit_domain(), |chunk| {
+      let mut nearest: Vec<_> = images.iter()
+        .map(|&(bounding_box, shape, offset)| {
+          let gap = (chunk.min - bounding_box.max).max(bounding_box.min - chunk.max).max(V2::zero());
+          (gap.length(), shape, offset)
+        })
+        .collect();
+      nearest.sort_by(|a, b| a.0.total_cmp(&b.0));
+      move |p, value: &mut f32| for &(gap, shape, offset) in &nearest {
+        if gap > 0.0 && gap >= *value { break; }
+        *value = value.min(shape.sdf(p + offset));
+      }
+    });
+    if let Some(index) = &mut self.shape_index {
+      shapes.into_iter().for_each(|shape| { index.insert(shape); });
+    }
+  }
+
+  /// Same as [`Argmax2D::insert_sdf_domain`], for a `shape`, which is recorded in the
+  /// [`Argmax2D::shape_index`], if any.
+  pub fn insert_shape_domain<S>(&mut self, domain: Rect<f32, WorldSpace>, shape: S)
+    where S: Shape<f32> + Send + Sync + 'static
+  {
+    self.insert_sdf_domain(domain, |p| shape.sdf(p));
+    if let Some(index) = &mut self.shape_index {
+      index.insert(shape);
+    }
+  }
+
+  fn insert_sdf_domain_a(&mut self, domain: Rect<f32, WorldSpace>, sdf: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send) {
+    self.update_domain(domain, move |xy_normalized, value| *value = value.min(sdf(xy_normalized)));
+  }
+
+  /// Invert distance field.
+  pub fn invert(&mut self) {
+    self.update_domain(Self::unit_domain(), |_, value| *value = -*value);
+  }
+
+  /// Bias the search of maxima towards regions with higher `weight`, which must be in `[0, 1]`.
+  /// Positive distances are multiplied by the weight of a pixel, hence [`Argmax2D::find_max`]
+  /// never reports more space than is actually available. Enables halftoning and stippling.
+  pub fn set_weight_map(&mut self, weight: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send) {
+    use rayon::prelude::*;
+
+    let mut weight_map = ZOrderStorage::new(self.dist_map.resolution, self.dist_map.chunk_size, 1.0)
+      .expect("same dimensions as the distance map");
+    let mut per_chunk = vec![(); weight_map.chunk_count() as usize];
+    weight_map.chunks_par_iter_mut(&mut per_chunk)
+      .for_each(|(mut chunk, _)| chunk.pixels_mut()
+        .for_each(|(xy_normalized, value)| *value = weight(xy_normalized).clamp(0.0, 1.0))
+      );
+    self.weight_map = Some(weight_map);
+    self.update_domain(Self::unit_domain(), |_, _| ());
+  }
+
+  /// Remove the weight map, see [`Argmax2D::set_weight_map`].
+  pub fn clear_weight_map(&mut self) {
+    self.weight_map = None;
+    self.update_domain(Self::unit_domain(), |_, _| ());
+  }
+
+  fn unit_domain() -> Rect<f32, WorldSpace> {
+    Rect::new(Point2D::splat(0.0), Size2D::splat(1.0))
+  }
+
+  // apply `op` to every pixel within `domain`, and refresh the argmax of affected chunks
+  fn update_domain(&mut self, domain: Rect<f32, WorldSpace>, op: impl Fn(Point2D<f32, WorldSpace>, &mut f32) + Sync + Send) {
+    self.update_chunks(domain, |_| &op);
+  }
+
+  // same as `update_domain`, with an `op` made for each chunk, given the box of its pixels
+  fn update_chunks<O>(&mut self, domain: Rect<f32, WorldSpace>, chunk_op: impl Fn(Box2D<f32, WorldSpace>) -> O + Sync + Send)
+    where O: FnMut(Point2D<f32, WorldSpace>, &mut f32)
+  {
     use rayon::prelude::*;
 
-    self.dist_map.chunks_domain_par_iter(domain)
-      .for_each(move |chunk_xy| {
-        let chunk = self.dist_map.get_chunk_xy(chunk_xy);
-        let max_dist = chunk.pixels_mut().map(|(xy_normalized, value)| {
-          *value = (*value).min(sdf(xy_normalized));
+    let weight_map = &self.weight_map;
+    self.dist_map.chunks_domain_par_iter_mut(domain, &mut self.chunk_argmax)
+      .for_each(move |(mut chunk, chunk_argmax)| {
+        let weights = weight_map.as_ref().map(|weights| weights.get_chunk(chunk.id));
+        let pixel = |xy: Point2D<u64, PixelSpace>| (xy.to_f32() / chunk.global_size as f32).cast_unit();
+        let mut op = chunk_op(Box2D::new(
+          pixel(chunk.top_left),
+          pixel(chunk.top_left + V2::splat(chunk.size - 1))
+        ));
+        *chunk_argmax = chunk.pixels_mut().enumerate().map(|(i, (xy_normalized, value))| {
+          op(xy_normalized, value);
+          let distance = match &weights {
+            Some(weights) if *value > 0.0 => *value * weights.slice[i],
+            _ => *value
+          };
           DistPoint {
-            distance: *value,
+            distance,
             point: xy_normalized
           }
         }).max()
           .unwrap();
-        self.write_cache(chunk.id, max_dist);
       });
   }
 
-  /// Invert distance field.
-  pub fn invert(&mut self) {
-    use rayon::prelude::*;
+  /// Points of the iso-contour `field = level`, at least `2 level` apart: circles of radius
+  /// `level`, centered at them, touch the shapes inserted so far, and not each other. Crossings
+  /// of the contour are interpolated between neighbouring pixels, and picked greedily, row by
+  /// row. Enables outline stippling, or shapes packed along the edges of others.
+  pub fn maxima_on_contour(&self, level: f32) -> Vec<DistPoint<f32, f32, WorldSpace>> {
+    use {rayon::prelude::*, std::collections::HashMap};
+
+    if level <= 0.0 || level.is_nan() {
+      return vec![];
+    }
+    let resolution = self.dist_map.resolution;
+    let value = &|x: u64, y: u64| self.dist_map.pixel([x, y].into()) - level;
+    let crossings: Vec<Point2D<f32, WorldSpace>> = (0..resolution).into_par_iter()
+      .flat_map_iter(|y| (0..resolution).flat_map(move |x| {
+        let v = value(x, y);
+        [(x + 1, y), (x, y + 1)].into_iter()
+          .filter(move |&(nx, ny)| nx < resolution && ny < resolution)
+          .filter_map(move |(nx, ny)| {
+            let w = value(nx, ny);
+            ((v < 0.0) != (w < 0.0)).then(|| {
+              let t = v / (v - w);
+              Point2D::new(x as f32 + (nx - x) as f32 * t, y as f32 + (ny - y) as f32 * t) / resolution as f32
+            })
+          })
+      }))
+      .collect();
 
-    self.dist_map.chunks_par_iter().for_each(|chunk| {
-      let max_dist = chunk.pixels_mut().map(|(xy_normalized, value)| {
-        *value = -*value;
-        DistPoint {
-          distance: *value,
-          point: xy_normalized
+    // cells of the minimum distance, hence the neighbours are within the adjacent ones
+    let spacing = level * 2.0;
+    let cell_of = |p: Point2D<f32, WorldSpace>| ((p.x / spacing) as i64, (p.y / spacing) as i64);
+    let mut cells: HashMap<(i64, i64), Vec<Point2D<f32, WorldSpace>>> = HashMap::new();
+    crossings.into_iter()
+      .filter(|&p| {
+        let (cx, cy) = cell_of(p);
+        let free = itertools::iproduct!(cx - 1..=cx + 1, cy - 1..=cy + 1)
+          .filter_map(|cell| cells.get(&cell))
+          .flatten()
+          .all(|q| (p - *q).length() >= spacing);
+        if free {
+          cells.entry((cx, cy)).or_default().push(p);
         }
-      }).max()
-        .unwrap();
-      self.write_cache(chunk.id, max_dist);
-    });
+        free
+      })
+      .map(|point| DistPoint { distance: level, point })
+      .collect()
+  }
+
+  /// The largest axis-aligned rectangle within the free region, where the field is positive,
+  /// e.g. for placing images or text boxes without inscribing them into a circle. Made of the
+  /// pixels, whose value exceeds the half of their diagonal, hence free entirely; `None` if
+  /// there are none. Does not wrap around the edges of a toroidal field.
+  pub fn max_empty_rect(&self) -> Option<Rect<f32, WorldSpace>> {
+    let resolution = self.dist_map.resolution;
+    let size = 1.0 / resolution as f32;
+    let margin = size * std::f32::consts::FRAC_1_SQRT_2;
+    let (x, y, width, height) = util::largest_free_rect(resolution as usize, resolution as usize, |x, y|
+      self.dist_map.pixel([x as u64, y as u64].into()) >= margin
+    )?;
+    // the pixel `(x, y)` samples the field at `(x, y) / resolution`, the center of its cell
+    Some(Rect::new(
+      Point2D::new(x as f32 - 0.5, y as f32 - 0.5) * size,
+      Size2D::new(width as f32, height as f32) * size
+    ))
+  }
+
+  /// Place circles at the global maxima, as decided by `placer`, inserting each one into the
+  /// field; ends once the placer rejects a maximum, or the budget of
+  /// [`ArgmaxIter::with_budget`] is spent. `rng_seed` seeds the placer.
+  pub fn place_iter<P: ShapePlacer<f32>>(&mut self, placer: P, rng_seed: u64) -> ArgmaxIter<'_, P> {
+    use rand::SeedableRng;
+    ArgmaxIter::new(self, placer, rand_pcg::Pcg64::seed_from_u64(rng_seed))
+  }
+
+  /// Capture the current state of the field. Chunks are shared with the snapshot, and only
+  /// copied once either of them is modified, so branching a distribution is cheap.
+  pub fn snapshot(&self) -> Snapshot {
+    Snapshot(self.clone())
+  }
+
+  /// Roll back to a previously captured state. The snapshot remains valid, and may be
+  /// restored again.
+  pub fn restore(&mut self, snapshot: &Snapshot) {
+    *self = snapshot.0.clone();
+  }
+
+  /// Copy the distance field into a [`Raster`], whose samples lie at the pixels.
+  /// See [`ADF::from_argmax2d`](crate::solver::ADF::from_argmax2d).
+  pub fn to_raster<T: Float>(&self) -> Raster<T> {
+    let resolution = self.dist_map.resolution;
+    let mut samples = vec![T::zero(); (resolution * resolution) as usize];
+    self.pixels().for_each(|DistPoint { distance, point }|
+      samples[(point.y * resolution + point.x) as usize] = T::from(distance).unwrap()
+    );
+    let size = T::from(resolution - 1).unwrap() / T::from(resolution).unwrap();
+    Raster {
+      rect: Rect::new(Point2D::origin(), Size2D::splat(size)),
+      width: resolution as usize,
+      height: resolution as usize,
+      samples
+    }
   }
 
   /// Read underlying distance field bitmap.
@@ -93,3 +352,20 @@ impl Argmax2D {
     self.dist_map.pixels()
   }
 }
+
+/// The field, interpolated bilinearly between the pixels, as [`Raster::eval`] does, without
+/// the weights of [`Argmax2D::set_weight_map`]; e.g. for [`util::contour`].
+impl SDF<f32> for Argmax2D {
+  fn sdf(&self, p: Point2D<f32, WorldSpace>) -> f32 {
+    let last = (self.dist_map.resolution - 1) as f32;
+    let xy = p.to_vector() * self.dist_map.resolution as f32;
+    let clamped = xy.max(V2::zero()).min(V2::splat(last));
+    let outside = (xy - clamped).length() / self.dist_map.resolution as f32;
+    let (x0, y0) = (clamped.x.floor() as u64, clamped.y.floor() as u64);
+    let (x1, y1) = ((x0 + 1).min(last as u64), (y0 + 1).min(last as u64));
+    let (tx, ty) = (clamped.x.fract(), clamped.y.fract());
+    let at = |x: u64, y: u64| self.dist_map.pixel([x, y].into());
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    lerp(lerp(at(x0, y0), at(x1, y0), tx), lerp(at(x0, y1), at(x1, y1), tx), ty) + outside
+  }
+}
@@ -0,0 +1,76 @@
+use {
+  super::Argmax2D,
+  crate::geometry::{DistPoint, WorldSpace},
+  anyhow::{Result, bail},
+  euclid::{Rect, Point2D, SideOffsets2D}
+};
+
+/// Several classes of shapes, sharing one domain (e.g. "red" and "blue" shapes), with distinct
+/// minimum separations between each pair of classes.
+///
+/// For every class `j` a combined field `min_i(dist_i - separation[i][j])` is maintained, hence
+/// [`MultiArgmax2D::find_max`] reports the largest shape of class `j`, which may be inserted
+/// without violating any separation.
+#[derive(Clone)]
+pub struct MultiArgmax2D {
+  fields: Vec<Argmax2D>,
+  separation: Vec<Vec<f32>>
+}
+
+impl MultiArgmax2D {
+  /// `separation[i][j]` is the minimum gap between shapes of classes `i` and `j`.
+  pub fn new(resolution: u64, chunk_size: u64, separation: Vec<Vec<f32>>) -> Result<Self> {
+    let classes = separation.len();
+    if classes == 0 || separation.iter().any(|row| row.len() != classes) {
+      bail!("separation matrix must be square and non-empty")
+    }
+    Ok(Self {
+      fields: (0..classes)
+        .map(|_| Argmax2D::new(resolution, chunk_size))
+        .collect::<Result<_>>()?,
+      separation
+    })
+  }
+
+  pub fn classes(&self) -> usize {
+    self.fields.len()
+  }
+
+  /// Combined field of `class`.
+  pub fn field(&self, class: usize) -> &Argmax2D {
+    &self.fields[class]
+  }
+
+  /// Insert an obstacle, shared by all classes (such as `sdf::boundary_rect`).
+  pub fn insert_sdf(&mut self, sdf: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send) {
+    self.fields.iter_mut()
+      .for_each(|field| field.insert_sdf(&sdf));
+  }
+
+  /// Insert a shape of `class`.
+  pub fn insert_sdf_domain(
+    &mut self,
+    class: usize,
+    domain: Rect<f32, WorldSpace>,
+    sdf: impl Fn(Point2D<f32, WorldSpace>) -> f32 + Sync + Send
+  ) {
+    let separation = &self.separation[class];
+    self.fields.iter_mut()
+      .zip(separation.iter())
+      .for_each(|(field, &gap)| field.insert_sdf_domain(
+        // the shape reaches further, by the gap
+        domain.outer_rect(SideOffsets2D::new_all_same(gap.max(0.0))),
+        |p| sdf(p) - gap
+      ));
+  }
+
+  /// Largest shape of `class`, that may still be inserted.
+  pub fn find_max(&self, class: usize) -> DistPoint<f32, f32, WorldSpace> {
+    self.fields[class].find_max()
+  }
+
+  /// See [`Argmax2D::find_max_above`].
+  pub fn find_max_above(&self, class: usize, min_dist: f32) -> Option<DistPoint<f32, f32, WorldSpace>> {
+    self.fields[class].find_max_above(min_dist)
+  }
+}
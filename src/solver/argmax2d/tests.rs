@@ -0,0 +1,334 @@
+use {
+  super::*,
+  crate::{
+    geometry::{Circle, Shape},
+    sdf::{self, SDF},
+    util::{self, Budget}
+  },
+  euclid::Vector2D as V2
+};
+
+fn distance_at(argmax: &Argmax2D, xy: [u64; 2]) -> f32 {
+  argmax.dist_map.pixel(xy.into())
+}
+
+#[test] fn toroidal() -> Result<()> {
+  let circle = Circle
+    .translate(V2::new(0.98, 0.5))
+    .scale(0.05);
+  let domain = util::domain_empirical(DistPoint { distance: 0.05, point: [0.98, 0.5].into() });
+
+  let mut plane = Argmax2D::new(64, 8)?;
+  plane.insert_sdf_domain(domain, |p| circle.sdf(p));
+  let mut torus = Argmax2D::new(64, 8)?.with_toroidal(true);
+  torus.insert_sdf_domain(domain, |p| circle.sdf(p));
+
+  // the circle wraps around the right edge
+  assert!(distance_at(&plane, [1, 32]) > 0.5);
+  assert!(distance_at(&torus, [1, 32]) < 0.0);
+  // identical away from the edges
+  assert_eq!(distance_at(&plane, [60, 32]), distance_at(&torus, [60, 32]));
+  Ok(())
+}
+
+#[test] fn boundary() -> Result<()> {
+  let argmax = Argmax2D::new(64, 8)?
+    .with_boundary(Circle.translate(V2::splat(0.5)).scale(0.25));
+  let max = argmax.find_max();
+  assert!(max.point.distance_to([0.5, 0.5].into()) < 1.0 / 32.0);
+  assert!((max.distance - 0.25).abs() < 1.0 / 32.0);
+  assert!(distance_at(&argmax, [2, 2]) < 0.0);
+
+  assert!(argmax.find_max_above(0.2).is_some());
+  assert!(argmax.find_max_above(0.3).is_none());
+  Ok(())
+}
+
+// Parallel insertion must agree with a sequential evaluation of the field. Kept small enough
+// to run under miri (`cargo +nightly miri test argmax2d`).
+#[test] fn parallel_insertion() -> Result<()> {
+  // domains are aligned to chunks (4px), thus pixels outside of them must remain unchanged
+  let circles = [
+    (V2::new(0.3, 0.4), 0.1, Rect::new(Point2D::new(0.125, 0.25), Size2D::new(0.375, 0.25))),
+    (V2::new(0.7, 0.2), 0.05, Rect::new(Point2D::new(0.5, 0.0), Size2D::new(0.5, 0.5))),
+    (V2::new(0.55, 0.8), 0.2, Rect::new(Point2D::new(0.25, 0.5), Size2D::new(0.75, 0.5))),
+  ];
+  let mut argmax = Argmax2D::new(32, 4)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  for (offset, r, domain) in circles {
+    let circle = Circle.translate(offset).scale(r);
+    argmax.insert_sdf_domain(domain, |p| circle.sdf(p));
+  }
+  argmax.invert();
+  argmax.invert();
+
+  let reference = |p: Point2D<f32, WorldSpace>| circles.iter()
+    .filter(|(_, _, domain)| domain.contains(p))
+    .map(|&(offset, r, _)| Circle.translate(offset).scale(r).sdf(p))
+    .fold(sdf::boundary_rect(p), f32::min);
+  let mut max = DistPoint { distance: f32::MIN, point: Point2D::origin() };
+  for DistPoint { distance, point } in argmax.pixels() {
+    let p = (point.cast::<f32>() / 32.0).cast_unit();
+    assert!((distance - reference(p)).abs() < 1e-6, "{point:?}");
+    max = max.max(DistPoint { distance, point: p });
+  }
+  assert_eq!(argmax.find_max().distance, max.distance);
+  Ok(())
+}
+
+#[test] fn weight_map() -> Result<()> {
+  let mut argmax = Argmax2D::new(64, 8)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  argmax.set_weight_map(|p| if p.x < 0.5 { 0.25 } else { 1.0 });
+  let max = argmax.find_max();
+  assert!(max.point.x >= 0.5);
+  assert!(max.distance <= 0.5);
+
+  // dark regions still receive (smaller) maxima
+  argmax.insert_sdf(|p| 0.5 - p.x);
+  let max = argmax.find_max();
+  assert!(max.point.x < 0.5);
+  assert!((max.distance - 0.25 * 0.25).abs() < 1.0 / 32.0);
+
+  argmax.clear_weight_map();
+  assert!((argmax.find_max().distance - 0.25).abs() < 1.0 / 32.0);
+  Ok(())
+}
+
+#[test] fn anisotropic() -> Result<()> {
+  use crate::geometry::Metric;
+
+  let tensor = Metric::tensor([[1.0f32, 0.5], [0.5, 2.0]]);
+  let v = V2::new(0.3, -0.7);
+  let quadratic = v.x * v.x + 2.0 * 0.5 * v.x * v.y + 2.0 * v.y * v.y;
+  assert!((tensor.length(v) - quadratic.sqrt()).abs() < 1e-6);
+  assert!((tensor.from_metric(tensor.to_metric(v)) - v).length() < 1e-6);
+
+  // distances along y count double, so a metric circle is an ellipse wide along x
+  let metric = Metric::axes(1.0, 2.0);
+  let mut argmax = Argmax2D::new(64, 8)?;
+  argmax.insert_sdf(|p| metric.boundary_rect(p));
+  assert!((argmax.find_max().distance - 0.5).abs() < 1.0 / 32.0);
+
+  let max = DistPoint { distance: 0.4, point: [0.5, 0.5].into() };
+  let ellipse = Circle
+    .translate(metric.to_metric(max.point.to_vector()))
+    .scale(max.distance)
+    .anisotropic(metric);
+  let domain = util::domain_empirical_metric(max, metric);
+  argmax.insert_sdf_domain(domain, |p| ellipse.sdf(p));
+
+  assert!(distance_at(&argmax, [32 + 20, 32]) < 0.0);
+  assert!(distance_at(&argmax, [32, 32 + 20]) > 0.0);
+  Ok(())
+}
+
+#[test] fn snapshot() -> Result<()> {
+  let mut argmax = Argmax2D::new(64, 8)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  let snapshot = argmax.snapshot();
+  let before: Vec<_> = argmax.pixels().collect();
+
+  let circle = Circle.translate(V2::splat(0.25)).scale(0.1);
+  argmax.insert_sdf_domain(
+    util::domain_empirical(DistPoint { distance: 0.1, point: [0.25, 0.25].into() }),
+    |p| circle.sdf(p)
+  );
+  assert!(distance_at(&argmax, [16, 16]) < 0.0);
+
+  argmax.restore(&snapshot);
+  assert!(argmax.pixels().eq(before.iter().copied()));
+  assert_eq!(argmax.find_max(), snapshot.0.find_max());
+  Ok(())
+}
+
+#[test] fn chunk_order() -> Result<()> {
+  use {z_order_storage::ChunkOrder, rayon::prelude::*};
+
+  let domain = Rect::new(Point2D::splat(0.0), Size2D::splat(1.0));
+  let storage = ZOrderStorage::new(64, 8, 0.0f32)?;
+  for chunk_order in [ChunkOrder::RowMajor, ChunkOrder::Morton, ChunkOrder::Hilbert] {
+    let chunks: Vec<_> = storage.clone().with_chunk_order(chunk_order)
+      .chunks_domain_par_iter(domain).collect();
+    assert_eq!(chunks.len(), 64);
+    assert!(chunks.windows(2).all(|w| chunk_order.key(w[0]) < chunk_order.key(w[1])));
+    if chunk_order == ChunkOrder::Hilbert {
+      // consecutive chunks are always adjacent
+      assert!(chunks.windows(2).all(|w| (w[0].to_i64() - w[1].to_i64()).abs().to_array().iter().sum::<i64>() == 1));
+    }
+
+    // chunks [2, 6) x [1, 5) of a partial domain, through either iterator
+    let mut storage = storage.clone().with_chunk_order(chunk_order);
+    let domain = Rect::new(Point2D::new(0.3, 0.15), Size2D::new(0.4, 0.45));
+    let chunks: Vec<_> = storage.chunks_domain_par_iter(domain).collect();
+    let mut per_chunk = vec![(); 64];
+    let chunks_mut: Vec<_> = storage.chunks_domain_par_iter_mut(domain, &mut per_chunk)
+      .map(|(chunk, _)| chunk.top_left / 8).collect();
+    assert_eq!(chunks, chunks_mut);
+    assert!(chunks.windows(2).all(|w| chunk_order.key(w[0]) < chunk_order.key(w[1])));
+    let mut expected: Vec<_> = itertools::iproduct!(2..6, 1..5).map(|(x, y)| Point2D::new(x, y)).collect();
+    expected.sort_by_key(|&xy| chunk_order.key(xy));
+    assert_eq!(chunks, expected);
+  }
+
+  // the order does not affect the result
+  let circle = Circle.translate(V2::splat(0.5)).scale(0.25);
+  let [a, b] = [ChunkOrder::RowMajor, ChunkOrder::Hilbert].map(|chunk_order| {
+    let mut argmax = Argmax2D::new(64, 8).unwrap().with_chunk_order(chunk_order);
+    argmax.insert_sdf_domain(domain, |p| circle.sdf(p));
+    argmax
+  });
+  assert!(a.pixels().eq(b.pixels()));
+  assert_eq!(a.find_max(), b.find_max());
+
+  // reordering a field with content
+  let mut argmax = Argmax2D::new(64, 8)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  let mut argmax = argmax.with_chunk_order(ChunkOrder::Hilbert);
+  argmax.insert_sdf_domain(Rect::new(Point2D::new(0.5, 0.0), Size2D::new(0.5, 1.0)), |p| 0.5 - p.x);
+  let max = argmax.find_max();
+  assert!(max.point.x < 0.5 && (max.distance - argmax.pixels().map(|p| p.distance).fold(0.0, f32::max)).abs() < 1e-6, "{max:?}");
+  Ok(())
+}
+
+#[test] fn multi_class() -> Result<()> {
+  // red shapes keep 0.1 away from blue ones, but may touch each other
+  let separation = vec![vec![0.0, 0.1], vec![0.1, 0.0]];
+  assert!(MultiArgmax2D::new(64, 8, vec![vec![0.0, 0.1]]).is_err());
+  let mut multi = MultiArgmax2D::new(64, 8, separation)?;
+  multi.insert_sdf(sdf::boundary_rect);
+
+  let circle = Circle.translate(V2::splat(0.5)).scale(0.2);
+  let domain = util::domain_empirical(DistPoint { distance: 0.2, point: [0.5, 0.5].into() });
+  multi.insert_sdf_domain(0, domain, |p| circle.sdf(p));
+
+  let [red, blue] = [0, 1].map(|class| distance_at(multi.field(class), [32, 4]));
+  // 0.5 - 1/16 - 0.2 away from the red circle, bounded by the edge
+  assert!((red - 1.0 / 16.0).abs() < 1e-6);
+  assert!((blue - 1.0 / 16.0).abs() < 1e-6);
+  let [red, blue] = [0, 1].map(|class| distance_at(multi.field(class), [32, 20]));
+  assert!((red - (0.5 - 20.0 / 64.0 - 0.2)).abs() < 1e-6);
+  assert!((blue - (0.5 - 20.0 / 64.0 - 0.3)).abs() < 1e-6);
+  assert!(multi.find_max(1).distance <= multi.find_max(0).distance);
+  Ok(())
+}
+
+#[test] fn from_shapes() -> Result<()> {
+  let circles: Vec<_> = (0..40)
+    .map(|i| Circle.scale(0.01 + 0.002 * (i % 7) as f32).translate(V2::new((i * 37 % 40) as f32 / 40.0, i as f32 / 40.0)))
+    .collect();
+  let mut sequential = Argmax2D::new(64, 8)?;
+  circles.iter().for_each(|circle| sequential.insert_sdf(|p| circle.sdf(p)));
+  let bulk = Argmax2D::from_shapes(64, 8, circles.clone())?;
+  assert!(bulk.pixels().eq(sequential.pixels()));
+  assert_eq!(bulk.find_max(), sequential.find_max());
+
+  // periodic images, and a field inserted beforehand
+  let mut sequential = Argmax2D::new(64, 8)?.with_toroidal(true);
+  let mut bulk = sequential.clone();
+  [&mut sequential, &mut bulk].into_iter().for_each(|argmax| argmax.insert_sdf(sdf::boundary_rect));
+  circles.iter().for_each(|circle| sequential.insert_sdf_domain(util::changed_domain(circle, 1.0), |p| circle.sdf(p)));
+  bulk.insert_shapes(circles);
+  assert!(bulk.pixels().eq(sequential.pixels()));
+  Ok(())
+}
+
+#[test] fn shape_index() -> Result<()> {
+  let circles: Vec<_> = (0..4).map(|i| Circle.scale(0.05).translate(V2::new(0.2 * i as f32 + 0.1, 0.5))).collect();
+  let mut argmax = Argmax2D::new(64, 8)?.with_shape_index(util::ShapeIndex::new(0.1));
+  argmax.insert_shapes(circles[..2].to_vec());
+  circles[2..].iter().for_each(|&circle| argmax.insert_shape_domain(util::changed_domain(&circle, 1.0), circle));
+  let index = argmax.shape_index().unwrap();
+  assert_eq!(index.len(), 4);
+  assert_eq!(index.nearest_shape([0.45, 0.5].into()).map(|(id, _)| id), Some(2));
+  assert!(Argmax2D::from_shapes(64, 8, circles)?.shape_index().is_none());
+  Ok(())
+}
+
+#[test] fn maxima_on_contour() -> Result<()> {
+  let circle = Circle.scale(0.2).translate(V2::splat(0.5));
+  let mut argmax = Argmax2D::new(256, 16)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  argmax.insert_sdf(|p| circle.sdf(p));
+  let exact = |p: Point2D<f32, WorldSpace>| sdf::boundary_rect(p).min(circle.sdf(p));
+
+  let level = 0.02;
+  let maxima = argmax.maxima_on_contour(level);
+  assert!(maxima.iter().all(|c| c.distance == level && (exact(c.point) - level).abs() < 1.0 / 256.0));
+  assert!(maxima.iter().enumerate()
+    .all(|(i, a)| maxima[i + 1..].iter().all(|b| (a.point - b.point).length() >= 2.0 * level)));
+  // around the circle, of a circumference of 2π 0.22, and the edges of the boundary
+  let around = maxima.iter().filter(|c| (c.point - Point2D::splat(0.5)).length() < 0.3).count();
+  assert!((25..=35).contains(&around), "{around}");
+  assert!(maxima.len() - around > 70);
+  assert!(argmax.maxima_on_contour(0.0).is_empty());
+  Ok(())
+}
+
+#[test] fn max_empty_rect() -> Result<()> {
+  let circle = Circle.scale(0.2).translate(V2::new(0.3, 0.5));
+  let mut argmax = Argmax2D::new(256, 16)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  argmax.insert_sdf(|p| circle.sdf(p));
+  // the strip to the right of the circle
+  let rect = argmax.max_empty_rect().unwrap();
+  assert!(rect.area() > 0.48 && rect.min_x() >= 0.5, "{rect:?}");
+  let inside = itertools::iproduct!(0..=32, 0..=32)
+    .map(|(x, y)| rect.origin + rect.size.to_vector().component_mul(V2::new(x as f32, y as f32) / 32.0))
+    .all(|p| sdf::boundary_rect(p) >= -1e-6 && circle.sdf(p) >= -1e-6);
+  assert!(inside);
+  argmax.insert_sdf(|_| -1.0);
+  assert!(argmax.max_empty_rect().is_none());
+  Ok(())
+}
+
+#[test] fn contour() -> Result<()> {
+  let circle = Circle.scale(0.2).translate(V2::splat(0.5));
+  let mut argmax = Argmax2D::new(128, 16)?;
+  argmax.insert_sdf(|p| circle.sdf(p));
+  assert!((argmax.sdf(Point2D::new(0.5, 0.1)) - 0.2).abs() < 1e-6);
+  let contours = util::contour(&argmax, 0.05, 64);
+  assert_eq!(contours.len(), 1);
+  assert!(contours[0].closed);
+  assert!(contours[0].vertices.iter().all(|p| ((*p - Point2D::splat(0.5)).length() - 0.25).abs() < 1e-3));
+  Ok(())
+}
+
+#[test] fn place_iter() -> Result<()> {
+  use crate::solver::{Concentric, FixedRadius};
+
+  let mut argmax = Argmax2D::new(256, 16)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  let circles: Vec<_> = argmax.place_iter(Concentric { ratio: 0.5 }, 0).take(100)
+    .map(|placed| DistPoint { distance: placed.radius, point: placed.center })
+    .collect();
+  assert_eq!(circles.len(), 100);
+  assert!((circles[0].distance - 0.25).abs() < 1e-2);
+  assert!(util::validate_no_overlap(circles.iter().copied(), 1e-6).is_empty());
+  // ends once there is no room left
+  let mut argmax = Argmax2D::new(256, 16)?;
+  argmax.insert_sdf(sdf::boundary_rect);
+  let count = argmax.place_iter(FixedRadius { radius: 0.1 }, 0).count();
+  assert!((4..=25).contains(&count), "{count}");
+  assert!(argmax.find_max().distance < 0.1);
+  Ok(())
+}
+
+#[test] fn place_iter_budget() -> Result<()> {
+  use {crate::solver::RandomInscribed, std::time::Duration};
+
+  let run = |budget| -> Result<(Vec<_>, u64)> {
+    let mut argmax = Argmax2D::new(256, 16)?;
+    argmax.insert_sdf(sdf::boundary_rect);
+    let mut placement = argmax.place_iter(RandomInscribed { max_radius: 0.05 }, 0).with_budget(budget);
+    let placed: Vec<_> = placement.by_ref().collect();
+    Ok((placed, placement.sdf_evals()))
+  };
+  let (placed, evals) = run(Budget::SdfEvals(100_000))?;
+  assert!(!placed.is_empty() && evals >= 100_000);
+  // checked before each placement, the last one exceeding the budget
+  assert_eq!(run(Budget::SdfEvals(evals - 1))?.0, placed);
+  assert_eq!(run(Budget::SdfEvals(100_000))?.0, placed);
+  assert!(run(Budget::Time(Duration::ZERO))?.0.is_empty());
+  Ok(())
+}
@@ -2,64 +2,183 @@ use {
   crate::{
     geometry::{DistPoint, WorldSpace, PixelSpace}
   },
-  euclid::{Point2D, Rect, Box2D},
-  rayon::iter::ParallelIterator,
+  euclid::{Point2D, Rect, Box2D, Vector2D as V2},
+  rayon::iter::{ParallelIterator, IndexedParallelIterator},
   anyhow::{Result, bail},
+  std::sync::Arc
 };
 use num_traits::{NumCast, Float};
 
+/// Chunked square bitmap, each chunk of which is a `T`.
+/// Chunks are reference counted and copied on write, hence cloning the storage is cheap.
+/// They are stored in [`ChunkOrder`], and addressed by their row-major id.
+#[derive(Clone)]
 pub struct ZOrderStorage<T> {
-  data: T,
+  data: Vec<Arc<T>>,
+  // id of the chunk at each position of `data`, and the position of each id;
+  // both empty in row-major order
+  ids: Arc<[u64]>,
+  positions: Arc<[u64]>,
   pub resolution: u64,
   pub chunk_size: u64,
+  chunk_order: ChunkOrder
+}
+
+/// Order of visiting chunks within a domain. Space-filling curves keep consecutive chunks
+/// close to each other, which may improve memory locality when a domain spans many chunks;
+/// see `benches/chunk_order.rs`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ChunkOrder {
+  #[default]
+  RowMajor,
+  /// Z-order curve
+  Morton,
+  Hilbert
+}
+
+impl ChunkOrder {
+  /// Position of chunk `xy` along the curve.
+  pub fn key(self, xy: Point2D<u64, PixelSpace>) -> u64 {
+    match self {
+      ChunkOrder::RowMajor => xy.y << 32 | xy.x,
+      ChunkOrder::Morton => morton_key(xy),
+      ChunkOrder::Hilbert => hilbert_key(xy)
+    }
+  }
+}
+
+fn morton_key(xy: Point2D<u64, PixelSpace>) -> u64 {
+  fn spread(mut v: u64) -> u64 {
+    v &= 0xFFFF_FFFF;
+    v = (v | v << 16) & 0x0000_FFFF_0000_FFFF;
+    v = (v | v << 8) & 0x00FF_00FF_00FF_00FF;
+    v = (v | v << 4) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | v << 2) & 0x3333_3333_3333_3333;
+    (v | v << 1) & 0x5555_5555_5555_5555
+  }
+  spread(xy.x) | spread(xy.y) << 1
+}
+
+fn hilbert_key(xy: Point2D<u64, PixelSpace>) -> u64 {
+  // supports up to 2^16 chunks per side
+  let (mut x, mut y) = (xy.x, xy.y);
+  let mut key = 0;
+  let mut s = 1u64 << 15;
+  while s > 0 {
+    let rx = (x & s > 0) as u64;
+    let ry = (y & s > 0) as u64;
+    key += s * s * ((3 * rx) ^ ry);
+    // rotate the quadrant
+    if ry == 0 {
+      if rx == 1 {
+        x = s - 1 - (x & (s - 1));
+        y = s - 1 - (y & (s - 1));
+      }
+      std::mem::swap(&mut x, &mut y);
+    }
+    s >>= 1;
+  }
+  key
 }
 
 impl <T> ZOrderStorage<T> {
+  /// Rearrange the chunks in `chunk_order`.
+  pub fn with_chunk_order(mut self, chunk_order: ChunkOrder) -> Self {
+    let chunks_per_row = self.resolution / self.chunk_size;
+    let mut ids: Vec<u64> = (0..self.chunk_count()).collect();
+    ids.sort_by_cached_key(|&id| chunk_order.key(offset_to_xy(id, chunks_per_row)));
+    let mut positions = vec![0; ids.len()];
+    ids.iter().enumerate().for_each(|(position, &id)| positions[id as usize] = position as u64);
+    let mut data: Vec<Option<Arc<T>>> = std::mem::take(&mut self.data).into_iter().map(Some).collect();
+    self.data = ids.iter()
+      .map(|&id| data[self.position(id) as usize].take().unwrap())
+      .collect();
+    (self.ids, self.positions) = match chunk_order {
+      ChunkOrder::RowMajor => Default::default(),
+      _ => (ids.into(), positions.into())
+    };
+    self.chunk_order = chunk_order;
+    self
+  }
+
+  pub fn chunk_order(&self) -> ChunkOrder {
+    self.chunk_order
+  }
+
   pub fn chunk_count(&self) -> u64 {
     (self.resolution / self.chunk_size).pow(2)
   }
 
-  pub fn chunks_domain_par_iter<P>(&self, domain: Rect<P, WorldSpace>)
-    -> impl ParallelIterator<Item = Point2D<u64, PixelSpace>>
-    where P: NumCast + Copy {
-    use rayon::prelude::*;
+  // position of chunk `id` within `data`
+  fn position(&self, id: u64) -> u64 {
+    if self.positions.is_empty() { id } else { self.positions[id as usize] }
+  }
 
+  /// Range of chunks (in chunk coordinates), covering `domain`.
+  fn chunk_span<P>(&self, domain: Rect<P, WorldSpace>) -> Box2D<u64, PixelSpace>
+    where P: NumCast + Copy {
     let domain = domain.cast::<f64>().to_box2d().intersection_unchecked(
       &Box2D::new(
         Point2D::splat(0.0),
         Point2D::splat(1.0)
       )
     ) * self.resolution as f64;
-    let chunk_span = (domain / self.chunk_size as f64)
+    (domain / self.chunk_size as f64)
       .round_out()
-      .cast::<u64>();
+      .cast::<u64>()
+      .cast_unit()
+  }
 
-    (chunk_span.min.y .. chunk_span.max.y)
-      .into_par_iter()
-      .flat_map(move |chunk_y|
-        (chunk_span.min.x .. chunk_span.max.x)
-          .into_par_iter().map(move |chunk_x| [chunk_x, chunk_y].into())
-      )
+  // Positions within `data`, enclosing every chunk of `span`. The first and the last chunk of
+  // a span lie on its boundary in every `ChunkOrder`: row-major and Morton keys grow along
+  // both axes, and consecutive chunks of the Hilbert curve are adjacent.
+  fn positions_span(&self, span: Box2D<u64, PixelSpace>) -> std::ops::Range<u64> {
+    if span.is_empty() {
+      return 0..0;
+    }
+    let chunks_per_row = self.resolution / self.chunk_size;
+    let (min, max) = (span.min, span.max - V2::splat(1));
+    let rows = [min.y, max.y].into_iter().flat_map(|y| (min.x ..= max.x).map(move |x| (x, y)));
+    let columns = [min.x, max.x].into_iter().flat_map(|x| (min.y ..= max.y).map(move |y| (x, y)));
+    let (first, last) = rows.chain(columns)
+      .map(|(x, y)| self.position(xy_to_offset([x, y].into(), chunks_per_row)))
+      .fold((u64::MAX, 0), |(first, last), position| (first.min(position), last.max(position)));
+    first .. last + 1
+  }
+
+  /// Chunks (in chunk coordinates) covering `domain`, in [`ChunkOrder`].
+  pub fn chunks_domain_par_iter<P>(&self, domain: Rect<P, WorldSpace>)
+    -> impl ParallelIterator<Item = Point2D<u64, PixelSpace>>
+    where P: NumCast + Copy {
+    use rayon::prelude::*;
+
+    let chunk_span = self.chunk_span(domain);
+    let (chunks_per_row, ids) = (self.resolution / self.chunk_size, self.ids.clone());
+    self.positions_span(chunk_span).into_par_iter()
+      .map(move |position| offset_to_xy(id_at(&ids, position), chunks_per_row))
+      .filter(move |xy| chunk_span.contains(*xy))
   }
 }
 
 impl <T: Clone> ZOrderStorage<Vec<T>> {
   pub fn new(resolution: u64, chunk_size: u64, default: T) -> Result<Self> {
-    if resolution % chunk_size != 0 {
+    if !resolution.is_multiple_of(chunk_size) {
       bail!("distance map resolution is not divisible by the chunk resolution")
     };
-    let chunk_area = resolution.pow(2);
+    let chunk = Arc::new(vec![default; chunk_size.pow(2) as usize]);
     Ok(Self {
-      data: vec![default; chunk_area as usize],
+      data: vec![chunk; (resolution / chunk_size).pow(2) as usize],
+      ids: Default::default(),
+      positions: Default::default(),
       resolution,
-      chunk_size
+      chunk_size,
+      chunk_order: ChunkOrder::default()
     })
   }
 
-  pub fn get_chunk(&self, id: u64) -> Chunk<T> {
-    let chunk_area = self.chunk_size.pow(2);
+  pub fn get_chunk(&self, id: u64) -> Chunk<'_, T> {
     Chunk {
-      slice: &self.data[(chunk_area * id) as usize .. (chunk_area * (id + 1)) as usize],
+      slice: &self.data[self.position(id) as usize][..],
       top_left: offset_to_xy(id, self.resolution / self.chunk_size) * self.chunk_size,
       id,
       size: self.chunk_size,
@@ -67,11 +186,11 @@ impl <T: Clone> ZOrderStorage<Vec<T>> {
     }
   }
 
-  pub fn get_chunk_xy(&self, xy: Point2D<u64, PixelSpace>) -> Chunk<T> {
+  pub fn get_chunk_xy(&self, xy: Point2D<u64, PixelSpace>) -> Chunk<'_, T> {
     self.get_chunk(xy_to_offset(xy, self.resolution / self.chunk_size))
   }
 
-  pub fn chunks(&self) -> impl Iterator<Item = Chunk<T>> {
+  pub fn chunks(&self) -> impl Iterator<Item = Chunk<'_, T>> {
     let chunk_count = (self.resolution / self.chunk_size).pow(2);
     (0..chunk_count).map(move |id| self.get_chunk(id))
   }
@@ -96,13 +215,55 @@ impl <T: Clone> ZOrderStorage<Vec<T>> {
 }
 
 impl<T> ZOrderStorage<Vec<T>> where T: Clone + Send + Sync {
-  pub fn chunks_par_iter(&self) -> impl ParallelIterator<Item = Chunk<T>> {
+  pub fn chunks_par_iter(&self) -> impl ParallelIterator<Item = Chunk<'_, T>> {
     use rayon::prelude::*;
 
     let chunk_count = (self.resolution / self.chunk_size).pow(2);
     (0..chunk_count).into_par_iter()
       .map(move |id| self.get_chunk(id))
   }
+
+  /// Split the storage into disjoint mutable chunks, each paired with its own slot of
+  /// `per_chunk` (one element per chunk, in [`ChunkOrder`]).
+  pub fn chunks_par_iter_mut<'a, C: Send>(&'a mut self, per_chunk: &'a mut [C])
+    -> impl IndexedParallelIterator<Item = (ChunkMut<'a, T>, &'a mut C)> {
+    use rayon::prelude::*;
+
+    assert_eq!(per_chunk.len() as u64, self.chunk_count());
+    let (resolution, chunk_size, ids) = (self.resolution, self.chunk_size, self.ids.clone());
+    self.data.par_iter_mut()
+      .zip(per_chunk.par_iter_mut())
+      .enumerate()
+      .map(move |(position, (chunk, slot))| {
+        let id = id_at(&ids, position as u64);
+        (ChunkMut::new(Arc::<Vec<T>>::make_mut(chunk), id, chunk_size, resolution), slot)
+      })
+  }
+
+  /// Same as [`Self::chunks_par_iter_mut`], but only yields chunks intersecting with `domain`,
+  /// in [`ChunkOrder`].
+  pub fn chunks_domain_par_iter_mut<'a, P, C: Send>(&'a mut self, domain: Rect<P, WorldSpace>, per_chunk: &'a mut [C])
+    -> impl ParallelIterator<Item = (ChunkMut<'a, T>, &'a mut C)>
+    where P: NumCast + Copy {
+    use rayon::prelude::*;
+
+    assert_eq!(per_chunk.len() as u64, self.chunk_count());
+    let chunk_span = self.chunk_span(domain);
+    let positions = self.positions_span(chunk_span);
+    let (resolution, chunk_size, ids) = (self.resolution, self.chunk_size, self.ids.clone());
+    let chunks_per_row = resolution / chunk_size;
+    let range = positions.start as usize .. positions.end as usize;
+
+    // the chunks of the span are scattered over a contiguous range of positions
+    self.data[range.clone()].par_iter_mut()
+      .zip(per_chunk[range].par_iter_mut())
+      .enumerate()
+      .map(move |(i, (chunk, slot))| (id_at(&ids, positions.start + i as u64), chunk, slot))
+      .filter(move |(id, ..)| chunk_span.contains(offset_to_xy(*id, chunks_per_row)))
+      .map(move |(id, chunk, slot)|
+        (ChunkMut::new(Arc::<Vec<T>>::make_mut(chunk), id, chunk_size, resolution), slot)
+      )
+  }
 }
 
 pub struct Chunk<'a, T> {
@@ -113,23 +274,53 @@ pub struct Chunk<'a, T> {
   pub global_size: u64
 }
 
-impl<'a, T> Chunk<'a, T> {
-  fn offset_to_xy_normalized<P: Float>(&self, offset: u64) -> Point2D<P, WorldSpace> {
-    let xy = offset_to_xy(offset, self.size) + self.top_left.to_vector();
-    (xy.cast::<P>() / P::from(self.global_size).unwrap()).cast_unit()
+/// Exclusive view of a single chunk.
+pub struct ChunkMut<'a, T> {
+  pub slice: &'a mut [T],
+  pub top_left: Point2D<u64, PixelSpace>,
+  pub id: u64,
+  pub size: u64,
+  pub global_size: u64
+}
+
+impl<'a, T> ChunkMut<'a, T> {
+  fn new(slice: &'a mut [T], id: u64, size: u64, global_size: u64) -> Self {
+    Self {
+      slice,
+      top_left: offset_to_xy(id, global_size / size) * size,
+      id,
+      size,
+      global_size
+    }
   }
 
-  pub(crate) fn pixels_mut<P: Float>(&self) -> impl Iterator<Item = (Point2D<P, WorldSpace>, &mut T)> {
-    unsafe { std::slice::from_raw_parts_mut(self.slice.as_ptr() as *mut T, self.slice.len()) }
+  pub fn pixels_mut<P: Float>(&mut self) -> impl Iterator<Item = (Point2D<P, WorldSpace>, &mut T)> {
+    let (top_left, size, global_size) = (self.top_left, self.size, self.global_size);
+    self.slice
       .iter_mut()
       .enumerate()
       .map(move |(i, value)| (
-        self.offset_to_xy_normalized(i as u64),
+        offset_to_xy_normalized(i as u64, top_left, size, global_size),
         value
       ))
   }
 }
 
+// chunk at `position` within the data, given the `ids` of a storage
+fn id_at(ids: &[u64], position: u64) -> u64 {
+  if ids.is_empty() { position } else { ids[position as usize] }
+}
+
+fn offset_to_xy_normalized<P: Float>(
+  offset: u64,
+  top_left: Point2D<u64, PixelSpace>,
+  size: u64,
+  global_size: u64
+) -> Point2D<P, WorldSpace> {
+  let xy = offset_to_xy(offset, size) + top_left.to_vector();
+  (xy.cast::<P>() / P::from(global_size).unwrap()).cast_unit()
+}
+
 fn offset_to_xy(offset: u64, width: u64) -> Point2D<u64, PixelSpace> {
   [ offset % width,
     offset / width,
@@ -1,4 +1,5 @@
-//! Implements Gradient Descent optimizer with exponential decay factor.
+//! Implements Gradient Descent optimizer with exponential decay factor, and its momentum-based
+//! variants, see [`Ascent`].
 
 #![allow(dead_code)]
 #![allow(non_snake_case)]
@@ -12,12 +13,110 @@ use {
 };
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineSearch<P> {
   /// Delta for calculating partial derivatives
   pub Δ: P,
   pub initial_step_size: P,
   pub decay_factor: P,
   pub step_limit: Option<u64>,
+  pub ascent: Ascent<P>,
+  /// Choose the step size by backtracking, instead of the geometric decay, see [`Armijo`]
+  pub armijo: Option<Armijo<P>>,
+}
+
+/// Backtracking line search: starting from the previous step size (grown by `1 / shrink`,
+/// initially `initial_step_size`), the step is shrunk by `shrink` until it increases `f` by at
+/// least `c * step * |grad|²`. Takes few evaluations of `f` per step, and far fewer steps than
+/// the geometric decay. `decay_factor` and [`Ascent`] are ignored in this mode, as well as by
+/// [`LineSearch::optimize_grad`]. `step_limit` counts every trial step, and the search stops
+/// where the gradient is not finite.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Armijo<P> {
+  pub c: P,
+  pub shrink: P,
+}
+
+impl <P: Float> Default for Armijo<P> {
+  fn default() -> Self {
+    Self {
+      c: P::from(1e-4).unwrap(),
+      shrink: P::from(0.5).unwrap(),
+    }}}
+
+/// Strategy of a step of [`LineSearch`], scaled by the decayed step size in every case.
+/// The adaptive ones converge faster on long thin ridges, e.g. those of elongated shapes.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ascent<P> {
+  /// Plain gradient ascent
+  #[default]
+  Gradient,
+  /// Accumulates the velocity, decayed by `beta` each step
+  Momentum { beta: P },
+  /// Normalizes the gradient per axis by its running RMS, decayed by `beta`
+  RMSProp { beta: P, epsilon: P },
+  /// Momentum and RMSProp combined, with bias correction
+  Adam { beta1: P, beta2: P, epsilon: P },
+}
+
+impl<P: Float> Ascent<P> {
+  pub fn momentum() -> Self {
+    Self::Momentum { beta: P::from(0.9).unwrap() }
+  }
+
+  pub fn rmsprop() -> Self {
+    Self::RMSProp { beta: P::from(0.9).unwrap(), epsilon: P::from(1e-8).unwrap() }
+  }
+
+  pub fn adam() -> Self {
+    Self::Adam {
+      beta1: P::from(0.9).unwrap(),
+      beta2: P::from(0.999).unwrap(),
+      epsilon: P::from(1e-8).unwrap()
+    }
+  }
+}
+
+// moments accumulated over the steps of a single search
+struct AscentState<P> {
+  first: V2<P, WorldSpace>,
+  second: V2<P, WorldSpace>,
+  t: i32,
+}
+
+impl<P: Float> AscentState<P> {
+  fn new() -> Self {
+    Self { first: V2::zero(), second: V2::zero(), t: 0 }
+  }
+
+  fn step(&mut self, ascent: Ascent<P>, grad: V2<P, WorldSpace>) -> V2<P, WorldSpace> {
+    let one = P::one();
+    let ema = |acc: V2<P, WorldSpace>, x: V2<P, WorldSpace>, beta: P| acc * beta + x * (one - beta);
+    let sq = |v: V2<P, WorldSpace>| v.component_mul(v);
+    let div_rms = |v: V2<P, WorldSpace>, s: V2<P, WorldSpace>, epsilon: P|
+      V2::new(v.x / (s.x.sqrt() + epsilon), v.y / (s.y.sqrt() + epsilon));
+    self.t += 1;
+    match ascent {
+      Ascent::Gradient => grad,
+      Ascent::Momentum { beta } => {
+        self.first = self.first * beta + grad;
+        self.first
+      },
+      Ascent::RMSProp { beta, epsilon } => {
+        self.second = ema(self.second, sq(grad), beta);
+        div_rms(grad, self.second, epsilon)
+      },
+      Ascent::Adam { beta1, beta2, epsilon } => {
+        self.first = ema(self.first, grad, beta1);
+        self.second = ema(self.second, sq(grad), beta2);
+        let first = self.first / (one - beta1.powi(self.t));
+        let second = self.second / (one - beta2.powi(self.t));
+        div_rms(first, second, epsilon)
+      }
+    }
+  }
 }
 
 impl <P: Float> Default for LineSearch<P> {
@@ -27,8 +126,53 @@ impl <P: Float> Default for LineSearch<P> {
       initial_step_size: P::one(),
       decay_factor: P::from(0.85).unwrap(),
       step_limit: None,
+      ascent: Ascent::Gradient,
+      armijo: None,
     }}}
 
+/// Why a local search has stopped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stop {
+  /// The steps (or the simplex) have shrunk below the tolerance
+  Converged,
+  /// `step_limit` was reached first
+  StepLimit,
+}
+
+/// Outcome of a local search, see [`Optimizer::maximize_diagnostics`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Convergence<P> {
+  pub point: P2<P>,
+  pub iterations: u64,
+  /// Norm of the last sampled gradient, `None` for derivative-free optimizers
+  pub gradient_norm: Option<P>,
+  pub stop: Stop,
+}
+
+/// Local maximizer, used by [`util::find_max_parallel`](crate::util::find_max_parallel).
+pub trait Optimizer<P> {
+  /// Find a local maxima of `f`, using `p` as an initial location.
+  fn maximize(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> P2<P> {
+    self.maximize_diagnostics(f, p).point
+  }
+  /// Same as [`Optimizer::maximize`], also reporting how the search went.
+  fn maximize_diagnostics(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> Convergence<P>;
+  /// Precision of the search; maxima with lower values are discarded.
+  fn tolerance(&self) -> P;
+}
+
+impl<P: Float> Optimizer<P> for LineSearch<P> {
+  fn maximize_diagnostics(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> Convergence<P> {
+    self.optimize_diagnostics(f, p)
+  }
+
+  fn tolerance(&self) -> P {
+    self.Δ
+  }
+}
+
 impl<P: Float> LineSearch<P> {
   /// Sample gradient of `f` at `p`.
   pub fn grad(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> V2<P, WorldSpace> {
@@ -40,15 +184,81 @@ impl<P: Float> LineSearch<P> {
   }
 
   /// Find a local maxima of `f`, using `p` as an initial location.
-  pub fn optimize(&self, f: impl Fn(P2<P>) -> P, mut p: P2<P>) -> P2<P> {
+  pub fn optimize(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> P2<P> {
+    self.optimize_diagnostics(f, p).point
+  }
+
+  /// Same as [`LineSearch::optimize`], also reporting how the search went.
+  pub fn optimize_diagnostics(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> Convergence<P> {
+    match self.armijo {
+      Some(armijo) => self.optimize_armijo(f, armijo, p),
+      None => self.optimize_grad_diagnostics(|p| self.grad(&f, p), p)
+    }
+  }
+
+  fn optimize_armijo(&self, f: impl Fn(P2<P>) -> P, armijo: Armijo<P>, mut p: P2<P>) -> Convergence<P> {
     let mut step_size = self.initial_step_size;
-    for _ in 0..self.step_limit.unwrap_or(u64::MAX) {
-      let grad = self.grad(&f, p) * step_size;
-      if grad.length() < self.Δ { break; }
+    let mut fp = f(p);
+    let mut gradient_norm = None;
+    let step_limit = self.step_limit.unwrap_or(u64::MAX);
+    let converged = |p, iterations, gradient_norm| Convergence { point: p, iterations, gradient_norm, stop: Stop::Converged };
+    // every trial step counts against the limit, the backtracking ones included
+    let mut i = 0;
+    while i < step_limit {
+      let grad = V2::new(
+        f(p + V2::new(self.Δ, P::zero())) - fp,
+        f(p + V2::new(P::zero(), self.Δ)) - fp,
+      ) / self.Δ;
+      let grad_sq = grad.square_length();
+      gradient_norm = Some(grad_sq.sqrt());
+      if !grad_sq.is_finite() {
+        // no direction to follow
+        return converged(p, i, gradient_norm);
+      }
+      let shrunk = |step_size: P| step_size <= P::zero() || (grad * step_size).length() < self.Δ;
+      loop {
+        i += 1;
+        let p1 = p + grad * step_size;
+        let fp1 = f(p1);
+        if fp1 >= fp + armijo.c * step_size * grad_sq {
+          // sufficient increase
+          p = p1;
+          fp = fp1;
+          break;
+        }
+        step_size = step_size * armijo.shrink;
+        if shrunk(step_size) { return converged(p, i, gradient_norm); }
+        if i >= step_limit { break; }
+      }
+      if shrunk(step_size) { return converged(p, i, gradient_norm); }
+      step_size = step_size / armijo.shrink;
+    }
+    Convergence { point: p, iterations: step_limit, gradient_norm, stop: Stop::StepLimit }
+  }
+
+  /// Same as [`LineSearch::optimize`], but using a dedicated gradient of the function,
+  /// such as [`ADF::gradient`](crate::solver::ADF::gradient).
+  pub fn optimize_grad(&self, grad: impl Fn(P2<P>) -> V2<P, WorldSpace>, p: P2<P>) -> P2<P> {
+    self.optimize_grad_diagnostics(grad, p).point
+  }
+
+  /// Same as [`LineSearch::optimize_grad`], also reporting how the search went.
+  pub fn optimize_grad_diagnostics(&self, grad: impl Fn(P2<P>) -> V2<P, WorldSpace>, mut p: P2<P>) -> Convergence<P> {
+    let mut step_size = self.initial_step_size;
+    let mut state = AscentState::new();
+    let mut gradient_norm = None;
+    let step_limit = self.step_limit.unwrap_or(u64::MAX);
+    for i in 0..step_limit {
+      let grad = grad(p);
+      gradient_norm = Some(grad.length());
+      let step = state.step(self.ascent, grad) * step_size;
+      if step.length() < self.Δ {
+        return Convergence { point: p, iterations: i, gradient_norm, stop: Stop::Converged };
+      }
       step_size = step_size * self.decay_factor;
-      p += grad
+      p += step
     }
-    p
+    Convergence { point: p, iterations: step_limit, gradient_norm, stop: Stop::StepLimit }
   }
 
   pub(crate) fn optimize_normal(&self, f: impl Fn(P2<P>) -> P, mut p: P2<P>) -> bool {
@@ -70,21 +280,103 @@ impl<P: Float> LineSearch<P> {
     false
   }
 
-  // for debugging only
-  fn trajectory(&self, grad: impl Fn(P2<P>) -> V2<P, WorldSpace>, mut p: P2<P>) -> Vec<P2<P>> {
+  /// Points visited by [`LineSearch::optimize_grad`] from `p`, for debugging, e.g. with
+  /// [`SvgBackend::push_polyline`](crate::drawing::SvgBackend::push_polyline).
+  pub fn trajectory(&self, grad: impl Fn(P2<P>) -> V2<P, WorldSpace>, mut p: P2<P>) -> Vec<P2<P>> {
     let mut trajectory = vec![p];
     let mut step_size = self.initial_step_size;
+    let mut state = AscentState::new();
     // decay -> limit:
     // fast: 0.5 -> 20
     // slow: 0.85 -> 40
     // veryslow: 0.95 -> 128
     for _ in 0..self.step_limit.unwrap_or(u64::MAX) {
-      let grad = grad(p) * step_size;
-      if grad.length() < self.Δ { break; }
+      let step = state.step(self.ascent, grad(p)) * step_size;
+      if step.length() < self.Δ { break; }
       step_size = step_size * self.decay_factor;
-      p += grad;
+      p += step;
       trajectory.push(p);
     }
     trajectory
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)] mod tests {
+  use {
+    super::*,
+    rand::SeedableRng
+  };
+
+  #[test] fn ascent() {
+    // long thin ridge along y = 0.5
+    let f = |p: P2<f64>| -100.0 * (p.y - 0.5).powi(2) - 0.1 * (p.x - 0.5).abs();
+    let p0 = P2::new(0.1, 0.52);
+    let distance = |ascent| {
+      let line_search = LineSearch {
+        initial_step_size: 0.004, decay_factor: 0.99, step_limit: Some(256), ascent,
+        ..Default::default()
+      };
+      (line_search.optimize(f, p0) - P2::splat(0.5)).length()
+    };
+    let plain = distance(Ascent::Gradient);
+    for ascent in [Ascent::momentum(), Ascent::rmsprop(), Ascent::adam()] {
+      assert!(distance(ascent) < plain, "{ascent:?}");
+    }
+  }
+
+  #[test] fn armijo() {
+    use std::cell::Cell;
+
+    let evaluations = Cell::new(0);
+    let f = |p: P2<f64>| {
+      evaluations.set(evaluations.get() + 1);
+      -(p.x - 0.3).powi(2) - 4.0 * (p.y - 0.6).powi(2)
+    };
+    let run = |line_search: LineSearch<f64>| {
+      evaluations.set(0);
+      let p = line_search.optimize(f, P2::new(0.9, 0.1));
+      (p.distance_to(P2::new(0.3, 0.6)), evaluations.get())
+    };
+    let decay = LineSearch { initial_step_size: 0.1, decay_factor: 0.99, ..Default::default() };
+    let (decay_error, decay_evaluations) = run(decay);
+    let (armijo_error, armijo_evaluations) = run(LineSearch { armijo: Some(Armijo::default()), ..decay });
+    assert!(armijo_error < 1e-4 && armijo_error <= decay_error * 10.0, "{armijo_error} {decay_error}");
+    assert!(armijo_evaluations * 2 < decay_evaluations, "{armijo_evaluations} {decay_evaluations}");
+  }
+
+  #[test] fn armijo_non_finite() {
+    // undefined beyond x = 0.5, the search starting right at the edge
+    let f = |p: P2<f64>| if p.x > 0.5 { f64::NAN } else { p.x };
+    let line_search = LineSearch { armijo: Some(Armijo::default()), step_limit: Some(8), ..Default::default() };
+    let convergence = line_search.optimize_diagnostics(f, P2::new(0.5 - 1e-9, 0.5));
+    assert!(convergence.iterations <= 8 && convergence.point.x <= 0.5, "{convergence:?}");
+    // unlimited
+    let line_search = LineSearch { step_limit: None, ..line_search };
+    assert!(line_search.optimize_diagnostics(f, P2::new(0.5 - 1e-9, 0.5)).point.x <= 0.5);
+    assert_eq!(line_search.optimize_diagnostics(|_| f64::NAN, P2::splat(0.5)).point, P2::splat(0.5));
+  }
+
+  #[test] fn diagnostics() {
+    let f = |p: P2<f64>| -(p.x - 0.3).powi(2) - (p.y - 0.6).powi(2);
+    let line_search = LineSearch { initial_step_size: 0.1, decay_factor: 0.99, ..Default::default() };
+    let converged = line_search.optimize_diagnostics(f, P2::new(0.9, 0.1));
+    assert_eq!(converged.stop, Stop::Converged);
+    assert!(converged.iterations > 0 && converged.gradient_norm.unwrap() < 1e-3);
+
+    let exhausted = LineSearch { step_limit: Some(4), ..line_search }
+      .optimize_diagnostics(f, P2::new(0.9, 0.1));
+    assert_eq!((exhausted.stop, exhausted.iterations), (Stop::StepLimit, 4));
+
+    let saturated = |_| -1.0;
+    let report = crate::util::find_max_parallel_report(saturated, 8, &mut rand_pcg::Pcg64::seed_from_u64(0), line_search);
+    assert_eq!((report.restarts, report.saturated, report.exhausted), (8, 8, 0));
+    assert!(matches!(
+      crate::util::try_find_max_parallel(saturated, 8, &mut rand_pcg::Pcg64::seed_from_u64(0), line_search),
+      Err(crate::util::SearchError::Saturated)
+    ));
+    assert!(matches!(
+      crate::util::try_find_max_parallel(f, 8, &mut rand_pcg::Pcg64::seed_from_u64(0), LineSearch { step_limit: Some(1), ..line_search }),
+      Err(crate::util::SearchError::BudgetExhausted)
+    ));
+  }
+}
@@ -1,9 +1,26 @@
 pub mod argmax2d;
-pub use argmax2d::Argmax2D;
+pub use argmax2d::{Argmax2D, MultiArgmax2D};
 
 pub mod line_search;
-pub use line_search::LineSearch;
+pub use line_search::{LineSearch, Ascent, Armijo};
+
+pub mod nelder_mead;
+pub use nelder_mead::NelderMead;
 
 pub mod adf;
-pub use adf::ADF;
+pub use adf::{ADF, SharedADF};
+
+
+pub mod relax;
+
+pub mod apollonian;
+pub use apollonian::ApollonianGasket;
+
+pub mod placer;
+pub use placer::{ShapePlacer, PlacedShape, Concentric, RandomInscribed, PowerLawRadius, Inscribed, FixedRadius};
+
+pub mod path;
+pub use path::along_path;
 
+pub mod sphere;
+pub use sphere::SphereArgmax;
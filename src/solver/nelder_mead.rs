@@ -0,0 +1,119 @@
+//! Implements Nelder–Mead simplex optimizer. Derivative-free, hence robust on piecewise-smooth
+//! fields (e.g. polygon-heavy ADFs), where finite-difference gradients are noisy.
+
+use {
+  crate::{
+    geometry::P2,
+    solver::line_search::{Optimizer, Convergence, Stop}
+  },
+  euclid::Vector2D as V2,
+  num_traits::Float
+};
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NelderMead<P> {
+  /// Edge length of the initial simplex
+  pub initial_size: P,
+  /// The search stops, once the simplex shrinks below this size
+  pub tolerance: P,
+  pub step_limit: Option<u64>,
+}
+
+impl <P: Float> Default for NelderMead<P> {
+  fn default() -> Self {
+    Self {
+      initial_size: P::from(0.05).unwrap(),
+      tolerance: P::from(1e-6).unwrap(),
+      step_limit: Some(256),
+    }}}
+
+impl<P: Float> NelderMead<P> {
+  /// Find a local maxima of `f`, using `p` as an initial location.
+  pub fn optimize(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> P2<P> {
+    self.optimize_diagnostics(f, p).point
+  }
+
+  /// Same as [`NelderMead::optimize`], also reporting how the search went.
+  pub fn optimize_diagnostics(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> Convergence<P> {
+    let half = P::from(0.5).unwrap();
+    let two = P::from(2.0).unwrap();
+    let mut simplex = [
+      p,
+      p + V2::new(self.initial_size, P::zero()),
+      p + V2::new(P::zero(), self.initial_size)
+    ].map(|p| (p, f(p)));
+
+    let step_limit = self.step_limit.unwrap_or(u64::MAX);
+    let (mut iterations, mut stop) = (step_limit, Stop::StepLimit);
+    for i in 0..step_limit {
+      // best first
+      simplex.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+      let [best, good, worst] = simplex;
+      if best.0.distance_to(good.0).max(best.0.distance_to(worst.0)) < self.tolerance {
+        (iterations, stop) = (i, Stop::Converged);
+        break;
+      }
+
+      let centroid = best.0.lerp(good.0, half);
+      let towards = |t: P| {
+        let p = centroid + (worst.0 - centroid) * t;
+        (p, f(p))
+      };
+      let reflected = towards(-P::one());
+      simplex[2] = if reflected.1 > best.1 {
+        let expanded = towards(-two);
+        if expanded.1 > reflected.1 { expanded } else { reflected }
+      } else if reflected.1 > good.1 {
+        reflected
+      } else {
+        let contracted = if reflected.1 > worst.1 { towards(-half) } else { towards(half) };
+        if contracted.1 > worst.1.max(reflected.1) {
+          contracted
+        } else {
+          // shrink towards the best vertex
+          let shrink = |(p, _): (P2<P>, P)| {
+            let p = best.0.lerp(p, half);
+            (p, f(p))
+          };
+          simplex[1] = shrink(good);
+          shrink(worst)
+        }
+      };
+    }
+    let point = simplex.into_iter()
+      .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+      .unwrap().0;
+    Convergence { point, iterations, gradient_norm: None, stop }
+  }
+}
+
+impl<P: Float> Optimizer<P> for NelderMead<P> {
+  fn maximize_diagnostics(&self, f: impl Fn(P2<P>) -> P, p: P2<P>) -> Convergence<P> {
+    self.optimize_diagnostics(f, p)
+  }
+
+  fn tolerance(&self) -> P {
+    self.tolerance
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {
+    super::*,
+    crate::{sdf, util}
+  };
+
+  #[test] fn optimize() {
+    // not differentiable at the maxima
+    let f = |p: P2<f64>| -(p.x - 0.3).abs() - 2.0 * (p.y - 0.6).abs();
+    let p = NelderMead::default().optimize(f, P2::new(0.9, 0.1));
+    assert!(p.distance_to(P2::new(0.3, 0.6)) < 1e-5, "{p:?}");
+
+    let maxima = util::local_maxima_iter(sdf::boundary_rect, 16, 0, NelderMead::default())
+      .next()
+      .unwrap();
+    assert!(maxima.point.distance_to(P2::splat(0.5)) < 1e-5);
+    assert!((maxima.distance - 0.5).abs() < 1e-5);
+  }
+}
@@ -0,0 +1,58 @@
+//! Placement along curves, e.g. for string art.
+
+use {
+  crate::geometry::P2,
+  num_traits::Float
+};
+
+/// The curve is flattened into this many segments, for its arc length
+const SAMPLES: usize = 4096;
+
+/// Points along a parametric `curve` over `t` in `0..=1`, such as [`Bezier3::point`] or
+/// [`Polyline::point`], `spacing` apart along its arc length, starting from `curve(0)`. Circles
+/// of radius `spacing / 2`, centered at them, are packed along the curve. Empty for a
+/// non-positive `spacing`.
+///
+/// [`Bezier3::point`]: crate::geometry::Bezier3::point
+/// [`Polyline::point`]: crate::geometry::Polyline::point
+pub fn along_path<T: Float>(curve: impl Fn(T) -> P2<T>, spacing: T) -> Vec<P2<T>> {
+  if spacing <= T::zero() || spacing.is_nan() {
+    return vec![];
+  }
+  let n = T::from(SAMPLES).unwrap();
+  let samples: Vec<_> = (0..=SAMPLES).map(|i| curve(T::from(i).unwrap() / n)).collect();
+  let mut points = vec![samples[0]];
+  let (mut travelled, mut next) = (T::zero(), spacing);
+  samples.windows(2).for_each(|w| {
+    let length = (w[1] - w[0]).length();
+    while travelled + length >= next {
+      points.push(w[0].lerp(w[1], (next - travelled) / length));
+      next = next + spacing;
+    }
+    travelled = travelled + length;
+  });
+  points
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::{Bezier3, Polyline}};
+
+  #[test] fn along_path() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    // a square of perimeter 4
+    let vertices = [P2::new(0.0, 0.0), P2::new(1.0, 0.0), P2::new(1.0, 1.0), P2::new(0.0, 1.0)];
+    let square = Polyline { vertices, thickness: 0.0, closed: true };
+    let points = super::along_path(|t| square.point(t), 0.25);
+    assert_eq!(points.len(), 17);
+    assert_eq!(points[4], P2::new(1.0, 0.0));
+    assert!(close(points[6].x, 1.0) && close(points[6].y, 0.5));
+    assert!(close((points[16] - points[0]).length(), 0.0));
+
+    // evenly spaced, unlike the parameter of a curve
+    let curve = Bezier3 { a: P2::new(0.0, 0.0), b: P2::new(0.1, 0.9), c: P2::new(0.2, 0.9), d: P2::new(1.0, 0.0), thickness: 0.0 };
+    let points = super::along_path(|t| curve.point(t), 0.05);
+    assert!(points.windows(2).all(|w| (w[1] - w[0]).length() <= 0.05 + 1e-12));
+    assert!(points.windows(2).all(|w| (w[1] - w[0]).length() > 0.049));
+    assert!(super::along_path(|t| curve.point(t), 0.0).is_empty());
+  }
+}
@@ -0,0 +1,178 @@
+//! Strategies of placing a shape within the free space around a maximum of the distance field,
+//! for the iterators of the solvers, e.g. [`Argmax2D::place_iter`](super::Argmax2D::place_iter)
+//! and [`SharedADF::place_iter`](super::SharedADF::place_iter).
+
+use {
+  num_traits::{Float, FloatConst},
+  rand::prelude::*,
+  euclid::Vector2D as V2,
+  crate::{
+    geometry::{Circle, DistPoint, Measure, Scale, Translation, WorldSpace, P2},
+    util::radius_sampler
+  }
+};
+
+/// Circle, within which a shape is placed: shapes are assumed to be centered at the origin,
+/// and enclosed by the unit circle, as the primitives are.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacedShape<T> {
+  pub center: P2<T>,
+  pub radius: T
+}
+
+impl<T: Copy> PlacedShape<T> {
+  /// `shape.translate(center.to_vector()).scale(radius)`
+  pub fn place<S>(&self, shape: S) -> Scale<Translation<S, T>, T> {
+    Scale {
+      shape: Translation { shape, offset: self.center.to_vector() },
+      scale: self.radius
+    }
+  }
+
+  pub fn circle(&self) -> Scale<Translation<Circle, T>, T> {
+    self.place(Circle)
+  }
+}
+
+impl<T: Float + FloatConst> Measure<T> for PlacedShape<T> {
+  fn area(&self) -> T {
+    T::PI() * self.radius * self.radius
+  }
+  fn perimeter(&self) -> T {
+    T::TAU() * self.radius
+  }
+  fn centroid(&self) -> P2<T> {
+    self.center
+  }
+}
+
+/// Decides the size and position of the next shape, given a maximum of the field. `None`
+/// rejects the maximum.
+///
+/// Implemented for closures, as well as the built-in strategies: [`Concentric`],
+/// [`RandomInscribed`], [`PowerLawRadius`], [`Inscribed`] and [`FixedRadius`].
+pub trait ShapePlacer<T> {
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, rng: &mut dyn RngCore) -> Option<PlacedShape<T>>;
+}
+
+impl<T, F> ShapePlacer<T> for F
+  where F: FnMut(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> Option<PlacedShape<T>>
+{
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, rng: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    self(max, rng)
+  }
+}
+
+/// At the maximum, with radius of `distance * ratio`; a fractal-like distribution.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Concentric<T> {
+  pub ratio: T
+}
+
+impl<T: Float> ShapePlacer<T> for Concentric<T> {
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, _: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    let radius = max.distance * self.ratio;
+    (radius > T::zero()).then_some(PlacedShape { center: max.point, radius })
+  }
+}
+
+/// Radius uniformly distributed in `(0, distance]`, up to `max_radius`, touching the circle
+/// inscribed at the maximum in a random direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RandomInscribed<T> {
+  pub max_radius: T
+}
+
+impl<T: Float> Default for RandomInscribed<T> {
+  fn default() -> Self {
+    Self { max_radius: T::infinity() }
+  }
+}
+
+impl<T: Float + FloatConst + Send + Sync> ShapePlacer<T> for RandomInscribed<T> {
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, rng: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    PowerLawRadius { exponent: T::one(), max_radius: self.max_radius }.place(max, rng)
+  }
+}
+
+/// [`RandomInscribed`], with radius of `distance * u^exponent`, `u` being uniform in `(0, 1]`:
+/// exponents above 1 favour small shapes, and a long tail of large ones.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PowerLawRadius<T> {
+  pub exponent: T,
+  pub max_radius: T
+}
+
+impl<T: Float + FloatConst + Send + Sync> ShapePlacer<T> for PowerLawRadius<T> {
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, rng: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    let radius = radius_sampler::capped(radius_sampler::power_law(self.exponent), self.max_radius);
+    Inscribed { radius }.place(max, rng)
+  }
+}
+
+/// Radius drawn from a distribution, such as those of [`radius_sampler`], touching the circle
+/// inscribed at the maximum in a random direction. Rejects the maximum on a zero radius.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Inscribed<F> {
+  pub radius: F
+}
+
+impl<T, F> ShapePlacer<T> for Inscribed<F>
+  where T: Float + FloatConst,
+        F: Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T
+{
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, rng: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    if max.distance <= T::zero() || max.distance.is_nan() {
+      return None;
+    }
+    let angle = T::from(rng.gen_range(-f64::PI()..=f64::PI())).unwrap();
+    let radius = (self.radius)(max, rng);
+    if radius <= T::zero() || radius.is_nan() {
+      return None;
+    }
+    let offset = V2::new(angle.cos(), angle.sin()) * (max.distance - radius);
+    Some(PlacedShape { center: max.point - offset, radius })
+  }
+}
+
+/// At the maximum, if there is room for a shape of `radius`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FixedRadius<T> {
+  pub radius: T
+}
+
+impl<T: Float> ShapePlacer<T> for FixedRadius<T> {
+  fn place(&mut self, max: DistPoint<T, T, WorldSpace>, _: &mut dyn RngCore) -> Option<PlacedShape<T>> {
+    (max.distance >= self.radius).then_some(PlacedShape { center: max.point, radius: self.radius })
+  }
+}
+
+#[cfg(test)] mod tests {
+  use super::*;
+
+  #[test] fn placers() {
+    let max = DistPoint { distance: 0.2, point: P2::new(0.5, 0.5) };
+    let rng = &mut rand_pcg::Pcg64::seed_from_u64(0);
+    assert_eq!(Concentric { ratio: 0.5 }.place(max, rng), Some(PlacedShape { center: max.point, radius: 0.1 }));
+    assert_eq!(FixedRadius { radius: 0.3 }.place(max, rng), None);
+    let placers: [Box<dyn ShapePlacer<f64>>; 4] = [
+      Box::new(RandomInscribed::default()),
+      Box::new(PowerLawRadius { exponent: 3.0, max_radius: 0.05 }),
+      Box::new(Inscribed { radius: radius_sampler::lognormal(0.05, 1.0) }),
+      Box::new(|max: DistPoint<f64, f64, WorldSpace>, _: &mut dyn RngCore| Some(PlacedShape { center: max.point, radius: 0.01 }))
+    ];
+    for mut placer in placers {
+      for _ in 0..100 {
+        // within the circle inscribed at the maximum
+        let placed = placer.place(max, rng).unwrap();
+        assert!(placed.radius > 0.0 && placed.radius <= max.distance);
+        assert!((placed.center - max.point).length() + placed.radius <= max.distance + 1e-9);
+      }
+    }
+    let placed = PowerLawRadius { exponent: 1.0, max_radius: 0.05 }.place(max, rng).unwrap();
+    assert!(placed.radius <= 0.05 && ((placed.center - max.point).length() - (0.2 - placed.radius)).abs() < 1e-9);
+    assert_eq!(RandomInscribed::default().place(DistPoint { distance: 0.0, ..max }, rng), None);
+    assert_eq!(Inscribed { radius: radius_sampler::discrete([0.3]) }.place(max, rng), None);
+  }
+}
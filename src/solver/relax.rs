@@ -0,0 +1,230 @@
+//! Post-processing of generated point sets, such as the centers of placed shapes.
+
+use {
+  crate::{geometry::{DistPoint, WorldSpace, P2}, sdf::central_difference, util},
+  euclid::Vector2D as V2,
+  num_traits::Float
+};
+
+/// Move `points` towards a centroidal Voronoi tessellation of the domain, where `boundary` is
+/// positive (e.g. [`sdf::boundary_rect`](crate::sdf::boundary_rect)), by `iterations` of
+/// Lloyd's algorithm: every point moves to the centroid of its Voronoi cell. The cells are
+/// sampled on a grid over the unit square, of about 64 samples per point. Evens out the spacing
+/// of a distribution, before the shapes are drawn.
+pub fn lloyd<T>(points: &[P2<T>], boundary: impl Fn(P2<T>) -> T + Sync, iterations: u32) -> Vec<P2<T>>
+  where T: Float + Send + Sync
+{
+  lloyd_weighted(points, boundary, |_| T::one(), iterations)
+}
+
+/// Same as [`lloyd`], the centroids being weighted by a non-negative `density`, so that points
+/// gather where it is higher. Points, whose cells have no weight, stay in place.
+pub fn lloyd_weighted<T>(
+  points: &[P2<T>],
+  boundary: impl Fn(P2<T>) -> T + Sync,
+  density: impl Fn(P2<T>) -> T + Sync,
+  iterations: u32
+) -> Vec<P2<T>>
+  where T: Float + Send + Sync
+{
+  use rayon::prelude::*;
+
+  let mut points = points.to_vec();
+  if points.is_empty() {
+    return points;
+  }
+  let resolution = ((points.len() as f64 * 64.0).sqrt().ceil() as usize).clamp(64, 2048);
+  let scale = T::from(resolution).unwrap();
+  let half = T::from(0.5).unwrap();
+  let samples: Vec<(P2<T>, T)> = (0..resolution * resolution).into_par_iter()
+    .map(|i| P2::new(T::from(i % resolution).unwrap() + half, T::from(i / resolution).unwrap() + half) / scale)
+    .filter(|&p| boundary(p) > T::zero())
+    .map(|p| (p, density(p).max(T::zero())))
+    .filter(|&(_, weight)| weight > T::zero())
+    .collect();
+  let n = points.len();
+  for _ in 0..iterations {
+    let grid = PointGrid::new(&points);
+    let (weights, moments) = samples.par_iter()
+      .fold(
+        || (vec![T::zero(); n], vec![V2::zero(); n]),
+        |(mut weights, mut moments), &(p, weight)| {
+          let i = grid.nearest(&points, p);
+          weights[i] = weights[i] + weight;
+          moments[i] += p.to_vector() * weight;
+          (weights, moments)
+        })
+      .reduce(
+        || (vec![T::zero(); n], vec![V2::zero(); n]),
+        |(mut weights, mut moments), (w, m)| {
+          weights.iter_mut().zip(w).for_each(|(a, b)| *a = *a + b);
+          moments.iter_mut().zip(m).for_each(|(a, b)| *a += b);
+          (weights, moments)
+        });
+    points.iter_mut().zip(weights.into_iter().zip(moments))
+      .filter(|(_, (weight, _))| *weight > T::zero())
+      .for_each(|(p, (weight, moment))| *p = (moment / weight).to_point());
+  }
+  points
+}
+
+/// Push apart `circles` (of radius `distance`) overlapping each other, until there is at
+/// least `padding` between them, and between them and the edge of the domain, where
+/// `boundary` is positive. Each iteration moves both circles of every pair by a half of the
+/// overlap, and the ones crossing the edge back along the gradient of `boundary`, until
+/// nothing moves. Rescues distributions, slightly overlapping due to the imprecision of a
+/// solver; see [`util::validate_no_overlap`].
+pub fn repel<T>(
+  circles: &[DistPoint<T, T, WorldSpace>],
+  boundary: impl Fn(P2<T>) -> T + Sync,
+  padding: T,
+  iterations: u32
+) -> Vec<DistPoint<T, T, WorldSpace>>
+  where T: Float + Send + Sync
+{
+  use rayon::prelude::*;
+
+  let half = T::from(0.5).unwrap();
+  let mut circles = circles.to_vec();
+  for _ in 0..iterations {
+    let mut moves = vec![V2::zero(); circles.len()];
+    let inflated = circles.iter().map(|c| DistPoint { distance: c.distance + padding * half, point: c.point });
+    let overlapping = util::validate_no_overlap(inflated, T::zero());
+    overlapping.iter().for_each(|&(i, j)| {
+      let (a, b) = (circles[i], circles[j]);
+      let d = b.point - a.point;
+      let length = d.length();
+      // coincident ones are split along `x`
+      let direction = if length > T::zero() { d / length } else { V2::new(T::one(), T::zero()) };
+      let push = direction * ((a.distance + b.distance + padding - length) * half);
+      moves[i] -= push;
+      moves[j] += push;
+    });
+    // a Newton step towards the level `radius + padding` of the boundary
+    let crossing = moves.par_iter_mut().zip(&circles)
+      .map(|(delta, c)| {
+        let p = c.point + *delta;
+        let deficit = c.distance + padding - boundary(p);
+        let gradient = central_difference(&boundary, p, T::epsilon().cbrt());
+        let length2 = gradient.square_length();
+        if deficit <= T::zero() || length2 <= T::zero() {
+          return false;
+        }
+        *delta += gradient * (deficit / length2);
+        true
+      })
+      .filter(|&crossing| crossing)
+      .count();
+    if overlapping.is_empty() && crossing == 0 {
+      break;
+    }
+    circles.iter_mut().zip(moves).for_each(|(c, delta)| c.point += delta);
+  }
+  circles
+}
+
+// points, bucketed into a uniform grid over the unit square; the ones outside of it are
+// bucketed into the nearest cells
+struct PointGrid<T> {
+  size: usize,
+  cell: T,
+  cells: Vec<Vec<usize>>
+}
+
+impl<T: Float> PointGrid<T> {
+  fn new(points: &[P2<T>]) -> Self {
+    let size = (points.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let mut grid = Self { size, cell: T::one() / T::from(size).unwrap(), cells: vec![vec![]; size * size] };
+    points.iter().enumerate().for_each(|(i, &p)| {
+      let (x, y) = grid.cell_of(p);
+      grid.cells[y * size + x].push(i);
+    });
+    grid
+  }
+
+  fn cell_of(&self, p: P2<T>) -> (usize, usize) {
+    let f = |x: T| (x / self.cell).floor().max(T::zero()).to_usize().unwrap_or(0).min(self.size - 1);
+    (f(p.x), f(p.y))
+  }
+
+  // for `p` within the unit square: the points beyond `r` rings of cells around it are at
+  // least `r` cells away
+  fn nearest(&self, points: &[P2<T>], p: P2<T>) -> usize {
+    let (cx, cy) = self.cell_of(p);
+    let mut best = (T::infinity(), 0);
+    for r in 0..self.size {
+      let (x0, x1) = (cx.saturating_sub(r), (cx + r).min(self.size - 1));
+      let (y0, y1) = (cy.saturating_sub(r), (cy + r).min(self.size - 1));
+      for y in y0..=y1 {
+        let edge = y + r == cy || y == cy + r;
+        for x in x0..=x1 {
+          if !edge && x + r != cx && x != cx + r {
+            continue;
+          }
+          for &i in &self.cells[y * self.size + x] {
+            let distance = (points[i] - p).length();
+            if distance < best.0 {
+              best = (distance, i);
+            }
+          }
+        }
+      }
+      if best.0 <= T::from(r).unwrap() * self.cell {
+        break;
+      }
+    }
+    best.1
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf, rand::prelude::*};
+
+  // distances to the nearest neighbour
+  fn spacing(points: &[P2<f64>]) -> Vec<f64> {
+    points.iter().enumerate()
+      .map(|(i, p)| points.iter().enumerate()
+        .filter(|&(j, _)| j != i)
+        .map(|(_, q)| (*p - *q).length())
+        .fold(f64::INFINITY, f64::min))
+      .collect()
+  }
+
+  #[test] fn lloyd() {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+    let points: Vec<P2<f64>> = (0..100).map(|_| P2::new(rng.gen(), rng.gen())).collect();
+    let relaxed = super::lloyd(&points, sdf::boundary_rect, 20);
+    assert_eq!(relaxed.len(), points.len());
+    assert!(relaxed.iter().all(|p| (0.0..=1.0).contains(&p.x) && (0.0..=1.0).contains(&p.y)));
+    let [before, after] = [&points, &relaxed].map(|points| spacing(points));
+    let min = |spacing: &[f64]| spacing.iter().copied().fold(f64::INFINITY, f64::min);
+    let mean = |spacing: &[f64]| spacing.iter().sum::<f64>() / spacing.len() as f64;
+    assert!(min(&after) > 2.0 * min(&before));
+    assert!(mean(&after) > mean(&before));
+
+    // within a circle only, except for the points without samples in their cells
+    let relaxed = super::lloyd(&points, sdf::boundary_circle, 10);
+    assert!(relaxed.iter().zip(&points).all(|(p, q)| (*p - P2::splat(0.5)).length() < 0.5 || p == q));
+    assert!(relaxed.iter().filter(|p| (**p - P2::splat(0.5)).length() < 0.5).count() > 90);
+
+    // denser on the left
+    let relaxed = super::lloyd_weighted(&points, sdf::boundary_rect, |p| (1.0 - p.x).powi(4), 30);
+    assert!(relaxed.iter().filter(|p| p.x < 0.5).count() > 65);
+    assert!(super::lloyd(&[], sdf::boundary_rect::<f64>, 10).is_empty());
+  }
+
+  #[test] fn repel() {
+    // a 4x4 grid, overlapping by 0.01, with the first circle crossing the edge
+    let mut circles: Vec<_> = itertools::iproduct!(0..4, 0..4)
+      .map(|(x, y)| DistPoint { distance: 0.1, point: P2::new(0.2 + 0.19 * x as f64, 0.2 + 0.19 * y as f64) })
+      .collect();
+    circles[0].point = P2::new(0.05, 0.2);
+    let padding = 0.005;
+    let relaxed = super::repel(&circles, sdf::boundary_rect, padding, 200);
+    let inflated = relaxed.iter().map(|c| DistPoint { distance: c.distance + padding / 2.0 - 1e-9, point: c.point });
+    assert!(util::validate_no_overlap(inflated, 0.0).is_empty());
+    assert!(relaxed.iter().all(|c| sdf::boundary_rect(c.point) >= c.distance + padding - 1e-9));
+    // displaced slightly
+    assert!(relaxed.iter().zip(&circles).skip(1).all(|(a, b)| (a.point - b.point).length() < 0.05));
+  }
+}
@@ -0,0 +1,105 @@
+use {
+  super::{Cap, S2, distance, from_equirectangular, to_lat_long},
+  anyhow::{Result, ensure},
+  euclid::Point2D,
+  std::f32::consts::{FRAC_PI_2, PI}
+};
+
+/// Discrete distance field of the sphere, analogous to [`Argmax2D`](crate::solver::Argmax2D):
+/// an f32-bitmap of `2 resolution × resolution` pixels in the equirectangular projection,
+/// sampled at their centers, with the maximum of every row cached. The pixels get denser
+/// towards the poles, hence so does the precision. An empty field is `π` everywhere, the
+/// whole sphere being the largest cap; start by inserting a boundary, such as
+/// [`boundary_cap`](super::boundary_cap), or a cap of a chosen radius.
+#[derive(Debug, Clone)]
+pub struct SphereArgmax {
+  resolution: usize,
+  dist_map: Vec<f32>,
+  row_argmax: Vec<Cap<f32>>
+}
+
+impl SphereArgmax {
+  pub fn new(resolution: usize) -> Result<Self> {
+    ensure!(resolution > 0, "resolution must be positive");
+    Ok(Self {
+      resolution,
+      // no two points of the sphere are further apart
+      dist_map: vec![PI; 2 * resolution * resolution],
+      row_argmax: (0..resolution).map(|y| Cap::new(pixel(resolution, 0, y), PI)).collect()
+    })
+  }
+
+  pub fn resolution(&self) -> usize {
+    self.resolution
+  }
+
+  /// Find global maxima: the largest cap, which is disjoint from the inserted ones.
+  pub fn find_max(&self) -> Cap<f32> {
+    *self.row_argmax.iter()
+      .max_by(|a, b| a.radius.total_cmp(&b.radius))
+      .unwrap()
+  }
+
+  /// Find global maxima, if there is still a point at least `min_dist` away from the boundary
+  /// and all inserted shapes.
+  pub fn find_max_above(&self, min_dist: f32) -> Option<Cap<f32>> {
+    Some(self.find_max())
+      .filter(|max| max.radius >= min_dist)
+  }
+
+  pub fn insert_sdf(&mut self, sdf: impl Fn(S2<f32>) -> f32 + Sync + Send) {
+    self.insert_sdf_domain(Cap::new(S2::new(0.0, 0.0, 1.0), PI), sdf);
+  }
+
+  /// Update only the pixels within `domain`, outside of which `sdf` is known not to be lower
+  /// than the field.
+  pub fn insert_sdf_domain(&mut self, domain: Cap<f32>, sdf: impl Fn(S2<f32>) -> f32 + Sync + Send) {
+    use rayon::prelude::*;
+
+    let (latitude, _) = to_lat_long(domain.center);
+    let (north, south) = (latitude + domain.radius, latitude - domain.radius);
+    // rows, whose centers lie within the latitudes of the domain
+    let row_of = |latitude: f32| (0.5 - latitude / PI) * self.resolution as f32 - 0.5;
+    let rows = if north >= FRAC_PI_2 { 0 } else { row_of(north).ceil().max(0.0) as usize }
+      ..if south <= -FRAC_PI_2 { self.resolution } else { (row_of(south).floor() + 1.0).max(0.0) as usize };
+    let resolution = self.resolution;
+    self.dist_map.par_chunks_mut(2 * resolution)
+      .zip(self.row_argmax.par_iter_mut())
+      .enumerate()
+      .filter(|(y, _)| rows.contains(y))
+      .for_each(|(y, (row, max))| {
+        row.iter_mut().enumerate().for_each(|(x, value)| {
+          let p = pixel(resolution, x, y);
+          if distance(p, domain.center) <= domain.radius {
+            *value = value.min(sdf(p));
+          }
+        });
+        let (x, radius) = row.iter().copied().enumerate()
+          .max_by(|a, b| a.1.total_cmp(&b.1))
+          .unwrap();
+        *max = Cap::new(pixel(resolution, x, y), radius);
+      });
+  }
+
+  /// Insert a cap, updating only the pixels it may affect: the ones within the largest
+  /// distance of the field from it.
+  pub fn insert_cap(&mut self, cap: Cap<f32>) {
+    let domain = Cap::new(cap.center, cap.radius + self.find_max().radius);
+    self.insert_sdf_domain(domain, |p| cap.sdf(p));
+  }
+
+  /// Value of the field at the pixel `(x, y)`, of `x` within `2 resolution`.
+  pub fn value(&self, x: usize, y: usize) -> f32 {
+    self.dist_map[y * 2 * self.resolution + x]
+  }
+
+  /// The center of the pixel `(x, y)` on the sphere.
+  pub fn pixel(&self, x: usize, y: usize) -> S2<f32> {
+    pixel(self.resolution, x, y)
+  }
+}
+
+fn pixel(resolution: usize, x: usize, y: usize) -> S2<f32> {
+  let size = resolution as f32;
+  from_equirectangular(Point2D::new((x as f32 + 0.5) / (2.0 * size), (y as f32 + 0.5) / size))
+}
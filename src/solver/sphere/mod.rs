@@ -0,0 +1,76 @@
+//! Filling of the unit sphere S², e.g. for planet textures and globe art. Points of the sphere
+//! are unit vectors, `z` pointing to the north pole; distances are great-circle ones, i.e.
+//! angles in radians. The sphere is unrolled onto the unit square by the equirectangular
+//! projection: longitude along `x`, from `-π` to `π`, and latitude along `y`, from the north
+//! pole down to the south one.
+
+use {
+  euclid::Vector3D,
+  num_traits::{Float, FloatConst},
+  crate::geometry::{WorldSpace, P2}
+};
+
+mod field;
+pub use field::SphereArgmax;
+#[cfg(test)] mod tests;
+
+/// A point of the unit sphere, as a unit vector.
+pub type S2<T> = Vector3D<T, WorldSpace>;
+
+/// Great-circle distance between `a` and `b`.
+pub fn distance<T: Float>(a: S2<T>, b: S2<T>) -> T {
+  // more precise than `acos` of the dot product, for nearby points
+  a.cross(b).length().atan2(a.dot(b))
+}
+
+/// The point of the sphere at `latitude` and `longitude`, in radians.
+pub fn from_lat_long<T: Float>(latitude: T, longitude: T) -> S2<T> {
+  let (sin_lat, cos_lat) = latitude.sin_cos();
+  let (sin_long, cos_long) = longitude.sin_cos();
+  S2::new(cos_lat * cos_long, cos_lat * sin_long, sin_lat)
+}
+
+/// Latitude and longitude of `p`, in radians.
+pub fn to_lat_long<T: Float>(p: S2<T>) -> (T, T) {
+  (p.z.atan2((p.x * p.x + p.y * p.y).sqrt()), p.y.atan2(p.x))
+}
+
+/// The point of the sphere, which the equirectangular projection maps onto `uv` of the unit
+/// square.
+pub fn from_equirectangular<T: Float + FloatConst>(uv: P2<T>) -> S2<T> {
+  let half = T::from(0.5).unwrap();
+  from_lat_long((half - uv.y) * T::PI(), (uv.x - half) * T::TAU())
+}
+
+/// Equirectangular projection of `p` onto the unit square.
+pub fn to_equirectangular<T: Float + FloatConst>(p: S2<T>) -> P2<T> {
+  let half = T::from(0.5).unwrap();
+  let (latitude, longitude) = to_lat_long(p);
+  P2::new(longitude / T::TAU() + half, half - latitude / T::PI())
+}
+
+/// Spherical cap, the disc of the sphere: points within the great-circle distance `radius`
+/// of `center`. Found by [`SphereArgmax::find_max`], as the circles of the plane are.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cap<T> {
+  pub center: S2<T>,
+  pub radius: T
+}
+
+impl<T: Float> Cap<T> {
+  pub fn new(center: S2<T>, radius: T) -> Self {
+    Self { center, radius }
+  }
+
+  pub fn sdf(&self, p: S2<T>) -> T {
+    distance(p, self.center) - self.radius
+  }
+}
+
+/// Restrict the field to the interior of `cap`: positive inside, as
+/// [`sdf::boundary_rect`](crate::sdf::boundary_rect) is. E.g. a hemisphere, of the radius
+/// `π / 2`.
+pub fn boundary_cap<T: Float>(p: S2<T>, cap: Cap<T>) -> T {
+  -cap.sdf(p)
+}
@@ -0,0 +1,43 @@
+use {super::*, std::f64::consts::{FRAC_PI_2, PI}};
+
+#[test] fn projection() {
+  let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+  let p = from_lat_long(0.3, -2.0);
+  assert!(close(p.length(), 1.0));
+  let (latitude, longitude) = to_lat_long(p);
+  assert!(close(latitude, 0.3) && close(longitude, -2.0));
+  let uv = to_equirectangular(p);
+  assert!(close((from_equirectangular(uv) - p).length(), 0.0));
+  assert!(close(to_equirectangular(S2::new(1.0, 0.0, 0.0)).x, 0.5));
+  assert!(close(from_equirectangular(P2::new(0.25, 0.0)).z, 1.0));
+
+  let north = S2::new(0.0, 0.0, 1.0);
+  assert!(close(distance(north, S2::new(1.0, 0.0, 0.0)), FRAC_PI_2));
+  assert!(close(distance(north, -north), PI));
+  assert!(close(distance(from_lat_long(0.0, 0.1), from_lat_long(0.0, 0.1 + 1e-7)), 1e-7));
+  let hemisphere = Cap::new(north, FRAC_PI_2);
+  assert!(close(boundary_cap(from_lat_long(0.5, 1.0), hemisphere), 0.5));
+}
+
+#[test] fn sphere_argmax() -> anyhow::Result<()> {
+  let hemisphere = Cap::new(S2::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2);
+  let mut field = SphereArgmax::new(128)?;
+  // an empty field is `π` everywhere, with no largest cap
+  assert_eq!(field.find_max().radius, std::f32::consts::PI);
+  field.insert_sdf(|p| boundary_cap(p, hemisphere));
+  let first = field.find_max();
+  assert!((first.radius - hemisphere.radius).abs() < 0.02 && first.center.z > 0.999);
+
+  let mut caps = vec![];
+  while let Some(cap) = field.find_max_above(0.03) {
+    field.insert_cap(cap);
+    caps.push(cap);
+  }
+  assert!(caps.windows(2).all(|w| w[0].radius >= w[1].radius));
+  assert!(caps.iter().all(|c| boundary_cap(c.center, hemisphere) >= c.radius - 1e-4));
+  assert!(caps.iter().enumerate().all(|(i, a)| caps[i + 1..].iter()
+    .all(|b| distance(a.center, b.center) >= a.radius + b.radius - 1e-4)));
+  let area: f32 = caps.iter().map(|c| 1.0 - c.radius.cos()).sum();
+  assert!(area > 0.8, "{area}");
+  Ok(())
+}
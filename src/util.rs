@@ -1,13 +1,37 @@
 use {
   num_traits::{Float, FloatConst},
-  euclid::{Rect, Size2D, Vector2D as V2},
+  euclid::{Point2D, Rect, Size2D, Vector2D as V2},
   rand::prelude::*,
+  anyhow::Result,
   crate::{
-    geometry::{P2, DistPoint, WorldSpace},
-    solver::LineSearch,
+    geometry::{P2, DistPoint, WorldSpace, Metric, BoundingBox},
+    solver::line_search::{Optimizer, Stop},
   }
 };
 
+mod stats;
+pub use stats::{Stats, Histogram, Spacing};
+mod export;
+pub use export::{Format, export_circles, import_circles};
+mod overlap;
+pub use overlap::{Placed, validate_no_overlap};
+mod index;
+pub use index::ShapeIndex;
+mod fit;
+pub use fit::{max_fit_scale, max_fit_scale_rotated, place_rotated, Placement};
+mod medial;
+pub use medial::medial_axis;
+mod contour;
+pub use contour::contour;
+#[cfg(feature = "drawing")] pub(crate) use contour::trace_contours;
+mod recursive;
+pub use recursive::{FillStrategy, FillOptions, Nested, recursive_fill, recursive_fill_with};
+pub mod radius_sampler;
+mod coverage;
+pub use coverage::{TakeUntilArea, UntilArea};
+mod empty_rect;
+pub(crate) use empty_rect::largest_free_rect;
+
 pub fn domain_empirical<P: Float + FloatConst>(p: DistPoint<P, P, WorldSpace>) -> Rect<P, WorldSpace> {
   let size = p.distance * P::from(4.0).unwrap() * P::SQRT_2();
   Rect {
@@ -16,13 +40,98 @@ pub fn domain_empirical<P: Float + FloatConst>(p: DistPoint<P, P, WorldSpace>) -
   }
 }
 
-/// Find up to `batch_size` distinct local maxima using GD optimizer.
-pub fn find_max_parallel<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng: &mut impl Rng, line_search: LineSearch<_Float>)
+/// Region, where inserting `shape` may lower a field which is at most `field_bound`: the SDF of
+/// the shape is no less than the distance to its bounding box, by the triangle inequality,
+/// hence the box inflated by `field_bound`. Exact for an upper bound of the field, such as its
+/// global maximum, and an SDF which doesn't overestimate the distance. Unlike
+/// [`domain_empirical`], tight for small shapes, and covering for elongated ones.
+pub fn changed_domain<P: Float>(shape: &impl BoundingBox<P>, field_bound: P) -> Rect<P, WorldSpace> {
+  let field_bound = field_bound.max(P::zero());
+  shape.bounding_box()
+    .inflate(field_bound, field_bound)
+    .to_rect()
+}
+
+/// [`domain_empirical`] for fields measured in an anisotropic `metric`.
+pub fn domain_empirical_metric<P: Float + FloatConst>(p: DistPoint<P, P, WorldSpace>, metric: Metric<P>) -> Rect<P, WorldSpace> {
+  metric.ball_bounding_box(p.point, p.distance * P::from(2.0).unwrap() * P::SQRT_2())
+}
+
+/// Offsets of the periodic images of `domain`, which overlap the unit square.
+/// On a torus, every point `p` of the unit square within `domain - offset` must sample
+/// the field at `p + offset`. Also handy for drawing the wrapped copies of a shape.
+pub fn torus_images<P: Float>(domain: Rect<P, WorldSpace>) -> impl Iterator<Item = V2<P, WorldSpace>> {
+  let unit = Rect::new(Point2D::origin(), Size2D::splat(P::one()));
+  let k = [-P::one(), P::zero(), P::one()];
+  itertools::iproduct!(k, k)
+    .map(|(x, y)| V2::new(x, y))
+    .filter(move |&offset| domain.translate(-offset).intersects(&unit))
+}
+
+/// Find up to `batch_size` distinct local maxima using an optimizer, such as
+/// [`LineSearch`](crate::solver::LineSearch) or [`NelderMead`](crate::solver::NelderMead),
+/// deduplicated by the default [`LocalMaximaConfig::dedup_factor`].
+pub fn find_max_parallel<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng: &mut impl Rng, line_search: impl Optimizer<_Float> + Sync)
                                  -> Vec<DistPoint<_Float, _Float, WorldSpace>>
   where _Float: Float + Send + Sync
 {
-  use rayon::prelude::*;
+  find_max_parallel_report(f, batch_size, rng, line_search).maxima
+}
+
+/// Outcome of [`find_max_parallel_report`].
+#[derive(Debug, Clone)]
+pub struct SearchReport<P> {
+  pub maxima: Vec<DistPoint<P, P, WorldSpace>>,
+  /// Local searches started, one per random initial location
+  pub restarts: u64,
+  /// Total steps of all searches
+  pub iterations: u64,
+  /// Searches, which have reached the step limit
+  pub exhausted: u64,
+  /// Searches, which have converged to a point without space left (below the tolerance)
+  pub saturated: u64,
+  /// Maxima, discarded being too close to another one
+  pub duplicates: u64,
+}
+
+/// Failure of [`try_find_max_parallel`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SearchError {
+  /// Every search has converged, but no space is left
+  Saturated,
+  /// No maxima were found, and some searches have reached the step limit
+  BudgetExhausted,
+}
+
+impl std::fmt::Display for SearchError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Self::Saturated => "no space left",
+      Self::BudgetExhausted => "optimizer step limit exhausted"
+    })
+  }
+}
+
+impl std::error::Error for SearchError {}
+
+/// [`find_max_parallel`], telling apart the reasons of finding nothing.
+pub fn try_find_max_parallel<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng: &mut impl Rng, line_search: impl Optimizer<_Float> + Sync)
+                                     -> Result<Vec<DistPoint<_Float, _Float, WorldSpace>>, SearchError>
+  where _Float: Float + Send + Sync
+{
+  let report = find_max_parallel_report(f, batch_size, rng, line_search);
+  match report.maxima.is_empty() {
+    false => Ok(report.maxima),
+    true if report.exhausted > 0 => Err(SearchError::BudgetExhausted),
+    true => Err(SearchError::Saturated)
+  }
+}
 
+/// [`find_max_parallel`], along with diagnostics of the searches, useful for tuning the optimizer.
+pub fn find_max_parallel_report<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng: &mut impl Rng, line_search: impl Optimizer<_Float> + Sync)
+                                        -> SearchReport<_Float>
+  where _Float: Float + Send + Sync
+{
   let mut rng_buf = vec![P2::splat(_Float::zero()); batch_size as usize];
   rng_buf.iter_mut().for_each(|x| {
     *x = P2::new(
@@ -30,35 +139,334 @@ pub fn find_max_parallel<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync,
       _Float::from(rng.gen_range::<f64, _>(0.0..1.0)).unwrap(),
     );
   });
+  search_from(f, rng_buf, line_search, LocalMaximaConfig::default().dedup_factor)
+}
+
+// run a local search from each of `initial` locations, see `LocalMaximaConfig::dedup_factor`
+fn search_from<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, initial: Vec<P2<_Float>>, line_search: impl Optimizer<_Float> + Sync, dedup_factor: f64)
+                       -> SearchReport<_Float>
+  where _Float: Float + Send + Sync
+{
+  use rayon::prelude::*;
 
-  let points: Vec<_> = rng_buf.into_par_iter()
-    .filter_map(|p0| {
-      let p1 = line_search.optimize(&f, p0);
+  let restarts = initial.len() as u64;
+  let searches: Vec<_> = initial.into_par_iter()
+    .map(|p0| {
+      let convergence = line_search.maximize_diagnostics(&f, p0);
       let p1 = DistPoint {
-        point: p1,
-        distance: f(p1)
+        point: convergence.point,
+        distance: f(convergence.point)
       };
-      (p1.distance > line_search.Δ).then_some(p1)
+      (convergence, p1)
     })
     .collect();
-  let mut points1 = vec![];
-  points.into_iter()
-    .for_each(|pn| {
-      points1.iter()
-        .all(|p: &DistPoint<_, _, _>| p.point.distance_to(pn.point) > pn.distance * _Float::from(2.0).unwrap())
-        .then(|| points1.push(pn));
+  let mut report = SearchReport {
+    maxima: vec![],
+    restarts,
+    iterations: 0,
+    exhausted: 0,
+    saturated: 0,
+    duplicates: 0
+  };
+  searches.into_iter()
+    .for_each(|(convergence, pn)| {
+      report.iterations += convergence.iterations;
+      report.exhausted += (convergence.stop == Stop::StepLimit) as u64;
+      if pn.distance <= line_search.tolerance() {
+        report.saturated += (convergence.stop == Stop::Converged) as u64;
+      } else if report.maxima.iter()
+        .all(|p: &DistPoint<_, _, _>| p.point.distance_to(pn.point) > pn.distance * _Float::from(dedup_factor).unwrap()) {
+        report.maxima.push(pn);
+      } else {
+        report.duplicates += 1;
+      }
     });
-  points1
+  report
 }
 
-/// A convenience wrapper around [find_max_parallel], produces an infinite iterator.
-pub fn local_maxima_iter<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng_seed: u64, line_search: LineSearch<_Float>)
+/// A convenience wrapper around [find_max_parallel], produces an infinite iterator; same as
+/// [`local_maxima_iter_config`] with [`Sampling::Uniform`] from `rng_seed`.
+pub fn local_maxima_iter<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, rng_seed: u64, line_search: impl Optimizer<_Float> + Copy + Sync)
                                  -> impl Iterator<Item = DistPoint<_Float, _Float, WorldSpace>>
   where _Float: Float + Send + Sync
 {
-  let mut rng = rand_pcg::Lcg128Xsl64::seed_from_u64(rng_seed);
+  let sampling = Sampling::Uniform { seed: rng_seed };
+  local_maxima_iter_config(f, LocalMaximaConfig { batch_size, sampling, ..Default::default() }, line_search)
+}
+
+/// Derive the seed of a stochastic component (e.g. [`local_maxima_iter`], [`Sampling::Uniform`],
+/// or `patterns::FillStrategy::Random`) from the seed of a whole run, so that the run is
+/// reproducible from a single `u64`, while each `stream` remains uncorrelated with the others.
+pub fn split_seed(seed: u64, stream: u64) -> u64 {
+  // SplitMix64 finalizer
+  let mix = |mut z: u64| {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+  };
+  mix(seed.wrapping_add(mix(stream.wrapping_add(0x9E3779B97F4A7C15))))
+}
+
+/// Initial locations of the local searches, see [`local_maxima_iter_sampled`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampling {
+  /// Uniformly random, as in [`local_maxima_iter`]
+  Uniform { seed: u64 },
+  /// Halton sequence with bases 2 and 3, starting after `skip` points. Being low-discrepancy,
+  /// it covers the domain evenly, so on mostly empty fields, disjoint maxima are found with
+  /// far fewer attempts.
+  Halton { skip: u64 },
+}
 
-  std::iter::repeat(()).flat_map(move |_|
-    find_max_parallel(&f, batch_size, &mut rng, line_search)
-  )
-}
\ No newline at end of file
+/// Point `index` of Halton sequence with bases 2 and 3, within the unit square.
+pub fn halton(index: u64) -> P2<f64> {
+  let radical_inverse = |mut i: u64, base: u64| {
+    let (mut x, mut scale) = (0.0, 1.0);
+    while i > 0 {
+      scale /= base as f64;
+      x += (i % base) as f64 * scale;
+      i /= base;
+    }
+    x
+  };
+  P2::new(radical_inverse(index + 1, 2), radical_inverse(index + 1, 3))
+}
+
+/// [`local_maxima_iter`], with a choice of [`Sampling`] of the initial locations.
+pub fn local_maxima_iter_sampled<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, batch_size: u64, sampling: Sampling, line_search: impl Optimizer<_Float> + Copy + Sync)
+                                         -> impl Iterator<Item = DistPoint<_Float, _Float, WorldSpace>>
+  where _Float: Float + Send + Sync
+{
+  local_maxima_iter_config(f, LocalMaximaConfig { batch_size, sampling, ..Default::default() }, line_search)
+}
+
+/// See [`local_maxima_iter_config`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalMaximaConfig {
+  /// Local searches per batch, run in parallel
+  pub batch_size: u64,
+  pub sampling: Sampling,
+  /// Maxima closer than `dedup_factor * distance` to a previous one of the same batch are
+  /// discarded. The default of 2 keeps only those, whose inscribed circles are disjoint; lower
+  /// it when filling with shapes much smaller than the inscribed circle.
+  pub dedup_factor: f64,
+  pub termination: Termination,
+}
+
+impl Default for LocalMaximaConfig {
+  fn default() -> Self {
+    Self {
+      batch_size: 32,
+      sampling: Sampling::Uniform { seed: 0 },
+      dedup_factor: 2.0,
+      termination: Termination::default(),
+    }
+  }
+}
+
+impl LocalMaximaConfig {
+  /// Stop once `budget` is spent, yielding whatever was found so far, see [`Budget`].
+  pub fn with_budget(mut self, budget: Budget) -> Self {
+    match budget {
+      Budget::Time(timeout) => self.termination.timeout = Some(timeout),
+      Budget::SdfEvals(evals) => self.termination.max_sdf_evals = Some(evals)
+    }
+    self
+  }
+}
+
+/// Limit of the work of an iterator, such as [`local_maxima_iter_config`] or
+/// [`ArgmaxIter`](crate::solver::argmax2d::ArgmaxIter), checked before each step: a batch of
+/// local searches, or a placement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Budget {
+  /// Wall-clock time, since the creation of the iterator
+  Time(std::time::Duration),
+  /// Evaluations of the SDF; unlike the time, the output is reproducible on any machine
+  SdfEvals(u64)
+}
+
+/// Stops [`local_maxima_iter_config`] once any of the criteria is met; never by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Termination {
+  /// The best maxima of each of the last `patience` batches is below `min_dist`
+  pub min_dist: Option<f64>,
+  /// At least 1
+  pub patience: u64,
+  /// Time elapsed since the creation of the iterator, checked before each batch
+  pub timeout: Option<std::time::Duration>,
+  /// Fraction of the unit square without space left, estimated from the field at the initial
+  /// locations of a batch, hence as precise as the batch is large
+  pub coverage: Option<f64>,
+  /// Evaluations of the field since the creation of the iterator, checked before each batch
+  pub max_sdf_evals: Option<u64>,
+}
+
+/// [`local_maxima_iter`], configured by [`LocalMaximaConfig`].
+pub fn local_maxima_iter_config<_Float>(f: impl Fn(P2<_Float>) -> _Float + Send + Sync, config: LocalMaximaConfig, line_search: impl Optimizer<_Float> + Copy + Sync)
+                                        -> impl Iterator<Item = DistPoint<_Float, _Float, WorldSpace>>
+  where _Float: Float + Send + Sync
+{
+  let LocalMaximaConfig { batch_size, sampling, dedup_factor, termination } = config;
+  // initial locations of the searches
+  let mut sample: Box<dyn FnMut() -> P2<f64> + Send> = match sampling {
+    Sampling::Uniform { seed } => {
+      let mut rng = rand_pcg::Lcg128Xsl64::seed_from_u64(seed);
+      Box::new(move || P2::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)))
+    },
+    Sampling::Halton { skip } => {
+      let mut index = skip;
+      Box::new(move || {
+        index += 1;
+        halton(index - 1)
+      })
+    }
+  };
+
+  let start = std::time::Instant::now();
+  // consecutive batches with the best maxima below `termination.min_dist`
+  let mut below = 0;
+  let evals = std::sync::atomic::AtomicU64::new(0);
+
+  std::iter::from_fn(move || {
+    use std::sync::atomic::Ordering;
+
+    if termination.timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+      return None;
+    }
+    if termination.max_sdf_evals.is_some_and(|max| evals.load(Ordering::Relaxed) >= max) {
+      return None;
+    }
+    let f = |p| {
+      if termination.max_sdf_evals.is_some() {
+        evals.fetch_add(1, Ordering::Relaxed);
+      }
+      f(p)
+    };
+    let initial: Vec<P2<_Float>> = (0..batch_size).map(|_| sample().cast())
+      .collect();
+    if let Some(coverage) = termination.coverage {
+      let covered = initial.iter().filter(|&&p| f(p) <= line_search.tolerance()).count();
+      if covered as f64 >= coverage * initial.len() as f64 {
+        return None;
+      }
+    }
+    let maxima = search_from(f, initial, line_search, dedup_factor).maxima;
+    if let Some(min_dist) = termination.min_dist {
+      let best = maxima.iter().fold(_Float::neg_infinity(), |best, p| best.max(p.distance));
+      below = if best < _Float::from(min_dist).unwrap() { below + 1 } else { 0 };
+      if below >= termination.patience.max(1) {
+        return None;
+      }
+    }
+    Some(maxima)
+  }).flatten()
+}
+
+/// Run `solver_iter` on the current thread, and feed its items to `draw_sink` on a separate
+/// thread pool, through a channel holding up to `buffer` items. Overlaps generation with
+/// rasterization, instead of generating everything first. Returns once both are exhausted.
+pub fn pipeline<T: Send>(
+  solver_iter: impl Iterator<Item = T>,
+  draw_sink: impl Fn(T) + Send + Sync,
+  buffer: usize
+) -> Result<()> {
+  use rayon::prelude::*;
+
+  let pool = rayon::ThreadPoolBuilder::new().build()?;
+  let (tx, rx) = std::sync::mpsc::sync_channel(buffer);
+  std::thread::scope(|scope| {
+    let consumer = scope.spawn(|| pool.install(||
+      rx.into_iter().par_bridge().for_each(&draw_sink)
+    ));
+    for item in solver_iter {
+      if tx.send(item).is_err() { break; }
+    }
+    drop(tx);
+    consumer.join()
+      .unwrap_or_else(|e| std::panic::resume_unwind(e));
+  });
+  Ok(())
+}
+
+#[cfg(test)] mod tests {
+  use {
+    super::*,
+    crate::{sdf, solver::LineSearch}
+  };
+
+  #[test] fn changed_domain() {
+    use crate::{geometry::{Circle, Line, Shape}, sdf::SDF};
+    let circle = Circle.scale(0.05).translate(V2::new(0.3, 0.6));
+    let line = Line { a: P2::new(0.1, 0.1), b: P2::new(0.9, 0.3), thickness: 0.02 };
+    let bound = 0.2;
+    [super::changed_domain(&circle, bound), super::changed_domain(&line, bound)].iter().zip([
+      &circle as &dyn SDF<f64>, &line
+    ]).for_each(|(domain, shape)| {
+      // outside of the domain, the shape is farther than the field
+      itertools::iproduct!(0..41, 0..41)
+        .map(|(x, y)| P2::new(x as f64 / 40.0, y as f64 / 40.0))
+        .filter(|p| !domain.contains(*p))
+        .for_each(|p| assert!(shape.sdf(p) >= bound, "{p:?}"));
+    });
+    let domain = super::changed_domain(&circle, bound);
+    assert!((domain.size.width - 0.5).abs() < 1e-9 && (domain.origin.x - 0.05).abs() < 1e-9);
+    // the heuristic misses the ends of the line
+    assert!(!domain_empirical(DistPoint { distance: 0.1, point: P2::new(0.5, 0.2) }).contains(P2::new(0.1, 0.1)));
+  }
+
+  #[test] fn halton() {
+    let points: Vec<_> = (0..3).map(super::halton).collect();
+    assert_eq!(points, [P2::new(0.5, 1.0 / 3.0), P2::new(0.25, 2.0 / 3.0), P2::new(0.75, 1.0 / 9.0)]);
+
+    let sampled: Vec<_> = local_maxima_iter_sampled(sdf::boundary_rect::<f64>, 16, Sampling::Uniform { seed: 0 }, LineSearch::default())
+      .take(1).collect();
+    let uniform: Vec<_> = local_maxima_iter(sdf::boundary_rect::<f64>, 16, 0, LineSearch::default())
+      .take(1).collect();
+    assert_eq!(sampled, uniform);
+
+    let max = local_maxima_iter_sampled(sdf::boundary_rect::<f64>, 4, Sampling::Halton { skip: 0 }, LineSearch::default())
+      .next().unwrap();
+    assert!(max.point.distance_to(P2::splat(0.5)) < 1e-3);
+  }
+
+  #[test] fn termination() {
+    let config = |termination| LocalMaximaConfig { termination, ..Default::default() };
+    let count = |termination| local_maxima_iter_config(sdf::boundary_rect::<f64>, config(termination), LineSearch::default())
+      .take(16).count();
+    assert_eq!(count(Termination::default()), 16);
+    // a single maxima per batch, of distance 0.5
+    assert_eq!(count(Termination { min_dist: Some(0.6), patience: 2, ..Default::default() }), 1);
+    assert_eq!(count(Termination { timeout: Some(std::time::Duration::ZERO), ..Default::default() }), 0);
+    let saturated = local_maxima_iter_config(|_| -1.0, config(Termination { coverage: Some(0.99), ..Default::default() }), LineSearch::default())
+      .count();
+    assert_eq!(saturated, 0);
+    assert_eq!(count(Termination { max_sdf_evals: Some(0), ..Default::default() }), 0);
+    // a single batch
+    let budget = |budget| local_maxima_iter_config(sdf::boundary_rect::<f64>, LocalMaximaConfig::default().with_budget(budget), LineSearch::default())
+      .take(16).count();
+    assert_eq!(budget(Budget::SdfEvals(1)), 1);
+    assert_eq!(budget(Budget::Time(std::time::Duration::ZERO)), 0);
+  }
+
+  #[test] fn seed() {
+    let run = |seed| local_maxima_iter(sdf::boundary_rect::<f64>, 16, split_seed(seed, 0), LineSearch::default())
+      .take(4)
+      .map(|p| p.point)
+      .collect::<Vec<_>>();
+    assert_eq!(run(7), run(7));
+    assert_ne!(split_seed(7, 0), split_seed(7, 1));
+    assert_ne!(split_seed(7, 0), split_seed(8, 0));
+  }
+
+  #[test] fn dedup_factor() {
+    let initial: Vec<_> = (0..16).map(super::halton).collect();
+    let maxima = |dedup_factor| search_from(sdf::boundary_rect, initial.clone(), LineSearch::default(), dedup_factor).maxima;
+    assert_eq!(maxima(2.0).len(), 1);
+    assert!(maxima(0.0).len() > 1);
+  }
+}
@@ -0,0 +1,113 @@
+use {
+  std::collections::HashMap,
+  num_traits::Float,
+  crate::{geometry::{Polyline, P2}, sdf::SDF}
+};
+
+/// Iso-contours of `sdf` at `level`, by marching squares over a `resolution × resolution`
+/// grid on the unit square, the crossings being interpolated linearly along the edges of the
+/// cells. Works on any [`SDF`], the fields of [`ADF`](crate::solver::ADF) and
+/// [`Argmax2D`](crate::solver::Argmax2D) included, e.g. for outlines of shapes, or debugging
+/// a field. Contours leaving the unit square are open polylines, the rest are closed.
+pub fn contour<T: Float>(sdf: &impl SDF<T>, level: T, resolution: usize) -> Vec<Polyline<T, Vec<P2<T>>>> {
+  let n = resolution.max(1);
+  let step = T::one() / T::from(n).unwrap();
+  let corner = |i: usize, j: usize| P2::new(T::from(i).unwrap() * step, T::from(j).unwrap() * step);
+  let values: Vec<T> = itertools::iproduct!(0..=n, 0..=n)
+    .map(|(j, i)| sdf.sdf(corner(i, j)) - level)
+    .collect();
+  trace_contours(&values, n, n, corner).into_iter()
+    .map(|(vertices, closed)| Polyline { vertices, thickness: T::zero(), closed })
+    .collect()
+}
+
+// contours at zero of `values`, sampled at the corners of `nx × ny` cells, row-major; open
+// ones, touching the edges of the grid, first. Negative values are inside
+pub(crate) fn trace_contours<T: Float>(
+  values: &[T],
+  nx: usize,
+  ny: usize,
+  corner: impl Fn(usize, usize) -> P2<T>
+) -> Vec<(Vec<P2<T>>, bool)> {
+  let value = |i: usize, j: usize| values[j * (nx + 1) + i];
+  let quarter = T::from(0.25).unwrap();
+
+  // edges are identified by their first corner, and direction (horizontal or vertical)
+  let mut neighbours = HashMap::<(usize, usize, bool), Vec<(usize, usize, bool)>>::new();
+  let mut connect = |a, b| {
+    neighbours.entry(a).or_default().push(b);
+    neighbours.entry(b).or_default().push(a);
+  };
+  itertools::iproduct!(0..ny, 0..nx).for_each(|(j, i)| {
+    let inside = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)].map(|(i, j)| value(i, j) < T::zero());
+    let edges = [(i, j, true), (i + 1, j, false), (i, j + 1, true), (i, j, false)];
+    let crossed: Vec<_> = (0..4)
+      .filter(|&k| inside[k] != inside[(k + 1) % 4])
+      .map(|k| edges[k])
+      .collect();
+    match crossed[..] {
+      [a, b] => connect(a, b),
+      [e0, e1, e2, e3] => {
+        // saddle, resolved by the value at the center
+        let center = (value(i, j) + value(i + 1, j) + value(i + 1, j + 1) + value(i, j + 1)) * quarter;
+        if (center < T::zero()) == inside[0] {
+          connect(e0, e1);
+          connect(e2, e3);
+        } else {
+          connect(e0, e3);
+          connect(e1, e2);
+        }
+      },
+      _ => ()
+    }
+  });
+  let crossing = |(i, j, horizontal): (usize, usize, bool)| {
+    let (i1, j1) = if horizontal { (i + 1, j) } else { (i, j + 1) };
+    let (a, b) = (value(i, j), value(i1, j1));
+    corner(i, j).lerp(corner(i1, j1), a / (a - b))
+  };
+
+  let mut contours = vec![];
+  let mut keys: Vec<_> = neighbours.keys().copied().collect();
+  keys.sort_unstable();
+  // the ends of open contours cross a single cell
+  let (open, closed): (Vec<_>, Vec<_>) = keys.into_iter().partition(|key| neighbours[key].len() == 1);
+  for (start, closed) in open.into_iter().map(|key| (key, false)).chain(closed.into_iter().map(|key| (key, true))) {
+    if !neighbours.contains_key(&start) { continue; }
+    let (mut previous, mut current) = (start, start);
+    let mut contour = vec![];
+    while let Some(next) = neighbours.remove(&current) {
+      contour.push(crossing(current));
+      let next = next.into_iter().find(|&n| n != previous && neighbours.contains_key(&n));
+      previous = current;
+      match next {
+        Some(next) => current = next,
+        None => break
+      }
+    }
+    contours.push((contour, closed));
+  }
+  contours
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::{Circle, Shape}, euclid::Vector2D as V2};
+
+  #[test] fn contour() {
+    let center: P2<f64> = P2::splat(0.5);
+    let circle = Circle.scale(0.25).translate(center.to_vector());
+    for (level, radius) in [(0.0, 0.25), (0.1, 0.35)] {
+      let contours = super::contour(&circle, level, 64);
+      assert_eq!(contours.len(), 1);
+      assert!(contours[0].closed && contours[0].vertices.len() > 50);
+      assert!(contours[0].vertices.iter().all(|p| ((*p - center).length() - radius).abs() < 1e-3));
+    }
+    // cut by the edge of the unit square
+    let contours = super::contour(&Circle.scale(0.25).translate(V2::new(0.0, 0.5)), 0.0, 64);
+    assert_eq!(contours.len(), 1);
+    let half = &contours[0];
+    assert!(!half.closed);
+    assert!([half.vertices[0], *half.vertices.last().unwrap()].iter().all(|p| p.x == 0.0));
+    assert!(super::contour(&circle, -1.0, 64).is_empty());
+  }
+}
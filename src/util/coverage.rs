@@ -0,0 +1,88 @@
+use {
+  num_traits::Float,
+  crate::geometry::Measure
+};
+
+/// Iterator, ending once the shapes yielded so far cover a given area, see [`TakeUntilArea`].
+pub struct UntilArea<I, T> {
+  iter: I,
+  target: T,
+  area: T
+}
+
+impl<I, T> UntilArea<I, T> {
+  /// Total area of the shapes yielded so far.
+  pub fn area(&self) -> T where T: Copy {
+    self.area
+  }
+}
+
+impl<I, T> Iterator for UntilArea<I, T>
+  where I: Iterator,
+        I::Item: Measure<T>,
+        T: Float
+{
+  type Item = I::Item;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.area >= self.target {
+      return None;
+    }
+    let item = self.iter.next()?;
+    self.area = self.area + item.area();
+    Some(item)
+  }
+}
+
+/// Termination of the placement iterators, such as [`Argmax2D::place_iter`](crate::solver::Argmax2D::place_iter),
+/// by the cumulative area of the accepted shapes, rather than their count. The shape reaching
+/// the target is the last one; no further item is pulled, hence nothing more is inserted into
+/// the field. Unreachable targets never end an infinite iterator, e.g. the circles of
+/// [`Concentric`](crate::solver::Concentric) cover a fraction of the free space, depending on
+/// the ratio.
+pub trait TakeUntilArea: Iterator + Sized {
+  /// Until the total area of the shapes is at least `area`.
+  fn take_until_total_area<T: Float>(self, area: T) -> UntilArea<Self, T>
+    where Self::Item: Measure<T>
+  {
+    UntilArea { iter: self, target: area, area: T::zero() }
+  }
+
+  /// Until `fraction` of the unit square is covered, for shapes which don't overlap, as in
+  /// [`Stats::coverage`](super::Stats::coverage).
+  fn take_until_coverage<T: Float>(self, fraction: T) -> UntilArea<Self, T>
+    where Self::Item: Measure<T>
+  {
+    self.take_until_total_area(fraction)
+  }
+}
+
+impl<I: Iterator> TakeUntilArea for I {}
+
+#[cfg(test)] mod tests {
+  use {
+    super::*,
+    crate::{geometry::{Circle, Shape, Square}, solver::{Argmax2D, RandomInscribed}, sdf}
+  };
+
+  #[test] fn take_until_area() -> anyhow::Result<()> {
+    let squares = std::iter::repeat(Square.scale(0.25f64));
+    // each one of area 0.25
+    assert_eq!(squares.clone().take_until_total_area(1.0).count(), 4);
+    assert_eq!(squares.clone().take_until_total_area(0.9).count(), 4);
+    assert_eq!(squares.clone().take_until_coverage(0.0).count(), 0);
+    assert_eq!([Circle.scale(0.1)].into_iter().take_until_coverage(1.0).count(), 1);
+
+    let mut argmax = Argmax2D::new(256, 16)?;
+    argmax.insert_sdf(sdf::boundary_rect);
+    let mut pulled = 0;
+    let (count, area) = {
+      let mut placement = argmax.place_iter(RandomInscribed { max_radius: 0.05 }, 0)
+        .inspect(|_| pulled += 1)
+        .take_until_coverage(0.4f32);
+      (placement.by_ref().count(), placement.area())
+    };
+    assert!((0.4..0.41).contains(&area) && count == pulled, "{area} {count} {pulled}");
+    Ok(())
+  }
+}
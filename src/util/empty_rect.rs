@@ -0,0 +1,54 @@
+// The largest rectangle of `free` cells of a `width × height` grid, as `(x, y, width, height)`
+// in cells: for every row, the largest rectangle under the histogram of the free cells above
+// it, by a stack of the increasing bars; `O(width height)`.
+pub(crate) fn largest_free_rect(
+  width: usize,
+  height: usize,
+  free: impl Fn(usize, usize) -> bool + Sync
+) -> Option<(usize, usize, usize, usize)> {
+  use rayon::prelude::*;
+
+  let mask: Vec<bool> = (0..width * height).into_par_iter()
+    .map(|i| free(i % width, i / width))
+    .collect();
+  let mut bars = vec![0; width];
+  let mut best: Option<(usize, usize, usize, usize)> = None;
+  let mut stack: Vec<usize> = vec![];
+  for y in 0..height {
+    bars.iter_mut().zip(&mask[y * width..(y + 1) * width])
+      .for_each(|(bar, &free)| *bar = if free { *bar + 1 } else { 0 });
+    stack.clear();
+    for x in 0..=width {
+      let bar = if x < width { bars[x] } else { 0 };
+      while let Some(&top) = stack.last() {
+        if bars[top] < bar {
+          break;
+        }
+        stack.pop();
+        let left = stack.last().map_or(0, |&i| i + 1);
+        let (w, h) = (x - left, bars[top]);
+        if w * h > best.map_or(0, |(.., bw, bh)| bw * bh) {
+          best = Some((left, y + 1 - h, w, h));
+        }
+      }
+      stack.push(x);
+    }
+  }
+  best
+}
+
+#[cfg(test)] mod tests {
+  #[test] fn largest_free_rect() {
+    let grid = [
+      "#.....#",
+      "#..#..#",
+      "#.....#",
+      "#.....#",
+      "##....#",
+    ];
+    let free = |x: usize, y: usize| grid[y].as_bytes()[x] == b'.';
+    assert_eq!(super::largest_free_rect(7, 5, free), Some((2, 2, 4, 3)));
+    assert_eq!(super::largest_free_rect(7, 5, |_, _| false), None);
+    assert_eq!(super::largest_free_rect(3, 2, |_, _| true), Some((0, 0, 3, 2)));
+  }
+}
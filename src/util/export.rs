@@ -0,0 +1,118 @@
+use {
+  std::io::{BufRead, BufReader, Read, Write},
+  num_traits::Float,
+  euclid::Vector2D as V2,
+  anyhow::{anyhow, Context, Result},
+  crate::geometry::{Circle, DistPoint, Scale, Shape, Translation, WorldSpace}
+};
+
+/// File format of [`export_circles`] and [`import_circles`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Format {
+  /// A header of `x,y,r`, followed by a line per circle
+  Csv,
+  /// An array of `{"x": .., "y": .., "r": ..}` objects
+  #[cfg(feature = "serde")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+  Json
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Record {
+  x: f64,
+  y: f64,
+  r: f64
+}
+
+/// Write the centers and radii of `circles`, such as the placed maxima of a distribution, for
+/// post-processing in other tools, or rendering later without recomputation.
+pub fn export_circles<T: Float>(
+  circles: impl IntoIterator<Item = DistPoint<T, T, WorldSpace>>,
+  mut writer: impl Write,
+  format: Format
+) -> Result<()> {
+  let records = circles.into_iter().map(|c| {
+    let f = |x: T| x.to_f64().unwrap();
+    (f(c.point.x), f(c.point.y), f(c.distance))
+  });
+  match format {
+    Format::Csv => {
+      writeln!(writer, "x,y,r")?;
+      for (x, y, r) in records {
+        writeln!(writer, "{x},{y},{r}")?;
+      }
+    },
+    #[cfg(feature = "serde")]
+    Format::Json => {
+      let records: Vec<_> = records.map(|(x, y, r)| Record { x, y, r }).collect();
+      serde_json::to_writer(&mut writer, &records)?;
+    }
+  }
+  writer.flush()?;
+  Ok(())
+}
+
+/// Read the circles written by [`export_circles`], as shapes. The CSV header is optional.
+pub fn import_circles<T: Float>(reader: impl Read, format: Format) -> Result<Vec<Translation<Scale<Circle, T>, T>>> {
+  let records = match format {
+    Format::Csv => {
+      let mut records = vec![];
+      for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with('x')) {
+          continue;
+        }
+        let fields = line.split(',')
+          .map(|field| field.trim().parse::<f64>())
+          .collect::<std::result::Result<Vec<_>, _>>()
+          .with_context(|| format!("line {}", i + 1))?;
+        match fields[..] {
+          [x, y, r] => records.push((x, y, r)),
+          _ => return Err(anyhow!("line {}: expected 3 fields, found {}", i + 1, fields.len()))
+        }
+      }
+      records
+    },
+    #[cfg(feature = "serde")]
+    Format::Json => serde_json::from_reader::<_, Vec<Record>>(reader)?
+      .into_iter()
+      .map(|Record { x, y, r }| (x, y, r))
+      .collect()
+  };
+  let f = |x: f64| T::from(x).unwrap();
+  Ok(records.into_iter()
+    .map(|(x, y, r)| Circle.scale(f(r)).translate(V2::new(f(x), f(y))))
+    .collect())
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf::SDF, euclid::Point2D};
+
+  #[test] fn export_import() -> Result<()> {
+    let circles = [
+      DistPoint { distance: 0.25, point: Point2D::new(0.5, 0.5) },
+      DistPoint { distance: 0.0125, point: Point2D::new(0.1, 0.875) }
+    ];
+    let formats = [Format::Csv, #[cfg(feature = "serde")] Format::Json];
+    for format in formats {
+      let mut buffer = vec![];
+      export_circles(circles, &mut buffer, format)?;
+      let shapes = import_circles::<f64>(&buffer[..], format)?;
+      assert_eq!(shapes.len(), 2);
+      shapes.iter().zip(circles).for_each(|(shape, circle)| {
+        assert_eq!(shape.sdf(circle.point), -circle.distance);
+      });
+    }
+    let mut buffer = vec![];
+    export_circles(circles, &mut buffer, Format::Csv)?;
+    assert_eq!(String::from_utf8(buffer)?, "x,y,r\n0.5,0.5,0.25\n0.1,0.875,0.0125\n");
+    // without the header, and malformed
+    assert_eq!(import_circles::<f32>("1,2,3\n\n4, 5, 6\n".as_bytes(), Format::Csv)?.len(), 2);
+    assert!(import_circles::<f32>("x,y,r\n1,2\n".as_bytes(), Format::Csv).is_err());
+    assert!(import_circles::<f32>("x,y,r\n1,2,a\n".as_bytes(), Format::Csv).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,220 @@
+use {
+  num_traits::{Float, FloatConst},
+  euclid::{Angle, Point2D, Vector2D as V2},
+  crate::geometry::{self, DistPoint, Shape, WorldSpace, P2}
+};
+
+// samples per side of the bounding box of a shape
+const GRID: usize = 64;
+// steps of the search, for a vanishing `tolerance`
+const MAX_STEPS: u32 = 64;
+
+/// The largest scale, at which `shape.translate(center.to_vector()).scale(scale)` fits into
+/// the region, where `field_sdf` (e.g. the distance field of a solver) is non-negative: its
+/// minimum over samples of the shape, the boundary and the interior, is non-negative. Found by
+/// a binary search, up to `tolerance`, between the scales of the shape enclosed by the circle
+/// of the radius `field_sdf(center)`, and enclosing it. `0` when `center` does not fit at all.
+/// Places squares, stars and glyphs as tightly as `distance / 4` does circles; shapes are
+/// assumed to be centered at the origin, as the primitives are.
+pub fn max_fit_scale<T: Float>(shape: &impl Shape<T>, field_sdf: impl Fn(P2<T>) -> T, center: P2<T>, tolerance: T) -> T {
+  max_fit_scale_rotated(shape, field_sdf, center, [Angle::zero()], tolerance).0
+}
+
+/// [`max_fit_scale`] of `shape.rotate(angle).translate(center.to_vector()).scale(scale)`, at
+/// the best one of `angles`. Returns the scale and the angle.
+pub fn max_fit_scale_rotated<T: Float>(
+  shape: &impl Shape<T>,
+  field_sdf: impl Fn(P2<T>) -> T,
+  center: P2<T>,
+  angles: impl IntoIterator<Item = Angle<T>>,
+  tolerance: T
+) -> (T, Angle<T>) {
+  let samples = Samples::new(shape);
+  angles.into_iter()
+    .map(|angle| (samples.fit(&field_sdf, center, angle, tolerance), angle))
+    .fold((T::zero(), Angle::zero()), |best, fit| if fit.0 > best.0 { fit } else { best })
+}
+
+/// A shape, placed as `shape.rotate(angle).translate(center.to_vector()).scale(scale)`, see
+/// [`place_rotated`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Placement<T> {
+  pub center: P2<T>,
+  pub angle: Angle<T>,
+  pub scale: T
+}
+
+/// Place a non-circular shape at a local maximum of the field, such as the one found by a
+/// solver, in `start`: coordinate ascent over the position and the rotation angle, maximizing
+/// [`max_fit_scale`]. The steps start at a half of `start.distance` and a quarter turn, and
+/// are halved once neither improves the fit, down to `start.distance / 1000`. Anisotropic
+/// shapes thus turn towards the free space, instead of being dropped axis-aligned.
+pub fn place_rotated<T: Float + FloatConst>(
+  shape: &impl Shape<T>,
+  field_sdf: impl Fn(P2<T>) -> T,
+  start: DistPoint<T, T, WorldSpace>
+) -> Placement<T> {
+  let two = T::one() + T::one();
+  let tolerance = start.distance / T::from(1000).unwrap();
+  let samples = Samples::new(shape);
+  let fit = |center: P2<T>, angle: Angle<T>| Placement {
+    center, angle, scale: samples.fit(&field_sdf, center, angle, tolerance)
+  };
+  let mut best = fit(start.point, Angle::zero());
+  let (mut step, mut turn) = (start.distance / two, T::FRAC_PI_4());
+  for _ in 0..MAX_STEPS {
+    if step <= tolerance {
+      break;
+    }
+    let mut improved = false;
+    for (dx, dy, da) in [(step, T::zero(), T::zero()), (T::zero(), step, T::zero()), (T::zero(), T::zero(), turn)] {
+      for sign in [T::one(), -T::one()] {
+        let delta = V2::new(dx, dy) * sign;
+        let candidate = fit(best.center + delta, best.angle + Angle::radians(da * sign));
+        if candidate.scale > best.scale {
+          best = candidate;
+          improved = true;
+          break;
+        }
+      }
+    }
+    if !improved {
+      step = step / two;
+      turn = turn / two;
+    }
+  }
+  best
+}
+
+// samples of a shape, see `samples`
+struct Samples<'a, T, S> {
+  shape: &'a S,
+  pivot: P2<T>,
+  points: Vec<P2<T>>
+}
+
+impl<'a, T: Float, S: Shape<T>> Samples<'a, T, S> {
+  fn new(shape: &'a S) -> Self {
+    Self { shape, pivot: shape.bounding_box().center(), points: samples(shape) }
+  }
+
+  // `max_fit_scale` at `angle`
+  fn fit(&self, field_sdf: impl Fn(P2<T>) -> T, center: P2<T>, angle: Angle<T>, tolerance: T) -> T {
+    let to_world = geometry::rotation_to_world(self.pivot, angle);
+    let rotated: Vec<P2<T>> = self.points.iter().map(|&q| to_world.transform_point(q)).collect();
+    // `scale` is around the center of the bounding box, once rotated and translated
+    let origin = self.shape.tight_bounding_box(&to_world).center();
+    let fits = |scale: T| rotated.iter()
+      .all(|&q| field_sdf(center + origin.to_vector() + (q - origin) * scale) >= T::zero());
+    let inner = to_world.inverse()
+      .map_or(T::zero(), |to_shape| -self.shape.sdf(to_shape.transform_point(origin)))
+      .max(T::zero());
+    let outer = rotated.iter().map(|&q| (q - origin).length()).fold(T::zero(), T::max);
+    search(fits, field_sdf(center + origin.to_vector()), inner, outer, tolerance)
+  }
+}
+
+// `distance` is the field at the center of the scale; the shape encloses a circle of `inner`,
+// and is enclosed by a circle of `outer`, at the unit scale
+fn search<T: Float>(fits: impl Fn(T) -> bool, distance: T, inner: T, outer: T, tolerance: T) -> T {
+  let two = T::one() + T::one();
+  if distance < T::zero() || outer <= T::zero() || !fits(T::zero()) {
+    return T::zero();
+  }
+  let mut lo = distance / outer;
+  // the field is sampled, hence the bounds are merely approximate
+  if !fits(lo) {
+    lo = T::zero();
+  }
+  let mut hi = if inner > T::zero() { (distance / inner).max(lo) } else { lo.max(tolerance) };
+  for _ in 0..MAX_STEPS {
+    if !fits(hi) {
+      break;
+    }
+    lo = hi;
+    hi = hi * two;
+  }
+  for _ in 0..MAX_STEPS {
+    if hi - lo <= tolerance {
+      break;
+    }
+    let mid = (lo + hi) / two;
+    if fits(mid) { lo = mid } else { hi = mid }
+  }
+  lo
+}
+
+// nodes of a grid over the bounding box, within the shape, and the crossings of the boundary
+// along the edges of the grid
+fn samples<T: Float>(shape: &impl Shape<T>) -> Vec<P2<T>> {
+  let bounding_box = shape.bounding_box();
+  if bounding_box.is_empty() || !(bounding_box.max - bounding_box.min).length().is_finite() {
+    return vec![];
+  }
+  let step = (bounding_box.max - bounding_box.min) / T::from(GRID - 1).unwrap();
+  let node = |x: usize, y: usize| Point2D::new(
+    bounding_box.min.x + step.x * T::from(x).unwrap(),
+    bounding_box.min.y + step.y * T::from(y).unwrap()
+  );
+  let values: Vec<T> = itertools::iproduct!(0..GRID, 0..GRID)
+    .map(|(y, x)| shape.sdf(node(x, y)))
+    .collect();
+  let value = |x: usize, y: usize| values[y * GRID + x];
+  let mut samples = vec![];
+  for (y, x) in itertools::iproduct!(0..GRID, 0..GRID) {
+    let (v, p) = (value(x, y), node(x, y));
+    if v <= T::zero() {
+      samples.push(p);
+    }
+    for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+      if nx < GRID && ny < GRID && (v <= T::zero()) != (value(nx, ny) <= T::zero()) {
+        let t = v / (v - value(nx, ny));
+        samples.push(p + (node(nx, ny) - p) * t);
+      }
+    }
+  }
+  samples
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::{geometry::{Circle, Square, Star}, sdf::SDF}};
+
+  #[test] fn max_fit_scale() {
+    let center = Point2D::new(0.5, 0.5);
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+    // a free disc, and a free square
+    let disc = |p: P2<f64>| 0.3 - (p - center).length();
+    let square = |p: P2<f64>| -Square.scale(0.3).translate(center.to_vector()).sdf(p);
+    assert!(close(super::max_fit_scale(&Circle, disc, center, 1e-6), 0.3));
+    assert!(close(super::max_fit_scale(&Square, disc, center, 1e-6), 0.3 / 2.0.sqrt()));
+    assert!(close(super::max_fit_scale(&Square, square, center, 1e-6), 0.3));
+    // the tips touch the edge of the disc, up to the sampling of the shape
+    let star = super::max_fit_scale(&Star { n: 5, m: 3.0 }, disc, center, 1e-6);
+    assert!(star > 0.3 - 1e-3 && star < 0.305, "{star}");
+
+    let angles = [Angle::degrees(45.0), Angle::zero(), Angle::degrees(30.0)];
+    let (scale, angle) = max_fit_scale_rotated(&Square, square, center, angles, 1e-6);
+    assert!(close(scale, 0.3) && angle == Angle::zero());
+    let (scale, _) = max_fit_scale_rotated(&Square, square, center, [Angle::degrees(45.0)], 1e-6);
+    assert!(close(scale, 0.3 / 2.0.sqrt()));
+    // placed off the origin, and fitting against the edge of the free square
+    let off = Point2D::new(0.7, 0.5);
+    assert!(close(super::max_fit_scale(&Square, square, off, 1e-6), 0.1));
+    assert!(close(super::max_fit_scale(&Square.translate(V2::new(1.0, 0.0)), square, Point2D::new(-0.3, 0.5), 1e-6), 0.1));
+    assert_eq!(super::max_fit_scale(&Square, square, Point2D::new(0.9, 0.5), 1e-6), 0.0);
+  }
+
+  #[test] fn place_rotated() {
+    let center = Point2D::new(0.5, 0.5);
+    // a free diamond, where an axis-aligned square fits at `0.15 sqrt 2`, and a turned one at `0.3`
+    let diamond = |p: P2<f64>| -Square.rotate(Angle::degrees(45.0)).scale(0.3).translate(center.to_vector()).sdf(p);
+    let start = DistPoint { distance: 0.2, point: Point2D::new(0.53, 0.48) };
+    assert!(super::max_fit_scale(&Square, diamond, start.point, 1e-6) < 0.2);
+    let placement = super::place_rotated(&Square, diamond, start);
+    assert!((placement.scale - 0.3).abs() < 5e-3, "{placement:?}");
+    assert!((placement.center - center).length() < 5e-3);
+    let turn = placement.angle.radians.rem_euclid(f64::FRAC_PI_2());
+    assert!((turn - f64::FRAC_PI_4()).abs() < 0.02, "{turn}");
+  }
+}
@@ -0,0 +1,184 @@
+use {
+  std::{collections::{HashMap, HashSet}, fmt::{self, Debug}, sync::Arc},
+  num_traits::Float,
+  euclid::{Box2D, Rect},
+  crate::geometry::{Shape, WorldSpace, P2}
+};
+
+// shapes covering more cells are not stored in the grid, but visited by every query
+const MAX_CELLS: i64 = 64;
+
+type Cell = (i64, i64);
+
+/// Uniform grid of shapes over their bounding boxes, for nearest-shape and range queries, e.g.
+/// connecting neighbouring shapes in post-processing. May be maintained by the solvers, see
+/// [`Argmax2D::with_shape_index`](crate::solver::Argmax2D::with_shape_index) and
+/// [`ADF::with_shape_index`](crate::solver::ADF::with_shape_index). Shapes are identified by
+/// the order of insertion; distances are the values of their SDFs.
+#[derive(Clone)]
+pub struct ShapeIndex<T> {
+  cell: T,
+  grid: HashMap<Cell, Vec<usize>>,
+  large: Vec<usize>,
+  shapes: Vec<(Box2D<T, WorldSpace>, Arc<dyn Shape<T> + Send + Sync>)>,
+  // occupied cells, inclusive
+  extent: Option<(Cell, Cell)>
+}
+
+impl<T> Debug for ShapeIndex<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("ShapeIndex").field("shapes", &self.shapes.len()).finish()
+  }
+}
+
+impl<T: Float> ShapeIndex<T> {
+  /// A grid of square cells of `cell_size`, about the size of a typical shape.
+  pub fn new(cell_size: T) -> Self {
+    Self { cell: cell_size, grid: HashMap::new(), large: vec![], shapes: vec![], extent: None }
+  }
+
+  pub fn len(&self) -> usize {
+    self.shapes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.shapes.is_empty()
+  }
+
+  /// Returns the id of `shape`.
+  pub fn insert(&mut self, shape: impl Shape<T> + Send + Sync + 'static) -> usize {
+    self.insert_shared(Arc::new(shape))
+  }
+
+  pub(crate) fn insert_shared(&mut self, shape: Arc<dyn Shape<T> + Send + Sync>) -> usize {
+    let id = self.shapes.len();
+    let bounding_box = shape.bounding_box();
+    self.shapes.push((bounding_box, shape));
+    let (min, max) = (self.cell_of(bounding_box.min), self.cell_of(bounding_box.max));
+    if (max.0 - min.0 + 1).saturating_mul(max.1 - min.1 + 1) > MAX_CELLS {
+      self.large.push(id);
+      return id;
+    }
+    itertools::iproduct!(min.0..=max.0, min.1..=max.1)
+      .for_each(|cell| self.grid.entry(cell).or_default().push(id));
+    self.extent = Some(match self.extent {
+      Some((lo, hi)) => ((lo.0.min(min.0), lo.1.min(min.1)), (hi.0.max(max.0), hi.1.max(max.1))),
+      None => (min, max)
+    });
+    id
+  }
+
+  pub fn shape(&self, id: usize) -> Option<&(dyn Shape<T> + Send + Sync)> {
+    self.shapes.get(id).map(|(_, shape)| &**shape)
+  }
+
+  /// Ids of the shapes, whose bounding boxes intersect `rect`, in ascending order.
+  pub fn shapes_in(&self, rect: Rect<T, WorldSpace>) -> Vec<usize> {
+    let rect = rect.to_box2d();
+    let mut ids: Vec<usize> = self.large.iter().copied()
+      .filter(|&i| self.shapes[i].0.intersects(&rect))
+      .collect();
+    if let Some((lo, hi)) = self.extent {
+      let (min, max) = (self.cell_of(rect.min), self.cell_of(rect.max));
+      itertools::iproduct!(min.0.max(lo.0)..=max.0.min(hi.0), min.1.max(lo.1)..=max.1.min(hi.1))
+        .filter_map(|cell| self.grid.get(&cell))
+        .flatten()
+        .filter(|&&i| self.shapes[i].0.intersects(&rect))
+        .for_each(|&i| ids.push(i));
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+  }
+
+  /// The shape with the lowest SDF at `p`, and its distance.
+  pub fn nearest_shape(&self, p: P2<T>) -> Option<(usize, T)> {
+    self.k_nearest(p, 1).pop()
+  }
+
+  /// Up to `k` shapes with the lowest SDFs at `p`, nearest first.
+  pub fn k_nearest(&self, p: P2<T>, k: usize) -> Vec<(usize, T)> {
+    let mut nearest = vec![];
+    if k == 0 {
+      return nearest;
+    }
+    let mut seen = HashSet::new();
+    let mut visit = |ids: &mut dyn Iterator<Item = usize>, nearest: &mut Vec<(usize, T)>| {
+      ids.filter(|&i| seen.insert(i))
+        .for_each(|i| nearest.push((i, self.shapes[i].1.sdf(p))));
+      nearest.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+      nearest.truncate(k);
+    };
+    visit(&mut self.large.iter().copied(), &mut nearest);
+    let Some((lo, hi)) = self.extent else {
+      return nearest;
+    };
+    // rings of cells around `p`, starting from the nearest occupied one. Shapes outside of
+    // ring `r` and the ones within are at least `r - 1` cells away, hence no nearer than that
+    let c = self.cell_of(p);
+    let r0 = [lo.0 - c.0, c.0 - hi.0, lo.1 - c.1, c.1 - hi.1, 0].into_iter().max().unwrap();
+    for r in r0.. {
+      let bound = T::from(r - 1).unwrap().max(T::zero()) * self.cell;
+      if nearest.len() == k && nearest[k - 1].1 <= bound {
+        break;
+      }
+      let ring = (c.1 - r).max(lo.1)..=(c.1 + r).min(hi.1);
+      let mut ids = ring.flat_map(|y| {
+        let xs: Vec<i64> = if (y - c.1).abs() == r {
+          ((c.0 - r).max(lo.0)..=(c.0 + r).min(hi.0)).collect()
+        } else {
+          [c.0 - r, c.0 + r].into_iter().filter(|x| (lo.0..=hi.0).contains(x)).collect()
+        };
+        xs.into_iter().filter_map(move |x| self.grid.get(&(x, y)))
+      }).flatten().copied();
+      visit(&mut ids, &mut nearest);
+      // the whole grid is visited
+      if c.0 - r <= lo.0 && c.0 + r >= hi.0 && c.1 - r <= lo.1 && c.1 + r >= hi.1 {
+        break;
+      }
+    }
+    nearest
+  }
+
+  fn cell_of(&self, p: P2<T>) -> Cell {
+    let f = |x: T| (x / self.cell).floor().max(T::from(i32::MIN).unwrap()).min(T::from(i32::MAX).unwrap()).to_i64().unwrap_or(0);
+    (f(p.x), f(p.y))
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::{Circle, Square}, euclid::{Point2D, Size2D, Vector2D as V2}, rand::prelude::*};
+
+  #[test] fn shape_index() {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+    let circles: Vec<_> = (0..200)
+      .map(|_| Circle.scale(rng.gen_range(0.002..0.02)).translate(V2::new(rng.gen(), rng.gen())))
+      .collect();
+    let mut index = ShapeIndex::new(1.0 / 32.0);
+    circles.iter().for_each(|&circle| { index.insert(circle); });
+    // spans the whole grid
+    assert_eq!(index.insert(Square.scale(0.5).translate(V2::splat(2.0))), 200);
+    assert_eq!(index.len(), 201);
+
+    let all: Vec<_> = (0..index.len()).map(|i| index.shape(i).unwrap()).collect();
+    let brute = |p: P2<f64>, k: usize| {
+      let mut distances: Vec<_> = all.iter().enumerate().map(|(i, shape)| (i, shape.sdf(p))).collect();
+      distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+      distances.truncate(k);
+      distances
+    };
+    for p in [Point2D::new(0.5, 0.5), Point2D::new(0.01, 0.99), Point2D::new(-3.0, 0.5), Point2D::new(2.0, 2.0)] {
+      assert_eq!(index.k_nearest(p, 5), brute(p, 5), "{p:?}");
+      assert_eq!(index.nearest_shape(p), brute(p, 1).pop());
+    }
+    assert!(index.k_nearest(Point2D::origin(), 0).is_empty());
+    assert_eq!(index.k_nearest(Point2D::origin(), 1000).len(), 201);
+
+    let rect = Rect::new(Point2D::new(0.25, 0.25), Size2D::new(0.5, 0.25));
+    let expected: Vec<_> = (0..index.len())
+      .filter(|&i| all[i].bounding_box().intersects(&rect.to_box2d()))
+      .collect();
+    assert_eq!(index.shapes_in(rect), expected);
+    assert!(ShapeIndex::<f64>::new(0.1).nearest_shape(Point2D::origin()).is_none());
+  }
+}
@@ -0,0 +1,184 @@
+use {
+  std::collections::HashSet,
+  num_traits::Float,
+  crate::geometry::{Polyline, P2}
+};
+
+// the least drop of the slope across a ridge, i.e. the gradients of its sides are at least 60°
+// apart; a half of it, since a ridge may pass up to half a step away from the sample
+const KINK: f64 = 0.5;
+
+/// Skeleton of the free space, where `field` is positive: the ridges of the distance field,
+/// where its gradient is discontinuous, equidistant from two or more of the nearest shapes.
+/// Sampled at the centers of a `resolution × resolution` grid over the unit square, a sample
+/// lies on a ridge if it is a local maximum along a row, a column or a diagonal, with a kink
+/// (a drop of the slope across it) of at least 1. The ridges are thinned to a single pixel and
+/// traced into polylines between their ends and junctions, loops being closed ones. Useful for
+/// analysis, and for seeding placements along the skeleton, see
+/// [`solver::along_path`](crate::solver::along_path).
+pub fn medial_axis<T>(field: impl Fn(P2<T>) -> T + Sync, resolution: usize) -> Vec<Polyline<T, Vec<P2<T>>>>
+  where T: Float + Send + Sync
+{
+  use rayon::prelude::*;
+
+  let n = resolution;
+  let point = |x: usize, y: usize| P2::new(
+    (T::from(x).unwrap() + T::from(0.5).unwrap()) / T::from(n).unwrap(),
+    (T::from(y).unwrap() + T::from(0.5).unwrap()) / T::from(n).unwrap()
+  );
+  let values: Vec<T> = (0..n * n).into_par_iter()
+    .map(|i| field(point(i % n, i / n)))
+    .collect();
+  let step = T::one() / T::from(n).unwrap();
+  let kink = T::from(KINK).unwrap() * step;
+  let mut mask: Vec<bool> = (0..n * n).into_par_iter()
+    .map(|i| {
+      let (x, y) = (i % n, i / n);
+      let c = values[i];
+      if x == 0 || y == 0 || x + 1 == n || y + 1 == n || c <= T::zero() {
+        return false;
+      }
+      [(1, 0), (0, 1), (1, 1), (1, -1)].into_iter().any(|(dx, dy): (isize, isize)| {
+        let at = |sign: isize| values[(y as isize + dy * sign) as usize * n + (x as isize + dx * sign) as usize];
+        let (a, b) = (at(-1), at(1));
+        let length = if dx != 0 && dy != 0 { T::from(2.0).unwrap().sqrt() } else { T::one() };
+        c >= a && c >= b && c + c - a - b > kink * length
+      })
+    })
+    .collect();
+  thin(&mut mask, n);
+  trace(&mask, n).into_iter()
+    .map(|(pixels, closed)| Polyline {
+      vertices: pixels.into_iter().map(|i| point(i % n, i / n)).collect(),
+      thickness: T::zero(),
+      closed
+    })
+    .collect()
+}
+
+// neighbours of `i`, clockwise from the north, as in Zhang-Suen thinning
+fn neighbours(i: usize, n: usize) -> [Option<usize>; 8] {
+  let (x, y) = ((i % n) as isize, (i / n) as isize);
+  [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)].map(|(dx, dy)| {
+    let (x, y) = (x + dx, y + dy);
+    (x >= 0 && y >= 0 && x < n as isize && y < n as isize).then_some(y as usize * n + x as usize)
+  })
+}
+
+// Zhang-Suen thinning, down to the lines of a single pixel
+fn thin(mask: &mut [bool], n: usize) {
+  loop {
+    let mut changed = false;
+    for pass in 0..2 {
+      let removed: Vec<usize> = (0..n * n)
+        .filter(|&i| mask[i])
+        .filter(|&i| {
+          let p = neighbours(i, n).map(|j| j.is_some_and(|j| mask[j]));
+          let count = p.iter().filter(|&&set| set).count();
+          let transitions = (0..8).filter(|&k| !p[k] && p[(k + 1) % 8]).count();
+          let [north, _, east, _, south, _, west, _] = p;
+          let sides = if pass == 0 {
+            !(east && south && (north || west))
+          } else {
+            !(north && west && (east || south))
+          };
+          (2..=6).contains(&count) && transitions == 1 && sides
+        })
+        .collect();
+      changed |= !removed.is_empty();
+      removed.into_iter().for_each(|i| mask[i] = false);
+    }
+    if !changed {
+      break;
+    }
+  }
+}
+
+// chains of pixels between ends and junctions, and closed loops. A diagonal step is skipped,
+// where the pixels are connected by an orthogonal one as well, lest the corners of staircases
+// become junctions
+fn trace(mask: &[bool], n: usize) -> Vec<(Vec<usize>, bool)> {
+  let adjacent = |i: usize| -> Vec<usize> {
+    let p = neighbours(i, n);
+    let set = |k: usize| p[k].filter(|&j| mask[j]);
+    (0..8).filter_map(|k| {
+      let j = set(k)?;
+      let diagonal = k % 2 == 1;
+      (!diagonal || (set(k - 1).is_none() && set((k + 1) % 8).is_none())).then_some(j)
+    }).collect()
+  };
+  let pixels: Vec<usize> = (0..n * n).filter(|&i| mask[i]).collect();
+  let mut visited: HashSet<(usize, usize)> = HashSet::new();
+  let edge = |a: usize, b: usize| (a.min(b), a.max(b));
+  let mut chains = vec![];
+  let walk = |start: usize, next: usize, visited: &mut HashSet<(usize, usize)>| {
+    let mut chain = vec![start];
+    let (mut previous, mut current) = (start, next);
+    visited.insert(edge(previous, current));
+    loop {
+      chain.push(current);
+      let following: Vec<usize> = adjacent(current).into_iter().filter(|&j| j != previous).collect();
+      if following.len() != 1 || current == start {
+        break;
+      }
+      if !visited.insert(edge(current, following[0])) {
+        break;
+      }
+      (previous, current) = (current, following[0]);
+    }
+    chain
+  };
+  // open chains, from the ends and junctions
+  for &i in &pixels {
+    let next = adjacent(i);
+    if next.len() == 2 {
+      continue;
+    }
+    for j in next {
+      if !visited.contains(&edge(i, j)) {
+        chains.push((walk(i, j, &mut visited), false));
+      }
+    }
+  }
+  // the rest are loops
+  for &i in &pixels {
+    if let Some(&j) = adjacent(i).iter().find(|&&j| !visited.contains(&edge(i, j))) {
+      let mut chain = walk(i, j, &mut visited);
+      if chain.last() == Some(&i) {
+        chain.pop();
+      }
+      chains.push((chain, true));
+    }
+  }
+  chains
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::sdf};
+
+  #[test] fn medial_axis() {
+    let resolution = 128;
+    let pixel = 1.0 / resolution as f64;
+    // the diagonals of the square
+    let axis = super::medial_axis(sdf::boundary_rect, resolution);
+    let points: Vec<P2<f64>> = axis.iter().flat_map(|line| line.vertices.iter().copied()).collect();
+    assert!(points.iter().all(|p| (p.x - p.y).abs().min((p.x + p.y - 1.0).abs()) < 2.0 * pixel));
+    for corner in [P2::new(0.1, 0.1), P2::new(0.9, 0.1), P2::new(0.1, 0.9), P2::new(0.9, 0.9), P2::splat(0.5)] {
+      assert!(points.iter().any(|p| (*p - corner).length() < 2.0 * pixel), "{corner:?}");
+    }
+    assert!((4..=8).contains(&axis.len()), "{}", axis.len());
+    assert!(axis.iter().all(|line| !line.closed && line.vertices.len() >= 2));
+
+    // a ring around a hole in the middle, with spurs to the corners
+    let hole = |p: P2<f64>| sdf::boundary_rect(p).min((p - P2::splat(0.5)).length() - 0.2);
+    let axis = super::medial_axis(hole, resolution);
+    assert!(axis.iter().flat_map(|line| &line.vertices).all(|p| (*p - P2::splat(0.5)).length() > 0.2));
+    let ring = axis.iter().flat_map(|line| &line.vertices)
+      .filter(|p| ((**p - P2::splat(0.5)).length() - 0.35).abs() < 0.05)
+      .count();
+    assert!(ring > 100, "{ring}");
+    // a disc has a single point on its axis
+    let disc = super::medial_axis(sdf::boundary_circle, resolution);
+    assert!(disc.iter().flat_map(|line| &line.vertices).all(|p| (*p - P2::splat(0.5)).length() < 4.0 * pixel));
+  }
+}
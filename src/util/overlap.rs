@@ -0,0 +1,146 @@
+use {
+  std::collections::HashMap,
+  num_traits::Float,
+  euclid::{Box2D, Point2D, Vector2D as V2},
+  crate::geometry::{DistPoint, Shape, WorldSpace, P2}
+};
+
+/// A shape to validate with [`validate_no_overlap`].
+#[derive(Copy, Clone)]
+pub enum Placed<'a, T> {
+  /// A circle of radius `distance`, tested exactly against other circles
+  Circle(DistPoint<T, T, WorldSpace>),
+  Shape(&'a (dyn Shape<T> + Sync))
+}
+
+impl<T> From<DistPoint<T, T, WorldSpace>> for Placed<'_, T> {
+  fn from(circle: DistPoint<T, T, WorldSpace>) -> Self {
+    Placed::Circle(circle)
+  }
+}
+
+impl<'a, T, S: Shape<T> + Sync> From<&'a S> for Placed<'a, T> {
+  fn from(shape: &'a S) -> Self {
+    Placed::Shape(shape)
+  }
+}
+
+impl<T: Float> Placed<'_, T> {
+  fn bounding_box(&self) -> Box2D<T, WorldSpace> {
+    match self {
+      Placed::Circle(c) => Box2D::new(c.point - V2::splat(c.distance), c.point + V2::splat(c.distance)),
+      Placed::Shape(shape) => shape.bounding_box()
+    }
+  }
+
+  fn sdf(&self, p: P2<T>) -> T {
+    match self {
+      Placed::Circle(c) => (p - c.point).length() - c.distance,
+      Placed::Shape(shape) => shape.sdf(p)
+    }
+  }
+}
+
+/// Pairs of shapes, overlapping by more than `tolerance`, e.g. to validate a distribution.
+/// Candidates are found on a uniform grid over the bounding boxes, instead of testing every
+/// pair. Circles are tested against each other exactly; other pairs overlap where the sum of
+/// their SDFs is below `-tolerance`, searched for by subdividing the intersection of their
+/// bounding boxes, the SDFs being assumed 1-Lipschitz. Pairs, still inconclusive at the finest
+/// subdivision, are reported as well, hence touching shapes require a positive `tolerance`.
+/// Returns sorted pairs of indices `(i, j)`, `i < j`.
+pub fn validate_no_overlap<'a, T>(shapes: impl IntoIterator<Item = impl Into<Placed<'a, T>>>, tolerance: T) -> Vec<(usize, usize)>
+  where T: Float + Send + Sync + 'a
+{
+  use rayon::prelude::*;
+
+  let shapes: Vec<Placed<T>> = shapes.into_iter().map(Into::into).collect();
+  let boxes: Vec<_> = shapes.iter().map(Placed::bounding_box).collect();
+  // cells of the median size of a shape
+  let mut sizes: Vec<T> = boxes.iter()
+    .map(|b| b.width().max(b.height()))
+    .filter(|&size| size > T::zero() && size.is_finite())
+    .collect();
+  sizes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+  let cell = sizes.get(sizes.len() / 2).copied().unwrap_or_else(T::one);
+  let cell_of = |p: P2<T>| ((p.x / cell).floor().to_i64().unwrap_or(0), (p.y / cell).floor().to_i64().unwrap_or(0));
+  let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+  boxes.iter().enumerate().for_each(|(i, b)| {
+    let (min, max) = (cell_of(b.min), cell_of(b.max));
+    itertools::iproduct!(min.0..=max.0, min.1..=max.1).for_each(|xy| grid.entry(xy).or_default().push(i));
+  });
+  // a pair is only tested in the cell of the corner of the intersection of its boxes
+  let boxes = &boxes;
+  let candidates: Vec<(usize, usize)> = grid.iter()
+    .flat_map(|(&xy, members)| members.iter().enumerate()
+      .flat_map(move |(k, &i)| members[k + 1..].iter().map(move |&j| (i, j)))
+      .filter(move |&(i, j)| boxes[i].intersects(&boxes[j]) && cell_of(boxes[i].min.max(boxes[j].min)) == xy))
+    .collect();
+  let mut overlapping: Vec<_> = candidates.into_par_iter()
+    .filter(|&(i, j)| match (&shapes[i], &shapes[j]) {
+      (Placed::Circle(a), Placed::Circle(b)) => (a.point - b.point).length() + tolerance < a.distance + b.distance,
+      (a, b) => overlap_sampled(a, b, boxes[i].intersection_unchecked(&boxes[j]), tolerance)
+    })
+    .collect();
+  overlapping.sort_unstable();
+  overlapping
+}
+
+// whether `a.sdf + b.sdf < -tolerance` somewhere within `rect`; the sum is 2-Lipschitz, hence
+// exceeds its value at the center of a cell by at most the diagonal
+fn overlap_sampled<T: Float>(a: &Placed<T>, b: &Placed<T>, rect: Box2D<T, WorldSpace>, tolerance: T) -> bool {
+  const MAX_DEPTH: u32 = 16;
+  let mut cells = vec![(rect, 0)];
+  while let Some((rect, depth)) = cells.pop() {
+    let c = rect.center();
+    let sum = a.sdf(c) + b.sdf(c);
+    if sum < -tolerance {
+      return true;
+    }
+    if sum - (rect.max - rect.min).length() >= -tolerance {
+      continue;
+    }
+    if depth == MAX_DEPTH {
+      return true;
+    }
+    cells.extend([
+      Box2D::new(rect.min, c),
+      Box2D::new(c, rect.max),
+      Box2D::new(Point2D::new(c.x, rect.min.y), Point2D::new(rect.max.x, c.y)),
+      Box2D::new(Point2D::new(rect.min.x, c.y), Point2D::new(c.x, rect.max.y))
+    ].map(|cell| (cell, depth + 1)));
+  }
+  false
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::{Circle, Square}, rand::prelude::*};
+
+  #[test] fn validate_no_overlap() {
+    let circle = |x: f64, y: f64, r: f64| DistPoint { distance: r, point: Point2D::new(x, y) };
+    // touching, overlapping, and disjoint
+    let circles = [circle(0.2, 0.5, 0.1), circle(0.4, 0.5, 0.1), circle(0.55, 0.5, 0.1), circle(0.9, 0.9, 0.05)];
+    assert_eq!(super::validate_no_overlap(circles, 1e-9), [(1, 2)]);
+
+    // agrees with testing every pair
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+    let circles: Vec<_> = (0..300).map(|_| circle(rng.gen(), rng.gen(), rng.gen_range(0.001..0.03))).collect();
+    let expected: Vec<_> = (0..circles.len())
+      .flat_map(|i| (i + 1..circles.len()).map(move |j| (i, j)))
+      .filter(|&(i, j)| (circles[i].point - circles[j].point).length() < circles[i].distance + circles[j].distance)
+      .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(super::validate_no_overlap(circles.iter().copied(), 0.0), expected);
+
+    // sampled, against circles and each other
+    let squares = [
+      Square.scale(0.1).translate(V2::new(0.2, 0.2)),
+      Square.scale(0.1).translate(V2::new(0.4, 0.2)),
+      Square.scale(0.1).translate(V2::new(0.55, 0.25)),
+    ];
+    let disk = Circle.scale(0.1).translate(V2::new(0.2, 0.45));
+    let mut shapes: Vec<Placed<f64>> = squares.iter().map(Placed::from).collect();
+    shapes.push(Placed::from(&disk));
+    shapes.push(circle(0.2, 0.05, 0.06).into());
+    assert_eq!(super::validate_no_overlap(shapes, 1e-6), [(0, 4), (1, 2)]);
+  }
+}
@@ -0,0 +1,99 @@
+//! Distributions of the sizes of shapes: builders of closures `(local_max, rng) -> radius`,
+//! for [`solver::Inscribed`](crate::solver::Inscribed) or hand-written placement code. The
+//! radii are truncated to `[0, local_max.distance]`, so that a shape always fits within the
+//! circle inscribed at the maximum; `0` means there is no room for one.
+
+use {
+  num_traits::{Float, FloatConst},
+  rand::prelude::*,
+  crate::geometry::{DistPoint, WorldSpace}
+};
+
+// uniform in `(0, 1]`, lest a zero radius be drawn
+fn unit<T: Float>(rng: &mut dyn RngCore) -> T {
+  T::from(1.0 - rng.gen_range(0.0..1.0f64)).unwrap()
+}
+
+fn truncate<T: Float>(radius: T, max: DistPoint<T, T, WorldSpace>) -> T {
+  radius.min(max.distance).max(T::zero())
+}
+
+/// `distance * u^exponent`, `u` being uniform in `(0, 1]`. The exponent of 1 is uniform in
+/// `(0, distance]`; greater ones favour small shapes, with a long tail of large ones, giving
+/// the dense, fine-grained distributions.
+pub fn power_law<T>(exponent: T) -> impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T + Copy + Send + Sync
+  where T: Float + Send + Sync
+{
+  move |max, rng| truncate(unit::<T>(rng).powf(exponent) * max.distance, max)
+}
+
+/// Uniform in `(0, distance]`, i.e. [`power_law`] of the exponent 1.
+pub fn uniform<T: Float + Send + Sync>() -> impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T + Copy + Send + Sync {
+  power_law(T::one())
+}
+
+/// Log-normal of the given `median`, `sigma` being the standard deviation of the logarithm,
+/// independent of the available distance but for the truncation; natural-looking sizes, such
+/// as of pebbles or cells.
+pub fn lognormal<T>(median: T, sigma: T) -> impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T + Copy + Send + Sync
+  where T: Float + FloatConst + Send + Sync
+{
+  move |max, rng| {
+    // Box-Muller transform
+    let (u, v) = (unit::<T>(rng), unit::<T>(rng));
+    let normal = (-(T::one() + T::one()) * u.ln()).sqrt() * (T::TAU() * v).cos();
+    truncate(median * (sigma * normal).exp(), max)
+  }
+}
+
+/// One of `radii`, uniformly among those fitting within `distance`; `0` when none does.
+pub fn discrete<T>(radii: impl IntoIterator<Item = T>) -> impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T + Clone + Send + Sync
+  where T: Float + Send + Sync
+{
+  let mut radii: Vec<T> = radii.into_iter().filter(|r| *r > T::zero()).collect();
+  radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+  move |max, rng| {
+    let fitting = radii.partition_point(|r| *r <= max.distance);
+    if fitting == 0 {
+      return T::zero();
+    }
+    radii[rng.gen_range(0..fitting)]
+  }
+}
+
+/// `sampler`, capped at `max_radius`.
+pub fn capped<T: Float>(
+  sampler: impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T,
+  max_radius: T
+) -> impl Fn(DistPoint<T, T, WorldSpace>, &mut dyn RngCore) -> T {
+  move |max, rng| sampler(max, rng).min(max_radius)
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::P2};
+
+  #[test] fn radius_sampler() {
+    let rng = &mut rand_pcg::Pcg64::seed_from_u64(0);
+    let max = DistPoint { distance: 0.2, point: P2::new(0.5, 0.5) };
+    let mean = |sampler: &dyn Fn(DistPoint<f64, f64, WorldSpace>, &mut dyn RngCore) -> f64, rng: &mut dyn RngCore| {
+      let radii: Vec<f64> = (0..10000).map(|_| sampler(max, rng)).collect();
+      assert!(radii.iter().all(|r| *r > 0.0 && *r <= max.distance));
+      radii.iter().sum::<f64>() / radii.len() as f64
+    };
+    // `distance / (exponent + 1)`
+    assert!((mean(&uniform(), rng) - 0.1).abs() < 5e-3);
+    assert!((mean(&power_law(5.0), rng) - 0.2 / 6.0).abs() < 2e-3);
+    assert!(mean(&capped(uniform(), 0.05), rng) <= 0.05);
+
+    let lognormal = lognormal(0.05, 0.5);
+    let mut radii: Vec<f64> = (0..10001).map(|_| lognormal(max, rng)).collect();
+    radii.sort_by(f64::total_cmp);
+    assert!((radii[5000] - 0.05).abs() < 2e-3, "{}", radii[5000]);
+    assert!(radii.iter().all(|r| *r > 0.0 && *r <= 0.2) && radii[10000] == 0.2);
+
+    let discrete = discrete([0.1, 0.3, 0.05]);
+    let radii: Vec<f64> = (0..100).map(|_| discrete(max, rng)).collect();
+    assert!(radii.iter().all(|r| [0.05, 0.1].contains(r)) && radii.contains(&0.05) && radii.contains(&0.1));
+    assert_eq!(discrete(DistPoint { distance: 0.01, ..max }, rng), 0.0);
+  }
+}
@@ -0,0 +1,187 @@
+use {
+  anyhow::Result,
+  rand::prelude::*,
+  crate::{
+    geometry::{Circle, DistPoint, Shape, WorldSpace, P2},
+    sdf::{self, SDF},
+    solver::{Argmax2D, Concentric, PlacedShape, RandomInscribed, ShapePlacer},
+    util
+  }
+};
+
+/// How the free space is filled, see [`recursive_fill`] and
+/// [`patterns::poster`](crate::patterns::poster).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FillStrategy {
+  /// Place circles at the global maxima of the distance field, with radius of
+  /// `distance * scale`, producing a fractal-like distribution.
+  Fractal { scale: f32 },
+  /// Place circles at the global maxima, with radius uniformly distributed in
+  /// `(0, distance]`, offset randomly within the available space.
+  Random { seed: u64 }
+}
+
+impl FillStrategy {
+  // the circle placed at `global_max`, `None` once there is no room for one
+  pub(crate) fn place(&self, global_max: DistPoint<f32, f32, WorldSpace>, rng: &mut impl Rng) -> Option<DistPoint<f32, f32, WorldSpace>> {
+    let placed = match *self {
+      FillStrategy::Fractal { scale } => Concentric { ratio: scale }.place(global_max, rng),
+      FillStrategy::Random { .. } => RandomInscribed::default().place(global_max, rng)
+    };
+    placed.map(|PlacedShape { center, radius }| DistPoint { distance: radius, point: center })
+  }
+
+  pub(crate) fn seed(&self) -> u64 {
+    match *self {
+      FillStrategy::Random { seed } => seed,
+      _ => 0
+    }
+  }
+}
+
+/// Options of [`recursive_fill_with`], per filled shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillOptions {
+  /// Resolution of the [`Argmax2D`] field, over the bounding box of the shape; must be
+  /// divisible by `chunk_size`
+  pub field_resolution: u64,
+  pub chunk_size: u64,
+  /// Stop once there is no room for a circle of this radius, relative to the size of the shape
+  pub min_radius: f32,
+  pub max_shapes: usize
+}
+
+impl Default for FillOptions {
+  fn default() -> Self {
+    Self { field_resolution: 256, chunk_size: 16, min_radius: 1.0 / 64.0, max_shapes: 64 }
+  }
+}
+
+/// A circle placed by [`recursive_fill`], in world space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Nested {
+  pub circle: DistPoint<f32, f32, WorldSpace>,
+  /// 0 for the circles within the root shape
+  pub depth: u32,
+  /// Index of the circle, which this one is placed within
+  pub parent: Option<usize>
+}
+
+/// [`recursive_fill_with`] the default [`FillOptions`].
+pub fn recursive_fill(root_shape: &(impl Shape<f32> + Sync), depth: u32, strategy: FillStrategy) -> Result<Vec<Nested>> {
+  recursive_fill_with(root_shape, depth, strategy, &FillOptions::default())
+}
+
+/// Fill `root_shape` with circles, then the interior of each placed circle with a scaled-down
+/// distribution of its own, and so on, `depth` levels below the first one; the fractal in a
+/// fractal. Every shape is filled on a field of its own, over its bounding box mapped onto the
+/// unit square, so that the resolution is the same on every level. The distributions within
+/// circles are the same up to scale for [`FillStrategy::Fractal`], hence computed once, and
+/// seeded by [`util::split_seed`] per circle for [`FillStrategy::Random`]. Returns the circles
+/// level by level, parents first.
+pub fn recursive_fill_with(
+  root_shape: &(impl Shape<f32> + Sync),
+  depth: u32,
+  strategy: FillStrategy,
+  options: &FillOptions
+) -> Result<Vec<Nested>> {
+  let bounding_box = root_shape.bounding_box();
+  let size = bounding_box.width().max(bounding_box.height());
+  let center = bounding_box.center();
+  // a shape of `size` around `center`, in the coordinates of the unit square
+  let to_world = |center: P2<f32>, size: f32, circle: DistPoint<f32, f32, WorldSpace>| DistPoint {
+    distance: circle.distance * size,
+    point: center + (circle.point - P2::splat(0.5)) * size
+  };
+  let mut circles: Vec<Nested> = fill(
+    |p| -root_shape.sdf(center + (p - P2::splat(0.5)) * size) / size,
+    strategy,
+    strategy.seed(),
+    options
+  )?.into_iter()
+    .map(|circle| Nested { circle: to_world(center, size, circle), depth: 0, parent: None })
+    .collect();
+
+  let fractal = match strategy {
+    FillStrategy::Fractal { .. } => Some(fill(sdf::boundary_circle, strategy, 0, options)?),
+    FillStrategy::Random { .. } => None
+  };
+  let mut level = 0..circles.len();
+  for depth in 1..=depth {
+    let start = circles.len();
+    for parent in level {
+      let container = circles[parent].circle;
+      let layout = match &fractal {
+        Some(layout) => layout.clone(),
+        None => fill(sdf::boundary_circle, strategy, util::split_seed(strategy.seed(), parent as u64 + 1), options)?
+      };
+      circles.extend(layout.into_iter().map(|circle| Nested {
+        circle: to_world(container.point, container.distance * 2.0, circle),
+        depth,
+        parent: Some(parent)
+      }));
+    }
+    level = start..circles.len();
+  }
+  Ok(circles)
+}
+
+// circles within `boundary`, positive inside, on the unit square
+fn fill(
+  boundary: impl Fn(P2<f32>) -> f32 + Sync + Send,
+  strategy: FillStrategy,
+  seed: u64,
+  options: &FillOptions
+) -> Result<Vec<DistPoint<f32, f32, WorldSpace>>> {
+  let mut argmax = Argmax2D::new(options.field_resolution, options.chunk_size)?;
+  argmax.insert_sdf(boundary);
+  let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+  let mut circles = vec![];
+  while circles.len() < options.max_shapes {
+    let Some(global_max) = argmax.find_max_above(options.min_radius) else { break };
+    let Some(circle) = strategy.place(global_max, &mut rng) else { break };
+    let shape = Circle
+      .translate(circle.point.to_vector())
+      .scale(circle.distance);
+    argmax.insert_sdf_domain(
+      util::changed_domain(&shape, global_max.distance),
+      move |p| shape.sdf(p)
+    );
+    circles.push(circle);
+  }
+  Ok(circles)
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, euclid::Vector2D as V2};
+
+  #[test] fn recursive_fill() -> Result<()> {
+    let root = Circle.translate(V2::new(1.0, 2.0)).scale(0.5);
+    let options = FillOptions { field_resolution: 128, max_shapes: 12, ..Default::default() };
+    let circles = recursive_fill_with(&root, 2, FillStrategy::Fractal { scale: 0.5 }, &options)?;
+    let roots = circles.iter().filter(|c| c.depth == 0).count();
+    // the same distribution within every circle
+    let children = circles.iter().filter(|c| c.parent == Some(0)).count();
+    assert!(roots > 5 && children > 5);
+    assert_eq!(circles.len(), roots + roots * children + roots * children * children);
+    // within the parents, one pixel of the field of tolerance
+    circles.iter().for_each(|c| match c.parent {
+      None => assert!(root.sdf(c.circle.point) + c.circle.distance < 1.0 / 128.0),
+      Some(parent) => {
+        assert!(parent < circles.len() && circles[parent].depth + 1 == c.depth);
+        let parent = circles[parent].circle;
+        let gap = parent.distance - (c.circle.point - parent.point).length() - c.circle.distance;
+        assert!(gap > -parent.distance * 2.0 / 128.0);
+      }
+    });
+    // siblings are disjoint
+    let siblings = circles.iter().filter(|c| c.parent == Some(roots)).map(|c| c.circle);
+    assert!(util::validate_no_overlap(siblings, 1e-6).is_empty());
+
+    let random = |seed| super::recursive_fill_with(&root, 1, FillStrategy::Random { seed }, &options);
+    assert_eq!(random(1)?, random(1)?);
+    assert_ne!(random(1)?, random(2)?);
+    assert!(super::recursive_fill_with(&root, 0, FillStrategy::Random { seed: 0 }, &options)?.iter().all(|c| c.depth == 0));
+    Ok(())
+  }
+}
@@ -0,0 +1,182 @@
+use {
+  num_traits::{Float, FloatConst},
+  crate::geometry::{DistPoint, WorldSpace, Measure}
+};
+
+/// Statistics of a distribution of shapes in the unit square, each summarized by the circle of
+/// its center and radius. Feed it the placed circles with [`push`](Stats::push), arbitrary
+/// shapes with [`push_shape`](Stats::push_shape), or wrap the placement iterator with
+/// [`inspect`](Stats::inspect).
+#[derive(Debug, Clone, Default)]
+pub struct Stats<T> {
+  circles: Vec<DistPoint<T, T, WorldSpace>>,
+  area: T
+}
+
+/// Counts of values in `bins` equal intervals of `min..=max`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram<T> {
+  pub min: T,
+  pub max: T,
+  pub counts: Vec<usize>
+}
+
+/// Gaps between the edges of each shape and its nearest neighbor, see [`Stats::spacing`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spacing<T> {
+  pub min: T,
+  pub max: T,
+  pub mean: T,
+  pub std_dev: T
+}
+
+impl<T: Float + FloatConst> Stats<T> {
+  pub fn new() -> Self {
+    Self { circles: vec![], area: T::zero() }
+  }
+
+  /// A circle of radius `circle.distance`.
+  pub fn push(&mut self, circle: DistPoint<T, T, WorldSpace>) {
+    self.area = self.area + T::PI() * circle.distance * circle.distance;
+    self.circles.push(circle);
+  }
+
+  /// A shape, as the circle of the same area, centered at its centroid.
+  pub fn push_shape(&mut self, shape: &impl Measure<T>) {
+    let area = shape.area();
+    self.area = self.area + area;
+    self.circles.push(DistPoint { distance: (area / T::PI()).sqrt(), point: shape.centroid() });
+  }
+
+  /// Record every circle yielded by `placement`, such as the local maxima of the field.
+  pub fn inspect<'a, I>(&'a mut self, placement: I) -> impl Iterator<Item = I::Item> + 'a
+    where I: IntoIterator<Item = DistPoint<T, T, WorldSpace>> + 'a
+  {
+    placement.into_iter().inspect(move |&circle| self.push(circle))
+  }
+
+  pub fn count(&self) -> usize {
+    self.circles.len()
+  }
+
+  /// Fraction of the unit square covered, for shapes which don't overlap.
+  pub fn coverage(&self) -> T {
+    self.area
+  }
+
+  pub fn radius_histogram(&self, bins: usize) -> Histogram<T> {
+    let (min, max) = self.circles.iter().fold((T::infinity(), T::neg_infinity()), |(min, max), c| {
+      (min.min(c.distance), max.max(c.distance))
+    });
+    let mut counts = vec![0; bins];
+    if bins == 0 || self.circles.is_empty() {
+      return Histogram { min: T::zero(), max: T::zero(), counts };
+    }
+    let width = (max - min) / T::from(bins).unwrap();
+    self.circles.iter().for_each(|c| {
+      let bin = if width > T::zero() { ((c.distance - min) / width).to_usize().unwrap_or(0) } else { 0 };
+      counts[bin.min(bins - 1)] += 1;
+    });
+    Histogram { min, max, counts }
+  }
+
+  /// Exponent `D` of the power law `N(r) ∝ r^-D`, where `N(r)` is the number of shapes larger
+  /// than `r`, by least squares in log-log scale. For a space-filling packing, it estimates the
+  /// fractal dimension of the residual set. `None` for fewer than two distinct radii.
+  pub fn fractal_dimension(&self) -> Option<T> {
+    let mut radii: Vec<T> = self.circles.iter()
+      .map(|c| c.distance)
+      .filter(|&r| r > T::zero())
+      .collect();
+    radii.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    // `(-ln r, ln N)`, with `N` counting the shapes no smaller than `r`
+    let samples: Vec<(T, T)> = radii.iter().enumerate()
+      .map(|(i, &r)| (-r.ln(), T::from(i + 1).unwrap().ln()))
+      .collect();
+    let n = T::from(samples.len()).unwrap();
+    let (sx, sy) = samples.iter().fold((T::zero(), T::zero()), |(sx, sy), &(x, y)| (sx + x, sy + y));
+    let (mx, my) = (sx / n, sy / n);
+    let (sxy, sxx) = samples.iter().fold((T::zero(), T::zero()), |(sxy, sxx), &(x, y)| {
+      (sxy + (x - mx) * (y - my), sxx + (x - mx) * (x - mx))
+    });
+    (sxx > T::zero()).then(|| sxy / sxx)
+  }
+
+  /// Gap between the edge of each shape and the nearest other one, negative for overlaps.
+  /// `None` for fewer than two shapes.
+  pub fn spacing(&self) -> Option<Spacing<T>> {
+    let gaps = self.nearest_gaps();
+    if gaps.len() < 2 {
+      return None;
+    }
+    let n = T::from(gaps.len()).unwrap();
+    let mean = gaps.iter().fold(T::zero(), |sum, &g| sum + g) / n;
+    let variance = gaps.iter().fold(T::zero(), |sum, &g| sum + (g - mean) * (g - mean)) / n;
+    Some(Spacing {
+      min: gaps.iter().copied().fold(T::infinity(), T::min),
+      max: gaps.iter().copied().fold(T::neg_infinity(), T::max),
+      mean,
+      std_dev: variance.sqrt()
+    })
+  }
+
+  // by a sweep along `x`: the gap to a shape is no less than the distance along `x`, minus the
+  // two largest radii
+  fn nearest_gaps(&self) -> Vec<T> {
+    let mut circles = self.circles.clone();
+    circles.sort_by(|a, b| a.point.x.partial_cmp(&b.point.x).unwrap());
+    let r_max = circles.iter().fold(T::zero(), |max, c| max.max(c.distance));
+    let gap = |a: &DistPoint<T, T, WorldSpace>, b: &DistPoint<T, T, WorldSpace>| {
+      (a.point - b.point).length() - a.distance - b.distance
+    };
+    (0..circles.len()).map(|i| {
+      let c = &circles[i];
+      let bound = |other: &DistPoint<T, T, WorldSpace>| (other.point.x - c.point.x).abs() - c.distance - r_max;
+      let mut nearest = T::infinity();
+      for other in &circles[i + 1..] {
+        if bound(other) > nearest { break; }
+        nearest = nearest.min(gap(c, other));
+      }
+      for other in circles[..i].iter().rev() {
+        if bound(other) > nearest { break; }
+        nearest = nearest.min(gap(c, other));
+      }
+      nearest
+    }).filter(|gap| gap.is_finite()).collect()
+  }
+
+  /// Centers and radii of the recorded shapes, in the order of placement.
+  pub fn circles(&self) -> &[DistPoint<T, T, WorldSpace>] {
+    &self.circles
+  }
+}
+
+#[cfg(test)] mod tests {
+  use {super::*, crate::geometry::{Circle, Shape}, euclid::{Point2D, Vector2D as V2}};
+
+  #[test] fn stats() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    let mut stats = Stats::new();
+    // a row of four circles of radius 0.1, 0.05 apart
+    let row: Vec<_> = (0..4)
+      .map(|i| DistPoint { distance: 0.1, point: Point2D::new(0.15 + 0.25 * i as f64, 0.5) })
+      .collect();
+    assert_eq!(stats.inspect(row.clone()).count(), 4);
+    stats.push_shape(&Circle.scale(0.05).translate(V2::new(0.5, 0.2)));
+    assert_eq!(stats.count(), 5);
+    assert!(close(stats.coverage(), std::f64::consts::PI * (4.0 * 0.01 + 0.0025)));
+    assert_eq!(stats.circles()[4].point, Point2D::new(0.5, 0.2));
+    let histogram = stats.radius_histogram(2);
+    assert_eq!(histogram.counts, [1, 4]);
+    assert!(close(histogram.min, 0.05) && close(histogram.max, 0.1));
+    let spacing = stats.spacing().unwrap();
+    assert!(close(spacing.min, 0.05) && close(spacing.max, (0.1f64 * 0.1 + 0.3 * 0.3).sqrt() - 0.15));
+
+    // N(r) = r^-1.5
+    let mut power_law = Stats::new();
+    (1..=100).for_each(|n| power_law.push(DistPoint { distance: (n as f64).powf(-1.0 / 1.5), point: Point2D::origin() }));
+    assert!((power_law.fractal_dimension().unwrap() - 1.5).abs() < 1e-9);
+    assert_eq!(Stats::<f64>::new().fractal_dimension(), None);
+    assert_eq!(Stats::<f64>::new().spacing(), None);
+  }
+}